@@ -0,0 +1,188 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Interfaces implemented by sources of gamepads.
+
+use crate::{AxisInfo, EvCode, Event, FfDevice, PowerInfo, Trigger};
+
+use std::fmt::Debug;
+use std::io;
+use std::time::Duration;
+
+/// Source of gamepads and their events that can be chained with native backend by
+/// `Gilrs::add_backend()`.
+///
+/// Ids of events and gamepads are local to the backend, `Gilrs` translates them to its own ids.
+/// Gamepad has to be reported with `Connected` event before its other events, unless it was
+/// already connected when backend was added. Input events should use codes from
+/// `native_ev_codes`, so default mapping can be created for the gamepad.
+pub trait Backend: Debug + Send {
+    /// Returns oldest event or `None` if all events were processed. `Gilrs` doesn't block on
+    /// chained backends, so this function shouldn't block either.
+    fn next_event(&mut self) -> Option<Event>;
+
+    /// Borrows gamepad or returns `None` if index is invalid. Returned gamepad may be
+    /// disconnected.
+    fn gamepad(&self, id: usize) -> Option<&(dyn BackendGamepad + 'static)>;
+
+    /// Returns id greater than id of last connected gamepad.
+    fn last_gamepad_hint(&self) -> usize;
+
+    /// Returns true if gamepads of this backend are never devices connected to this computer,
+    /// for example gamepads received over network. They are not hidden as duplicates of gamepads
+    /// with the same UUID reported by other backends, and don't hide them either.
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    /// Called by `Gilrs::suspend()`.
+    fn suspend(&mut self) {}
+
+    /// Called by `Gilrs::resume()`.
+    fn resume(&mut self) {}
+}
+
+/// Backend without gamepads. `Gilrs::with_backend(Box::new(DummyBackend))` creates `Gilrs` that
+/// doesn't interact with OS, on every platform.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DummyBackend;
+
+impl Backend for DummyBackend {
+    fn next_event(&mut self) -> Option<Event> {
+        None
+    }
+
+    fn gamepad(&self, _id: usize) -> Option<&(dyn BackendGamepad + 'static)> {
+        None
+    }
+
+    fn last_gamepad_hint(&self) -> usize {
+        0
+    }
+}
+
+/// Gamepad of a backend, accessed through `Gamepad`. Only functions that report gamepad's
+/// identity and layout have to be implemented, other features are unsupported by default.
+pub trait BackendGamepad: Debug {
+    fn name(&self) -> &str;
+    /// SDL compatible UUID of gamepad model or nil UUID.
+    fn uuid(&self) -> [u8; 16];
+    fn is_connected(&self) -> bool;
+    fn buttons(&self) -> &[EvCode];
+    fn axes(&self) -> &[EvCode];
+    fn axis_info(&self, nec: EvCode) -> Option<&AxisInfo>;
+
+    fn power_info(&self) -> PowerInfo {
+        PowerInfo::Unknown
+    }
+
+    fn is_ff_supported(&self) -> bool {
+        false
+    }
+
+    /// Returns true if positive values of Y axes mean down.
+    fn is_y_axis_reversed(&self) -> bool {
+        false
+    }
+
+    fn ff_device(&self) -> Option<FfDevice> {
+        None
+    }
+
+    fn is_exclusive_supported(&self) -> bool {
+        false
+    }
+
+    fn set_exclusive(&self, _exclusive: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "exclusive access is not supported by this backend",
+        ))
+    }
+
+    fn has_mic_led(&self) -> bool {
+        false
+    }
+
+    fn set_mic_led(&self, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "gamepad doesn't have microphone LED",
+        ))
+    }
+
+    fn has_lightbar(&self) -> bool {
+        false
+    }
+
+    fn set_lightbar(&self, _color: [u8; 3]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "gamepad doesn't have lightbar",
+        ))
+    }
+
+    fn has_adaptive_triggers(&self) -> bool {
+        false
+    }
+
+    fn set_trigger_effect(&self, _trigger: Trigger, _effect: [u8; 11]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "gamepad doesn't have adaptive triggers",
+        ))
+    }
+}
+
+/// Force feedback of a backend's gamepad, wrapped by `FfDevice`.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait BackendFfDevice: Debug + Send {
+    /// Sets magnitude for strong and weak ff motors. Returns error if it couldn't be sent to the
+    /// device.
+    fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) -> io::Result<()>;
+
+    /// Returns `true` if gamepad has impulse motors in triggers, like Xbox One controller.
+    fn has_trigger_motors(&self) -> bool {
+        false
+    }
+
+    /// Sets magnitude for motors in left and right trigger. Only called if
+    /// `has_trigger_motors()` returns `true`.
+    fn set_trigger_state(
+        &mut self,
+        _left: u16,
+        _right: u16,
+        _min_duration: Duration,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Force feedback of a backend's gamepad, wrapped by `FfDevice`. On wasm effects are played from
+/// the thread that owns `Gilrs`, so it doesn't have to be `Send`.
+#[cfg(target_arch = "wasm32")]
+pub trait BackendFfDevice: Debug {
+    /// Sets magnitude for strong and weak ff motors. Returns error if it couldn't be sent to the
+    /// device.
+    fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) -> io::Result<()>;
+
+    /// Returns `true` if gamepad has impulse motors in triggers, like Xbox One controller.
+    fn has_trigger_motors(&self) -> bool {
+        false
+    }
+
+    /// Sets magnitude for motors in left and right trigger. Only called if
+    /// `has_trigger_motors()` returns `true`.
+    fn set_trigger_state(
+        &mut self,
+        _left: u16,
+        _right: u16,
+        _min_duration: Duration,
+    ) -> io::Result<()> {
+        Ok(())
+    }
+}
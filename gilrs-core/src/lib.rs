@@ -1,16 +1,56 @@
+#[cfg(feature = "log")]
 #[macro_use]
-extern crate log;
+extern crate log_rs as log;
+
+// Without `log` feature, logging macros only type check their arguments.
+#[cfg(not(feature = "log"))]
+macro_rules! log_stub {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! trace { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+#[cfg(not(feature = "log"))]
+macro_rules! debug { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+#[cfg(not(feature = "log"))]
+macro_rules! info { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+#[cfg(not(feature = "log"))]
+macro_rules! warn { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+#[cfg(not(feature = "log"))]
+macro_rules! error { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
 
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+use std::collections::VecDeque;
 use std::error;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::Duration;
 use std::time::SystemTime;
 
+mod backend;
 mod platform;
 pub mod utils;
+#[cfg(all(
+    feature = "virtual-gamepad",
+    any(target_os = "linux", target_os = "windows")
+))]
+mod virtual_gamepad;
+
+pub use crate::backend::{Backend, BackendFfDevice, BackendGamepad, DummyBackend};
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+pub use crate::platform::{GpioBackend, GpioGamepad};
+#[cfg(all(
+    feature = "virtual-gamepad",
+    any(target_os = "linux", target_os = "windows")
+))]
+pub use crate::virtual_gamepad::VirtualGamepad;
 
 /// True, if Y axis of sticks commonly points downwards.
 pub const IS_Y_AXIS_REVERSED: bool = platform::IS_Y_AXIS_REVERSED;
@@ -18,14 +58,43 @@ pub const IS_Y_AXIS_REVERSED: bool = platform::IS_Y_AXIS_REVERSED;
 /// Allow control of gamepad's force feedback.
 #[derive(Debug)]
 pub struct FfDevice {
-    inner: platform::FfDevice,
+    inner: Box<dyn BackendFfDevice>,
 }
 
 impl FfDevice {
-    /// Sets magnitude for strong and weak ff motors.
-    pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) {
+    /// Wraps force feedback device of a backend.
+    pub fn new<T: BackendFfDevice + 'static>(device: T) -> Self {
+        FfDevice {
+            inner: Box::new(device),
+        }
+    }
+
+    /// Sets magnitude for strong and weak ff motors. Returns error if it couldn't be sent to the
+    /// device.
+    pub fn set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        min_duration: Duration,
+    ) -> io::Result<()> {
         self.inner.set_ff_state(strong, weak, min_duration)
     }
+
+    /// Returns `true` if gamepad has impulse motors in triggers.
+    pub fn has_trigger_motors(&self) -> bool {
+        self.inner.has_trigger_motors()
+    }
+
+    /// Sets magnitude for motors in left and right trigger. Does nothing if gamepad doesn't
+    /// have them.
+    pub fn set_trigger_state(
+        &mut self,
+        left: u16,
+        right: u16,
+        min_duration: Duration,
+    ) -> io::Result<()> {
+        self.inner.set_trigger_state(left, right, min_duration)
+    }
 }
 
 /// Holds information about gamepad event.
@@ -55,6 +124,159 @@ pub enum EventType {
     AxisValueChanged(i32, EvCode),
     Connected,
     Disconnected,
+    /// Backend's event queue was full and this number of events was discarded. Sent before next
+    /// event of the gamepad.
+    Overflow(usize),
+    /// New reading of motion sensors. Only reported by HID drivers (`hidapi` feature).
+    Motion(MotionData),
+    /// Finger touched, moved on or left touchpad. Only reported by HID drivers (`hidapi`
+    /// feature).
+    Touch(TouchData),
+}
+
+/// Reading of gamepad's accelerometer and gyroscope.
+///
+/// Axes follow SDL's convention: when gamepad is held in front of player, X points to the right,
+/// Y up and Z towards the player.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct MotionData {
+    /// Acceleration in m/s², including gravity.
+    pub accel: [f32; 3],
+    /// Angular velocity in rad/s.
+    pub gyro: [f32; 3],
+}
+
+/// Position of finger on gamepad's touchpad.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct TouchData {
+    /// Index of touch point, starting from 0. Touchpads track up to two fingers.
+    pub finger: u8,
+    /// `false` if finger left touchpad. Position is the last known one then.
+    pub touching: bool,
+    /// Horizontal position from 0.0 (left edge) to 1.0 (right edge).
+    pub x: f32,
+    /// Vertical position from 0.0 (top edge) to 1.0 (bottom edge).
+    pub y: f32,
+}
+
+/// Trigger with adaptive resistance.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Left,
+    Right,
+}
+
+/// Non-fatal problem encountered by platform backend.
+///
+/// These errors don't stop `Gilrs` from working, but they usually mean that some functionality,
+/// like force feedback or battery status, is not available for the gamepad.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BackendError {
+    /// Id of gamepad this error is related to, if known.
+    pub id: Option<usize>,
+    /// Operation that failed.
+    pub kind: BackendErrorKind,
+    /// Platform specific description of the error.
+    pub description: String,
+    /// Platform specific error code, like `errno` on Linux or code returned by XInput on Windows.
+    pub code: Option<i32>,
+    /// Time when error occurred.
+    pub time: SystemTime,
+}
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "{} (gamepad {}): {}", self.kind, id, self.description),
+            None => write!(f, "{}: {}", self.kind, self.description),
+        }
+    }
+}
+
+impl error::Error for BackendError {}
+
+/// Kind of operation that caused `BackendError`.
+#[non_exhaustive]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BackendErrorKind {
+    /// Failed to open device.
+    DeviceOpen,
+    /// Failed to read state of device.
+    Poll,
+    /// Failed to query battery status.
+    PowerInfo,
+    /// Failed to upload or play force feedback effect.
+    ForceFeedback,
+    /// Backend thread died or stopped responding and was restarted.
+    Watchdog,
+    /// Other, platform specific problem.
+    Other,
+}
+
+impl Display for BackendErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            BackendErrorKind::DeviceOpen => "failed to open device",
+            BackendErrorKind::Poll => "failed to read device state",
+            BackendErrorKind::PowerInfo => "failed to query power info",
+            BackendErrorKind::ForceFeedback => "force feedback failed",
+            BackendErrorKind::Watchdog => "backend thread restarted",
+            BackendErrorKind::Other => "backend error",
+        };
+
+        f.write_str(s)
+    }
+}
+
+/// Bounded queue of `BackendError`s shared between `Gilrs` and objects that can fail outside of
+/// `Gilrs::next_event()`, like gamepads or force feedback devices.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ErrorQueue(Arc<Mutex<VecDeque<BackendError>>>);
+
+impl ErrorQueue {
+    /// When queue is full, the oldest error is dropped.
+    const MAX_LEN: usize = 64;
+
+    #[allow(dead_code)]
+    pub(crate) fn push<S: Into<String>>(
+        &self,
+        id: Option<usize>,
+        kind: BackendErrorKind,
+        description: S,
+    ) {
+        self.push_with_code(id, kind, None, description)
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn push_with_code<S: Into<String>>(
+        &self,
+        id: Option<usize>,
+        kind: BackendErrorKind,
+        code: Option<i32>,
+        description: S,
+    ) {
+        let error = BackendError {
+            id,
+            kind,
+            description: description.into(),
+            code,
+            time: utils::time_now(),
+        };
+        debug!("Backend error: {}", error);
+
+        let mut queue = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if queue.len() >= Self::MAX_LEN {
+            queue.pop_front();
+        }
+        queue.push_back(error);
+    }
+
+    fn pop(&self) -> Option<BackendError> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).pop_front()
+    }
 }
 
 /// Holds information about expected axis range and deadzone.
@@ -95,34 +317,396 @@ pub enum PowerInfo {
     Charged,
 }
 
+/// Platform object that is signaled when backend has new events, returned by
+/// `Gilrs::raw_wait_handle()`.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RawWaitHandle {
+    /// File descriptor that is readable while there are unread events (epoll instance on Linux,
+    /// kqueue on BSDs).
+    #[cfg(unix)]
+    Fd(std::os::unix::io::RawFd),
+    /// Manual-reset event object that is set while there are unread events.
+    #[cfg(windows)]
+    Handle(std::os::windows::io::RawHandle),
+}
+
+/// How backends that poll gamepads choose time between polls. See `Gilrs::set_poll_mode()`.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PollMode {
+    /// Always poll with interval set by `Gilrs::set_poll_interval()`.
+    Fixed,
+    /// Poll with interval set by `Gilrs::set_poll_interval()` while any gamepad is connected and
+    /// with `idle` interval otherwise. `idle` is capped to 500 ms.
+    Adaptive { idle: Duration },
+    /// Poll every millisecond (1 kHz) for lowest input latency, at cost of higher CPU usage.
+    LowLatency,
+}
+
+/// Describes native backend, returned by `Gilrs::backend_info()`.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BackendInfo {
+    /// Name of platform's API, for example `"evdev"` or `"XInput"`. `"none"` if `Gilrs` was
+    /// created without native backend.
+    pub name: &'static str,
+    /// File name of library that was loaded at runtime, for example `"xinput1_4.dll"`.
+    pub library: Option<&'static str>,
+}
+
+impl BackendInfo {
+    pub(crate) fn new(name: &'static str, library: Option<&'static str>) -> Self {
+        BackendInfo { name, library }
+    }
+}
+
+/// How often `wait()` checks chained backends for new events.
+const CHAINED_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Where gamepad with given id comes from.
+#[derive(Copy, Clone, Debug)]
+enum Source {
+    Native(usize),
+    /// Index of backend and id of gamepad in that backend.
+    Chained(usize, usize),
+}
+
+/// Gamepad of chained backend, indexed by its id in that backend.
+#[derive(Copy, Clone, Debug, Default)]
+struct ChainedSlot {
+    /// Assigned when gamepad connects for the first time and it isn't a duplicate.
+    id: Option<usize>,
+    /// `true` while gamepad is connected, but it's hidden because earlier backend reports the
+    /// same device.
+    duplicate: bool,
+}
+
+#[derive(Debug)]
+struct Chained {
+    backend: Box<dyn Backend>,
+    slots: Vec<ChainedSlot>,
+}
+
 /// Struct used to manage gamepads and retrieve events.
 #[derive(Debug)]
 pub struct Gilrs {
-    inner: platform::Gilrs,
+    /// `None` if `Gilrs` was created by `with_backend()`.
+    inner: Option<platform::Gilrs>,
+    errors: ErrorQueue,
+    /// Backends added by `add_backend()`. While it's empty, ids of native backend are used
+    /// directly and `ids`, `natives` and `events` are unused.
+    chained: Vec<Chained>,
+    ids: Vec<Source>,
+    /// Ids of native gamepads, indexed by native id.
+    natives: Vec<Option<usize>>,
+    /// Events of chained backends read by `wait()`.
+    events: VecDeque<Event>,
 }
 
 impl Gilrs {
+    #[allow(clippy::result_large_err)]
     pub fn new() -> Result<Self, Error> {
-        let inner = platform::Gilrs::new().map_err(|e| match e {
-            PlatformError::NotImplemented(inner) => Error::NotImplemented(Gilrs { inner }),
+        let errors = ErrorQueue::default();
+        let inner = platform::Gilrs::new(errors.clone()).map_err(|e| match e {
+            PlatformError::NotImplemented(inner) => {
+                Error::NotImplemented(Gilrs::with_native(Some(inner), errors.clone()))
+            }
             PlatformError::Other(e) => Error::Other(e),
         })?;
 
-        Ok(Gilrs { inner })
+        Ok(Gilrs::with_native(Some(inner), errors))
+    }
+
+    /// Creates `Gilrs` without native backend, gamepads are reported only by `backend` and by
+    /// backends added later with `add_backend()`. With `DummyBackend` no gamepads are reported
+    /// and OS is never accessed, which is useful on servers and in tests.
+    pub fn with_backend(backend: Box<dyn Backend>) -> Self {
+        let mut gilrs = Gilrs::with_native(None, ErrorQueue::default());
+        gilrs.add_backend(backend);
+
+        gilrs
+    }
+
+    fn with_native(inner: Option<platform::Gilrs>, errors: ErrorQueue) -> Self {
+        Gilrs {
+            inner,
+            errors,
+            chained: Vec::new(),
+            ids: Vec::new(),
+            natives: Vec::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Adds backend whose gamepads and events are merged with those of native backend and of
+    /// backends added earlier. Its gamepads get ids after ids of gamepads that are already known.
+    ///
+    /// The same device can be visible to more than one backend. When gamepad of added backend
+    /// connects for the first time while native or earlier backend has connected gamepad with the
+    /// same UUID, it's treated as the same device and is hidden until it disconnects. Gamepads with
+    /// nil UUID are never hidden.
+    ///
+    /// Chained backends can't be waited on, so `wait()` checks them every 10 ms and
+    /// `raw_wait_handle()` returns `None`. `stop()`, `restart()` and other functions that
+    /// configure backend only affect native backend.
+    pub fn add_backend(&mut self, backend: Box<dyn Backend>) {
+        if self.chained.is_empty() {
+            let len = self.native_gamepad_hint();
+            self.ids = (0..len).map(Source::Native).collect();
+            self.natives = (0..len).map(Some).collect();
+        }
+
+        let idx = self.chained.len();
+        let len = backend.last_gamepad_hint();
+        self.chained.push(Chained {
+            backend,
+            slots: vec![ChainedSlot::default(); len],
+        });
+        for local in 0..len {
+            let connected = match self.chained[idx].backend.gamepad(local) {
+                Some(gamepad) => gamepad.is_connected(),
+                None => false,
+            };
+            if connected {
+                self.connect_chained(idx, local);
+            }
+        }
     }
 
     /// Returns oldest event or `None` if all events were processed.
     pub fn next_event(&mut self) -> Option<Event> {
-        self.inner.next_event()
+        let inner = self.inner.as_mut();
+        if self.chained.is_empty() {
+            return inner?.next_event();
+        }
+
+        if let Some(event) = self.events.pop_front() {
+            return Some(event);
+        }
+
+        match inner.and_then(|inner| inner.next_event()) {
+            Some(event) => Some(self.map_native_event(event)),
+            None => self.next_chained_event(),
+        }
+    }
+
+    /// Blocks until new events may be available or `timeout` passes, whichever comes first.
+    /// `None` means no timeout. Returns immediately if there are already pending events.
+    ///
+    /// This function can return without new events (spurious wakeup), call `next_event()` and
+    /// wait again if needed. On wasm it never blocks.
+    pub fn wait(&mut self, timeout: Option<Duration>) {
+        if self.chained.is_empty() {
+            if let Some(ref mut inner) = self.inner {
+                inner.wait(timeout);
+            }
+            return;
+        }
+
+        if self.events.is_empty() {
+            if let Some(event) = self.next_chained_event() {
+                self.events.push_back(event);
+            }
+        }
+        if !self.events.is_empty() {
+            return;
+        }
+
+        let timeout = match timeout {
+            Some(timeout) => timeout.min(CHAINED_POLL_INTERVAL),
+            None => CHAINED_POLL_INTERVAL,
+        };
+        match self.inner {
+            Some(ref mut inner) => inner.wait(Some(timeout)),
+            None => thread::sleep(timeout),
+        }
+    }
+
+    /// Returns handle that becomes signaled when backend has new events, so `Gilrs` can be
+    /// waited on together with other sources by application's event loop (`poll()` on Linux,
+    /// `WaitForMultipleObjects()` on Windows). Handle is owned by `Gilrs` and must not be closed.
+    ///
+    /// Handle is signaled only by events that were not read yet, call `next_event()` until it
+    /// returns `None` before waiting. It stays signaled while backend is suspended if gamepads
+    /// send input. Returns `None` on macOS, wasm, unsupported platforms and when HID drivers
+    /// (`hidapi` feature) or chained backends are used.
+    pub fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        match self.inner {
+            Some(ref inner) if self.chained.is_empty() => inner.raw_wait_handle(),
+            _ => None,
+        }
+    }
+
+    /// Sets how often backend polls devices. Only backends that poll gamepads (currently XInput
+    /// on Windows and GameController on iOS, which polls in `wait()`) use it, on other platforms
+    /// it's a no-op. On Windows value of the `GILRS_POLL_INTERVAL_MS` environment variable, if
+    /// set, takes precedence.
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        if let Some(ref mut inner) = self.inner {
+            inner.set_poll_interval(interval)
+        }
+    }
+
+    /// Sets whether poll interval depends on connected gamepads. Defaults to `PollMode::Fixed`.
+    /// Only XInput backend on Windows uses it, on other platforms it's a no-op. On Windows value of
+    /// the `GILRS_POLL_INTERVAL_MS` environment variable, if set, takes precedence over
+    /// `PollMode::LowLatency`.
+    pub fn set_poll_mode(&mut self, mode: PollMode) {
+        if let Some(ref mut inner) = self.inner {
+            inner.set_poll_mode(mode)
+        }
+    }
+
+    /// Sets how often backend looks for newly connected gamepads. Only XInput backend on Windows
+    /// checks connections periodically (every second by default), and only if it couldn't
+    /// register for device notifications. Other backends ignore it.
+    pub fn set_connection_check_interval(&mut self, interval: Duration) {
+        if let Some(ref mut inner) = self.inner {
+            inner.set_connection_check_interval(interval)
+        }
+    }
+
+    /// Limits number of events that wait in backend's queue. When limit is reached, new input
+    /// events are discarded and `EventType::Overflow` is reported instead. `None` means no limit,
+    /// which is the default.
+    ///
+    /// Currently only implemented by XInput backend on Windows, on other platforms it's a no-op.
+    pub fn set_event_queue_capacity(&mut self, capacity: Option<usize>) {
+        if let Some(ref mut inner) = self.inner {
+            inner.set_event_queue_capacity(capacity)
+        }
+    }
+
+    /// Sets Steam Input action set that is activated for all controllers. Defaults to
+    /// `"gamepad"`. Does nothing if native backend is used.
+    #[cfg(all(
+        feature = "steam",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    pub fn set_steam_action_set(&mut self, name: &str) {
+        if let Some(ref mut inner) = self.inner {
+            inner.set_steam_action_set(name)
+        }
+    }
+
+    /// Returns `true` if gamepads are read through Steam Input instead of native backend.
+    #[cfg(all(
+        feature = "steam",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    pub fn is_steam_input(&self) -> bool {
+        match self.inner {
+            Some(ref inner) => inner.is_steam_input(),
+            None => false,
+        }
+    }
+
+    /// Returns oldest non-fatal error reported by backend or `None` if there are no new errors.
+    ///
+    /// Only limited number of errors is stored, so you should call this function regularly if you
+    /// want to observe all of them.
+    pub fn next_backend_error(&mut self) -> Option<BackendError> {
+        self.errors.pop()
+    }
+
+    /// Releases threads, file descriptors and device handles used by backend. All connected
+    /// gamepads are reported as disconnected, but they keep their ids, so after `restart()` they
+    /// will be reported as connected at the same place. Does nothing if backend is already
+    /// stopped.
+    ///
+    /// Dropping `Gilrs` also stops backend. On Windows both wait for event thread to exit.
+    pub fn stop(&mut self) {
+        if let Some(ref mut inner) = self.inner {
+            inner.stop()
+        }
+    }
+
+    /// Acquires resources released by `stop()`. Gamepads that are still available emit
+    /// `Connected` event. Does nothing if backend is not stopped.
+    pub fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>> {
+        match self.inner {
+            Some(ref mut inner) => inner.restart(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns `false` if backend stopped working and couldn't be restarted. Gamepads are reported
+    /// as disconnected and no new events will be emitted, but you can still try to call
+    /// `restart()`.
+    pub fn is_alive(&self) -> bool {
+        match self.inner {
+            Some(ref inner) => inner.is_alive(),
+            None => true,
+        }
+    }
+
+    /// Returns name of native backend and library it loaded. On Windows it reports which XInput
+    /// DLL was found, `"HID"` backend means that XInput is not available.
+    pub fn backend_info(&self) -> BackendInfo {
+        match self.inner {
+            Some(ref inner) => inner.backend_info(),
+            None => BackendInfo::new("none", None),
+        }
+    }
+
+    /// Returns `true` if platform doesn't report any gamepads until user interacts with one of
+    /// them, and this hasn't happened yet. Only browsers behave like this.
+    pub fn is_waiting_for_activation(&self) -> bool {
+        match self.inner {
+            Some(ref inner) => inner.is_waiting_for_activation(),
+            None => false,
+        }
+    }
+
+    /// Stops polling gamepads until `resume()` is called. Devices stay open, hotplug events are
+    /// reported after resuming.
+    pub fn suspend(&mut self) {
+        if let Some(ref mut inner) = self.inner {
+            inner.suspend();
+        }
+        for chained in &mut self.chained {
+            chained.backend.suspend();
+        }
+    }
+
+    /// Resumes polling. Input events that were generated while suspended are discarded where
+    /// possible, instead backend emits events for differences between current state of gamepad
+    /// and state that was last reported.
+    pub fn resume(&mut self) {
+        if let Some(ref mut inner) = self.inner {
+            inner.resume();
+        }
+        for chained in &mut self.chained {
+            chained.backend.resume();
+        }
+    }
+
+    /// Checks connection state of all gamepads on next poll, instead of waiting for next periodic
+    /// check.
+    /// Changes are reported with `Connected` and `Disconnected` events.
+    ///
+    /// XInput backend on Windows checks connections after device notification (or periodically
+    /// when notifications are unavailable), other backends are notified about changes
+    /// immediately and this function does nothing.
+    pub fn refresh(&mut self) {
+        if let Some(ref mut inner) = self.inner {
+            inner.refresh()
+        }
     }
 
     /// Borrows `Gamrpad` or return `None` if index is invalid. Returned gamepad may be disconnected.
     pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
-        unsafe {
-            let gp: Option<&platform::Gamepad> = self.inner.gamepad(id);
+        let gamepad = if self.chained.is_empty() {
+            self.native_gamepad(id)?
+        } else {
+            match *self.ids.get(id)? {
+                Source::Native(nid) => self.native_gamepad(nid)?,
+                Source::Chained(idx, local) => self.chained[idx].backend.gamepad(local)?,
+            }
+        };
 
-            gp.map(|gp| &*(gp as *const _ as *const Gamepad))
-        }
+        Some(Gamepad::from_backend(gamepad))
     }
 
     /// Returns id greater than id of last connected gamepad. The returned value is only hint
@@ -132,7 +716,182 @@ impl Gilrs {
     /// `gamepad(id)` should return `Some` if using id that is smaller than value returned from this
     /// function.
     pub fn last_gamepad_hint(&self) -> usize {
-        self.inner.last_gamepad_hint()
+        if self.chained.is_empty() {
+            self.native_gamepad_hint()
+        } else {
+            self.ids.len()
+        }
+    }
+
+    fn native_gamepad_hint(&self) -> usize {
+        match self.inner {
+            Some(ref inner) => inner.last_gamepad_hint(),
+            None => 0,
+        }
+    }
+
+    fn native_gamepad(&self, nid: usize) -> Option<&(dyn BackendGamepad + 'static)> {
+        self.inner
+            .as_ref()?
+            .gamepad(nid)
+            .map(|gamepad| gamepad as &dyn BackendGamepad)
+    }
+
+    /// Assigns id to native gamepad that is seen for the first time.
+    fn map_native_event(&mut self, event: Event) -> Event {
+        let nid = event.id;
+        if nid >= self.natives.len() {
+            self.natives.resize(nid + 1, None);
+        }
+
+        let id = match self.natives[nid] {
+            Some(id) => id,
+            None => {
+                let id = self.ids.len();
+                self.ids.push(Source::Native(nid));
+                self.natives[nid] = Some(id);
+                id
+            }
+        };
+
+        Event { id, ..event }
+    }
+
+    fn next_chained_event(&mut self) -> Option<Event> {
+        for idx in 0..self.chained.len() {
+            while let Some(event) = self.chained[idx].backend.next_event() {
+                if let Some(event) = self.map_chained_event(idx, event) {
+                    return Some(event);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Translates id of chained backend's event and filters out events of duplicates.
+    fn map_chained_event(&mut self, idx: usize, event: Event) -> Option<Event> {
+        let local = event.id;
+        let slots = &mut self.chained[idx].slots;
+        if local >= slots.len() {
+            slots.resize(local + 1, ChainedSlot::default());
+        }
+
+        let slot = slots[local];
+        let id = match event.event {
+            EventType::Connected => self.connect_chained(idx, local)?,
+            EventType::Disconnected if slot.duplicate => {
+                self.chained[idx].slots[local].duplicate = false;
+                return None;
+            }
+            _ if slot.duplicate => return None,
+            _ => slot.id?,
+        };
+
+        Some(Event { id, ..event })
+    }
+
+    /// Returns id of connected gamepad of chained backend or `None` if it's hidden.
+    fn connect_chained(&mut self, idx: usize, local: usize) -> Option<usize> {
+        let slot = self.chained[idx].slots[local];
+        if slot.id.is_none() && self.is_duplicate(idx, local) {
+            debug!(
+                "Gamepad {} of {:?} is already reported by other backend.",
+                local, self.chained[idx].backend
+            );
+            self.chained[idx].slots[local].duplicate = true;
+            return None;
+        }
+
+        let id = match slot.id {
+            Some(id) => id,
+            None => {
+                let id = self.ids.len();
+                self.ids.push(Source::Chained(idx, local));
+                id
+            }
+        };
+        self.chained[idx].slots[local] = ChainedSlot {
+            id: Some(id),
+            duplicate: false,
+        };
+
+        Some(id)
+    }
+
+    /// Returns true if native or earlier chained backend has more connected gamepads with the
+    /// same UUID than there are hidden duplicates of them in this backend. Remote backends are
+    /// excluded.
+    fn is_duplicate(&self, idx: usize, local: usize) -> bool {
+        if self.chained[idx].backend.is_remote() {
+            return false;
+        }
+        let uuid = match self.chained[idx].backend.gamepad(local) {
+            Some(gamepad) => gamepad.uuid(),
+            None => return false,
+        };
+        if uuid == [0; 16] {
+            return false;
+        }
+
+        let same = |gamepad: Option<&(dyn BackendGamepad + 'static)>| match gamepad {
+            Some(gamepad) => gamepad.is_connected() && gamepad.uuid() == uuid,
+            None => false,
+        };
+        let mut earlier = (0..self.native_gamepad_hint())
+            .filter(|&nid| same(self.native_gamepad(nid)))
+            .count();
+        for chained in &self.chained[..idx] {
+            if chained.backend.is_remote() {
+                continue;
+            }
+            earlier += (0..chained.slots.len())
+                .filter(|&l| !chained.slots[l].duplicate && same(chained.backend.gamepad(l)))
+                .count();
+        }
+        let chained = &self.chained[idx];
+        let hidden = (0..chained.slots.len())
+            .filter(|&l| {
+                l != local && chained.slots[l].duplicate && same(chained.backend.gamepad(l))
+            })
+            .count();
+
+        earlier > hidden
+    }
+
+    /// Passes `AInputEvent` read by the application (for example from `AInputQueue` or
+    /// `android_app`'s input callback) to the backend. Returns `true` if event came from gamepad
+    /// and was consumed, `false` if application should handle it itself.
+    ///
+    /// # Safety
+    ///
+    /// `event` must be null or point to valid `AInputEvent`.
+    #[cfg(target_os = "android")]
+    pub unsafe fn handle_android_input_event(
+        &mut self,
+        event: *const std::os::raw::c_void,
+    ) -> bool {
+        match self.inner {
+            Some(ref mut inner) => inner.handle_input_event(event as *const _),
+            None => false,
+        }
+    }
+
+    /// Reports gamepad from `InputDeviceListener.onInputDeviceAdded()`. Gamepads are also
+    /// connected by their first event, but then they only have generic name and nil UUID.
+    #[cfg(target_os = "android")]
+    pub fn android_device_added(&mut self, device_id: i32, name: &str, vendor: u16, product: u16) {
+        if let Some(ref mut inner) = self.inner {
+            inner.device_added(device_id, name, vendor, product)
+        }
+    }
+
+    /// Reports gamepad from `InputDeviceListener.onInputDeviceRemoved()`.
+    #[cfg(target_os = "android")]
+    pub fn android_device_removed(&mut self, device_id: i32) {
+        if let Some(ref mut inner) = self.inner {
+            inner.device_removed(device_id)
+        }
     }
 }
 
@@ -140,10 +899,14 @@ impl Gilrs {
 #[derive(Debug)]
 #[repr(transparent)]
 pub struct Gamepad {
-    inner: platform::Gamepad,
+    inner: dyn BackendGamepad,
 }
 
 impl Gamepad {
+    fn from_backend<'a>(gamepad: &'a (dyn BackendGamepad + 'static)) -> &'a Gamepad {
+        unsafe { &*(gamepad as *const dyn BackendGamepad as *const Gamepad) }
+    }
+
     /// Returns name of gamepad.
     pub fn name(&self) -> &str {
         self.inner.name()
@@ -162,7 +925,7 @@ impl Gamepad {
     /// It is recommended to process with the [UUID crate](https://crates.io/crates/uuid).
     /// Use `Uuid::from_bytes` method to create a `Uuid` from the returned bytes.
     pub fn uuid(&self) -> [u8; 16] {
-        *self.inner.uuid().as_bytes()
+        self.inner.uuid()
     }
 
     /// Returns device's power supply state.
@@ -175,33 +938,74 @@ impl Gamepad {
         self.inner.is_ff_supported()
     }
 
+    /// Returns true if Y axes of this gamepad point downwards. Usually the same as
+    /// `IS_Y_AXIS_REVERSED`, but on Windows it differs for HID gamepads.
+    pub fn is_y_axis_reversed(&self) -> bool {
+        self.inner.is_y_axis_reversed()
+    }
+
     /// Creates `FfDevice` corresponding to this gamepad.
     pub fn ff_device(&self) -> Option<FfDevice> {
-        self.inner.ff_device().map(|inner| FfDevice { inner })
+        self.inner.ff_device()
+    }
+
+    /// Returns true if the platform can give this process exclusive access to the device.
+    pub fn is_exclusive_supported(&self) -> bool {
+        self.inner.is_exclusive_supported()
+    }
+
+    /// Grabs the device so that other processes stop receiving its events, or releases it.
+    pub fn set_exclusive(&self, exclusive: bool) -> io::Result<()> {
+        self.inner.set_exclusive(exclusive)
+    }
+
+    /// Returns true if gamepad has microphone mute LED that can be controlled by `set_mic_led()`.
+    pub fn has_mic_led(&self) -> bool {
+        self.inner.has_mic_led()
+    }
+
+    /// Turns microphone mute LED on or off.
+    pub fn set_mic_led(&self, on: bool) -> io::Result<()> {
+        self.inner.set_mic_led(on)
+    }
+
+    /// Returns true if color of gamepad's lightbar can be changed, like on DualShock 4 and
+    /// DualSense. Only supported by HID drivers (`hidapi` feature).
+    pub fn has_lightbar(&self) -> bool {
+        self.inner.has_lightbar()
+    }
+
+    /// Sets color of lightbar.
+    pub fn set_lightbar(&self, red: u8, green: u8, blue: u8) -> io::Result<()> {
+        self.inner.set_lightbar([red, green, blue])
+    }
+
+    /// Returns true if gamepad's triggers have programmable resistance, like on DualSense. Only
+    /// supported by HID drivers (`hidapi` feature).
+    pub fn has_adaptive_triggers(&self) -> bool {
+        self.inner.has_adaptive_triggers()
+    }
+
+    /// Sets effect of adaptive trigger. `effect` is mode followed by 10 parameters, as in output
+    /// report of DualSense. Effect stays active until it's replaced.
+    pub fn set_trigger_effect(&self, trigger: Trigger, effect: [u8; 11]) -> io::Result<()> {
+        self.inner.set_trigger_effect(trigger, effect)
     }
 
     /// Returns slice with EvCodes that may appear in button related events.
     pub fn buttons(&self) -> &[EvCode] {
-        unsafe {
-            let bt: &[platform::EvCode] = self.inner.buttons();
-
-            &*(bt as *const _ as *const [EvCode])
-        }
+        self.inner.buttons()
     }
 
     /// Returns slice with EvCodes that may appear in axis related events.
     pub fn axes(&self) -> &[EvCode] {
-        unsafe {
-            let ax: &[platform::EvCode] = self.inner.axes();
-
-            &*(ax as *const _ as *const [EvCode])
-        }
+        self.inner.axes()
     }
 
     /// Returns information about specific axis. `None` may be returned if device doesn't have axis
     /// with provided `EvCode`.
     pub fn axis_info(&self, nec: EvCode) -> Option<&AxisInfo> {
-        self.inner.axis_info(nec.0)
+        self.inner.axis_info(nec)
     }
 }
 
@@ -218,6 +1022,10 @@ impl EvCode {
     pub fn into_u32(self) -> u32 {
         self.0.into_u32()
     }
+
+    pub(crate) fn from_native(codes: &[platform::EvCode]) -> &[EvCode] {
+        unsafe { &*(codes as *const [platform::EvCode] as *const [EvCode]) }
+    }
 }
 
 impl Display for EvCode {
@@ -327,4 +1135,165 @@ pub mod native_ev_codes {
     pub const BTN_DPAD_DOWN: EvCode = EvCode(nec::BTN_DPAD_DOWN);
     pub const BTN_DPAD_LEFT: EvCode = EvCode(nec::BTN_DPAD_LEFT);
     pub const BTN_DPAD_RIGHT: EvCode = EvCode(nec::BTN_DPAD_RIGHT);
+
+    /// Codes that are never reported by backends. `gilrs` uses them for elements mapped to part of
+    /// other element's axis, for example triggers sharing one axis.
+    pub const SPLIT_AXES: [EvCode; 4] = [
+        EvCode(nec::SPLIT_AXES[0]),
+        EvCode(nec::SPLIT_AXES[1]),
+        EvCode(nec::SPLIT_AXES[2]),
+        EvCode(nec::SPLIT_AXES[3]),
+    ];
+
+    #[cfg(all(
+        feature = "steam",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    pub use self::steam::*;
+
+    #[cfg(all(
+        feature = "steam",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    mod steam {
+        use super::{nec, EvCode};
+
+        pub const BTN_L4: EvCode = EvCode(nec::BTN_L4);
+        pub const BTN_R4: EvCode = EvCode(nec::BTN_R4);
+        pub const BTN_L5: EvCode = EvCode(nec::BTN_L5);
+        pub const BTN_R5: EvCode = EvCode(nec::BTN_R5);
+    }
+
+    #[cfg(all(
+        feature = "hidapi",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    pub use self::hid::*;
+
+    #[cfg(all(
+        feature = "hidapi",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    mod hid {
+        use super::{nec, EvCode};
+
+        /// Touchpad click of Sony controllers.
+        pub const BTN_TOUCHPAD: EvCode = EvCode(nec::BTN_TOUCHPAD);
+        /// Microphone button of DualSense.
+        pub const BTN_MISC1: EvCode = EvCode(nec::BTN_MISC1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        native_ev_codes, AxisInfo, Backend, BackendErrorKind, BackendGamepad, DummyBackend,
+        ErrorQueue, EvCode, Event, EventType, Gilrs,
+    };
+
+    use std::collections::VecDeque;
+
+    #[derive(Debug)]
+    struct MockGamepad([u8; 16]);
+
+    impl BackendGamepad for MockGamepad {
+        fn name(&self) -> &str {
+            "Mock"
+        }
+
+        fn uuid(&self) -> [u8; 16] {
+            self.0
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn buttons(&self) -> &[EvCode] {
+            &[]
+        }
+
+        fn axes(&self) -> &[EvCode] {
+            &[]
+        }
+
+        fn axis_info(&self, _nec: EvCode) -> Option<&AxisInfo> {
+            None
+        }
+    }
+
+    #[derive(Debug)]
+    struct MockBackend {
+        gamepads: Vec<MockGamepad>,
+        events: VecDeque<Event>,
+        remote: bool,
+    }
+
+    impl MockBackend {
+        /// Every gamepad sends one event.
+        fn new(uuids: &[[u8; 16]]) -> Self {
+            let event = EventType::AxisValueChanged(1, native_ev_codes::AXIS_LSTICKX);
+            MockBackend {
+                gamepads: uuids.iter().map(|&uuid| MockGamepad(uuid)).collect(),
+                events: (0..uuids.len()).map(|id| Event::new(id, event)).collect(),
+                remote: false,
+            }
+        }
+    }
+
+    impl Backend for MockBackend {
+        fn next_event(&mut self) -> Option<Event> {
+            self.events.pop_front()
+        }
+
+        fn gamepad(&self, id: usize) -> Option<&(dyn BackendGamepad + 'static)> {
+            self.gamepads.get(id).map(|g| g as _)
+        }
+
+        fn last_gamepad_hint(&self) -> usize {
+            self.gamepads.len()
+        }
+
+        fn is_remote(&self) -> bool {
+            self.remote
+        }
+    }
+
+    #[test]
+    fn chained_backends_skip_duplicates() {
+        let mut gilrs = Gilrs::with_backend(Box::new(DummyBackend));
+        gilrs.add_backend(Box::new(MockBackend::new(&[[1; 16]])));
+        // The first gamepad is the same device as above, nil UUID is never treated as duplicate.
+        gilrs.add_backend(Box::new(MockBackend::new(&[[1; 16], [0; 16], [0; 16]])));
+        assert_eq!(gilrs.last_gamepad_hint(), 3);
+
+        let mut ids = Vec::new();
+        while let Some(event) = gilrs.next_event() {
+            ids.push(event.id);
+        }
+        assert_eq!(ids, [0, 1, 2]);
+        assert_eq!(gilrs.gamepad(0).unwrap().uuid(), [1; 16]);
+        assert_eq!(gilrs.gamepad(1).unwrap().uuid(), [0; 16]);
+        assert_eq!(gilrs.gamepad(2).unwrap().uuid(), [0; 16]);
+
+        // Remote gamepad of the same model is a different device.
+        let mut remote = MockBackend::new(&[[1; 16]]);
+        remote.remote = true;
+        gilrs.add_backend(Box::new(remote));
+        assert_eq!(gilrs.next_event().map(|event| event.id), Some(3));
+    }
+
+    #[test]
+    fn error_queue_drops_oldest() {
+        let queue = ErrorQueue::default();
+        for i in 0..(ErrorQueue::MAX_LEN + 1) {
+            queue.push(Some(i), BackendErrorKind::Other, "");
+        }
+
+        assert_eq!(queue.pop().unwrap().id, Some(1));
+        for _ in 1..ErrorQueue::MAX_LEN {
+            assert!(queue.pop().is_some());
+        }
+        assert!(queue.pop().is_none());
+    }
 }
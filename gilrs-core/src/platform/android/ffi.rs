@@ -0,0 +1,78 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Subset of `<android/input.h>` and `<android/keycodes.h>` used by the backend.
+
+#![allow(non_camel_case_types)]
+
+use std::os::raw::c_float;
+
+#[repr(C)]
+pub struct AInputEvent {
+    _priv: [u8; 0],
+}
+
+pub const AINPUT_EVENT_TYPE_KEY: i32 = 1;
+pub const AINPUT_EVENT_TYPE_MOTION: i32 = 2;
+
+pub const AINPUT_SOURCE_CLASS_BUTTON: i32 = 0x0000_0001;
+pub const AINPUT_SOURCE_CLASS_JOYSTICK: i32 = 0x0000_0010;
+pub const AINPUT_SOURCE_GAMEPAD: i32 = 0x0000_0400 | AINPUT_SOURCE_CLASS_BUTTON;
+pub const AINPUT_SOURCE_JOYSTICK: i32 = 0x0100_0000 | AINPUT_SOURCE_CLASS_JOYSTICK;
+
+pub const AKEY_EVENT_ACTION_DOWN: i32 = 0;
+pub const AKEY_EVENT_ACTION_UP: i32 = 1;
+
+pub const AKEYCODE_DPAD_UP: i32 = 19;
+pub const AKEYCODE_DPAD_DOWN: i32 = 20;
+pub const AKEYCODE_DPAD_LEFT: i32 = 21;
+pub const AKEYCODE_DPAD_RIGHT: i32 = 22;
+pub const AKEYCODE_BUTTON_A: i32 = 96;
+pub const AKEYCODE_BUTTON_B: i32 = 97;
+pub const AKEYCODE_BUTTON_C: i32 = 98;
+pub const AKEYCODE_BUTTON_X: i32 = 99;
+pub const AKEYCODE_BUTTON_Y: i32 = 100;
+pub const AKEYCODE_BUTTON_Z: i32 = 101;
+pub const AKEYCODE_BUTTON_L1: i32 = 102;
+pub const AKEYCODE_BUTTON_R1: i32 = 103;
+pub const AKEYCODE_BUTTON_L2: i32 = 104;
+pub const AKEYCODE_BUTTON_R2: i32 = 105;
+pub const AKEYCODE_BUTTON_THUMBL: i32 = 106;
+pub const AKEYCODE_BUTTON_THUMBR: i32 = 107;
+pub const AKEYCODE_BUTTON_START: i32 = 108;
+pub const AKEYCODE_BUTTON_SELECT: i32 = 109;
+pub const AKEYCODE_BUTTON_MODE: i32 = 110;
+pub const AKEYCODE_BUTTON_1: i32 = 188;
+pub const AKEYCODE_BUTTON_16: i32 = 203;
+
+pub const AMOTION_EVENT_AXIS_X: i32 = 0;
+pub const AMOTION_EVENT_AXIS_Y: i32 = 1;
+pub const AMOTION_EVENT_AXIS_Z: i32 = 11;
+pub const AMOTION_EVENT_AXIS_RX: i32 = 12;
+pub const AMOTION_EVENT_AXIS_RY: i32 = 13;
+pub const AMOTION_EVENT_AXIS_RZ: i32 = 14;
+pub const AMOTION_EVENT_AXIS_HAT_X: i32 = 15;
+pub const AMOTION_EVENT_AXIS_HAT_Y: i32 = 16;
+pub const AMOTION_EVENT_AXIS_LTRIGGER: i32 = 17;
+pub const AMOTION_EVENT_AXIS_RTRIGGER: i32 = 18;
+pub const AMOTION_EVENT_AXIS_GAS: i32 = 22;
+pub const AMOTION_EVENT_AXIS_BRAKE: i32 = 23;
+
+#[link(name = "android")]
+extern "C" {
+    pub fn AInputEvent_getType(event: *const AInputEvent) -> i32;
+    pub fn AInputEvent_getDeviceId(event: *const AInputEvent) -> i32;
+    pub fn AInputEvent_getSource(event: *const AInputEvent) -> i32;
+    pub fn AKeyEvent_getAction(key_event: *const AInputEvent) -> i32;
+    pub fn AKeyEvent_getKeyCode(key_event: *const AInputEvent) -> i32;
+    pub fn AKeyEvent_getRepeatCount(key_event: *const AInputEvent) -> i32;
+    pub fn AMotionEvent_getAxisValue(
+        motion_event: *const AInputEvent,
+        axis: i32,
+        pointer_index: usize,
+    ) -> c_float;
+}
@@ -0,0 +1,533 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::ffi::{self, AInputEvent};
+use super::FfDevice;
+use crate::utils;
+use crate::{
+    AxisInfo, BackendInfo, ErrorQueue, Event, EventType, PlatformError, PollMode, PowerInfo,
+    RawWaitHandle,
+};
+use uuid::Uuid;
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// Axes are stored after all key codes, so both can share one `EvCode` space.
+const AXIS_OFFSET: u32 = 0x1000;
+const STICK_MAX: i32 = 32767;
+
+/// Android doesn't let libraries read activity's input queue, so the application forwards
+/// events with `handle_input_event()` and reports hotplug from its `InputDeviceListener`.
+#[derive(Debug)]
+pub struct Gilrs {
+    gamepads: Vec<Gamepad>,
+    events: VecDeque<Event>,
+    suspended: bool,
+    stopped: bool,
+}
+
+impl Gilrs {
+    pub(crate) fn new(_errors: ErrorQueue) -> Result<Self, PlatformError> {
+        Ok(Gilrs {
+            gamepads: Vec::new(),
+            events: VecDeque::new(),
+            suspended: false,
+            stopped: false,
+        })
+    }
+
+    pub(crate) fn is_alive(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        BackendInfo::new("Android input", None)
+    }
+
+    pub(crate) fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        // Events are pushed from the same thread that reads them, so there is nothing to wait for.
+        if self.events.is_empty() {
+            thread::sleep(timeout.unwrap_or(utils::WAIT_SLICE));
+        }
+    }
+
+    pub(crate) fn set_poll_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn set_poll_mode(&mut self, _mode: PollMode) {}
+
+    pub(crate) fn set_connection_check_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        None
+    }
+
+    pub(crate) fn refresh(&mut self) {}
+
+    pub(crate) fn set_event_queue_capacity(&mut self, _capacity: Option<usize>) {}
+
+    pub(crate) fn stop(&mut self) {
+        if self.stopped {
+            return;
+        }
+
+        self.stopped = true;
+        for id in 0..self.gamepads.len() {
+            self.disconnect(id);
+        }
+    }
+
+    pub(crate) fn suspend(&mut self) {
+        self.suspended = true;
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.suspended = false;
+    }
+
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        // Gamepads are connected again with the next event they send.
+        self.stopped = false;
+        Ok(())
+    }
+
+    pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
+        self.gamepads.get(id)
+    }
+
+    /// Returns index greater than index of last connected gamepad.
+    pub fn last_gamepad_hint(&self) -> usize {
+        self.gamepads.len()
+    }
+
+    /// Translates `AInputEvent` to gamepad events. Returns `false` if event doesn't come from
+    /// gamepad or isn't recognized, application should handle it itself then.
+    pub(crate) unsafe fn handle_input_event(&mut self, event: *const AInputEvent) -> bool {
+        if event.is_null() || self.stopped {
+            return false;
+        }
+
+        let source = ffi::AInputEvent_getSource(event);
+        if !is_gamepad_source(source) {
+            return false;
+        }
+
+        let device_id = ffi::AInputEvent_getDeviceId(event);
+        match ffi::AInputEvent_getType(event) {
+            ffi::AINPUT_EVENT_TYPE_KEY => {
+                let key_code = ffi::AKeyEvent_getKeyCode(event);
+                let btn = match BUTTONS.iter().position(|btn| btn.0 == key_code as u32) {
+                    Some(btn) => btn,
+                    None => return false,
+                };
+
+                let pressed = match ffi::AKeyEvent_getAction(event) {
+                    ffi::AKEY_EVENT_ACTION_DOWN => true,
+                    ffi::AKEY_EVENT_ACTION_UP => false,
+                    _ => return true,
+                };
+
+                if ffi::AKeyEvent_getRepeatCount(event) > 0 || self.suspended {
+                    return true;
+                }
+
+                let id = self.connect(device_id, None);
+                let gamepad = &mut self.gamepads[id];
+                if gamepad.pressed[btn] != pressed {
+                    gamepad.pressed[btn] = pressed;
+                    let code = crate::EvCode(BUTTONS[btn]);
+                    let ev = if pressed {
+                        EventType::ButtonPressed(code)
+                    } else {
+                        EventType::ButtonReleased(code)
+                    };
+                    self.events.push_back(Event::new(id, ev));
+                }
+
+                true
+            }
+            ffi::AINPUT_EVENT_TYPE_MOTION => {
+                if self.suspended {
+                    return true;
+                }
+
+                let id = self.connect(device_id, None);
+                for (idx, axis) in AXES.iter().enumerate() {
+                    let raw = ffi::AMotionEvent_getAxisValue(event, axis.android_axis(), 0);
+                    let value = scale_axis(*axis, raw);
+                    let gamepad = &mut self.gamepads[id];
+                    if gamepad.values[idx] != value {
+                        gamepad.values[idx] = value;
+                        let ev = EventType::AxisValueChanged(value, crate::EvCode(*axis));
+                        self.events.push_back(Event::new(id, ev));
+                    }
+                }
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Connects gamepad reported by `InputDeviceListener.onInputDeviceAdded()` or updates
+    /// name and UUID of gamepad that was already connected by its events.
+    pub(crate) fn device_added(&mut self, device_id: i32, name: &str, vendor: u16, product: u16) {
+        if self.stopped {
+            return;
+        }
+
+        let info = Some((name, create_uuid(vendor, product)));
+        self.connect(device_id, info);
+    }
+
+    /// Disconnects gamepad reported by `InputDeviceListener.onInputDeviceRemoved()`.
+    pub(crate) fn device_removed(&mut self, device_id: i32) {
+        if let Some(id) = self.gamepads.iter().position(|g| g.device_id == device_id) {
+            self.disconnect(id);
+        }
+    }
+
+    fn connect(&mut self, device_id: i32, info: Option<(&str, Uuid)>) -> usize {
+        let id = match self.gamepads.iter().position(|g| g.device_id == device_id) {
+            Some(id) => id,
+            None => {
+                self.gamepads.push(Gamepad::new(device_id));
+                self.gamepads.len() - 1
+            }
+        };
+
+        let gamepad = &mut self.gamepads[id];
+        if let Some((name, uuid)) = info {
+            gamepad.name = name.to_owned();
+            gamepad.uuid = uuid;
+        }
+
+        if !gamepad.is_connected {
+            gamepad.is_connected = true;
+            self.events.push_back(Event::new(id, EventType::Connected));
+        }
+
+        id
+    }
+
+    fn disconnect(&mut self, id: usize) {
+        let gamepad = &mut self.gamepads[id];
+        if gamepad.is_connected {
+            gamepad.is_connected = false;
+            gamepad.pressed.iter_mut().for_each(|p| *p = false);
+            gamepad.values.iter_mut().for_each(|v| *v = 0);
+            self.events
+                .push_back(Event::new(id, EventType::Disconnected));
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Gamepad {
+    name: String,
+    uuid: Uuid,
+    device_id: i32,
+    is_connected: bool,
+    pressed: Vec<bool>,
+    values: Vec<i32>,
+}
+
+impl Gamepad {
+    fn new(device_id: i32) -> Self {
+        Gamepad {
+            name: format!("Android gamepad {}", device_id),
+            uuid: Uuid::nil(),
+            device_id,
+            is_connected: false,
+            pressed: vec![false; BUTTONS.len()],
+            values: vec![0; AXES.len()],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn power_info(&self) -> PowerInfo {
+        PowerInfo::Unknown
+    }
+
+    pub fn is_ff_supported(&self) -> bool {
+        false
+    }
+
+    pub fn is_y_axis_reversed(&self) -> bool {
+        super::IS_Y_AXIS_REVERSED
+    }
+
+    pub fn has_mic_led(&self) -> bool {
+        false
+    }
+
+    pub fn set_mic_led(&self, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "microphone LED is not supported on this platform",
+        ))
+    }
+
+    pub fn is_exclusive_supported(&self) -> bool {
+        false
+    }
+
+    pub fn set_exclusive(&self, _exclusive: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "exclusive access is not supported on this platform",
+        ))
+    }
+
+    /// Creates Ffdevice corresponding to this gamepad.
+    pub fn ff_device(&self) -> Option<FfDevice> {
+        None
+    }
+
+    /// NDK can't query device's capabilities, so all buttons known to Android are reported.
+    pub fn buttons(&self) -> &[EvCode] {
+        &BUTTONS
+    }
+
+    pub fn axes(&self) -> &[EvCode] {
+        &AXES
+    }
+
+    pub(crate) fn axis_info(&self, nec: EvCode) -> Option<&AxisInfo> {
+        if !AXES.contains(&nec) {
+            None
+        } else if nec == native_ev_codes::AXIS_DPADX || nec == native_ev_codes::AXIS_DPADY {
+            Some(&HAT_INFO)
+        } else if is_trigger(nec) {
+            Some(&TRIGGER_INFO)
+        } else {
+            Some(&STICK_INFO)
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+}
+
+fn is_gamepad_source(source: i32) -> bool {
+    source & ffi::AINPUT_SOURCE_GAMEPAD == ffi::AINPUT_SOURCE_GAMEPAD
+        || source & ffi::AINPUT_SOURCE_JOYSTICK == ffi::AINPUT_SOURCE_JOYSTICK
+}
+
+fn is_trigger(axis: EvCode) -> bool {
+    axis == native_ev_codes::AXIS_LT
+        || axis == native_ev_codes::AXIS_RT
+        || axis == native_ev_codes::AXIS_LT2
+        || axis == native_ev_codes::AXIS_RT2
+}
+
+/// Android reports axes as floats between -1.0 and 1.0 (0.0 and 1.0 for triggers).
+fn scale_axis(axis: EvCode, value: f32) -> i32 {
+    if axis == native_ev_codes::AXIS_DPADX || axis == native_ev_codes::AXIS_DPADY {
+        value.round() as i32
+    } else {
+        (value * STICK_MAX as f32) as i32
+    }
+}
+
+/// UUID in the same format as the one SDL uses, with bus type set to USB.
+fn create_uuid(vendor: u16, product: u16) -> Uuid {
+    if vendor == 0 && product == 0 {
+        return Uuid::nil();
+    }
+
+    Uuid::from_fields(
+        u32::from(0x03u16.to_be()),
+        vendor.to_be(),
+        0,
+        &[(product >> 8) as u8, product as u8, 0, 0, 0, 0, 0, 0],
+    )
+    .unwrap()
+}
+
+const STICK_INFO: AxisInfo = AxisInfo {
+    min: -STICK_MAX,
+    max: STICK_MAX,
+    deadzone: None,
+};
+
+const TRIGGER_INFO: AxisInfo = AxisInfo {
+    min: 0,
+    max: STICK_MAX,
+    deadzone: None,
+};
+
+const HAT_INFO: AxisInfo = AxisInfo {
+    min: -1,
+    max: 1,
+    deadzone: None,
+};
+
+const BUTTONS: [EvCode; 35] = {
+    use self::native_ev_codes::*;
+
+    [
+        BTN_SOUTH,
+        BTN_EAST,
+        BTN_C,
+        BTN_NORTH,
+        BTN_WEST,
+        BTN_Z,
+        BTN_LT,
+        BTN_RT,
+        BTN_LT2,
+        BTN_RT2,
+        BTN_SELECT,
+        BTN_START,
+        BTN_MODE,
+        BTN_LTHUMB,
+        BTN_RTHUMB,
+        BTN_DPAD_UP,
+        BTN_DPAD_DOWN,
+        BTN_DPAD_LEFT,
+        BTN_DPAD_RIGHT,
+        EvCode::key(ffi::AKEYCODE_BUTTON_1),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 1),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 2),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 3),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 4),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 5),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 6),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 7),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 8),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 9),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 10),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 11),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 12),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 13),
+        EvCode::key(ffi::AKEYCODE_BUTTON_1 + 14),
+        EvCode::key(ffi::AKEYCODE_BUTTON_16),
+    ]
+};
+
+const AXES: [EvCode; 12] = {
+    use self::native_ev_codes::*;
+
+    [
+        AXIS_LSTICKX,
+        AXIS_LSTICKY,
+        AXIS_LEFTZ,
+        AXIS_RSTICKX,
+        AXIS_RSTICKY,
+        AXIS_RIGHTZ,
+        AXIS_DPADX,
+        AXIS_DPADY,
+        AXIS_RT,
+        AXIS_LT,
+        AXIS_RT2,
+        AXIS_LT2,
+    ]
+};
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct EvCode(u32);
+
+impl EvCode {
+    const fn key(code: i32) -> Self {
+        EvCode(code as u32)
+    }
+
+    const fn axis(code: i32) -> Self {
+        EvCode(AXIS_OFFSET + code as u32)
+    }
+
+    /// Android's `AMOTION_EVENT_AXIS_*` value of axis.
+    fn android_axis(self) -> i32 {
+        (self.0 - AXIS_OFFSET) as i32
+    }
+
+    pub fn into_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for EvCode {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if self.0 >= AXIS_OFFSET {
+            write!(f, "AXIS({})", self.0 - AXIS_OFFSET)
+        } else {
+            write!(f, "KEYCODE({})", self.0)
+        }
+    }
+}
+
+pub mod native_ev_codes {
+    use super::ffi::*;
+    use super::EvCode;
+
+    pub const AXIS_LSTICKX: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_X);
+    pub const AXIS_LSTICKY: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_Y);
+    pub const AXIS_LEFTZ: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_RX);
+    pub const AXIS_RSTICKX: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_Z);
+    pub const AXIS_RSTICKY: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_RZ);
+    pub const AXIS_RIGHTZ: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_RY);
+    pub const AXIS_DPADX: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_HAT_X);
+    pub const AXIS_DPADY: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_HAT_Y);
+    pub const AXIS_RT: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_GAS);
+    pub const AXIS_LT: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_BRAKE);
+    pub const AXIS_RT2: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_RTRIGGER);
+    pub const AXIS_LT2: EvCode = EvCode::axis(AMOTION_EVENT_AXIS_LTRIGGER);
+
+    pub const BTN_SOUTH: EvCode = EvCode::key(AKEYCODE_BUTTON_A);
+    pub const BTN_EAST: EvCode = EvCode::key(AKEYCODE_BUTTON_B);
+    pub const BTN_C: EvCode = EvCode::key(AKEYCODE_BUTTON_C);
+    pub const BTN_NORTH: EvCode = EvCode::key(AKEYCODE_BUTTON_Y);
+    pub const BTN_WEST: EvCode = EvCode::key(AKEYCODE_BUTTON_X);
+    pub const BTN_Z: EvCode = EvCode::key(AKEYCODE_BUTTON_Z);
+    pub const BTN_LT: EvCode = EvCode::key(AKEYCODE_BUTTON_L1);
+    pub const BTN_RT: EvCode = EvCode::key(AKEYCODE_BUTTON_R1);
+    pub const BTN_LT2: EvCode = EvCode::key(AKEYCODE_BUTTON_L2);
+    pub const BTN_RT2: EvCode = EvCode::key(AKEYCODE_BUTTON_R2);
+    pub const BTN_SELECT: EvCode = EvCode::key(AKEYCODE_BUTTON_SELECT);
+    pub const BTN_START: EvCode = EvCode::key(AKEYCODE_BUTTON_START);
+    pub const BTN_MODE: EvCode = EvCode::key(AKEYCODE_BUTTON_MODE);
+    pub const BTN_LTHUMB: EvCode = EvCode::key(AKEYCODE_BUTTON_THUMBL);
+    pub const BTN_RTHUMB: EvCode = EvCode::key(AKEYCODE_BUTTON_THUMBR);
+
+    pub const BTN_DPAD_UP: EvCode = EvCode::key(AKEYCODE_DPAD_UP);
+    pub const BTN_DPAD_DOWN: EvCode = EvCode::key(AKEYCODE_DPAD_DOWN);
+    pub const BTN_DPAD_LEFT: EvCode = EvCode::key(AKEYCODE_DPAD_LEFT);
+    pub const BTN_DPAD_RIGHT: EvCode = EvCode::key(AKEYCODE_DPAD_RIGHT);
+
+    // Codes of elements mapped to part of other element's axis, never reported by backend. They
+    // are right before axes, far after all key codes.
+    pub const SPLIT_AXES: [EvCode; 4] = [
+        EvCode(super::AXIS_OFFSET - 4),
+        EvCode(super::AXIS_OFFSET - 3),
+        EvCode(super::AXIS_OFFSET - 2),
+        EvCode(super::AXIS_OFFSET - 1),
+    ];
+}
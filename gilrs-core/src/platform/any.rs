@@ -0,0 +1,13 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Trait objects used by backends that combine native backend with other sources of gamepads.
+
+use crate::BackendGamepad;
+
+/// Gamepad of native backend or of one of the other sources.
+pub type Gamepad = dyn BackendGamepad;
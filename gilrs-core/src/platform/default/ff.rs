@@ -5,6 +5,7 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::io;
 use std::time::Duration;
 
 #[derive(Debug)]
@@ -13,5 +14,15 @@ pub struct Device;
 
 impl Device {
     /// Sets magnitude for strong and weak ff motors.
-    pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) {}
+    pub fn set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        min_duration: Duration,
+    ) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "force feedback is not supported",
+        ))
+    }
 }
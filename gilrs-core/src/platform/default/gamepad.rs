@@ -7,23 +7,70 @@
 #![allow(unused_variables)]
 
 use super::FfDevice;
-use crate::{AxisInfo, Event, PlatformError, PowerInfo};
+use crate::utils;
+use crate::{
+    AxisInfo, BackendInfo, ErrorQueue, Event, PlatformError, PollMode, PowerInfo, RawWaitHandle,
+};
 use uuid::Uuid;
 
+use std::error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io;
+use std::thread;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct Gilrs {}
 
 impl Gilrs {
-    pub(crate) fn new() -> Result<Self, PlatformError> {
+    pub(crate) fn new(errors: ErrorQueue) -> Result<Self, PlatformError> {
         Err(PlatformError::NotImplemented(Gilrs {}))
     }
 
+    pub(crate) fn is_alive(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        BackendInfo::new("dummy", None)
+    }
+
     pub(crate) fn next_event(&mut self) -> Option<Event> {
         None
     }
 
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        thread::sleep(timeout.unwrap_or(utils::WAIT_SLICE));
+    }
+
+    pub(crate) fn set_poll_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn set_poll_mode(&mut self, _mode: PollMode) {}
+
+    pub(crate) fn set_connection_check_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        None
+    }
+
+    pub(crate) fn refresh(&mut self) {}
+
+    pub(crate) fn set_event_queue_capacity(&mut self, _capacity: Option<usize>) {}
+
+    pub(crate) fn stop(&mut self) {}
+
+    pub(crate) fn suspend(&mut self) {}
+
+    pub(crate) fn resume(&mut self) {}
+
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        Ok(())
+    }
+
     pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
         None
     }
@@ -56,6 +103,32 @@ impl Gamepad {
         false
     }
 
+    pub fn is_y_axis_reversed(&self) -> bool {
+        super::IS_Y_AXIS_REVERSED
+    }
+
+    pub fn has_mic_led(&self) -> bool {
+        false
+    }
+
+    pub fn set_mic_led(&self, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "microphone LED is not supported on this platform",
+        ))
+    }
+
+    pub fn is_exclusive_supported(&self) -> bool {
+        false
+    }
+
+    pub fn set_exclusive(&self, _exclusive: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "exclusive access is not supported on this platform",
+        ))
+    }
+
     /// Creates Ffdevice corresponding to this gamepad.
     pub fn ff_device(&self) -> Option<FfDevice> {
         Some(FfDevice)
@@ -133,4 +206,7 @@ pub mod native_ev_codes {
     pub const BTN_DPAD_DOWN: EvCode = EvCode(28);
     pub const BTN_DPAD_LEFT: EvCode = EvCode(29);
     pub const BTN_DPAD_RIGHT: EvCode = EvCode(30);
+
+    // Codes of elements mapped to part of other element's axis, never reported by backend.
+    pub const SPLIT_AXES: [EvCode; 4] = [EvCode(252), EvCode(253), EvCode(254), EvCode(255)];
 }
@@ -0,0 +1,969 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HID drivers for controllers that native backends don't support fully.
+//!
+//! Devices are opened with hidapi and read from thread that owns `Gilrs`, their events are merged
+//! with events of native backend. While driver's gamepad is connected, native gamepad with the
+//! same vendor and product ID is reported as disconnected, so controller is reported only once.
+//! On Linux devices bound to vendor kernel driver (for example `hid-nintendo` or
+//! `hid-playstation`) are left to native backend.
+
+mod sony;
+mod switch;
+
+use super::platform as native;
+use crate::{
+    AxisInfo, BackendErrorKind, BackendGamepad, BackendInfo, ErrorQueue, Event, EventType,
+    FfDevice, MotionData, PlatformError, PollMode, PowerInfo, RawWaitHandle, TouchData, Trigger,
+};
+
+use hidapi::{BusType, DeviceInfo, HidApi, HidError};
+use uuid::Uuid;
+
+use std::collections::VecDeque;
+use std::error;
+use std::ffi::CString;
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::io;
+use std::time::{Duration, Instant};
+
+pub use super::any::Gamepad;
+pub use super::platform::{native_ev_codes, EvCode, IS_Y_AXIS_REVERSED};
+
+/// How often list of HID devices is refreshed.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+/// How long `wait()` blocks in native backend while HID gamepads are connected.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(4);
+const AXIS_MAX: i32 = 32767;
+
+/// Where gamepad with given id comes from.
+#[derive(Copy, Clone, Debug)]
+enum Source {
+    Native(usize),
+    Driver(usize),
+}
+
+/// Native gamepad, indexed by its native id.
+#[derive(Copy, Clone, Debug)]
+struct NativeSlot {
+    /// Assigned when `Connected` event is reported for the first time.
+    id: Option<usize>,
+    /// `false` while gamepad is disconnected or hidden by driver's gamepad.
+    visible: bool,
+}
+
+pub struct Gilrs {
+    native: native::Gilrs,
+    api: Option<HidApi>,
+    errors: ErrorQueue,
+    natives: Vec<NativeSlot>,
+    gamepads: Vec<DriverGamepad>,
+    ids: Vec<Source>,
+    /// Paths of devices that failed to open. They are not retried until they disappear.
+    failed: Vec<CString>,
+    events: VecDeque<Event>,
+    next_scan: Instant,
+    poll_interval: Duration,
+    suspended: bool,
+    stopped: bool,
+}
+
+impl Gilrs {
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn new(errors: ErrorQueue) -> Result<Self, PlatformError> {
+        let native = native::Gilrs::new(errors.clone())?;
+        let api = match HidApi::new() {
+            Ok(api) => Some(api),
+            Err(e) => {
+                let description = format!("failed to initialize hidapi: {}", e);
+                errors.push(None, BackendErrorKind::Other, description);
+                None
+            }
+        };
+
+        let mut natives = Vec::new();
+        let mut ids = Vec::new();
+        for id in 0..native.last_gamepad_hint() {
+            let visible = match native.gamepad(id) {
+                Some(gamepad) => gamepad.is_connected(),
+                None => false,
+            };
+            natives.push(NativeSlot {
+                id: Some(ids.len()),
+                visible,
+            });
+            ids.push(Source::Native(id));
+        }
+
+        let mut gilrs = Gilrs {
+            native,
+            api,
+            errors,
+            natives,
+            gamepads: Vec::new(),
+            ids,
+            failed: Vec::new(),
+            events: VecDeque::new(),
+            next_scan: Instant::now(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            suspended: false,
+            stopped: false,
+        };
+        gilrs.scan(false);
+
+        Ok(gilrs)
+    }
+
+    pub(crate) fn next_event(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+
+            match self.native.next_event() {
+                Some(event) => {
+                    if let Some(event) = self.map_native_event(event) {
+                        return Some(event);
+                    }
+                }
+                None => {
+                    self.poll_drivers();
+                    if self.events.is_empty() {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        if self.api.is_none() || self.stopped || self.suspended {
+            return self.native.wait(timeout);
+        }
+
+        self.poll_drivers();
+        if !self.events.is_empty() {
+            return;
+        }
+
+        let slice = if self.gamepads.iter().any(|g| g.is_connected()) {
+            self.poll_interval
+        } else {
+            self.next_scan.saturating_duration_since(Instant::now())
+        };
+        let timeout = match timeout {
+            Some(timeout) => timeout.min(slice),
+            None => slice,
+        };
+        self.native.wait(Some(timeout));
+    }
+
+    /// HID gamepads don't signal native wait handle, so it's not available when drivers are
+    /// used.
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        match self.api {
+            Some(_) => None,
+            None => self.native.raw_wait_handle(),
+        }
+    }
+
+    pub(crate) fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+        self.native.set_poll_interval(interval);
+    }
+
+    pub(crate) fn set_poll_mode(&mut self, mode: PollMode) {
+        self.native.set_poll_mode(mode)
+    }
+
+    pub(crate) fn set_connection_check_interval(&mut self, interval: Duration) {
+        self.native.set_connection_check_interval(interval)
+    }
+
+    pub(crate) fn set_event_queue_capacity(&mut self, capacity: Option<usize>) {
+        self.native.set_event_queue_capacity(capacity)
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.native.stop();
+        if self.stopped {
+            return;
+        }
+
+        self.stopped = true;
+        for did in 0..self.gamepads.len() {
+            if self.gamepads[did].is_connected() {
+                self.disconnect_driver(did);
+            }
+        }
+    }
+
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.native.restart()?;
+        if self.stopped {
+            self.stopped = false;
+            self.failed.clear();
+            self.scan(true);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn is_alive(&self) -> bool {
+        self.native.is_alive()
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        self.native.is_waiting_for_activation()
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        self.native.backend_info()
+    }
+
+    pub(crate) fn suspend(&mut self) {
+        self.native.suspend();
+        self.suspended = true;
+    }
+
+    /// Reports that were queued while suspended are skipped, only difference to their last
+    /// state is reported.
+    pub(crate) fn resume(&mut self) {
+        self.native.resume();
+        if !self.suspended {
+            return;
+        }
+
+        self.suspended = false;
+        self.next_scan = Instant::now();
+        self.poll_gamepads(true);
+    }
+
+    pub(crate) fn refresh(&mut self) {
+        self.native.refresh();
+        self.next_scan = Instant::now();
+    }
+
+    pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
+        match *self.ids.get(id)? {
+            Source::Native(nid) => {
+                let gamepad = self.native.gamepad(nid)?;
+                if self.natives[nid].visible {
+                    Some(gamepad as &Gamepad)
+                } else {
+                    Some(Hidden::new(gamepad) as &Gamepad)
+                }
+            }
+            Source::Driver(did) => Some(&self.gamepads[did] as &Gamepad),
+        }
+    }
+
+    pub fn last_gamepad_hint(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Translates id of native event and filters out events of hidden gamepads.
+    fn map_native_event(&mut self, event: Event) -> Option<Event> {
+        let nid = event.id;
+        if nid >= self.natives.len() {
+            self.natives.resize(
+                nid + 1,
+                NativeSlot {
+                    id: None,
+                    visible: false,
+                },
+            );
+        }
+
+        match event.event {
+            EventType::Connected => {
+                let usb_ids = self.native_usb_ids(nid)?;
+                if Controller::is_supported(usb_ids) {
+                    // Driver may not know about this device yet.
+                    self.scan(true);
+                    if self.claimed(usb_ids) > self.hidden(usb_ids) {
+                        debug!("Native gamepad {} is handled by HID driver.", nid);
+                        return None;
+                    }
+                }
+
+                let id = self.show_native(nid);
+                Some(Event { id, ..event })
+            }
+            EventType::Disconnected => {
+                let slot = &mut self.natives[nid];
+                if !slot.visible {
+                    return None;
+                }
+                slot.visible = false;
+                Some(Event {
+                    id: slot.id?,
+                    ..event
+                })
+            }
+            _ => {
+                let slot = self.natives[nid];
+                if slot.visible {
+                    Some(Event {
+                        id: slot.id?,
+                        ..event
+                    })
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Makes native gamepad visible and returns its id.
+    fn show_native(&mut self, nid: usize) -> usize {
+        let slot = &mut self.natives[nid];
+        slot.visible = true;
+        match slot.id {
+            Some(id) => id,
+            None => {
+                let id = self.ids.len();
+                slot.id = Some(id);
+                self.ids.push(Source::Native(nid));
+                id
+            }
+        }
+    }
+
+    /// Vendor and product ID of connected native gamepad.
+    fn native_usb_ids(&self, nid: usize) -> Option<(u16, u16)> {
+        match self.native.gamepad(nid) {
+            Some(gamepad) if gamepad.is_connected() => Some(usb_ids(gamepad.uuid())),
+            _ => None,
+        }
+    }
+
+    /// Number of connected driver's gamepads with given vendor and product ID.
+    fn claimed(&self, usb_ids: (u16, u16)) -> usize {
+        self.gamepads
+            .iter()
+            .filter(|g| g.is_connected() && g.usb_ids == usb_ids)
+            .count()
+    }
+
+    /// Number of connected native gamepads with given vendor and product ID that are hidden.
+    fn hidden(&self, usb_ids: (u16, u16)) -> usize {
+        (0..self.natives.len())
+            .filter(|&nid| !self.natives[nid].visible && self.native_usb_ids(nid) == Some(usb_ids))
+            .count()
+    }
+
+    /// Looks for new supported devices and opens them.
+    fn scan(&mut self, emit_events: bool) {
+        if self.stopped {
+            return;
+        }
+
+        self.next_scan = Instant::now() + SCAN_INTERVAL;
+        let api = match self.api {
+            Some(ref mut api) => api,
+            None => return,
+        };
+        if let Err(e) = api.refresh_devices() {
+            debug!("Failed to refresh HID devices: {}", e);
+            return;
+        }
+
+        let gamepads = &self.gamepads;
+        let failed = &mut self.failed;
+        failed.retain(|path| api.device_list().any(|info| info.path() == path.as_c_str()));
+        let new: Vec<DeviceInfo> = api
+            .device_list()
+            .filter(|info| {
+                Controller::is_supported((info.vendor_id(), info.product_id()))
+                    && !has_kernel_driver(info)
+            })
+            .filter(|info| {
+                !gamepads
+                    .iter()
+                    .any(|g| g.is_connected() && g.path.as_c_str() == info.path())
+                    && !failed.iter().any(|path| path.as_c_str() == info.path())
+            })
+            .cloned()
+            .collect();
+
+        for info in new {
+            let player = self.gamepads.iter().filter(|g| g.is_connected()).count();
+            let api = self.api.as_ref().unwrap();
+            match Controller::open(api, &info, player) {
+                Ok(controller) => self.connect_driver(&info, controller, emit_events),
+                Err(e) => {
+                    let description = format!(
+                        "failed to open {:?} with HID driver: {}",
+                        info.product_string().unwrap_or(""),
+                        e
+                    );
+                    self.errors
+                        .push(None, BackendErrorKind::DeviceOpen, description);
+                    self.failed.push(info.path().to_owned());
+                }
+            }
+        }
+    }
+
+    fn connect_driver(&mut self, info: &DeviceInfo, controller: Controller, emit_events: bool) {
+        let gamepad = DriverGamepad::new(info, controller);
+        let usb_ids = gamepad.usb_ids;
+        info!("Opened {} with HID driver.", gamepad.name);
+
+        // Reuse slot of the same controller, so it gets its old id.
+        let slot = self
+            .gamepads
+            .iter()
+            .position(|g| !g.is_connected() && g.uuid == gamepad.uuid);
+        let did = match slot {
+            Some(did) => {
+                self.gamepads[did] = gamepad;
+                did
+            }
+            None => {
+                self.gamepads.push(gamepad);
+                self.gamepads.len() - 1
+            }
+        };
+        let id = match self.driver_id(did) {
+            Some(id) => id,
+            None => {
+                self.ids.push(Source::Driver(did));
+                self.ids.len() - 1
+            }
+        };
+        if emit_events {
+            self.events.push_back(Event::new(id, EventType::Connected));
+        }
+
+        // Hide native gamepad that represents the same controller.
+        let native = (0..self.natives.len())
+            .find(|&nid| self.natives[nid].visible && self.native_usb_ids(nid) == Some(usb_ids));
+        if let Some(nid) = native {
+            self.natives[nid].visible = false;
+            if let (true, Some(id)) = (emit_events, self.natives[nid].id) {
+                self.events
+                    .push_back(Event::new(id, EventType::Disconnected));
+            }
+        }
+    }
+
+    fn disconnect_driver(&mut self, did: usize) {
+        let usb_ids = self.gamepads[did].usb_ids;
+        self.gamepads[did].controller = None;
+        if let Some(id) = self.driver_id(did) {
+            self.events
+                .push_back(Event::new(id, EventType::Disconnected));
+        }
+
+        // Native gamepad can be used again.
+        if self.hidden(usb_ids) > self.claimed(usb_ids) {
+            let native = (0..self.natives.len()).find(|&nid| {
+                !self.natives[nid].visible && self.native_usb_ids(nid) == Some(usb_ids)
+            });
+            if let Some(nid) = native {
+                let id = self.show_native(nid);
+                self.events.push_back(Event::new(id, EventType::Connected));
+            }
+        }
+    }
+
+    fn driver_id(&self, did: usize) -> Option<usize> {
+        self.ids.iter().position(|source| match *source {
+            Source::Driver(d) => d == did,
+            Source::Native(_) => false,
+        })
+    }
+
+    fn poll_drivers(&mut self) {
+        if self.stopped || self.suspended {
+            return;
+        }
+
+        if Instant::now() >= self.next_scan {
+            self.scan(true);
+        }
+        self.poll_gamepads(false);
+    }
+
+    /// Reads reports of all connected gamepads. If `coalesce` is `true`, only difference between
+    /// last reported and current state is reported.
+    fn poll_gamepads(&mut self, coalesce: bool) {
+        for did in 0..self.gamepads.len() {
+            if !self.gamepads[did].is_connected() {
+                continue;
+            }
+
+            let id = match self.driver_id(did) {
+                Some(id) => id,
+                None => continue,
+            };
+            if let Err(e) = self.gamepads[did].poll(id, coalesce, &mut self.events) {
+                debug!("Failed to read {}: {}", self.gamepads[did].name, e);
+                self.disconnect_driver(did);
+            }
+        }
+    }
+}
+
+impl Debug for Gilrs {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("Gilrs")
+            .field("native", &self.native)
+            .field("gamepads", &self.gamepads)
+            .field("ids", &self.ids)
+            .finish()
+    }
+}
+
+/// Controller supported by one of the drivers.
+#[derive(Debug)]
+enum Controller {
+    Switch(switch::Controller),
+    Sony(sony::Controller),
+}
+
+impl Controller {
+    fn is_supported((vendor_id, product_id): (u16, u16)) -> bool {
+        switch::is_supported(vendor_id, product_id) || sony::is_supported(vendor_id, product_id)
+    }
+
+    fn open(api: &HidApi, info: &DeviceInfo, player: usize) -> Result<Self, HidError> {
+        if info.vendor_id() == sony::VENDOR_ID {
+            sony::Controller::open(api, info, player).map(Controller::Sony)
+        } else {
+            switch::Controller::open(api, info, player).map(Controller::Switch)
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            Controller::Switch(ref c) => c.name(),
+            Controller::Sony(ref c) => c.name(),
+        }
+    }
+
+    fn buttons(&self) -> Vec<EvCode> {
+        match *self {
+            Controller::Switch(ref c) => c.buttons(),
+            Controller::Sony(ref c) => c.buttons(),
+        }
+    }
+
+    fn axes(&self) -> Vec<EvCode> {
+        match *self {
+            Controller::Switch(ref c) => c.axes(),
+            Controller::Sony(ref c) => c.axes(),
+        }
+    }
+
+    fn touch_points(&self) -> usize {
+        match *self {
+            Controller::Switch(_) => 0,
+            Controller::Sony(ref c) => c.touch_points(),
+        }
+    }
+
+    fn read(&mut self, state: &mut State) -> Result<bool, HidError> {
+        match *self {
+            Controller::Switch(ref mut c) => c.read(state),
+            Controller::Sony(ref mut c) => c.read(state),
+        }
+    }
+
+    fn ff_device(&self) -> FfDevice {
+        match *self {
+            Controller::Switch(ref c) => FfDevice::new(c.ff_device()),
+            Controller::Sony(ref c) => FfDevice::new(c.ff_device()),
+        }
+    }
+
+    fn sony(&self) -> Option<&sony::Controller> {
+        match *self {
+            Controller::Sony(ref c) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+/// State decoded from input report. `pressed` and `values` have the same order as gamepad's
+/// buttons and axes.
+#[derive(Clone, Debug)]
+pub struct State {
+    pub pressed: Vec<bool>,
+    pub values: Vec<i32>,
+    /// Set for every report that has motion data.
+    pub motion: Option<MotionData>,
+    /// Last state of every touch point.
+    pub touch: Vec<TouchData>,
+    pub power: PowerInfo,
+}
+
+#[derive(Debug)]
+struct DriverGamepad {
+    name: &'static str,
+    uuid: Uuid,
+    usb_ids: (u16, u16),
+    path: CString,
+    buttons: Vec<EvCode>,
+    axes: Vec<EvCode>,
+    state: State,
+    /// `None` when disconnected.
+    controller: Option<Controller>,
+}
+
+impl DriverGamepad {
+    fn new(info: &DeviceInfo, controller: Controller) -> Self {
+        let buttons = controller.buttons();
+        let axes = controller.axes();
+        let state = State {
+            pressed: vec![false; buttons.len()],
+            values: vec![0; axes.len()],
+            motion: None,
+            touch: (0..controller.touch_points())
+                .map(|finger| TouchData {
+                    finger: finger as u8,
+                    ..TouchData::default()
+                })
+                .collect(),
+            power: PowerInfo::Unknown,
+        };
+
+        DriverGamepad {
+            name: controller.name(),
+            uuid: create_uuid(info),
+            usb_ids: (info.vendor_id(), info.product_id()),
+            path: info.path().to_owned(),
+            buttons,
+            axes,
+            state,
+            controller: Some(controller),
+        }
+    }
+
+    fn sony(&self) -> io::Result<&sony::Controller> {
+        match self.controller {
+            Some(ref controller) => controller.sony().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Other, "not supported by this controller")
+            }),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "gamepad is disconnected",
+            )),
+        }
+    }
+
+    fn poll(
+        &mut self,
+        id: usize,
+        coalesce: bool,
+        events: &mut VecDeque<Event>,
+    ) -> Result<(), HidError> {
+        let controller = match self.controller {
+            Some(ref mut controller) => controller,
+            None => return Ok(()),
+        };
+
+        let mut next = self.state.clone();
+        let mut changed = false;
+        while controller.read(&mut next)? {
+            changed = true;
+            if !coalesce {
+                emit_changes(
+                    id,
+                    &self.buttons,
+                    &self.axes,
+                    &self.state,
+                    &mut next,
+                    events,
+                );
+                self.state = next.clone();
+            }
+        }
+
+        if changed && coalesce {
+            next.motion = None;
+            emit_changes(
+                id,
+                &self.buttons,
+                &self.axes,
+                &self.state,
+                &mut next,
+                events,
+            );
+            self.state = next;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares `next` with `prev` and reports differences.
+fn emit_changes(
+    id: usize,
+    buttons: &[EvCode],
+    axes: &[EvCode],
+    prev: &State,
+    next: &mut State,
+    events: &mut VecDeque<Event>,
+) {
+    for (idx, &code) in buttons.iter().enumerate() {
+        if prev.pressed[idx] != next.pressed[idx] {
+            let code = crate::EvCode(code);
+            let ev = if next.pressed[idx] {
+                EventType::ButtonPressed(code)
+            } else {
+                EventType::ButtonReleased(code)
+            };
+            events.push_back(Event::new(id, ev));
+        }
+    }
+
+    for (idx, &code) in axes.iter().enumerate() {
+        if prev.values[idx] != next.values[idx] {
+            let ev = EventType::AxisValueChanged(next.values[idx], crate::EvCode(code));
+            events.push_back(Event::new(id, ev));
+        }
+    }
+
+    for (prev, next) in prev.touch.iter().zip(&next.touch) {
+        let moved = next.touching && (prev.x != next.x || prev.y != next.y);
+        if prev.touching != next.touching || moved {
+            events.push_back(Event::new(id, EventType::Touch(*next)));
+        }
+    }
+
+    if let Some(motion) = next.motion.take() {
+        events.push_back(Event::new(id, EventType::Motion(motion)));
+    }
+}
+
+impl BackendGamepad for DriverGamepad {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn uuid(&self) -> [u8; 16] {
+        *self.uuid.as_bytes()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.controller.is_some()
+    }
+
+    fn power_info(&self) -> PowerInfo {
+        self.state.power
+    }
+
+    fn is_ff_supported(&self) -> bool {
+        true
+    }
+
+    /// Drivers report positive Y for up.
+    fn is_y_axis_reversed(&self) -> bool {
+        false
+    }
+
+    fn ff_device(&self) -> Option<FfDevice> {
+        self.controller.as_ref().map(Controller::ff_device)
+    }
+
+    fn is_exclusive_supported(&self) -> bool {
+        false
+    }
+
+    fn set_exclusive(&self, _exclusive: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "exclusive access is not supported by HID drivers",
+        ))
+    }
+
+    fn has_mic_led(&self) -> bool {
+        match self.sony() {
+            Ok(c) => c.has_mic_led(),
+            Err(_) => false,
+        }
+    }
+
+    fn set_mic_led(&self, on: bool) -> io::Result<()> {
+        self.sony()?.set_mic_led(on).map_err(to_io_error)
+    }
+
+    fn buttons(&self) -> &[crate::EvCode] {
+        crate::EvCode::from_native(&self.buttons)
+    }
+
+    fn axes(&self) -> &[crate::EvCode] {
+        crate::EvCode::from_native(&self.axes)
+    }
+
+    fn has_lightbar(&self) -> bool {
+        self.sony().is_ok()
+    }
+
+    fn set_lightbar(&self, color: [u8; 3]) -> io::Result<()> {
+        self.sony()?.set_lightbar(color).map_err(to_io_error)
+    }
+
+    fn has_adaptive_triggers(&self) -> bool {
+        match self.sony() {
+            Ok(c) => c.has_adaptive_triggers(),
+            Err(_) => false,
+        }
+    }
+
+    fn set_trigger_effect(&self, trigger: Trigger, effect: [u8; 11]) -> io::Result<()> {
+        let controller = self.sony()?;
+        if !controller.has_adaptive_triggers() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "gamepad doesn't have adaptive triggers",
+            ));
+        }
+        controller
+            .set_trigger_effect(trigger, effect)
+            .map_err(to_io_error)
+    }
+
+    fn axis_info(&self, nec: crate::EvCode) -> Option<&AxisInfo> {
+        let nec = nec.0;
+        if !self.axes.contains(&nec) {
+            None
+        } else if nec == native_ev_codes::AXIS_LT2 || nec == native_ev_codes::AXIS_RT2 {
+            Some(&TRIGGER_INFO)
+        } else {
+            Some(&STICK_INFO)
+        }
+    }
+}
+
+/// Native gamepad that is reported as disconnected.
+#[derive(Debug)]
+#[repr(transparent)]
+struct Hidden(native::Gamepad);
+
+impl Hidden {
+    fn new(gamepad: &native::Gamepad) -> &Hidden {
+        unsafe { &*(gamepad as *const native::Gamepad as *const Hidden) }
+    }
+}
+
+/// Features that are not forwarded to native gamepad use default implementation.
+impl BackendGamepad for Hidden {
+    fn name(&self) -> &str {
+        BackendGamepad::name(&self.0)
+    }
+
+    fn uuid(&self) -> [u8; 16] {
+        BackendGamepad::uuid(&self.0)
+    }
+
+    fn is_connected(&self) -> bool {
+        false
+    }
+
+    fn buttons(&self) -> &[crate::EvCode] {
+        BackendGamepad::buttons(&self.0)
+    }
+
+    fn axes(&self) -> &[crate::EvCode] {
+        BackendGamepad::axes(&self.0)
+    }
+
+    fn axis_info(&self, nec: crate::EvCode) -> Option<&AxisInfo> {
+        BackendGamepad::axis_info(&self.0, nec)
+    }
+
+    fn is_y_axis_reversed(&self) -> bool {
+        BackendGamepad::is_y_axis_reversed(&self.0)
+    }
+
+    fn set_exclusive(&self, exclusive: bool) -> io::Result<()> {
+        BackendGamepad::set_exclusive(&self.0, exclusive)
+    }
+
+    fn set_mic_led(&self, on: bool) -> io::Result<()> {
+        BackendGamepad::set_mic_led(&self.0, on)
+    }
+}
+
+pub(super) fn to_io_error(e: HidError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+/// Vendor and product ID stored in SDL compatible UUID.
+fn usb_ids(uuid: Uuid) -> (u16, u16) {
+    let bytes = uuid.as_bytes();
+    (
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[8], bytes[9]]),
+    )
+}
+
+/// Creates UUID in format used by SDL's HIDAPI drivers. They have no entries in SDL mapping
+/// database, so default mapping is used.
+fn create_uuid(info: &DeviceInfo) -> Uuid {
+    let bus: u16 = match info.bus_type() {
+        BusType::Bluetooth => 0x05,
+        _ => 0x03,
+    };
+    let vendor = info.vendor_id().to_le_bytes();
+    let product = info.product_id().to_le_bytes();
+    let bus = bus.to_le_bytes();
+
+    Uuid::from_bytes([
+        bus[0], bus[1], 0, 0, vendor[0], vendor[1], 0, 0, product[0], product[1], 0, 0, 0, 0, b'h',
+        0,
+    ])
+}
+
+/// Returns `true` if hidraw device is bound to other kernel driver than `hid-generic`.
+#[cfg(target_os = "linux")]
+fn has_kernel_driver(info: &DeviceInfo) -> bool {
+    use std::fs;
+    use std::path::Path;
+
+    let path = match info.path().to_str() {
+        Ok(path) => Path::new(path),
+        Err(_) => return false,
+    };
+    let name = match path.file_name() {
+        Some(name) => name,
+        None => return false,
+    };
+
+    let driver = Path::new("/sys/class/hidraw")
+        .join(name)
+        .join("device/driver");
+    match fs::read_link(driver) {
+        Ok(link) => match link.file_name() {
+            Some(driver) => driver != "hid-generic",
+            None => false,
+        },
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn has_kernel_driver(_info: &DeviceInfo) -> bool {
+    false
+}
+
+const STICK_INFO: AxisInfo = AxisInfo {
+    min: -AXIS_MAX,
+    max: AXIS_MAX,
+    deadzone: None,
+};
+
+const TRIGGER_INFO: AxisInfo = AxisInfo {
+    min: 0,
+    max: AXIS_MAX,
+    deadzone: None,
+};
@@ -0,0 +1,631 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Sony DualShock 4 and DualSense.
+//!
+//! Report formats follow Linux's `hid-playstation` driver. Over Bluetooth controllers send
+//! reduced reports until calibration feature report is read, after that extended reports with
+//! motion sensors and touchpad are used, the same as over USB.
+
+use super::{native_ev_codes, to_io_error, EvCode, State};
+use crate::utils;
+use crate::BackendFfDevice;
+use crate::{MotionData, PowerInfo, TouchData, Trigger};
+
+use hidapi::{BusType, DeviceInfo, HidApi, HidDevice, HidError};
+
+use std::f32::consts::PI;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const VENDOR_ID: u16 = 0x054c;
+const PRODUCT_DS4: u16 = 0x05c4;
+const PRODUCT_DS4_V2: u16 = 0x09cc;
+const PRODUCT_DS4_DONGLE: u16 = 0x0ba0;
+const PRODUCT_DUALSENSE: u16 = 0x0ce6;
+const PRODUCT_DUALSENSE_EDGE: u16 = 0x0df2;
+
+const DS4_REPORT_USB: u8 = 0x01;
+const DS4_REPORT_BT: u8 = 0x11;
+const DS4_FEATURE_CALIBRATION_USB: u8 = 0x02;
+const DS4_FEATURE_CALIBRATION_BT: u8 = 0x05;
+const DS4_OUTPUT_USB: u8 = 0x05;
+const DS4_OUTPUT_BT: u8 = 0x11;
+
+const DS_REPORT_USB: u8 = 0x01;
+const DS_REPORT_BT: u8 = 0x31;
+const DS_FEATURE_CALIBRATION: u8 = 0x05;
+const DS_FEATURE_FIRMWARE: u8 = 0x20;
+const DS_OUTPUT_USB: u8 = 0x02;
+const DS_OUTPUT_BT: u8 = 0x31;
+/// Firmware that supports improved rumble emulation.
+const DS_VIBRATION_V2_VERSION: u16 = 0x0224;
+
+/// Seed of CRC32 of Bluetooth output reports.
+const BT_OUTPUT_CRC_SEED: u8 = 0xa2;
+const BT_REPORT_LEN: usize = 78;
+
+const AXIS_MAX: i32 = 32767;
+const STANDARD_GRAVITY: f32 = 9.806_65;
+const TOUCHPAD_WIDTH: f32 = 1920.0;
+
+/// Lightbar colors used by PlayStation for players 1-4.
+const PLAYER_COLORS: [[u8; 3]; 4] = [[0, 0, 64], [64, 0, 0], [0, 64, 0], [32, 0, 32]];
+/// Player LEDs of DualSense for players 1-4.
+const PLAYER_LEDS: [u8; 4] = [0x04, 0x0a, 0x15, 0x1b];
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Kind {
+    DualShock4,
+    DualSense,
+    DualSenseEdge,
+}
+
+/// Byte of buttons (relative to first one), bit mask and code. D-pad is reported as hat in low
+/// nibble of first byte.
+const BUTTONS: [(usize, u8, EvCode); 12] = {
+    use self::native_ev_codes::*;
+
+    [
+        (0, 0x10, BTN_WEST),
+        (0, 0x20, BTN_SOUTH),
+        (0, 0x40, BTN_EAST),
+        (0, 0x80, BTN_NORTH),
+        (1, 0x01, BTN_LT),
+        (1, 0x02, BTN_RT),
+        (1, 0x10, BTN_SELECT),
+        (1, 0x20, BTN_START),
+        (1, 0x40, BTN_LTHUMB),
+        (1, 0x80, BTN_RTHUMB),
+        (2, 0x01, BTN_MODE),
+        (2, 0x02, BTN_TOUCHPAD),
+    ]
+};
+
+/// Microphone button of DualSense.
+const BUTTON_MIC: (usize, u8, EvCode) = (2, 0x04, native_ev_codes::BTN_MISC1);
+
+const DPAD: [EvCode; 4] = {
+    use self::native_ev_codes::*;
+
+    [BTN_DPAD_UP, BTN_DPAD_RIGHT, BTN_DPAD_DOWN, BTN_DPAD_LEFT]
+};
+
+/// Sticks, then L2 and R2.
+const AXES: [EvCode; 6] = {
+    use self::native_ev_codes::*;
+
+    [
+        AXIS_LSTICKX,
+        AXIS_LSTICKY,
+        AXIS_RSTICKX,
+        AXIS_RSTICKY,
+        AXIS_LT2,
+        AXIS_RT2,
+    ]
+};
+
+/// Offsets of fields in input report, relative to start of common part.
+#[derive(Copy, Clone, Debug)]
+struct Layout {
+    triggers: usize,
+    buttons: usize,
+    gyro: usize,
+    accel: usize,
+    touch: usize,
+    status: usize,
+    touchpad_height: f32,
+}
+
+const DS4_LAYOUT: Layout = Layout {
+    triggers: 7,
+    buttons: 4,
+    gyro: 12,
+    accel: 18,
+    // After number of touch reports and timestamp of the first one.
+    touch: 34,
+    status: 29,
+    touchpad_height: 943.0,
+};
+
+const DS_LAYOUT: Layout = Layout {
+    triggers: 4,
+    buttons: 7,
+    gyro: 15,
+    accel: 21,
+    touch: 32,
+    status: 52,
+    touchpad_height: 1080.0,
+};
+
+pub fn is_supported(vendor_id: u16, product_id: u16) -> bool {
+    vendor_id == VENDOR_ID
+        && (product_id == PRODUCT_DS4
+            || product_id == PRODUCT_DS4_V2
+            || product_id == PRODUCT_DS4_DONGLE
+            || product_id == PRODUCT_DUALSENSE
+            || product_id == PRODUCT_DUALSENSE_EDGE)
+}
+
+#[derive(Debug)]
+pub struct Controller {
+    output: Arc<Mutex<Output>>,
+    kind: Kind,
+    bluetooth: bool,
+    buttons: Vec<(usize, u8, EvCode)>,
+    imu: ImuCalibration,
+}
+
+impl Controller {
+    /// Initializes controller and reads its calibration. `player` (0-3) selects color of lightbar
+    /// and player LEDs.
+    pub fn open(api: &HidApi, info: &DeviceInfo, player: usize) -> Result<Self, HidError> {
+        let kind = match info.product_id() {
+            PRODUCT_DUALSENSE => Kind::DualSense,
+            PRODUCT_DUALSENSE_EDGE => Kind::DualSenseEdge,
+            _ => Kind::DualShock4,
+        };
+        let bluetooth = match info.bus_type() {
+            BusType::Bluetooth => true,
+            _ => false,
+        };
+        // Wireless adapter is connected over USB, but reports calibration like Bluetooth.
+        let dongle = info.product_id() == PRODUCT_DS4_DONGLE;
+
+        let device = api.open_path(info.path())?;
+        let imu = read_imu_calibration(&device, kind, bluetooth, dongle);
+        let vibration_v2 = kind != Kind::DualShock4
+            && firmware_version(&device)
+                .map(|version| version >= DS_VIBRATION_V2_VERSION)
+                .unwrap_or(false);
+
+        let mut output = Output {
+            device,
+            kind,
+            bluetooth,
+            sequence: 0,
+            vibration_v2,
+            rumble: [0; 2],
+            lightbar: PLAYER_COLORS[player % 4],
+            player_leds: PLAYER_LEDS[player % 4],
+            mic_led: false,
+            triggers: [None; 2],
+            release_lightbar: true,
+        };
+        output.send()?;
+        output.device.set_blocking_mode(false)?;
+
+        let mut buttons = BUTTONS.to_vec();
+        if kind != Kind::DualShock4 {
+            buttons.push(BUTTON_MIC);
+        }
+
+        Ok(Controller {
+            output: Arc::new(Mutex::new(output)),
+            kind,
+            bluetooth,
+            buttons,
+            imu,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            Kind::DualShock4 => "Sony DualShock 4",
+            Kind::DualSense => "Sony DualSense",
+            Kind::DualSenseEdge => "Sony DualSense Edge",
+        }
+    }
+
+    pub fn buttons(&self) -> Vec<EvCode> {
+        self.buttons
+            .iter()
+            .map(|&(_, _, code)| code)
+            .chain(DPAD.iter().cloned())
+            .collect()
+    }
+
+    pub fn axes(&self) -> Vec<EvCode> {
+        AXES.to_vec()
+    }
+
+    pub fn touch_points(&self) -> usize {
+        2
+    }
+
+    /// Reads one pending input report. Returns `false` if there are none.
+    pub fn read(&mut self, state: &mut State) -> Result<bool, HidError> {
+        let mut buf = [0u8; BT_REPORT_LEN];
+        let offset = loop {
+            let len = self.output.lock().unwrap().device.read(&mut buf)?;
+            if len == 0 {
+                return Ok(false);
+            }
+            // Reduced Bluetooth reports have the same ID as USB reports, but are shorter.
+            let offset = match (self.kind, self.bluetooth, buf[0]) {
+                (Kind::DualShock4, false, DS4_REPORT_USB) if len >= 64 => 1,
+                (Kind::DualShock4, true, DS4_REPORT_BT) if len >= BT_REPORT_LEN => 3,
+                (_, false, DS_REPORT_USB) if len >= 64 => 1,
+                (_, true, DS_REPORT_BT) if len >= BT_REPORT_LEN => 2,
+                _ => continue,
+            };
+            break offset;
+        };
+
+        let layout = if self.kind == Kind::DualShock4 {
+            DS4_LAYOUT
+        } else {
+            DS_LAYOUT
+        };
+        let data = &buf[offset..];
+
+        let buttons = &data[layout.buttons..layout.buttons + 3];
+        for (idx, &(byte, mask, _)) in self.buttons.iter().enumerate() {
+            state.pressed[idx] = buttons[byte] & mask != 0;
+        }
+        // Hat with 8 positions, 8 is centered.
+        let (x, y) = utils::hat_to_axes(i32::from(buttons[0] & 0x0f), 0, 7);
+        let dpad = [y < 0, x > 0, y > 0, x < 0];
+        let first_dpad = self.buttons.len();
+        state.pressed[first_dpad..first_dpad + 4].copy_from_slice(&dpad);
+
+        state.values[0] = stick(data[0]);
+        state.values[1] = -stick(data[1]);
+        state.values[2] = stick(data[2]);
+        state.values[3] = -stick(data[3]);
+        state.values[4] = trigger(data[layout.triggers]);
+        state.values[5] = trigger(data[layout.triggers + 1]);
+
+        for (i, touch) in state.touch.iter_mut().enumerate() {
+            let point = &data[layout.touch + i * 4..layout.touch + i * 4 + 4];
+            let x = u16::from(point[1]) | (u16::from(point[2] & 0x0f) << 8);
+            let y = (u16::from(point[2]) >> 4) | (u16::from(point[3]) << 4);
+            *touch = TouchData {
+                finger: i as u8,
+                touching: point[0] & 0x80 == 0,
+                x: (f32::from(x) / TOUCHPAD_WIDTH).min(1.0),
+                y: (f32::from(y) / layout.touchpad_height).min(1.0),
+            };
+        }
+
+        state.motion = Some(self.imu.motion(&data[layout.gyro..], &data[layout.accel..]));
+        state.power = if self.kind == Kind::DualShock4 {
+            ds4_power_info(data[layout.status])
+        } else {
+            dualsense_power_info(data[layout.status])
+        };
+
+        Ok(true)
+    }
+
+    pub fn ff_device(&self) -> FfDevice {
+        FfDevice {
+            output: self.output.clone(),
+        }
+    }
+
+    pub fn set_lightbar(&self, color: [u8; 3]) -> Result<(), HidError> {
+        let mut output = self.output.lock().unwrap();
+        output.lightbar = color;
+        output.send()
+    }
+
+    pub fn has_mic_led(&self) -> bool {
+        self.kind != Kind::DualShock4
+    }
+
+    pub fn set_mic_led(&self, on: bool) -> Result<(), HidError> {
+        let mut output = self.output.lock().unwrap();
+        output.mic_led = on;
+        output.send()
+    }
+
+    pub fn has_adaptive_triggers(&self) -> bool {
+        self.kind != Kind::DualShock4
+    }
+
+    pub fn set_trigger_effect(&self, trigger: Trigger, effect: [u8; 11]) -> Result<(), HidError> {
+        let mut output = self.output.lock().unwrap();
+        let idx = match trigger {
+            Trigger::Left => 0,
+            Trigger::Right => 1,
+        };
+        output.triggers[idx] = Some(effect);
+        output.send()
+    }
+}
+
+fn stick(raw: u8) -> i32 {
+    ((i32::from(raw) - 128) * AXIS_MAX / 127).max(-AXIS_MAX)
+}
+
+fn trigger(raw: u8) -> i32 {
+    i32::from(raw) * AXIS_MAX / 255
+}
+
+/// State of output report, shared with force feedback thread. Every report contains all of it.
+#[derive(Debug)]
+struct Output {
+    device: HidDevice,
+    kind: Kind,
+    bluetooth: bool,
+    /// Sequence number of DualSense Bluetooth reports.
+    sequence: u8,
+    vibration_v2: bool,
+    /// Strong (left) and weak (right) motor.
+    rumble: [u8; 2],
+    lightbar: [u8; 3],
+    player_leds: u8,
+    mic_led: bool,
+    /// Effects of left and right trigger waiting to be sent.
+    triggers: [Option<[u8; 11]>; 2],
+    /// DualSense keeps lightbar for itself until it's released once.
+    release_lightbar: bool,
+}
+
+impl Output {
+    fn send(&mut self) -> Result<(), HidError> {
+        let mut report = [0u8; BT_REPORT_LEN];
+        let len = match self.kind {
+            Kind::DualShock4 => self.ds4_report(&mut report),
+            _ => self.dualsense_report(&mut report),
+        };
+
+        if self.bluetooth {
+            let crc = crc32(BT_OUTPUT_CRC_SEED, &report[..BT_REPORT_LEN - 4]);
+            report[BT_REPORT_LEN - 4..].copy_from_slice(&crc.to_le_bytes());
+        }
+
+        self.device.write(&report[..len]).map(|_| ())
+    }
+
+    fn ds4_report(&mut self, report: &mut [u8; BT_REPORT_LEN]) -> usize {
+        let (common, len) = if self.bluetooth {
+            report[0] = DS4_OUTPUT_BT;
+            // Enables HID and CRC, 4 ms report interval.
+            report[1] = 0xc4;
+            (&mut report[3..], BT_REPORT_LEN)
+        } else {
+            report[0] = DS4_OUTPUT_USB;
+            (&mut report[1..], 32)
+        };
+
+        // Rumble and lightbar.
+        common[0] = 0x03;
+        common[3] = self.rumble[1];
+        common[4] = self.rumble[0];
+        common[5..8].copy_from_slice(&self.lightbar);
+
+        len
+    }
+
+    fn dualsense_report(&mut self, report: &mut [u8; BT_REPORT_LEN]) -> usize {
+        let (common, len) = if self.bluetooth {
+            report[0] = DS_OUTPUT_BT;
+            report[1] = self.sequence << 4;
+            report[2] = 0x10;
+            self.sequence = (self.sequence + 1) & 0x0f;
+            (&mut report[3..], BT_REPORT_LEN)
+        } else {
+            report[0] = DS_OUTPUT_USB;
+            (&mut report[1..], 63)
+        };
+
+        // Haptics in rumble emulation mode.
+        common[0] = 0x02;
+        if self.vibration_v2 {
+            common[38] |= 0x04;
+        } else {
+            common[0] |= 0x01;
+        }
+        common[2] = self.rumble[1];
+        common[3] = self.rumble[0];
+
+        // Mic LED, lightbar and player LEDs.
+        common[1] = 0x01 | 0x04 | 0x10;
+        common[8] = self.mic_led as u8;
+        common[43] = self.player_leds;
+        common[44..47].copy_from_slice(&self.lightbar);
+        if self.release_lightbar {
+            common[38] |= 0x02;
+            common[41] = 0x02;
+            self.release_lightbar = false;
+        }
+
+        // Trigger effects are sent only once, so running effects don't restart.
+        if let Some(effect) = self.triggers[1].take() {
+            common[0] |= 0x04;
+            common[10..21].copy_from_slice(&effect);
+        }
+        if let Some(effect) = self.triggers[0].take() {
+            common[0] |= 0x08;
+            common[21..32].copy_from_slice(&effect);
+        }
+
+        len
+    }
+}
+
+/// CRC32 (the one used by zlib) of `seed` followed by `data`.
+fn crc32(seed: u8, data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in [seed].iter().chain(data) {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Update version of DualSense firmware.
+fn firmware_version(device: &HidDevice) -> Option<u16> {
+    let mut buf = [0u8; 64];
+    buf[0] = DS_FEATURE_FIRMWARE;
+    match device.get_feature_report(&mut buf) {
+        Ok(len) if len >= 46 => Some(u16::from_le_bytes([buf[44], buf[45]])),
+        _ => None,
+    }
+}
+
+/// Coefficients that convert raw IMU values to m/s² and rad/s.
+#[derive(Copy, Clone, Debug)]
+struct ImuCalibration {
+    gyro_bias: [f32; 3],
+    gyro_scale: [f32; 3],
+    accel_bias: [f32; 3],
+    accel_scale: [f32; 3],
+}
+
+impl ImuCalibration {
+    /// Parses calibration feature report. Gyroscope ranges are stored as three maximums followed
+    /// by three minimums if `grouped`, otherwise maximum and minimum of each axis are next to each
+    /// other.
+    fn new(buf: &[u8], grouped: bool, use_gyro_bias: bool) -> Option<Self> {
+        let value = |i: usize| f32::from(i16::from_le_bytes([buf[i], buf[i + 1]]));
+        let speed = value(19) + value(21);
+        let mut calibration = ImuCalibration::default();
+
+        for axis in 0..3 {
+            let bias = if use_gyro_bias {
+                value(1 + axis * 2)
+            } else {
+                0.0
+            };
+            let (plus, minus) = if grouped {
+                (value(7 + axis * 2), value(13 + axis * 2))
+            } else {
+                (value(7 + axis * 4), value(9 + axis * 4))
+            };
+            let range = (plus - bias).abs() + (minus - bias).abs();
+            if range == 0.0 || speed == 0.0 {
+                return None;
+            }
+            calibration.gyro_bias[axis] = bias;
+            calibration.gyro_scale[axis] = speed / range * PI / 180.0;
+
+            let accel_plus = value(23 + axis * 4);
+            let accel_minus = value(25 + axis * 4);
+            let accel_range = accel_plus - accel_minus;
+            if accel_range == 0.0 {
+                return None;
+            }
+            calibration.accel_bias[axis] = accel_plus - accel_range / 2.0;
+            calibration.accel_scale[axis] = 2.0 / accel_range * STANDARD_GRAVITY;
+        }
+
+        Some(calibration)
+    }
+
+    /// Axes of both controllers already follow SDL's convention.
+    fn motion(&self, gyro: &[u8], accel: &[u8]) -> MotionData {
+        let raw = |data: &[u8], axis: usize| {
+            f32::from(i16::from_le_bytes([data[axis * 2], data[axis * 2 + 1]]))
+        };
+
+        let mut motion = MotionData::default();
+        for axis in 0..3 {
+            motion.gyro[axis] = (raw(gyro, axis) - self.gyro_bias[axis]) * self.gyro_scale[axis];
+            motion.accel[axis] =
+                (raw(accel, axis) - self.accel_bias[axis]) * self.accel_scale[axis];
+        }
+        motion
+    }
+}
+
+impl Default for ImuCalibration {
+    /// Nominal values of 16 per °/s and 8192 per G.
+    fn default() -> Self {
+        ImuCalibration {
+            gyro_bias: [0.0; 3],
+            gyro_scale: [PI / 180.0 / 16.0; 3],
+            accel_bias: [0.0; 3],
+            accel_scale: [STANDARD_GRAVITY / 8192.0; 3],
+        }
+    }
+}
+
+/// Reading calibration also switches Bluetooth controllers to extended reports.
+fn read_imu_calibration(
+    device: &HidDevice,
+    kind: Kind,
+    bluetooth: bool,
+    dongle: bool,
+) -> ImuCalibration {
+    let id = if kind != Kind::DualShock4 {
+        DS_FEATURE_CALIBRATION
+    } else if bluetooth {
+        DS4_FEATURE_CALIBRATION_BT
+    } else {
+        DS4_FEATURE_CALIBRATION_USB
+    };
+    let mut buf = [0u8; 41];
+    buf[0] = id;
+
+    let calibration = match device.get_feature_report(&mut buf) {
+        Ok(len) if len >= 35 => {
+            let grouped = kind == Kind::DualShock4 && !bluetooth && !dongle;
+            // DualSense applies gyroscope bias itself.
+            ImuCalibration::new(&buf, grouped, kind == Kind::DualShock4)
+        }
+        Ok(_) => None,
+        Err(e) => {
+            debug!("Failed to read calibration of Sony controller: {}", e);
+            None
+        }
+    };
+
+    calibration.unwrap_or_else(|| {
+        debug!("Sony controller has invalid IMU calibration, using defaults.");
+        ImuCalibration::default()
+    })
+}
+
+/// Decodes status byte of DualShock 4. Battery level is 0-10 on battery and 0-11 on cable.
+fn ds4_power_info(byte: u8) -> PowerInfo {
+    let level = byte & 0x0f;
+    let cable = byte & 0x10 != 0;
+    match (cable, level) {
+        (true, level) if level >= 10 => PowerInfo::Charged,
+        (true, level) => PowerInfo::Charging(level * 10 + 5),
+        (false, level) => PowerInfo::Discharging((level * 10 + 5).min(100)),
+    }
+}
+
+/// Decodes status byte of DualSense. Low nibble is battery level (0-10), high nibble is charging
+/// state.
+fn dualsense_power_info(byte: u8) -> PowerInfo {
+    let level = ((byte & 0x0f) * 10 + 5).min(100);
+    match byte >> 4 {
+        0x0 => PowerInfo::Discharging(level),
+        0x1 => PowerInfo::Charging(level),
+        0x2 => PowerInfo::Charged,
+        _ => PowerInfo::Unknown,
+    }
+}
+
+/// Strong magnitude drives left (heavier) motor and weak magnitude right motor.
+#[derive(Debug)]
+pub struct FfDevice {
+    output: Arc<Mutex<Output>>,
+}
+
+impl BackendFfDevice for FfDevice {
+    fn set_ff_state(&mut self, strong: u16, weak: u16, _min_duration: Duration) -> io::Result<()> {
+        let mut output = self.output.lock().unwrap();
+        output.rumble = [(strong >> 8) as u8, (weak >> 8) as u8];
+        output.send().map_err(|e| {
+            error!("Failed to send rumble to Sony controller: {}", e);
+            to_io_error(e)
+        })
+    }
+}
@@ -0,0 +1,576 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Nintendo Switch Pro Controller and Joy-Cons.
+//!
+//! Protocol is described in
+//! <https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering>. Controllers are switched to
+//! full input report mode (0x30), which contains buttons, sticks and three IMU samples.
+
+use super::{native_ev_codes, to_io_error, EvCode, State};
+use crate::BackendFfDevice;
+use crate::{MotionData, PowerInfo};
+
+use hidapi::{BusType, DeviceInfo, HidApi, HidDevice, HidError};
+
+use std::f32::consts::PI;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub const VENDOR_ID: u16 = 0x057e;
+const PRODUCT_JOYCON_L: u16 = 0x2006;
+const PRODUCT_JOYCON_R: u16 = 0x2007;
+const PRODUCT_PRO: u16 = 0x2009;
+
+const REPORT_STATE: u8 = 0x30;
+const REPORT_REPLY: u8 = 0x21;
+const REPORT_USB_REPLY: u8 = 0x81;
+const OUTPUT_SUBCOMMAND: u8 = 0x01;
+const OUTPUT_RUMBLE: u8 = 0x10;
+const OUTPUT_USB: u8 = 0x80;
+
+const SUBCMD_SET_REPORT_MODE: u8 = 0x03;
+const SUBCMD_SPI_READ: u8 = 0x10;
+const SUBCMD_SET_PLAYER_LIGHTS: u8 = 0x30;
+const SUBCMD_ENABLE_IMU: u8 = 0x40;
+const SUBCMD_ENABLE_VIBRATION: u8 = 0x48;
+
+const USB_HANDSHAKE: u8 = 0x02;
+const USB_HIGH_SPEED: u8 = 0x03;
+const USB_FORCE_HID: u8 = 0x04;
+
+const SPI_USER_STICKS: u32 = 0x8010;
+const SPI_FACTORY_STICKS: u32 = 0x603d;
+const SPI_USER_IMU: u32 = 0x8026;
+const SPI_FACTORY_IMU: u32 = 0x6020;
+/// Written before user calibration if it's present.
+const SPI_USER_MAGIC: [u8; 2] = [0xb2, 0xa1];
+
+const REPLY_TIMEOUT: Duration = Duration::from_millis(250);
+const NEUTRAL_RUMBLE: [u8; 4] = [0x00, 0x01, 0x40, 0x40];
+const AXIS_MAX: i32 = 32767;
+const STANDARD_GRAVITY: f32 = 9.806_65;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Kind {
+    Pro,
+    JoyConL,
+    JoyConR,
+}
+
+impl Kind {
+    fn has(self, side: Side) -> bool {
+        match self {
+            Kind::Pro => true,
+            Kind::JoyConL => side == Side::Left,
+            Kind::JoyConR => side == Side::Right,
+        }
+    }
+}
+
+/// Half of controller that has element.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Byte of state report, bit mask, code and side of buttons. Capture and SL/SR buttons of
+/// Joy-Cons don't have equivalent in `native_ev_codes` and are not reported.
+const BUTTONS: [(usize, u8, EvCode, Side); 17] = {
+    use self::native_ev_codes::*;
+
+    [
+        (3, 0x01, BTN_WEST, Side::Right),
+        (3, 0x02, BTN_NORTH, Side::Right),
+        (3, 0x04, BTN_SOUTH, Side::Right),
+        (3, 0x08, BTN_EAST, Side::Right),
+        (3, 0x40, BTN_RT, Side::Right),
+        (3, 0x80, BTN_RT2, Side::Right),
+        (4, 0x01, BTN_SELECT, Side::Left),
+        (4, 0x02, BTN_START, Side::Right),
+        (4, 0x04, BTN_RTHUMB, Side::Right),
+        (4, 0x08, BTN_LTHUMB, Side::Left),
+        (4, 0x10, BTN_MODE, Side::Right),
+        (5, 0x01, BTN_DPAD_DOWN, Side::Left),
+        (5, 0x02, BTN_DPAD_UP, Side::Left),
+        (5, 0x04, BTN_DPAD_RIGHT, Side::Left),
+        (5, 0x08, BTN_DPAD_LEFT, Side::Left),
+        (5, 0x40, BTN_LT, Side::Left),
+        (5, 0x80, BTN_LT2, Side::Left),
+    ]
+};
+
+/// Offset of stick data in state report, codes of its axes and side.
+const STICKS: [(usize, EvCode, EvCode, Side); 2] = {
+    use self::native_ev_codes::*;
+
+    [
+        (6, AXIS_LSTICKX, AXIS_LSTICKY, Side::Left),
+        (9, AXIS_RSTICKX, AXIS_RSTICKY, Side::Right),
+    ]
+};
+
+pub fn is_supported(vendor_id: u16, product_id: u16) -> bool {
+    vendor_id == VENDOR_ID
+        && (product_id == PRODUCT_JOYCON_L
+            || product_id == PRODUCT_JOYCON_R
+            || product_id == PRODUCT_PRO)
+}
+
+#[derive(Debug)]
+pub struct Controller {
+    connection: Arc<Mutex<Connection>>,
+    kind: Kind,
+    /// Indexes of `BUTTONS` and `STICKS` that controller has.
+    buttons: Vec<usize>,
+    sticks: Vec<usize>,
+    /// Calibration of left and right stick.
+    calibration: [StickCalibration; 2],
+    imu: ImuCalibration,
+}
+
+impl Controller {
+    /// Initializes controller and reads its calibration. `player` (0-3) selects player light.
+    pub fn open(api: &HidApi, info: &DeviceInfo, player: usize) -> Result<Self, HidError> {
+        let kind = match info.product_id() {
+            PRODUCT_JOYCON_L => Kind::JoyConL,
+            PRODUCT_JOYCON_R => Kind::JoyConR,
+            _ => Kind::Pro,
+        };
+        let is_usb = match info.bus_type() {
+            BusType::Usb => true,
+            _ => false,
+        };
+
+        let device = api.open_path(info.path())?;
+        let mut connection = Connection {
+            device,
+            counter: 0,
+            // Output reports are 64 bytes long over USB and 49 over Bluetooth.
+            report_len: if is_usb { 64 } else { 49 },
+        };
+
+        if is_usb {
+            // Makes controller talk HID instead of being charged silently. Commands can fail if
+            // controller was already initialized.
+            for &cmd in &[USB_HANDSHAKE, USB_HIGH_SPEED, USB_HANDSHAKE, USB_FORCE_HID] {
+                if let Err(e) = connection.usb_command(cmd) {
+                    debug!("Switch controller USB command {:#x} failed: {}", cmd, e);
+                }
+            }
+        }
+
+        connection.subcommand(SUBCMD_SET_REPORT_MODE, &[REPORT_STATE])?;
+        connection.subcommand(SUBCMD_ENABLE_IMU, &[0x01])?;
+        connection.subcommand(SUBCMD_ENABLE_VIBRATION, &[0x01])?;
+        connection.subcommand(SUBCMD_SET_PLAYER_LIGHTS, &[1 << (player % 4)])?;
+
+        let calibration = read_stick_calibration(&mut connection);
+        let imu = read_imu_calibration(&mut connection);
+        connection.device.set_blocking_mode(false)?;
+
+        let buttons = (0..BUTTONS.len())
+            .filter(|&i| kind.has(BUTTONS[i].3))
+            .collect();
+        let sticks = (0..STICKS.len())
+            .filter(|&i| kind.has(STICKS[i].3))
+            .collect();
+
+        Ok(Controller {
+            connection: Arc::new(Mutex::new(connection)),
+            kind,
+            buttons,
+            sticks,
+            calibration,
+            imu,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            Kind::Pro => "Nintendo Switch Pro Controller",
+            Kind::JoyConL => "Nintendo Switch Joy-Con (L)",
+            Kind::JoyConR => "Nintendo Switch Joy-Con (R)",
+        }
+    }
+
+    pub fn buttons(&self) -> Vec<EvCode> {
+        self.buttons.iter().map(|&i| BUTTONS[i].2).collect()
+    }
+
+    pub fn axes(&self) -> Vec<EvCode> {
+        self.sticks
+            .iter()
+            .flat_map(|&i| vec![STICKS[i].1, STICKS[i].2])
+            .collect()
+    }
+
+    /// Reads one pending state report. Returns `false` if there are none.
+    pub fn read(&mut self, state: &mut State) -> Result<bool, HidError> {
+        let mut buf = [0u8; 64];
+        loop {
+            let len = self.connection.lock().unwrap().device.read(&mut buf)?;
+            if len == 0 {
+                return Ok(false);
+            }
+            if buf[0] == REPORT_STATE && len >= 49 {
+                break;
+            }
+        }
+
+        for (idx, &i) in self.buttons.iter().enumerate() {
+            let (byte, mask, _, _) = BUTTONS[i];
+            state.pressed[idx] = buf[byte] & mask != 0;
+        }
+
+        for (idx, &i) in self.sticks.iter().enumerate() {
+            let (offset, _, _, side) = STICKS[i];
+            let data = &buf[offset..offset + 3];
+            let x = u16::from(data[0]) | (u16::from(data[1] & 0x0f) << 8);
+            let y = (u16::from(data[1]) >> 4) | (u16::from(data[2]) << 4);
+            let calibration = &self.calibration[side as usize];
+            state.values[idx * 2] = calibration.x.normalize(x);
+            state.values[idx * 2 + 1] = calibration.y.normalize(y);
+        }
+
+        state.power = power_info(buf[2]);
+        state.motion = Some(self.motion(&buf[13..49]));
+
+        Ok(true)
+    }
+
+    pub fn ff_device(&self) -> FfDevice {
+        FfDevice {
+            connection: self.connection.clone(),
+        }
+    }
+
+    /// Averages three IMU samples and converts them to SDL's coordinate system.
+    fn motion(&self, data: &[u8]) -> MotionData {
+        let mut accel = [0.0; 3];
+        let mut gyro = [0.0; 3];
+        for sample in data.chunks(12) {
+            for axis in 0..3 {
+                let raw_accel = i16::from_le_bytes([sample[axis * 2], sample[axis * 2 + 1]]);
+                let raw_gyro = i16::from_le_bytes([sample[6 + axis * 2], sample[7 + axis * 2]]);
+                accel[axis] += self.imu.accel(axis, raw_accel) / 3.0;
+                gyro[axis] += self.imu.gyro(axis, raw_gyro) / 3.0;
+            }
+        }
+
+        // IMU of right Joy-Con is rotated by 180° around Y axis.
+        let sign = if self.kind == Kind::JoyConR {
+            -1.0
+        } else {
+            1.0
+        };
+        MotionData {
+            accel: [-accel[1] * sign, accel[2], -accel[0] * sign],
+            gyro: [-gyro[1] * sign, gyro[2], -gyro[0] * sign],
+        }
+    }
+}
+
+/// Device shared with force feedback thread.
+#[derive(Debug)]
+struct Connection {
+    device: HidDevice,
+    /// Packet number, increased with every output report.
+    counter: u8,
+    report_len: usize,
+}
+
+impl Connection {
+    fn write(&mut self, report_id: u8, rumble: [u8; 8], data: &[u8]) -> Result<(), HidError> {
+        let mut report = [0u8; 64];
+        report[0] = report_id;
+        report[1] = self.counter;
+        report[2..10].copy_from_slice(&rumble);
+        report[10..10 + data.len()].copy_from_slice(data);
+        self.counter = (self.counter + 1) & 0x0f;
+
+        self.device.write(&report[..self.report_len]).map(|_| ())
+    }
+
+    /// Sends subcommand and waits for its reply. Returns the reply report.
+    fn subcommand(&mut self, id: u8, args: &[u8]) -> Result<[u8; 64], HidError> {
+        let mut data = [0u8; 40];
+        data[0] = id;
+        data[1..=args.len()].copy_from_slice(args);
+        let mut rumble = [0; 8];
+        rumble[..4].copy_from_slice(&NEUTRAL_RUMBLE);
+        rumble[4..].copy_from_slice(&NEUTRAL_RUMBLE);
+        self.write(OUTPUT_SUBCOMMAND, rumble, &data[..=args.len()])?;
+
+        self.wait_for(|buf| buf[0] == REPORT_REPLY && buf[14] == id)
+    }
+
+    fn usb_command(&mut self, cmd: u8) -> Result<[u8; 64], HidError> {
+        let mut report = [0u8; 64];
+        report[0] = OUTPUT_USB;
+        report[1] = cmd;
+        self.device.write(&report[..self.report_len])?;
+
+        self.wait_for(|buf| buf[0] == REPORT_USB_REPLY && buf[1] == cmd)
+    }
+
+    fn wait_for<F: Fn(&[u8; 64]) -> bool>(&mut self, is_reply: F) -> Result<[u8; 64], HidError> {
+        let start = Instant::now();
+        let mut buf = [0u8; 64];
+        while start.elapsed() < REPLY_TIMEOUT {
+            let len = self.device.read_timeout(&mut buf, 50)?;
+            if len > 0 && is_reply(&buf) {
+                return Ok(buf);
+            }
+        }
+
+        Err(HidError::HidApiError {
+            message: "controller didn't reply".to_owned(),
+        })
+    }
+
+    /// Reads `len` (at most 29) bytes from controller's SPI flash.
+    fn spi_read(&mut self, address: u32, len: u8) -> Result<Vec<u8>, HidError> {
+        let mut args = [0u8; 5];
+        args[..4].copy_from_slice(&address.to_le_bytes());
+        args[4] = len;
+        let reply = self.subcommand(SUBCMD_SPI_READ, &args)?;
+
+        Ok(reply[20..20 + len as usize].to_vec())
+    }
+}
+
+/// Center and distance to both ends of one stick axis.
+#[derive(Copy, Clone, Debug)]
+struct AxisCalibration {
+    center: u16,
+    below: u16,
+    above: u16,
+}
+
+impl AxisCalibration {
+    fn normalize(self, raw: u16) -> i32 {
+        let (value, range) = if raw >= self.center {
+            (i32::from(raw - self.center), i32::from(self.above))
+        } else {
+            (-i32::from(self.center - raw), i32::from(self.below))
+        };
+
+        (value * AXIS_MAX / range.max(1))
+            .max(-AXIS_MAX)
+            .min(AXIS_MAX)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct StickCalibration {
+    x: AxisCalibration,
+    y: AxisCalibration,
+}
+
+impl Default for StickCalibration {
+    fn default() -> Self {
+        let axis = AxisCalibration {
+            center: 2048,
+            below: 1500,
+            above: 1500,
+        };
+        StickCalibration { x: axis, y: axis }
+    }
+}
+
+/// Decodes 9 bytes of stick calibration into six 12-bit values.
+fn decode_stick(data: &[u8]) -> [u16; 6] {
+    let mut values = [0; 6];
+    for i in 0..3 {
+        let d = &data[i * 3..i * 3 + 3];
+        values[i * 2] = ((u16::from(d[1]) << 8) & 0xf00) | u16::from(d[0]);
+        values[i * 2 + 1] = (u16::from(d[2]) << 4) | (u16::from(d[1]) >> 4);
+    }
+    values
+}
+
+/// Prefers user calibration from system settings, falls back to factory calibration and then
+/// to defaults.
+fn read_stick_calibration(connection: &mut Connection) -> [StickCalibration; 2] {
+    let sticks = match connection.spi_read(SPI_USER_STICKS, 22) {
+        Ok(ref user) if user[..2] == SPI_USER_MAGIC && user[11..13] == SPI_USER_MAGIC => {
+            let mut sticks = user[2..11].to_vec();
+            sticks.extend_from_slice(&user[13..22]);
+            Some(sticks)
+        }
+        _ => connection.spi_read(SPI_FACTORY_STICKS, 18).ok(),
+    };
+
+    let sticks = match sticks {
+        // Erased flash reads as 0xff.
+        Some(ref sticks) if sticks.iter().any(|&b| b != 0xff) => sticks,
+        _ => {
+            debug!("Switch controller has no stick calibration, using defaults.");
+            return [StickCalibration::default(); 2];
+        }
+    };
+
+    // Left stick stores distance above center first, right stick stores center first.
+    let left = decode_stick(&sticks[..9]);
+    let right = decode_stick(&sticks[9..]);
+    [
+        StickCalibration {
+            x: AxisCalibration {
+                center: left[2],
+                below: left[4],
+                above: left[0],
+            },
+            y: AxisCalibration {
+                center: left[3],
+                below: left[5],
+                above: left[1],
+            },
+        },
+        StickCalibration {
+            x: AxisCalibration {
+                center: right[0],
+                below: right[2],
+                above: right[4],
+            },
+            y: AxisCalibration {
+                center: right[1],
+                below: right[3],
+                above: right[5],
+            },
+        },
+    ]
+}
+
+/// Coefficients that convert raw IMU values to m/s² and rad/s.
+#[derive(Copy, Clone, Debug)]
+struct ImuCalibration {
+    accel_scale: [f32; 3],
+    gyro_origin: [f32; 3],
+    gyro_scale: [f32; 3],
+}
+
+impl ImuCalibration {
+    fn new(data: &[u8]) -> Self {
+        let value = |i: usize| f32::from(i16::from_le_bytes([data[i * 2], data[i * 2 + 1]]));
+        let mut calibration = ImuCalibration {
+            accel_scale: [0.0; 3],
+            gyro_origin: [0.0; 3],
+            gyro_scale: [0.0; 3],
+        };
+
+        for axis in 0..3 {
+            let accel_range = (value(3 + axis) - value(axis)).max(1.0);
+            let gyro_range = (value(9 + axis) - value(6 + axis)).max(1.0);
+            calibration.accel_scale[axis] = 4.0 / accel_range * STANDARD_GRAVITY;
+            calibration.gyro_origin[axis] = value(6 + axis);
+            calibration.gyro_scale[axis] = 936.0 / gyro_range * PI / 180.0;
+        }
+
+        calibration
+    }
+
+    fn accel(&self, axis: usize, raw: i16) -> f32 {
+        f32::from(raw) * self.accel_scale[axis]
+    }
+
+    fn gyro(&self, axis: usize, raw: i16) -> f32 {
+        (f32::from(raw) - self.gyro_origin[axis]) * self.gyro_scale[axis]
+    }
+}
+
+impl Default for ImuCalibration {
+    /// Nominal values for ±8 G and ±2000 °/s ranges.
+    fn default() -> Self {
+        let mut data = [0u8; 24];
+        for axis in 0..3 {
+            data[6 + axis * 2..8 + axis * 2].copy_from_slice(&16384i16.to_le_bytes());
+            data[18 + axis * 2..20 + axis * 2].copy_from_slice(&13371i16.to_le_bytes());
+        }
+        ImuCalibration::new(&data)
+    }
+}
+
+fn read_imu_calibration(connection: &mut Connection) -> ImuCalibration {
+    let user = connection.spi_read(SPI_USER_IMU, 26);
+    let data = match user {
+        Ok(ref user) if user[..2] == SPI_USER_MAGIC => Some(user[2..].to_vec()),
+        _ => connection.spi_read(SPI_FACTORY_IMU, 24).ok(),
+    };
+
+    match data {
+        Some(ref data) if data.iter().any(|&b| b != 0xff) => ImuCalibration::new(data),
+        _ => ImuCalibration::default(),
+    }
+}
+
+/// Decodes battery nibble of state report.
+fn power_info(byte: u8) -> PowerInfo {
+    let level = (byte >> 5) * 25;
+    let charging = byte & 0x10 != 0;
+    match (charging, level) {
+        (true, 100) => PowerInfo::Charged,
+        (true, level) => PowerInfo::Charging(level),
+        (false, level) => PowerInfo::Discharging(level),
+    }
+}
+
+/// Plays rumble with HD rumble packets. Strong magnitude drives low (160 Hz) band and weak
+/// magnitude high (320 Hz) band of both actuators.
+#[derive(Debug)]
+pub struct FfDevice {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl BackendFfDevice for FfDevice {
+    fn set_ff_state(&mut self, strong: u16, weak: u16, _min_duration: Duration) -> io::Result<()> {
+        let side = encode_rumble(weak, strong);
+        let mut rumble = [0; 8];
+        rumble[..4].copy_from_slice(&side);
+        rumble[4..].copy_from_slice(&side);
+
+        let mut connection = self.connection.lock().unwrap();
+        connection.write(OUTPUT_RUMBLE, rumble, &[]).map_err(|e| {
+            error!("Failed to send Switch rumble packet: {}", e);
+            to_io_error(e)
+        })
+    }
+}
+
+/// Encodes amplitudes of high and low band into 4 bytes of HD rumble data.
+fn encode_rumble(high: u16, low: u16) -> [u8; 4] {
+    // Encoded 320 Hz and 160 Hz.
+    let high_freq: u16 = 0x0100;
+    let low_freq: u8 = 0x40;
+    let high_amp = encode_amplitude(high) * 2;
+    let low_amp = u16::from(encode_amplitude(low) / 2) + 0x40;
+
+    [
+        (high_freq & 0xff) as u8,
+        high_amp + (high_freq >> 8) as u8,
+        low_freq + (low_amp >> 8) as u8,
+        (low_amp & 0xff) as u8,
+    ]
+}
+
+/// Maximum (100) is the safe limit of actuators.
+fn encode_amplitude(magnitude: u16) -> u8 {
+    if magnitude == 0 {
+        return 0;
+    }
+
+    let amp = f32::from(magnitude) / f32::from(u16::max_value());
+    let encoded = if amp > 0.23 {
+        (amp * 8.7).log2() * 32.0
+    } else {
+        (amp * 17.0).log2() * 16.0
+    };
+
+    encoded.round().max(0.0).min(100.0) as u8
+}
@@ -0,0 +1,170 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::objc::{self, Id, BOOL};
+
+use std::io;
+use std::ptr;
+use std::time::Duration;
+use std::u16;
+
+#[derive(Debug)]
+/// Represents gamepad. Reexported as FfDevice
+pub struct Device {
+    engine: Id,
+    player: Id,
+}
+
+// CHHapticEngine and its players can be used from any thread.
+unsafe impl Send for Device {}
+
+impl Device {
+    /// Creates CoreHaptics engine for `GCController`. Returns `None` if controller has no
+    /// haptics or OS is older than iOS 14.
+    pub(crate) fn new(controller: Id) -> Option<Self> {
+        unsafe {
+            let pool = objc::objc_autoreleasePoolPush();
+            let haptics = objc::get_optional(controller, "haptics\0");
+            let engine: Id = if haptics.is_null() {
+                objc::nil
+            } else {
+                objc::send1(
+                    haptics,
+                    "createEngineWithLocality:\0",
+                    objc::GCHapticsLocalityDefault,
+                )
+            };
+
+            let device = if engine.is_null() {
+                None
+            } else {
+                let error: *mut Id = ptr::null_mut();
+                let started: BOOL = objc::send1(engine, "startAndReturnError:\0", error);
+                if started == 0 {
+                    warn!("Failed to start haptic engine");
+                }
+                objc::objc_retain(engine);
+
+                Some(Device {
+                    engine,
+                    player: objc::nil,
+                })
+            };
+            objc::objc_autoreleasePoolPop(pool);
+
+            device
+        }
+    }
+
+    /// Sets magnitude for strong and weak ff motors.
+    pub fn set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        min_duration: Duration,
+    ) -> io::Result<()> {
+        let started = unsafe {
+            let pool = objc::objc_autoreleasePoolPush();
+            self.stop_player();
+
+            let magnitude = strong.max(weak);
+            if magnitude != 0 {
+                self.player =
+                    self.start_player(f32::from(magnitude) / f32::from(u16::MAX), min_duration);
+            }
+            objc::objc_autoreleasePoolPop(pool);
+
+            magnitude == 0 || !self.player.is_null()
+        };
+
+        if started {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to start haptic player",
+            ))
+        }
+    }
+
+    /// Plays continuous haptic event with given intensity (0.0 - 1.0).
+    unsafe fn start_player(&self, intensity: f32, duration: Duration) -> Id {
+        let error: *mut Id = ptr::null_mut();
+
+        let param: Id = objc::send(objc::class("CHHapticEventParameter\0"), "alloc\0");
+        let param: Id = objc::send2(
+            param,
+            "initWithParameterID:value:\0",
+            objc::CHHapticEventParameterIDHapticIntensity,
+            intensity,
+        );
+        let event: Id = objc::send(objc::class("CHHapticEvent\0"), "alloc\0");
+        let event: Id = objc::send4(
+            event,
+            "initWithEventType:parameters:relativeTime:duration:\0",
+            objc::CHHapticEventTypeHapticContinuous,
+            objc::array(&[param]),
+            0.0f64,
+            duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9,
+        );
+        let pattern: Id = objc::send(objc::class("CHHapticPattern\0"), "alloc\0");
+        let pattern: Id = objc::send3(
+            pattern,
+            "initWithEvents:parameters:error:\0",
+            objc::array(&[event]),
+            objc::array(&[]),
+            error,
+        );
+        objc::objc_release(param);
+        objc::objc_release(event);
+
+        if pattern.is_null() {
+            error!("Failed to create haptic pattern");
+            return objc::nil;
+        }
+
+        let player: Id = objc::send2(
+            self.engine,
+            "createPlayerWithPattern:error:\0",
+            pattern,
+            error,
+        );
+        objc::objc_release(pattern);
+        if player.is_null() {
+            error!("Failed to create haptic player");
+            return objc::nil;
+        }
+
+        // CHHapticTimeImmediate
+        let started: BOOL = objc::send2(player, "startAtTime:error:\0", 0.0f64, error);
+        if started == 0 {
+            error!("Failed to start haptic player");
+            return objc::nil;
+        }
+
+        objc::objc_retain(player)
+    }
+
+    unsafe fn stop_player(&mut self) {
+        if !self.player.is_null() {
+            let error: *mut Id = ptr::null_mut();
+            let _: BOOL = objc::send2(self.player, "stopAtTime:error:\0", 0.0f64, error);
+            objc::objc_release(self.player);
+            self.player = objc::nil;
+        }
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        unsafe {
+            self.stop_player();
+            objc::send1::<_, ()>(self.engine, "stopWithCompletionHandler:\0", objc::nil);
+            objc::objc_release(self.engine);
+        }
+    }
+}
@@ -0,0 +1,508 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::objc::{self, Id, NSInteger, NSUInteger, BOOL};
+use super::FfDevice;
+use crate::utils;
+use crate::{
+    AxisInfo, BackendInfo, ErrorQueue, Event, EventType, PlatformError, PollMode, PowerInfo,
+    RawWaitHandle,
+};
+use uuid::Uuid;
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(8);
+const AXIS_MAX: i32 = 32767;
+
+/// Key paths of `GCExtendedGamepad` buttons. Missing elements (for example `buttonHome` before
+/// iOS 14) are skipped.
+const BUTTON_PATHS: [(&[&str], EvCode); 17] = {
+    use self::native_ev_codes::*;
+
+    [
+        (&["buttonA\0"], BTN_SOUTH),
+        (&["buttonB\0"], BTN_EAST),
+        (&["buttonX\0"], BTN_WEST),
+        (&["buttonY\0"], BTN_NORTH),
+        (&["leftShoulder\0"], BTN_LT),
+        (&["rightShoulder\0"], BTN_RT),
+        (&["leftTrigger\0"], BTN_LT2),
+        (&["rightTrigger\0"], BTN_RT2),
+        (&["buttonOptions\0"], BTN_SELECT),
+        (&["buttonMenu\0"], BTN_START),
+        (&["buttonHome\0"], BTN_MODE),
+        (&["leftThumbstickButton\0"], BTN_LTHUMB),
+        (&["rightThumbstickButton\0"], BTN_RTHUMB),
+        (&["dpad\0", "up\0"], BTN_DPAD_UP),
+        (&["dpad\0", "down\0"], BTN_DPAD_DOWN),
+        (&["dpad\0", "left\0"], BTN_DPAD_LEFT),
+        (&["dpad\0", "right\0"], BTN_DPAD_RIGHT),
+    ]
+};
+
+/// Key paths of `GCExtendedGamepad` axes. Triggers are `GCControllerButtonInput`, which also
+/// has `value` property.
+const AXIS_PATHS: [(&[&str], EvCode); 6] = {
+    use self::native_ev_codes::*;
+
+    [
+        (&["leftThumbstick\0", "xAxis\0"], AXIS_LSTICKX),
+        (&["leftThumbstick\0", "yAxis\0"], AXIS_LSTICKY),
+        (&["rightThumbstick\0", "xAxis\0"], AXIS_RSTICKX),
+        (&["rightThumbstick\0", "yAxis\0"], AXIS_RSTICKY),
+        (&["leftTrigger\0"], AXIS_LT2),
+        (&["rightTrigger\0"], AXIS_RT2),
+    ]
+};
+
+#[derive(Debug)]
+pub struct Gilrs {
+    gamepads: Vec<Gamepad>,
+    events: VecDeque<Event>,
+    poll_interval: Duration,
+    suspended: bool,
+    stopped: bool,
+}
+
+impl Gilrs {
+    pub(crate) fn new(_errors: ErrorQueue) -> Result<Self, PlatformError> {
+        let mut gilrs = Gilrs {
+            gamepads: Vec::new(),
+            events: VecDeque::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            suspended: false,
+            stopped: false,
+        };
+        gilrs.poll();
+
+        Ok(gilrs)
+    }
+
+    pub(crate) fn is_alive(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        BackendInfo::new("GameController", None)
+    }
+
+    pub(crate) fn next_event(&mut self) -> Option<Event> {
+        if self.events.is_empty() {
+            self.poll();
+        }
+
+        self.events.pop_front()
+    }
+
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        let timeout = timeout.unwrap_or(utils::WAIT_SLICE);
+        let start = Instant::now();
+
+        loop {
+            if self.events.is_empty() {
+                self.poll();
+            }
+
+            let elapsed = start.elapsed();
+            if !self.events.is_empty() || elapsed >= timeout {
+                return;
+            }
+
+            thread::sleep(self.poll_interval.min(timeout - elapsed));
+        }
+    }
+
+    pub(crate) fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+    }
+
+    pub(crate) fn set_poll_mode(&mut self, _mode: PollMode) {}
+
+    pub(crate) fn set_connection_check_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        None
+    }
+
+    pub(crate) fn refresh(&mut self) {}
+
+    pub(crate) fn set_event_queue_capacity(&mut self, _capacity: Option<usize>) {}
+
+    pub(crate) fn stop(&mut self) {
+        if self.stopped {
+            return;
+        }
+
+        self.stopped = true;
+        for (id, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if gamepad.is_connected {
+                gamepad.disconnect();
+                self.events
+                    .push_back(Event::new(id, EventType::Disconnected));
+            }
+        }
+    }
+
+    pub(crate) fn suspend(&mut self) {
+        self.suspended = true;
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.suspended = false;
+    }
+
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.stopped = false;
+        self.poll();
+
+        Ok(())
+    }
+
+    pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
+        self.gamepads.get(id)
+    }
+
+    /// Returns index greater than index of last connected gamepad.
+    pub fn last_gamepad_hint(&self) -> usize {
+        self.gamepads.len()
+    }
+
+    /// Compares `[GCController controllers]` with known gamepads and reads state of connected
+    /// ones. GameController updates the list from main run loop, so application has to run it.
+    fn poll(&mut self) {
+        if self.stopped || self.suspended {
+            return;
+        }
+
+        unsafe {
+            let pool = objc::objc_autoreleasePoolPush();
+
+            let controllers: Id = objc::send(objc::class("GCController\0"), "controllers\0");
+            let count: NSUInteger = objc::send(controllers, "count\0");
+            let current: Vec<Id> = (0..count)
+                .map(|i| objc::send1(controllers, "objectAtIndex:\0", i))
+                .collect();
+
+            for (id, gamepad) in self.gamepads.iter_mut().enumerate() {
+                if gamepad.is_connected && !current.contains(&gamepad.controller) {
+                    gamepad.disconnect();
+                    self.events
+                        .push_back(Event::new(id, EventType::Disconnected));
+                }
+            }
+
+            for controller in current {
+                let id = match self
+                    .gamepads
+                    .iter()
+                    .position(|g| g.controller == controller)
+                {
+                    Some(id) => id,
+                    None => {
+                        self.gamepads.push(Gamepad::new(controller));
+                        self.gamepads.len() - 1
+                    }
+                };
+
+                let gamepad = &mut self.gamepads[id];
+                if !gamepad.is_connected {
+                    if !gamepad.connect() {
+                        continue;
+                    }
+                    self.events.push_back(Event::new(id, EventType::Connected));
+                }
+
+                gamepad.collect_events(id, &mut self.events);
+            }
+
+            objc::objc_autoreleasePoolPop(pool);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Gamepad {
+    name: String,
+    controller: Id,
+    is_connected: bool,
+    buttons: Vec<EvCode>,
+    button_elements: Vec<Id>,
+    pressed: Vec<bool>,
+    axes: Vec<EvCode>,
+    axis_elements: Vec<Id>,
+    values: Vec<i32>,
+}
+
+// GCController and its elements can be read from any thread.
+unsafe impl Send for Gamepad {}
+
+impl Gamepad {
+    unsafe fn new(controller: Id) -> Self {
+        let vendor_name = objc::get_optional(controller, "vendorName\0");
+        let name =
+            objc::string_to_rust(vendor_name).unwrap_or_else(|| "Unknown gamepad".to_owned());
+
+        Gamepad {
+            name,
+            controller: objc::objc_retain(controller),
+            is_connected: false,
+            buttons: Vec::new(),
+            button_elements: Vec::new(),
+            pressed: Vec::new(),
+            axes: Vec::new(),
+            axis_elements: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Looks up elements of extended gamepad profile. Controllers without it (Siri Remote,
+    /// micro gamepad) are ignored.
+    unsafe fn connect(&mut self) -> bool {
+        let profile = objc::get_optional(self.controller, "extendedGamepad\0");
+        if profile.is_null() {
+            return false;
+        }
+
+        self.buttons.clear();
+        self.button_elements.clear();
+        for &(path, code) in BUTTON_PATHS.iter() {
+            let element = resolve(profile, path);
+            if !element.is_null() {
+                self.buttons.push(code);
+                self.button_elements.push(element);
+            }
+        }
+
+        self.axes.clear();
+        self.axis_elements.clear();
+        for &(path, code) in AXIS_PATHS.iter() {
+            let element = resolve(profile, path);
+            if !element.is_null() {
+                self.axes.push(code);
+                self.axis_elements.push(element);
+            }
+        }
+
+        self.pressed = vec![false; self.buttons.len()];
+        self.values = vec![0; self.axes.len()];
+        self.is_connected = true;
+
+        true
+    }
+
+    fn disconnect(&mut self) {
+        self.is_connected = false;
+        self.button_elements.clear();
+        self.axis_elements.clear();
+    }
+
+    unsafe fn collect_events(&mut self, id: usize, events: &mut VecDeque<Event>) {
+        for (idx, &element) in self.button_elements.iter().enumerate() {
+            let pressed: BOOL = objc::send(element, "isPressed\0");
+            let pressed = pressed != 0;
+            if self.pressed[idx] != pressed {
+                self.pressed[idx] = pressed;
+                let code = crate::EvCode(self.buttons[idx]);
+                let ev = if pressed {
+                    EventType::ButtonPressed(code)
+                } else {
+                    EventType::ButtonReleased(code)
+                };
+                events.push_back(Event::new(id, ev));
+            }
+        }
+
+        for (idx, &element) in self.axis_elements.iter().enumerate() {
+            let value: f32 = objc::send(element, "value\0");
+            let value = (value * AXIS_MAX as f32) as i32;
+            if self.values[idx] != value {
+                self.values[idx] = value;
+                let ev = EventType::AxisValueChanged(value, crate::EvCode(self.axes[idx]));
+                events.push_back(Event::new(id, ev));
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// GameController doesn't expose vendor and product IDs.
+    pub fn uuid(&self) -> Uuid {
+        Uuid::nil()
+    }
+
+    pub fn power_info(&self) -> PowerInfo {
+        unsafe {
+            let battery = objc::get_optional(self.controller, "battery\0");
+            if battery.is_null() {
+                return PowerInfo::Unknown;
+            }
+
+            let level: f32 = objc::send(battery, "batteryLevel\0");
+            let level = (level * 100.0) as u8;
+            let state: NSInteger = objc::send(battery, "batteryState\0");
+            // GCDeviceBatteryState
+            match state {
+                0 => PowerInfo::Discharging(level),
+                1 => PowerInfo::Charging(level),
+                2 => PowerInfo::Charged,
+                _ => PowerInfo::Unknown,
+            }
+        }
+    }
+
+    pub fn is_ff_supported(&self) -> bool {
+        unsafe { !objc::get_optional(self.controller, "haptics\0").is_null() }
+    }
+
+    pub fn is_y_axis_reversed(&self) -> bool {
+        super::IS_Y_AXIS_REVERSED
+    }
+
+    pub fn has_mic_led(&self) -> bool {
+        false
+    }
+
+    pub fn set_mic_led(&self, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "microphone LED is not supported on this platform",
+        ))
+    }
+
+    pub fn is_exclusive_supported(&self) -> bool {
+        false
+    }
+
+    pub fn set_exclusive(&self, _exclusive: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "exclusive access is not supported on this platform",
+        ))
+    }
+
+    /// Creates Ffdevice corresponding to this gamepad.
+    pub fn ff_device(&self) -> Option<FfDevice> {
+        FfDevice::new(self.controller)
+    }
+
+    pub fn buttons(&self) -> &[EvCode] {
+        &self.buttons
+    }
+
+    pub fn axes(&self) -> &[EvCode] {
+        &self.axes
+    }
+
+    pub(crate) fn axis_info(&self, nec: EvCode) -> Option<&AxisInfo> {
+        if !self.axes.contains(&nec) {
+            None
+        } else if nec == native_ev_codes::AXIS_LT2 || nec == native_ev_codes::AXIS_RT2 {
+            Some(&TRIGGER_INFO)
+        } else {
+            Some(&STICK_INFO)
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+}
+
+impl Drop for Gamepad {
+    fn drop(&mut self) {
+        unsafe { objc::objc_release(self.controller) }
+    }
+}
+
+unsafe fn resolve(profile: Id, path: &[&str]) -> Id {
+    path.iter()
+        .fold(profile, |obj, name| objc::get_optional(obj, name))
+}
+
+const STICK_INFO: AxisInfo = AxisInfo {
+    min: -AXIS_MAX,
+    max: AXIS_MAX,
+    deadzone: None,
+};
+
+const TRIGGER_INFO: AxisInfo = AxisInfo {
+    min: 0,
+    max: AXIS_MAX,
+    deadzone: None,
+};
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct EvCode(u8);
+
+impl EvCode {
+    pub fn into_u32(self) -> u32 {
+        u32::from(self.0)
+    }
+}
+
+impl Display for EvCode {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        self.0.fmt(f)
+    }
+}
+
+pub mod native_ev_codes {
+    use super::EvCode;
+
+    pub const AXIS_LSTICKX: EvCode = EvCode(0);
+    pub const AXIS_LSTICKY: EvCode = EvCode(1);
+    pub const AXIS_LEFTZ: EvCode = EvCode(2);
+    pub const AXIS_RSTICKX: EvCode = EvCode(3);
+    pub const AXIS_RSTICKY: EvCode = EvCode(4);
+    pub const AXIS_RIGHTZ: EvCode = EvCode(5);
+    pub const AXIS_DPADX: EvCode = EvCode(6);
+    pub const AXIS_DPADY: EvCode = EvCode(7);
+    pub const AXIS_RT: EvCode = EvCode(8);
+    pub const AXIS_LT: EvCode = EvCode(9);
+    pub const AXIS_RT2: EvCode = EvCode(10);
+    pub const AXIS_LT2: EvCode = EvCode(11);
+
+    pub const BTN_SOUTH: EvCode = EvCode(12);
+    pub const BTN_EAST: EvCode = EvCode(13);
+    pub const BTN_C: EvCode = EvCode(14);
+    pub const BTN_NORTH: EvCode = EvCode(15);
+    pub const BTN_WEST: EvCode = EvCode(16);
+    pub const BTN_Z: EvCode = EvCode(17);
+    pub const BTN_LT: EvCode = EvCode(18);
+    pub const BTN_RT: EvCode = EvCode(19);
+    pub const BTN_LT2: EvCode = EvCode(20);
+    pub const BTN_RT2: EvCode = EvCode(21);
+    pub const BTN_SELECT: EvCode = EvCode(22);
+    pub const BTN_START: EvCode = EvCode(23);
+    pub const BTN_MODE: EvCode = EvCode(24);
+    pub const BTN_LTHUMB: EvCode = EvCode(25);
+    pub const BTN_RTHUMB: EvCode = EvCode(26);
+
+    pub const BTN_DPAD_UP: EvCode = EvCode(27);
+    pub const BTN_DPAD_DOWN: EvCode = EvCode(28);
+    pub const BTN_DPAD_LEFT: EvCode = EvCode(29);
+    pub const BTN_DPAD_RIGHT: EvCode = EvCode(30);
+
+    // Codes of elements mapped to part of other element's axis, never reported by backend.
+    pub const SPLIT_AXES: [EvCode; 4] = [EvCode(252), EvCode(253), EvCode(254), EvCode(255)];
+}
@@ -0,0 +1,133 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Minimal Objective-C runtime bindings used to talk to GameController and CoreHaptics.
+//!
+//! Selector names passed to `send*()` functions must end with `\0`.
+
+#![allow(non_upper_case_globals, clippy::upper_case_acronyms)]
+
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+
+pub type Id = *mut c_void;
+pub type Sel = *const c_void;
+pub type NSInteger = isize;
+pub type NSUInteger = usize;
+pub type BOOL = i8;
+
+pub const nil: Id = 0 as Id;
+
+#[link(name = "objc")]
+extern "C" {
+    fn objc_getClass(name: *const c_char) -> Id;
+    fn sel_registerName(name: *const c_char) -> Sel;
+    fn objc_msgSend();
+    pub fn objc_retain(obj: Id) -> Id;
+    pub fn objc_release(obj: Id);
+    pub fn objc_autoreleasePoolPush() -> *mut c_void;
+    pub fn objc_autoreleasePoolPop(pool: *mut c_void);
+}
+
+#[link(name = "Foundation", kind = "framework")]
+extern "C" {}
+
+#[link(name = "GameController", kind = "framework")]
+extern "C" {
+    pub static GCHapticsLocalityDefault: Id;
+}
+
+#[link(name = "CoreHaptics", kind = "framework")]
+extern "C" {
+    pub static CHHapticEventTypeHapticContinuous: Id;
+    pub static CHHapticEventParameterIDHapticIntensity: Id;
+}
+
+pub unsafe fn class(name: &str) -> Id {
+    objc_getClass(name.as_ptr() as *const c_char)
+}
+
+unsafe fn sel(name: &str) -> Sel {
+    debug_assert!(name.ends_with('\0'));
+    sel_registerName(name.as_ptr() as *const c_char)
+}
+
+pub unsafe fn send<R>(obj: Id, name: &str) -> R {
+    let f: unsafe extern "C" fn(Id, Sel) -> R =
+        mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    f(obj, sel(name))
+}
+
+pub unsafe fn send1<A, R>(obj: Id, name: &str, a: A) -> R {
+    let f: unsafe extern "C" fn(Id, Sel, A) -> R =
+        mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    f(obj, sel(name), a)
+}
+
+pub unsafe fn send2<A, B, R>(obj: Id, name: &str, a: A, b: B) -> R {
+    let f: unsafe extern "C" fn(Id, Sel, A, B) -> R =
+        mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    f(obj, sel(name), a, b)
+}
+
+pub unsafe fn send3<A, B, C, R>(obj: Id, name: &str, a: A, b: B, c: C) -> R {
+    let f: unsafe extern "C" fn(Id, Sel, A, B, C) -> R =
+        mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    f(obj, sel(name), a, b, c)
+}
+
+pub unsafe fn send4<A, B, C, D, R>(obj: Id, name: &str, a: A, b: B, c: C, d: D) -> R {
+    let f: unsafe extern "C" fn(Id, Sel, A, B, C, D) -> R =
+        mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    f(obj, sel(name), a, b, c, d)
+}
+
+/// Returns `nil` instead of raising exception if `obj` doesn't implement property `name`.
+/// Used for properties added in newer OS versions.
+pub unsafe fn get_optional(obj: Id, name: &str) -> Id {
+    if obj.is_null() {
+        return nil;
+    }
+
+    let responds: BOOL = send1(obj, "respondsToSelector:\0", sel(name));
+    if responds != 0 {
+        send(obj, name)
+    } else {
+        nil
+    }
+}
+
+pub unsafe fn string_to_rust(string: Id) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+
+    let utf8: *const c_char = send(string, "UTF8String\0");
+    if utf8.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(utf8).to_string_lossy().into_owned())
+    }
+}
+
+/// Creates autoreleased `NSArray` with given objects.
+pub unsafe fn array(objects: &[Id]) -> Id {
+    let ptr = if objects.is_empty() {
+        ptr::null()
+    } else {
+        objects.as_ptr()
+    };
+
+    send2(
+        class("NSArray\0"),
+        "arrayWithObjects:count:\0",
+        ptr,
+        objects.len() as NSUInteger,
+    )
+}
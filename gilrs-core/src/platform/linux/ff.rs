@@ -11,41 +11,86 @@ use std::os::unix::io::AsRawFd;
 use std::u16::MAX as U16_MAX;
 use std::{mem, slice};
 
-use super::ioctl::{self, ff_effect, ff_replay, ff_rumble_effect, input_event};
+use super::ioctl::{self, ff_effect, ff_periodic_effect, ff_replay, ff_rumble_effect, input_event};
+use crate::{BackendErrorKind, ErrorQueue};
 use std::time::Duration;
 
 #[derive(Debug)]
 pub struct Device {
     effect: i16,
+    // FF_RUMBLE or FF_PERIODIC, for devices that can't play rumble effects.
+    kind: u16,
     file: File,
+    id: usize,
+    errors: ErrorQueue,
+    // Effects are updated every few milliseconds, so report only first error in a row.
+    is_failing: bool,
 }
 
 impl Device {
-    pub(crate) fn new(path: &str) -> IoResult<Self> {
+    pub(crate) fn new(path: &str, id: usize, errors: ErrorQueue) -> IoResult<Self> {
         let file = File::create(path)?;
+
+        for &kind in &[FF_RUMBLE, FF_PERIODIC] {
+            let mut effect = Self::effect(kind, -1, 0, 0, 0);
+
+            #[allow(clippy::unnecessary_mut_passed)]
+            let res = unsafe { ioctl::eviocsff(file.as_raw_fd(), &mut effect) };
+
+            if res.is_ok() {
+                debug!(
+                    "Uploaded {} effect {} to {}",
+                    if kind == FF_RUMBLE {
+                        "rumble"
+                    } else {
+                        "periodic"
+                    },
+                    effect.id,
+                    path
+                );
+
+                return Ok(Device {
+                    effect: effect.id,
+                    kind,
+                    file,
+                    id,
+                    errors,
+                    is_failing: false,
+                });
+            }
+        }
+
+        Err(IoError::new(ErrorKind::Other, "Failed to create effect"))
+    }
+
+    fn effect(kind: u16, id: i16, strong: u16, weak: u16, length: u16) -> ff_effect {
         let mut effect = ff_effect {
-            type_: FF_RUMBLE,
-            id: -1,
+            type_: kind,
+            id,
             direction: 0,
             trigger: Default::default(),
-            replay: Default::default(),
+            replay: ff_replay { delay: 0, length },
             u: Default::default(),
         };
 
-        #[allow(clippy::unnecessary_mut_passed)]
-        let res = unsafe { ioctl::eviocsff(file.as_raw_fd(), &mut effect) };
-
-        if res.is_err() {
-            Err(IoError::new(ErrorKind::Other, "Failed to create effect"))
-        } else {
-            Ok(Device {
-                effect: effect.id,
-                file,
-            })
+        unsafe {
+            if kind == FF_RUMBLE {
+                let rumble = &mut effect.u as *mut _ as *mut ff_rumble_effect;
+                (*rumble).strong_magnitude = strong;
+                (*rumble).weak_magnitude = weak;
+            } else {
+                // Single motor, so play the stronger of both.
+                let periodic = &mut effect.u as *mut _ as *mut ff_periodic_effect;
+                (*periodic).waveform = FF_SINE;
+                (*periodic).period = PERIODIC_PERIOD_MS;
+                (*periodic).magnitude = (strong.max(weak) >> 1) as i16;
+            }
         }
+
+        effect
     }
 
-    pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) {
+    pub fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) -> IoResult<()> {
         let duration = min_duration.as_secs() * 1000 + u64::from(min_duration.subsec_millis());
         let duration = if duration > u64::from(U16_MAX) {
             U16_MAX
@@ -53,30 +98,21 @@ impl Device {
             duration as u16
         };
 
-        let mut effect = ff_effect {
-            type_: FF_RUMBLE,
-            id: self.effect,
-            direction: 0,
-            trigger: Default::default(),
-            replay: ff_replay {
-                delay: 0,
-                length: duration,
-            },
-            u: Default::default(),
-        };
+        let effect = Self::effect(self.kind, self.effect, strong, weak, duration);
 
         unsafe {
-            let rumble = &mut effect.u as *mut _ as *mut ff_rumble_effect;
-            (*rumble).strong_magnitude = strong;
-            (*rumble).weak_magnitude = weak;
-
             if let Err(err) = ioctl::eviocsff(self.file.as_raw_fd(), &effect) {
                 error!(
                     "Failed to modify effect of gamepad {:?}, error: {}",
                     self.file, err
                 );
+                let code = err.as_errno().map(|errno| errno as i32);
+                self.report_error(code, format!("failed to modify effect: {}", err));
 
-                return;
+                return Err(match code {
+                    Some(code) => IoError::from_raw_os_error(code),
+                    None => IoError::new(ErrorKind::InvalidInput, err),
+                });
             }
         };
 
@@ -95,19 +131,35 @@ impl Device {
         let s = unsafe { slice::from_raw_parts(&ev as *const _ as *const u8, size) };
 
         match self.file.write(s) {
-            Ok(s) if s == size => (),
+            Ok(s) if s == size => {
+                self.is_failing = false;
+                Ok(())
+            }
             Ok(_) => unreachable!(),
-            Err(e) => error!("Failed to set ff state: {}", e),
+            Err(e) => {
+                error!("Failed to set ff state: {}", e);
+                self.report_error(e.raw_os_error(), format!("failed to play effect: {}", e));
+                Err(e)
+            }
+        }
+    }
+
+    fn report_error(&mut self, code: Option<i32>, description: String) {
+        if !self.is_failing {
+            self.is_failing = true;
+            self.errors.push_with_code(
+                Some(self.id),
+                BackendErrorKind::ForceFeedback,
+                code,
+                description,
+            );
         }
     }
 }
 
 impl Drop for Device {
     fn drop(&mut self) {
-        #[cfg(target_pointer_width = "64")]
-        let effect = self.effect as u64;
-        #[cfg(target_pointer_width = "32")]
-        let effect = self.effect as u32;
+        let effect = self.effect as ioctl::ioctl_param_type;
 
         if let Err(err) = unsafe { ioctl::eviocrmff(self.file.as_raw_fd(), effect) } {
             error!(
@@ -120,3 +172,6 @@ impl Drop for Device {
 
 const EV_FF: u16 = 0x15;
 const FF_RUMBLE: u16 = 0x50;
+const FF_PERIODIC: u16 = 0x51;
+const FF_SINE: u16 = 0x5a;
+const PERIODIC_PERIOD_MS: u16 = 50;
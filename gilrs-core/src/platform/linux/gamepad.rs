@@ -10,70 +10,186 @@ use super::ioctl;
 use super::ioctl::{input_absinfo, input_event};
 use super::udev::*;
 use crate::utils;
-use crate::{AxisInfo, Event, EventType};
-use crate::{PlatformError, PowerInfo};
+use crate::{AxisInfo, BackendInfo, Event, EventType, RawWaitHandle};
+use crate::{BackendErrorKind, ErrorQueue, PlatformError, PollMode, PowerInfo};
 
 use libc as c;
 use uuid::Uuid;
 use vec_map::VecMap;
 
+use std::collections::VecDeque;
+use std::convert::TryFrom;
 use std::error;
 use std::ffi::CStr;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs;
+use std::io;
 use std::mem::{self, MaybeUninit};
 use std::ops::Index;
 use std::os::raw::c_char;
+use std::path::PathBuf;
+#[cfg(not(target_os = "linux"))]
+use std::ptr;
 use std::str;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Debug)]
 pub struct Gilrs {
     gamepads: Vec<Gamepad>,
-    monitor: Monitor,
+    /// `None` when backend is stopped.
+    monitor: Option<Monitor>,
     event_counter: usize,
+    pending_events: VecDeque<Event>,
+    is_suspended: bool,
+    errors: ErrorQueue,
+    /// Watches file descriptors of all gamepads and udev monitor. -1 if it couldn't be created.
+    poller: c::c_int,
 }
 
 impl Gilrs {
-    pub(crate) fn new() -> Result<Self, PlatformError> {
-        let mut gamepads = Vec::new();
+    pub(crate) fn new(errors: ErrorQueue) -> Result<Self, PlatformError> {
+        let (gamepads, monitor) =
+            Self::enumerate(&errors).map_err(|e| PlatformError::Other(Box::new(e)))?;
 
-        let udev = match Udev::new() {
-            Some(udev) => udev,
-            None => {
-                return Err(PlatformError::Other(Box::new(Error::UdevCtx)));
-            }
-        };
-        let en = match udev.enumerate() {
-            Some(en) => en,
-            None => {
-                return Err(PlatformError::Other(Box::new(Error::UdevEnumerate)));
-            }
+        let poller = create_poller();
+        if poller == -1 {
+            warn!(
+                "Failed to create {} instance: {}",
+                POLLER_NAME,
+                io::Error::last_os_error()
+            );
+        }
+
+        let gilrs = Gilrs {
+            gamepads,
+            monitor: Some(monitor),
+            event_counter: 0,
+            pending_events: VecDeque::new(),
+            is_suspended: false,
+            errors,
+            poller,
         };
+        for gamepad in &gilrs.gamepads {
+            gilrs.watch(gamepad.fd);
+        }
+        gilrs.watch(gilrs.monitor.as_ref().map_or(-1, Monitor::fd));
+
+        Ok(gilrs)
+    }
+
+    /// Adds `fd` to epoll (or kqueue) instance. Closed file descriptors are removed
+    /// automatically.
+    fn watch(&self, fd: c::c_int) {
+        if self.poller < 0 || fd < 0 {
+            return;
+        }
+
+        if !add_to_poller(self.poller, fd) {
+            warn!("Failed to watch fd {}: {}", fd, io::Error::last_os_error());
+        }
+    }
+
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        if self.poller >= 0 {
+            Some(RawWaitHandle::Fd(self.poller))
+        } else {
+            None
+        }
+    }
+
+    fn enumerate(errors: &ErrorQueue) -> Result<(Vec<Gamepad>, Monitor), Error> {
+        let mut gamepads = Vec::new();
+
+        let udev = Udev::new().ok_or(Error::UdevCtx)?;
+        let en = udev.enumerate().ok_or(Error::UdevEnumerate)?;
 
         unsafe { en.add_match_property(cstr_new(b"ID_INPUT_JOYSTICK\0"), cstr_new(b"1\0")) }
         en.scan_devices();
 
         for dev in en.iter() {
             if let Some(dev) = Device::from_syspath(&udev, &dev) {
-                if let Some(gamepad) = Gamepad::open(&dev) {
+                if let Some(mut gamepad) = Gamepad::open(&dev, errors) {
+                    gamepad.id = gamepads.len();
                     gamepads.push(gamepad);
                 }
             }
         }
 
-        let monitor = match Monitor::new(&udev) {
-            Some(m) => m,
-            None => return Err(PlatformError::Other(Box::new(Error::UdevMonitor))),
-        };
+        debug!(
+            "Found {} gamepads during initial enumeration.",
+            gamepads.len()
+        );
 
-        Ok(Gilrs {
-            gamepads,
-            monitor,
-            event_counter: 0,
-        })
+        let monitor = Monitor::new(&udev).ok_or(Error::UdevMonitor)?;
+
+        Ok((gamepads, monitor))
+    }
+
+    pub(crate) fn stop(&mut self) {
+        if self.monitor.take().is_none() {
+            return;
+        }
+
+        for gamepad in self.gamepads.iter_mut().filter(|gp| gp.is_connected) {
+            gamepad.disconnect();
+            self.pending_events
+                .push_back(Event::new(gamepad.id, EventType::Disconnected));
+        }
+    }
+
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        if self.monitor.is_some() {
+            return Ok(());
+        }
+
+        let (gamepads, monitor) = Self::enumerate(&self.errors)?;
+        for gamepad in gamepads {
+            let id = self.insert_gamepad(gamepad);
+            self.pending_events
+                .push_back(Event::new(id, EventType::Connected));
+        }
+        self.watch(monitor.fd());
+        self.monitor = Some(monitor);
+
+        Ok(())
+    }
+
+    pub(crate) fn suspend(&mut self) {
+        self.is_suspended = true;
+    }
+
+    pub(crate) fn resume(&mut self) {
+        if !self.is_suspended {
+            return;
+        }
+
+        self.is_suspended = false;
+        for gamepad in self.gamepads.iter_mut().filter(|gp| gp.is_connected) {
+            gamepad.resync();
+        }
+    }
+
+    pub(crate) fn is_alive(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        BackendInfo::new("evdev", None)
     }
 
     pub(crate) fn next_event(&mut self) -> Option<Event> {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Some(event);
+        }
+
+        if self.is_suspended {
+            return None;
+        }
+
         if let Some(event) = self.handle_hotplug() {
             return Some(event);
         }
@@ -108,6 +224,48 @@ impl Gilrs {
         }
     }
 
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        if !self.pending_events.is_empty()
+            || self.is_suspended
+            || self.gamepads.iter().any(|gp| !gp.events.is_empty())
+        {
+            return;
+        }
+
+        let mut fds: Vec<_> = self
+            .gamepads
+            .iter()
+            .filter(|gp| gp.is_connected)
+            .map(|gp| gp.fd)
+            .chain(self.monitor.as_ref().map(Monitor::fd))
+            .map(|fd| c::pollfd {
+                fd,
+                events: c::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        // Round up, so we don't return early and spin until deadline. Timeouts that don't fit in
+        // c_int (~24 days) are treated as infinite.
+        let timeout = timeout.map_or(-1, |t| {
+            let ms = t.as_millis() + u128::from(t.subsec_nanos() % 1_000_000 != 0);
+            c::c_int::try_from(ms).unwrap_or(-1)
+        });
+
+        unsafe {
+            c::poll(fds.as_mut_ptr(), fds.len() as c::nfds_t, timeout);
+        }
+    }
+
+    pub(crate) fn set_poll_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn set_poll_mode(&mut self, _mode: PollMode) {}
+
+    pub(crate) fn set_connection_check_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn refresh(&mut self) {}
+
+    pub(crate) fn set_event_queue_capacity(&mut self, _capacity: Option<usize>) {}
+
     pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
         self.gamepads.get(id)
     }
@@ -116,9 +274,43 @@ impl Gilrs {
         self.gamepads.len()
     }
 
+    /// Puts `gamepad` in slot of disconnected gamepad that is most likely the same device, or in
+    /// new slot, and returns its id.
+    ///
+    /// Slots are matched by serial number, then by port the device is plugged into, and finally
+    /// by UUID alone, but never to a gamepad with different serial number.
+    fn insert_gamepad(&mut self, mut gamepad: Gamepad) -> usize {
+        let same_model = |gp: &Gamepad| !gp.is_connected && gp.uuid == gamepad.uuid;
+        let position = |pred: &dyn Fn(&Gamepad) -> bool| {
+            self.gamepads
+                .iter()
+                .position(|gp| same_model(gp) && pred(gp))
+        };
+        let id = position(&|gp| gamepad.uniq.is_some() && gp.uniq == gamepad.uniq)
+            .or_else(|| position(&|gp| gamepad.phys.is_some() && gp.phys == gamepad.phys))
+            .or_else(|| position(&|gp| gp.uniq.is_none() || gamepad.uniq.is_none()));
+
+        self.watch(gamepad.fd);
+
+        if let Some(id) = id {
+            gamepad.id = id;
+            self.gamepads[id] = gamepad;
+            id
+        } else {
+            gamepad.id = self.gamepads.len();
+            self.gamepads.push(gamepad);
+            self.gamepads.len() - 1
+        }
+    }
+
     fn handle_hotplug(&mut self) -> Option<Event> {
-        while self.monitor.hotplug_available() {
-            let dev = self.monitor.device();
+        let monitor = match self.monitor {
+            Some(ref monitor) => monitor,
+            None => return None,
+        };
+
+        while monitor.hotplug_available() {
+            let dev = monitor.device();
 
             unsafe {
                 if let Some(val) = dev.property_value(cstr_new(b"ID_INPUT_JOYSTICK\0")) {
@@ -126,6 +318,7 @@ impl Gilrs {
                         continue;
                     }
                 } else {
+                    trace!("Hotplug event for {:?} is not for joystick.", dev.devnode());
                     continue;
                 }
 
@@ -135,18 +328,9 @@ impl Gilrs {
                 };
 
                 if action == cstr_new(b"add\0") {
-                    if let Some(gamepad) = Gamepad::open(&dev) {
-                        if let Some(id) = self
-                            .gamepads
-                            .iter()
-                            .position(|gp| gp.uuid() == gamepad.uuid && !gp.is_connected)
-                        {
-                            self.gamepads[id] = gamepad;
-                            return Some(Event::new(id, EventType::Connected));
-                        } else {
-                            self.gamepads.push(gamepad);
-                            return Some(Event::new(self.gamepads.len() - 1, EventType::Connected));
-                        }
+                    if let Some(gamepad) = Gamepad::open(&dev, &self.errors) {
+                        let id = self.insert_gamepad(gamepad);
+                        return Some(Event::new(id, EventType::Connected));
                     }
                 } else if action == cstr_new(b"remove\0") {
                     if let Some(devnode) = dev.devnode() {
@@ -155,6 +339,10 @@ impl Gilrs {
                             .iter()
                             .position(|gp| is_eq_cstr_str(devnode, &gp.devpath) && gp.is_connected)
                         {
+                            info!(
+                                "Gamepad {} ({}) disconnected.",
+                                self.gamepads[id].devpath, self.gamepads[id].name
+                            );
                             self.gamepads[id].disconnect();
                             return Some(Event::new(id, EventType::Disconnected));
                         } else {
@@ -168,6 +356,53 @@ impl Gilrs {
     }
 }
 
+impl Drop for Gilrs {
+    fn drop(&mut self) {
+        if self.poller >= 0 {
+            unsafe {
+                c::close(self.poller);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+const POLLER_NAME: &str = "epoll";
+#[cfg(not(target_os = "linux"))]
+const POLLER_NAME: &str = "kqueue";
+
+#[cfg(target_os = "linux")]
+fn create_poller() -> c::c_int {
+    unsafe { c::epoll_create1(c::EPOLL_CLOEXEC) }
+}
+
+/// kqueue descriptors are not inherited by child processes, so there is no need for CLOEXEC.
+#[cfg(not(target_os = "linux"))]
+fn create_poller() -> c::c_int {
+    unsafe { c::kqueue() }
+}
+
+#[cfg(target_os = "linux")]
+fn add_to_poller(poller: c::c_int, fd: c::c_int) -> bool {
+    let mut event = c::epoll_event {
+        events: c::EPOLLIN as u32,
+        u64: fd as u64,
+    };
+    unsafe { c::epoll_ctl(poller, c::EPOLL_CTL_ADD, fd, &mut event) != -1 }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn add_to_poller(poller: c::c_int, fd: c::c_int) -> bool {
+    unsafe {
+        // Layout of kevent differs between BSDs and FreeBSD versions.
+        let mut change: c::kevent = mem::zeroed();
+        change.ident = fd as c::uintptr_t;
+        change.filter = c::EVFILT_READ;
+        change.flags = c::EV_ADD;
+        c::kevent(poller, &change, 1, ptr::null_mut(), 0, ptr::null()) != -1
+    }
+}
+
 fn is_eq_cstr_str(l: &CStr, r: &str) -> bool {
     unsafe {
         let mut l_ptr = l.as_ptr();
@@ -237,20 +472,28 @@ pub struct Gamepad {
     devpath: String,
     name: String,
     uuid: Uuid,
+    /// Physical location, like "usb-0000:00:14.0-2/input0".
+    phys: Option<String>,
+    /// Serial number or Bluetooth address.
+    uniq: Option<String>,
     // TODO: path or RefCell<File>
     bt_capacity_fd: i32,
     // TODO: path or RefCell<File>
     bt_status_fd: i32,
+    mic_led: Option<PathBuf>,
     axes_values: VecMap<i32>,
     buttons_values: VecMap<bool>,
     events: Vec<input_event>,
     axes: Vec<EvCode>,
     buttons: Vec<EvCode>,
     is_connected: bool,
+    // Index in `Gilrs::gamepads`, set after gamepad is opened.
+    id: usize,
+    errors: ErrorQueue,
 }
 
 impl Gamepad {
-    fn open(dev: &Device) -> Option<Gamepad> {
+    fn open(dev: &Device, errors: &ErrorQueue) -> Option<Gamepad> {
         let path = match dev.devnode() {
             Some(path) => path,
             None => return None,
@@ -263,7 +506,14 @@ impl Gamepad {
 
         let fd = unsafe { c::open(path.as_ptr(), c::O_RDWR | c::O_NONBLOCK) };
         if fd < 0 {
-            error!("Failed to open {:?}", path);
+            let err = std::io::Error::last_os_error();
+            error!("Failed to open {:?}: {}", path, err);
+            errors.push_with_code(
+                None,
+                BackendErrorKind::DeviceOpen,
+                err.raw_os_error(),
+                format!("failed to open {:?}: {}", path, err),
+            );
             return None;
         }
 
@@ -271,6 +521,11 @@ impl Gamepad {
             Some(uuid) => uuid,
             None => {
                 error!("Failed to get id of device {:?}", path);
+                errors.push(
+                    None,
+                    BackendErrorKind::DeviceOpen,
+                    format!("failed to get id of device {:?}", path),
+                );
                 unsafe {
                     c::close(fd);
                 }
@@ -282,10 +537,13 @@ impl Gamepad {
             error!("Failed to get name od device {:?}", path);
             "Unknown".into()
         });
+        let phys = Self::get_string(fd, ioctl::eviocgphys).filter(|s| !s.is_empty());
+        let uniq = Self::get_string(fd, ioctl::eviocguniq).filter(|s| !s.is_empty());
 
         let axesi = AxesInfo::new(fd);
         let ff_supported = Self::test_ff(fd);
         let (cap, status) = Self::battery_fd(&dev);
+        let mic_led = Self::mic_led_path(dev);
 
         let mut gamepad = Gamepad {
             fd,
@@ -294,14 +552,19 @@ impl Gamepad {
             devpath: path.to_string_lossy().into_owned(),
             name,
             uuid,
+            phys,
+            uniq,
             bt_capacity_fd: cap,
             bt_status_fd: status,
+            mic_led,
             axes_values: VecMap::new(),
             buttons_values: VecMap::new(),
             events: Vec::new(),
             axes: Vec::new(),
             buttons: Vec::new(),
             is_connected: true,
+            id: 0,
+            errors: errors.clone(),
         };
 
         gamepad.collect_axes_and_buttons();
@@ -352,9 +615,16 @@ impl Gamepad {
     }
 
     fn get_name(fd: i32) -> Option<String> {
+        Self::get_string(fd, ioctl::eviocgname)
+    }
+
+    fn get_string(
+        fd: i32,
+        request: unsafe fn(c::c_int, &mut [MaybeUninit<u8>]) -> nix::Result<c::c_int>,
+    ) -> Option<String> {
         unsafe {
             let mut namebuff: [MaybeUninit<u8>; 128] = MaybeUninit::uninit().assume_init();
-            if ioctl::eviocgname(fd, &mut namebuff).is_err() {
+            if request(fd, &mut namebuff).is_err() {
                 None
             } else {
                 Some(
@@ -468,6 +738,21 @@ impl Gamepad {
         (-1, -1)
     }
 
+    fn mic_led_path(dev: &Device) -> Option<PathBuf> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::Path;
+
+        let syspath = Path::new(OsStr::from_bytes(dev.syspath().to_bytes()));
+        // LEDs are registered by HID driver (hid-playstation for DualSense) next to power_supply,
+        // with names following "<device>:<color>:micmute" convention.
+        let read_dir = fs::read_dir(syspath.join("device/device/leds")).ok()?;
+        read_dir
+            .filter_map(|entry| entry.ok())
+            .find(|entry| entry.file_name().to_string_lossy().ends_with(":micmute"))
+            .map(|entry| entry.path().join("brightness"))
+    }
+
     fn event(&mut self) -> Option<(EventType, SystemTime)> {
         let mut skip = false;
         // Skip all unknown events and return Option on first know event or when there is no more
@@ -551,6 +836,14 @@ impl Gamepad {
         }
     }
 
+    /// Discards all unread events and replaces them with events describing difference between
+    /// current state of device and last state that was read.
+    fn resync(&mut self) {
+        self.events.clear();
+        while self.next_event().is_some() {}
+        self.compare_state();
+    }
+
     fn compare_state(&mut self) {
         let mut absinfo = input_absinfo::default();
         for axis in self.axes.iter().cloned() {
@@ -631,9 +924,12 @@ impl Gamepad {
                     let cap = match str::from_utf8_unchecked(&buff[..(len - 1)]).parse() {
                         Ok(cap) => cap,
                         Err(_) => {
-                            error!(
-                                "Failed to parse battery capacity: {}",
-                                str::from_utf8_unchecked(&buff[..(len - 1)])
+                            let cap = str::from_utf8_unchecked(&buff[..(len - 1)]);
+                            error!("Failed to parse battery capacity: {}", cap);
+                            self.errors.push(
+                                Some(self.id),
+                                BackendErrorKind::PowerInfo,
+                                format!("invalid battery capacity: {}", cap),
                             );
                             return PowerInfo::Unknown;
                         }
@@ -652,6 +948,11 @@ impl Gamepad {
                             "Full" | "Not charging" => PowerInfo::Charged,
                             s => {
                                 error!("Unknown battery status value: {}", s);
+                                self.errors.push(
+                                    Some(self.id),
+                                    BackendErrorKind::PowerInfo,
+                                    format!("unknown battery status: {}", s),
+                                );
                                 PowerInfo::Unknown
                             }
                         };
@@ -670,6 +971,10 @@ impl Gamepad {
         self.ff_supported
     }
 
+    pub fn is_y_axis_reversed(&self) -> bool {
+        super::IS_Y_AXIS_REVERSED
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -678,9 +983,46 @@ impl Gamepad {
         self.uuid
     }
 
+    pub fn is_exclusive_supported(&self) -> bool {
+        true
+    }
+
+    pub fn set_exclusive(&self, exclusive: bool) -> io::Result<()> {
+        match unsafe { ioctl::eviocgrab(self.fd, exclusive as ioctl::ioctl_param_type) } {
+            Ok(_) => Ok(()),
+            Err(_) => Err(io::Error::last_os_error()),
+        }
+    }
+
+    pub fn has_mic_led(&self) -> bool {
+        self.mic_led.is_some()
+    }
+
+    pub fn set_mic_led(&self, on: bool) -> io::Result<()> {
+        match self.mic_led {
+            Some(ref path) => fs::write(path, if on { "1" } else { "0" }),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "gamepad doesn't have microphone LED",
+            )),
+        }
+    }
+
     pub fn ff_device(&self) -> Option<FfDevice> {
         if self.is_ff_supported() {
-            FfDevice::new(&self.devpath).ok()
+            match FfDevice::new(&self.devpath, self.id, self.errors.clone()) {
+                Ok(device) => Some(device),
+                Err(e) => {
+                    error!("Failed to open ff device {}: {}", self.devpath, e);
+                    self.errors.push_with_code(
+                        Some(self.id),
+                        BackendErrorKind::ForceFeedback,
+                        e.raw_os_error(),
+                        format!("failed to open {}: {}", self.devpath, e),
+                    );
+                    None
+                }
+            }
         } else {
             None
         }
@@ -817,52 +1159,53 @@ impl Display for Error {
 
 impl error::Error for Error {}
 
-const KEY_MAX: u16 = 0x2ff;
+pub(super) const KEY_MAX: u16 = 0x2ff;
 #[allow(dead_code)]
 const EV_MAX: u16 = 0x1f;
-const EV_SYN: u16 = 0x00;
-const EV_KEY: u16 = 0x01;
-const EV_ABS: u16 = 0x03;
-const ABS_MAX: u16 = 0x3f;
+pub(super) const EV_SYN: u16 = 0x00;
+pub(super) const EV_KEY: u16 = 0x01;
+pub(super) const EV_ABS: u16 = 0x03;
+pub(super) const ABS_MAX: u16 = 0x3f;
 const EV_FF: u16 = 0x15;
 
-const SYN_REPORT: u16 = 0x00;
+pub(super) const SYN_REPORT: u16 = 0x00;
 const SYN_DROPPED: u16 = 0x03;
 
 const BTN_MISC: u16 = 0x100;
 const BTN_MOUSE: u16 = 0x110;
 const BTN_JOYSTICK: u16 = 0x120;
-const BTN_SOUTH: u16 = 0x130;
-const BTN_EAST: u16 = 0x131;
+pub(super) const BTN_SOUTH: u16 = 0x130;
+pub(super) const BTN_EAST: u16 = 0x131;
 #[allow(dead_code)]
 const BTN_C: u16 = 0x132;
-const BTN_NORTH: u16 = 0x133;
-const BTN_WEST: u16 = 0x134;
+pub(super) const BTN_NORTH: u16 = 0x133;
+pub(super) const BTN_WEST: u16 = 0x134;
 #[allow(dead_code)]
 const BTN_Z: u16 = 0x135;
-const BTN_TL: u16 = 0x136;
-const BTN_TR: u16 = 0x137;
+pub(super) const BTN_TL: u16 = 0x136;
+pub(super) const BTN_TR: u16 = 0x137;
 const BTN_TL2: u16 = 0x138;
 const BTN_TR2: u16 = 0x139;
-const BTN_SELECT: u16 = 0x13a;
-const BTN_START: u16 = 0x13b;
-const BTN_MODE: u16 = 0x13c;
-const BTN_THUMBL: u16 = 0x13d;
-const BTN_THUMBR: u16 = 0x13e;
+pub(super) const BTN_SELECT: u16 = 0x13a;
+pub(super) const BTN_START: u16 = 0x13b;
+pub(super) const BTN_MODE: u16 = 0x13c;
+pub(super) const BTN_THUMBL: u16 = 0x13d;
+pub(super) const BTN_THUMBR: u16 = 0x13e;
 
 const BTN_DPAD_UP: u16 = 0x220;
 const BTN_DPAD_DOWN: u16 = 0x221;
 const BTN_DPAD_LEFT: u16 = 0x222;
 const BTN_DPAD_RIGHT: u16 = 0x223;
-
-const ABS_X: u16 = 0x00;
-const ABS_Y: u16 = 0x01;
-const ABS_Z: u16 = 0x02;
-const ABS_RX: u16 = 0x03;
-const ABS_RY: u16 = 0x04;
-const ABS_RZ: u16 = 0x05;
-const ABS_HAT0X: u16 = 0x10;
-const ABS_HAT0Y: u16 = 0x11;
+const BTN_TRIGGER_HAPPY1: u16 = 0x2c0;
+
+pub(super) const ABS_X: u16 = 0x00;
+pub(super) const ABS_Y: u16 = 0x01;
+pub(super) const ABS_Z: u16 = 0x02;
+pub(super) const ABS_RX: u16 = 0x03;
+pub(super) const ABS_RY: u16 = 0x04;
+pub(super) const ABS_RZ: u16 = 0x05;
+pub(super) const ABS_HAT0X: u16 = 0x10;
+pub(super) const ABS_HAT0Y: u16 = 0x11;
 const ABS_HAT1X: u16 = 0x12;
 const ABS_HAT1Y: u16 = 0x13;
 const ABS_HAT2X: u16 = 0x14;
@@ -954,6 +1297,60 @@ pub mod native_ev_codes {
         code: super::BTN_DPAD_RIGHT,
     };
 
+    // Back buttons of Steam Deck, only reported by Steam Input backend.
+    #[cfg(feature = "steam")]
+    pub const BTN_L4: EvCode = EvCode {
+        kind: EV_KEY,
+        code: super::BTN_TRIGGER_HAPPY1,
+    };
+    #[cfg(feature = "steam")]
+    pub const BTN_R4: EvCode = EvCode {
+        kind: EV_KEY,
+        code: super::BTN_TRIGGER_HAPPY1 + 1,
+    };
+    #[cfg(feature = "steam")]
+    pub const BTN_L5: EvCode = EvCode {
+        kind: EV_KEY,
+        code: super::BTN_TRIGGER_HAPPY1 + 2,
+    };
+    #[cfg(feature = "steam")]
+    pub const BTN_R5: EvCode = EvCode {
+        kind: EV_KEY,
+        code: super::BTN_TRIGGER_HAPPY1 + 3,
+    };
+
+    // Touchpad click and microphone button of Sony controllers, only reported by HID drivers.
+    #[cfg(feature = "hidapi")]
+    pub const BTN_TOUCHPAD: EvCode = EvCode {
+        kind: EV_KEY,
+        code: super::BTN_TRIGGER_HAPPY1 + 4,
+    };
+    #[cfg(feature = "hidapi")]
+    pub const BTN_MISC1: EvCode = EvCode {
+        kind: EV_KEY,
+        code: super::BTN_TRIGGER_HAPPY1 + 5,
+    };
+
+    // Codes of elements mapped to part of other element's axis, never reported by backend.
+    pub const SPLIT_AXES: [EvCode; 4] = [
+        EvCode {
+            kind: EV_KEY,
+            code: super::BTN_TRIGGER_HAPPY1 + 6,
+        },
+        EvCode {
+            kind: EV_KEY,
+            code: super::BTN_TRIGGER_HAPPY1 + 7,
+        },
+        EvCode {
+            kind: EV_KEY,
+            code: super::BTN_TRIGGER_HAPPY1 + 8,
+        },
+        EvCode {
+            kind: EV_KEY,
+            code: super::BTN_TRIGGER_HAPPY1 + 9,
+        },
+    ];
+
     pub const AXIS_LSTICKX: EvCode = EvCode {
         kind: EV_ABS,
         code: super::ABS_X,
@@ -0,0 +1,537 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Backend for joysticks wired to GPIO and ADC pins of single-board computers.
+//!
+//! Kernel drivers like `gpio-keys` and `adc-joystick` expose pins as evdev devices, but they
+//! usually report keyboard keys or only axes, so Linux backend doesn't treat them as gamepads.
+//! This backend reads them directly and combines one or more devices into gamepad described by
+//! `GpioGamepad`.
+
+use super::gamepad::{ABS_MAX, EV_ABS, EV_KEY, KEY_MAX};
+use super::ioctl::{self, input_absinfo, input_event};
+use crate::{utils, AxisInfo, Backend, BackendGamepad, Event, EventType};
+
+use libc as c;
+
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io;
+use std::mem::{self, MaybeUninit};
+use std::os::raw::c_char;
+use std::time::{Duration, Instant};
+
+/// How often devices of disconnected gamepads are searched for.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+const INPUT_DIR: &str = "/dev/input";
+
+/// Gamepad assembled from evdev devices created by `gpio-keys`, `adc-joystick` or similar
+/// drivers, read by `GpioBackend`.
+///
+/// ```no_run
+/// use gilrs_core::{native_ev_codes as nec, GpioBackend, GpioGamepad};
+///
+/// // Codes are set by `linux,code` properties in device tree.
+/// let stick = GpioGamepad::new("Arcade stick")
+///     .add_device("gpio-keys")
+///     .add_device("adc-joystick")
+///     .map_button(28, nec::BTN_SOUTH)
+///     .map_button(1, nec::BTN_EAST)
+///     .map_button(103, nec::BTN_DPAD_UP)
+///     .map_axis(0, nec::AXIS_LSTICKX)
+///     .map_axis(1, nec::AXIS_LSTICKY);
+/// let backend = GpioBackend::new().add_gamepad(stick);
+/// ```
+#[derive(Clone, Debug)]
+pub struct GpioGamepad {
+    name: String,
+    devices: Vec<String>,
+    buttons: Vec<(u16, crate::EvCode)>,
+    axes: Vec<(u16, crate::EvCode)>,
+}
+
+impl GpioGamepad {
+    /// Creates gamepad with given name, without any devices.
+    pub fn new(name: &str) -> Self {
+        GpioGamepad {
+            name: name.to_owned(),
+            devices: Vec::new(),
+            buttons: Vec::new(),
+            axes: Vec::new(),
+        }
+    }
+
+    /// Adds evdev device that is part of the gamepad. `device` is either path to device node
+    /// (`/dev/input/event3`) or name reported by device (`gpio-keys`). Gamepad is connected while
+    /// all its devices are available.
+    pub fn add_device(mut self, device: &str) -> Self {
+        self.devices.push(device.to_owned());
+
+        self
+    }
+
+    /// Maps key code reported by one of devices to button. `button` should be one of
+    /// `native_ev_codes::BTN_*`, so default mapping translates it to gilrs' `Button`.
+    pub fn map_button(mut self, key: u16, button: crate::EvCode) -> Self {
+        self.buttons.retain(|&(k, _)| k != key);
+        self.buttons.push((key, button));
+
+        self
+    }
+
+    /// Maps absolute axis reported by one of devices (`ABS_X` is 0, `ABS_Y` is 1) to one of
+    /// `native_ev_codes::AXIS_*`.
+    pub fn map_axis(mut self, abs: u16, axis: crate::EvCode) -> Self {
+        self.axes.retain(|&(a, _)| a != abs);
+        self.axes.push((abs, axis));
+
+        self
+    }
+}
+
+/// Backend that reads gamepads described by `GpioGamepad`. Add it with `Gilrs::add_backend()`.
+///
+/// Devices are opened read-only when `next_event()` is called, so user needs permission to read
+/// them. Devices of disconnected gamepads are searched for every second.
+#[derive(Debug, Default)]
+pub struct GpioBackend {
+    gamepads: Vec<Gamepad>,
+    events: VecDeque<Event>,
+    last_scan: Option<Instant>,
+}
+
+impl GpioBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds gamepad. Its id is the number of gamepads added before it.
+    pub fn add_gamepad(mut self, gamepad: GpioGamepad) -> Self {
+        self.gamepads.push(Gamepad::new(gamepad));
+
+        self
+    }
+
+    fn scan(&mut self) {
+        let mut used = self
+            .gamepads
+            .iter()
+            .flat_map(|g| g.devices.iter().flatten().map(|d| d.path.clone()))
+            .collect::<Vec<_>>();
+
+        for (id, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if gamepad.is_connected {
+                continue;
+            }
+
+            for (spec, slot) in gamepad
+                .config
+                .devices
+                .iter()
+                .zip(gamepad.devices.iter_mut())
+            {
+                if slot.is_none() {
+                    *slot = Device::find(spec, &used);
+                    if let Some(ref device) = *slot {
+                        used.push(device.path.clone());
+                    }
+                }
+            }
+
+            if !gamepad.devices.is_empty() && gamepad.devices.iter().all(Option::is_some) {
+                gamepad.connect(id, &mut self.events);
+            }
+        }
+    }
+}
+
+impl Backend for GpioBackend {
+    fn next_event(&mut self) -> Option<Event> {
+        if !self.events.is_empty() {
+            return self.events.pop_front();
+        }
+
+        let scan = match self.last_scan {
+            Some(time) => time.elapsed() >= SCAN_INTERVAL,
+            None => true,
+        };
+        if scan {
+            self.last_scan = Some(Instant::now());
+            self.scan();
+        }
+
+        for (id, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if gamepad.is_connected && !gamepad.read(id, &mut self.events) {
+                info!("GPIO gamepad {} disconnected.", gamepad.config.name);
+                gamepad.disconnect();
+                self.events
+                    .push_back(Event::new(id, EventType::Disconnected));
+            }
+        }
+
+        self.events.pop_front()
+    }
+
+    fn gamepad(&self, id: usize) -> Option<&(dyn BackendGamepad + 'static)> {
+        self.gamepads
+            .get(id)
+            .map(|g| g as &(dyn BackendGamepad + 'static))
+    }
+
+    fn last_gamepad_hint(&self) -> usize {
+        self.gamepads.len()
+    }
+}
+
+#[derive(Debug)]
+struct Gamepad {
+    config: GpioGamepad,
+    /// Open devices, in order of `config.devices`.
+    devices: Vec<Option<Device>>,
+    buttons: Vec<crate::EvCode>,
+    axes: Vec<crate::EvCode>,
+    axes_info: Vec<(crate::EvCode, AxisInfo)>,
+    is_connected: bool,
+}
+
+impl Gamepad {
+    fn new(config: GpioGamepad) -> Self {
+        let mut buttons = config.buttons.iter().map(|&(_, b)| b).collect::<Vec<_>>();
+        buttons.sort();
+        buttons.dedup();
+        let mut axes = config.axes.iter().map(|&(_, a)| a).collect::<Vec<_>>();
+        axes.sort();
+        axes.dedup();
+
+        Gamepad {
+            devices: config.devices.iter().map(|_| None).collect(),
+            config,
+            buttons,
+            axes,
+            axes_info: Vec::new(),
+            is_connected: false,
+        }
+    }
+
+    /// Reads axis ranges and reports current state of mapped keys and axes, so gamepad doesn't
+    /// look idle until user touches it.
+    fn connect(&mut self, id: usize, events: &mut VecDeque<Event>) {
+        info!("GPIO gamepad {} connected.", self.config.name);
+        events.push_back(Event::new(id, EventType::Connected));
+        self.is_connected = true;
+
+        let mut axes_info = Vec::new();
+        for device in self.devices.iter().flatten() {
+            for (key, pressed) in device.pressed_keys() {
+                if let (Some(button), true) = (self.button(key), pressed) {
+                    events.push_back(Event::new(id, EventType::ButtonPressed(button)));
+                }
+            }
+
+            for (abs, info) in device.axes() {
+                if let Some(axis) = self.axis(abs) {
+                    axes_info.push((
+                        axis,
+                        AxisInfo {
+                            min: info.minimum,
+                            max: info.maximum,
+                            deadzone: Some(info.flat as u32),
+                        },
+                    ));
+                    events.push_back(Event::new(
+                        id,
+                        EventType::AxisValueChanged(info.value, axis),
+                    ));
+                }
+            }
+        }
+        self.axes_info = axes_info;
+    }
+
+    fn disconnect(&mut self) {
+        for device in &mut self.devices {
+            *device = None;
+        }
+        self.is_connected = false;
+    }
+
+    /// Returns `false` if one of devices is no longer available.
+    fn read(&self, id: usize, events: &mut VecDeque<Event>) -> bool {
+        let mut raw = Vec::new();
+        for device in self.devices.iter().flatten() {
+            raw.clear();
+            if !device.read(&mut raw) {
+                return false;
+            }
+
+            for ev in &raw {
+                if let Some(event) = self.translate(ev) {
+                    events.push_back(Event::new(id, event));
+                }
+            }
+        }
+
+        true
+    }
+
+    fn translate(&self, ev: &input_event) -> Option<EventType> {
+        match ev.type_ {
+            EV_KEY => {
+                let button = self.button(ev.code)?;
+                // 2 is autorepeat.
+                match ev.value {
+                    0 => Some(EventType::ButtonReleased(button)),
+                    1 => Some(EventType::ButtonPressed(button)),
+                    _ => None,
+                }
+            }
+            EV_ABS => {
+                let axis = self.axis(ev.code)?;
+                Some(EventType::AxisValueChanged(ev.value, axis))
+            }
+            _ => None,
+        }
+    }
+
+    fn button(&self, key: u16) -> Option<crate::EvCode> {
+        self.config
+            .buttons
+            .iter()
+            .find(|&&(k, _)| k == key)
+            .map(|&(_, b)| b)
+    }
+
+    fn axis(&self, abs: u16) -> Option<crate::EvCode> {
+        self.config
+            .axes
+            .iter()
+            .find(|&&(a, _)| a == abs)
+            .map(|&(_, a)| a)
+    }
+}
+
+impl BackendGamepad for Gamepad {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn uuid(&self) -> [u8; 16] {
+        [0; 16]
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    fn buttons(&self) -> &[crate::EvCode] {
+        &self.buttons
+    }
+
+    fn axes(&self) -> &[crate::EvCode] {
+        &self.axes
+    }
+
+    fn axis_info(&self, nec: crate::EvCode) -> Option<&AxisInfo> {
+        self.axes_info
+            .iter()
+            .find(|&&(axis, _)| axis == nec)
+            .map(|(_, info)| info)
+    }
+
+    /// Same as other evdev devices.
+    fn is_y_axis_reversed(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug)]
+struct Device {
+    fd: i32,
+    path: String,
+}
+
+impl Device {
+    fn open(path: &str) -> Option<Self> {
+        let cpath = CString::new(path).ok()?;
+        let fd = unsafe { c::open(cpath.as_ptr(), c::O_RDONLY | c::O_NONBLOCK) };
+        if fd < 0 {
+            debug!("Failed to open {}: {}", path, io::Error::last_os_error());
+            return None;
+        }
+
+        Some(Device {
+            fd,
+            path: path.to_owned(),
+        })
+    }
+
+    /// Opens device with given path or, if `spec` is not a path, first device with given name.
+    /// Devices in `used` are skipped.
+    fn find(spec: &str, used: &[String]) -> Option<Self> {
+        if spec.starts_with('/') {
+            if used.iter().any(|p| p == spec) {
+                return None;
+            }
+            return Device::open(spec);
+        }
+
+        let mut paths = fs::read_dir(INPUT_DIR)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name.starts_with("event"))
+            .map(|name| format!("{}/{}", INPUT_DIR, name))
+            .filter(|path| !used.contains(path))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            if let Some(device) = Device::open(&path) {
+                match device.name() {
+                    Some(ref name) if name == spec => return Some(device),
+                    _ => (),
+                }
+            }
+        }
+
+        None
+    }
+
+    fn name(&self) -> Option<String> {
+        unsafe {
+            let mut buf: [MaybeUninit<u8>; 128] = MaybeUninit::uninit().assume_init();
+            if ioctl::eviocgname(self.fd, &mut buf).is_err() {
+                return None;
+            }
+
+            Some(
+                CStr::from_ptr(buf.as_ptr() as *const c_char)
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    /// Returns state of all keys supported by device.
+    fn pressed_keys(&self) -> Vec<(u16, bool)> {
+        let mut key_bits = [0u8; (KEY_MAX / 8) as usize + 1];
+        let mut key_state = [0u8; (KEY_MAX / 8) as usize + 1];
+        unsafe {
+            ioctl::eviocgbit(
+                self.fd,
+                u32::from(EV_KEY),
+                key_bits.len() as i32,
+                key_bits.as_mut_ptr(),
+            );
+            let _ = ioctl::eviocgkey(self.fd, &mut key_state);
+        }
+
+        (0..=KEY_MAX)
+            .filter(|&key| utils::test_bit(key, &key_bits))
+            .map(|key| (key, utils::test_bit(key, &key_state)))
+            .collect()
+    }
+
+    /// Returns range and current value of all axes supported by device.
+    fn axes(&self) -> Vec<(u16, input_absinfo)> {
+        let mut abs_bits = [0u8; (ABS_MAX / 8) as usize + 1];
+        unsafe {
+            ioctl::eviocgbit(
+                self.fd,
+                u32::from(EV_ABS),
+                abs_bits.len() as i32,
+                abs_bits.as_mut_ptr(),
+            );
+        }
+
+        (0..=ABS_MAX)
+            .filter(|&abs| utils::test_bit(abs, &abs_bits))
+            .filter_map(|abs| {
+                let mut info = input_absinfo::default();
+                if unsafe { ioctl::eviocgabs(self.fd, u32::from(abs), &mut info) } < 0 {
+                    None
+                } else {
+                    Some((abs, info))
+                }
+            })
+            .collect()
+    }
+
+    /// Reads all pending events. Returns `false` if device is no longer available.
+    fn read(&self, events: &mut Vec<input_event>) -> bool {
+        let mut buf = [input_event::default(); 16];
+        let size = mem::size_of::<input_event>();
+        loop {
+            let n = unsafe {
+                c::read(
+                    self.fd,
+                    buf.as_mut_ptr() as *mut c::c_void,
+                    size * buf.len(),
+                )
+            };
+
+            if n < 0 {
+                let err = io::Error::last_os_error();
+                return err.kind() == io::ErrorKind::WouldBlock;
+            }
+
+            let n = n as usize / size;
+            events.extend_from_slice(&buf[..n]);
+            if n < buf.len() {
+                return true;
+            }
+        }
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        unsafe {
+            c::close(self.fd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{input_event, EV_ABS, EV_KEY};
+    use super::{Gamepad, GpioGamepad};
+    use crate::native_ev_codes as nec;
+    use crate::EventType;
+
+    #[test]
+    fn translate_mapped_codes() {
+        let gamepad = Gamepad::new(
+            GpioGamepad::new("test")
+                .map_button(28, nec::BTN_SOUTH)
+                .map_axis(0, nec::AXIS_LSTICKX),
+        );
+        let event = |type_, code, value| input_event {
+            type_,
+            code,
+            value,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            gamepad.translate(&event(EV_KEY, 28, 1)),
+            Some(EventType::ButtonPressed(nec::BTN_SOUTH))
+        );
+        assert_eq!(
+            gamepad.translate(&event(EV_KEY, 28, 0)),
+            Some(EventType::ButtonReleased(nec::BTN_SOUTH))
+        );
+        assert_eq!(gamepad.translate(&event(EV_KEY, 28, 2)), None);
+        assert_eq!(gamepad.translate(&event(EV_KEY, 29, 1)), None);
+        assert_eq!(
+            gamepad.translate(&event(EV_ABS, 0, -300)),
+            Some(EventType::AxisValueChanged(-300, nec::AXIS_LSTICKX))
+        );
+        assert_eq!(gamepad.buttons, vec![nec::BTN_SOUTH]);
+    }
+}
@@ -10,6 +10,8 @@
 // difference, so it looks like conditional compilation is not needed.
 
 use nix::{ioctl_read, ioctl_read_buf, ioctl_write_int, ioctl_write_ptr, request_code_read};
+// Argument type of `ioctl_write_int!` functions, `c_ulong` on Linux and `c_int` on BSDs.
+pub use nix::sys::ioctl::ioctl_param_type;
 use std::mem::MaybeUninit;
 
 #[cfg(target_env = "musl")]
@@ -19,10 +21,24 @@ pub type IoctlRequest = libc::c_ulong;
 
 ioctl_read!(eviocgid, b'E', 0x02, /*struct*/ input_id);
 ioctl_write_int!(eviocrmff, b'E', 0x81);
+ioctl_write_int!(eviocgrab, b'E', 0x90);
 ioctl_write_ptr!(eviocsff, b'E', 0x80, ff_effect);
 ioctl_read_buf!(eviocgname, b'E', 0x06, MaybeUninit<u8>);
+ioctl_read_buf!(eviocgphys, b'E', 0x07, MaybeUninit<u8>);
+ioctl_read_buf!(eviocguniq, b'E', 0x08, MaybeUninit<u8>);
 ioctl_read_buf!(eviocgkey, b'E', 0x18, u8);
 
+#[cfg(all(feature = "virtual-gamepad", target_os = "linux"))]
+nix::ioctl_none!(ui_dev_create, b'U', 1);
+#[cfg(all(feature = "virtual-gamepad", target_os = "linux"))]
+nix::ioctl_none!(ui_dev_destroy, b'U', 2);
+#[cfg(all(feature = "virtual-gamepad", target_os = "linux"))]
+ioctl_write_int!(ui_set_evbit, b'U', 100);
+#[cfg(all(feature = "virtual-gamepad", target_os = "linux"))]
+ioctl_write_int!(ui_set_keybit, b'U', 101);
+#[cfg(all(feature = "virtual-gamepad", target_os = "linux"))]
+ioctl_write_int!(ui_set_absbit, b'U', 103);
+
 pub unsafe fn eviocgbit(fd: libc::c_int, ev: u32, len: libc::c_int, buf: *mut u8) -> libc::c_int {
     ::nix::libc::ioctl(
         fd,
@@ -74,6 +90,21 @@ pub struct input_id {
     pub version: u16,
 }
 
+/// Legacy setup of uinput device, written to `/dev/uinput` before `UI_DEV_CREATE`. Unlike
+/// `UI_DEV_SETUP`, it's supported by all kernels.
+#[cfg(all(feature = "virtual-gamepad", target_os = "linux"))]
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct uinput_user_dev {
+    pub name: [libc::c_char; 80],
+    pub id: input_id,
+    pub ff_effects_max: u32,
+    pub absmax: [i32; 64],
+    pub absmin: [i32; 64],
+    pub absfuzz: [i32; 64],
+    pub absflat: [i32; 64],
+}
+
 #[derive(Copy, Clone, Default, PartialEq, Debug)]
 #[repr(C)]
 pub struct input_absinfo {
@@ -7,10 +7,18 @@
 // Copyright 2016 GilRs Developers
 mod ff;
 mod gamepad;
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+mod gpio;
 mod ioctl;
 mod udev;
+#[cfg(all(feature = "virtual-gamepad", target_os = "linux"))]
+mod uinput;
 
 pub use self::ff::Device as FfDevice;
 pub use self::gamepad::{native_ev_codes, EvCode, Gamepad, Gilrs};
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+pub use self::gpio::{GpioBackend, GpioGamepad};
+#[cfg(all(feature = "virtual-gamepad", target_os = "linux"))]
+pub use self::uinput::VirtualDevice;
 
 pub const IS_Y_AXIS_REVERSED: bool = true;
@@ -222,10 +222,14 @@ impl Monitor {
         }
     }
 
+    pub fn fd(&self) -> c::c_int {
+        unsafe { ud::udev_monitor_get_fd(self.0) }
+    }
+
     pub fn hotplug_available(&self) -> bool {
         unsafe {
             let mut fds = c::pollfd {
-                fd: ud::udev_monitor_get_fd(self.0),
+                fd: self.fd(),
                 events: c::POLLIN,
                 revents: 0,
             };
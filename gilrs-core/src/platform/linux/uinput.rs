@@ -0,0 +1,172 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Virtual gamepad created with uinput. It has the same buttons, axes and ids as Xbox 360
+//! controller handled by `xpad` driver, so applications use their mapping for it.
+
+use super::gamepad::{
+    ABS_HAT0X, ABS_HAT0Y, ABS_RX, ABS_RY, ABS_RZ, ABS_X, ABS_Y, ABS_Z, BTN_EAST, BTN_MODE,
+    BTN_NORTH, BTN_SELECT, BTN_SOUTH, BTN_START, BTN_THUMBL, BTN_THUMBR, BTN_TL, BTN_TR, BTN_WEST,
+    EV_ABS, EV_KEY, EV_SYN, SYN_REPORT,
+};
+use super::ioctl::{self, input_event, input_id, uinput_user_dev};
+use crate::virtual_gamepad::{self as xusb, Report};
+
+use libc as c;
+
+use std::ffi::CString;
+use std::io;
+use std::mem;
+
+const UINPUT_PATH: &str = "/dev/uinput";
+
+const BUS_USB: u16 = 0x03;
+const XBOX_360_VENDOR: u16 = 0x045e;
+const XBOX_360_PRODUCT: u16 = 0x028e;
+const XBOX_360_VERSION: u16 = 0x0110;
+
+// Like xpad, X and Y buttons are reported as `BTN_X` and `BTN_Y`, which are aliases of
+// `BTN_NORTH` and `BTN_WEST`.
+const BUTTONS: [(u16, u16); 11] = [
+    (xusb::A, BTN_SOUTH),
+    (xusb::B, BTN_EAST),
+    (xusb::X, BTN_NORTH),
+    (xusb::Y, BTN_WEST),
+    (xusb::LEFT_SHOULDER, BTN_TL),
+    (xusb::RIGHT_SHOULDER, BTN_TR),
+    (xusb::BACK, BTN_SELECT),
+    (xusb::START, BTN_START),
+    (xusb::GUIDE, BTN_MODE),
+    (xusb::LEFT_THUMB, BTN_THUMBL),
+    (xusb::RIGHT_THUMB, BTN_THUMBR),
+];
+
+#[derive(Debug)]
+pub struct VirtualDevice {
+    fd: i32,
+}
+
+impl VirtualDevice {
+    pub fn new(name: &str) -> io::Result<Self> {
+        let path = CString::new(UINPUT_PATH).unwrap();
+        let fd = unsafe { c::open(path.as_ptr(), c::O_WRONLY | c::O_NONBLOCK) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // Closes fd if setup fails.
+        let device = VirtualDevice { fd };
+        device.setup(name)?;
+        info!("Created virtual gamepad {}", name);
+
+        Ok(device)
+    }
+
+    fn setup(&self, name: &str) -> io::Result<()> {
+        // Buttons and axes of `xpad`. Sticks have fuzz and flat like in xpad, so applications
+        // apply similar deadzone.
+        let axes = [
+            (ABS_X, -32768, 32767, 16, 128),
+            (ABS_Y, -32768, 32767, 16, 128),
+            (ABS_RX, -32768, 32767, 16, 128),
+            (ABS_RY, -32768, 32767, 16, 128),
+            (ABS_Z, 0, 255, 0, 0),
+            (ABS_RZ, 0, 255, 0, 0),
+            (ABS_HAT0X, -1, 1, 0, 0),
+            (ABS_HAT0Y, -1, 1, 0, 0),
+        ];
+
+        let mut dev: uinput_user_dev = unsafe { mem::zeroed() };
+        for (dst, &src) in dev.name.iter_mut().zip(name.as_bytes().iter().take(79)) {
+            *dst = src as c::c_char;
+        }
+        dev.id = input_id {
+            bustype: BUS_USB,
+            vendor: XBOX_360_VENDOR,
+            product: XBOX_360_PRODUCT,
+            version: XBOX_360_VERSION,
+        };
+
+        unsafe {
+            ioctl::ui_set_evbit(self.fd, EV_KEY as ioctl::ioctl_param_type).map_err(nix_err)?;
+            ioctl::ui_set_evbit(self.fd, EV_ABS as ioctl::ioctl_param_type).map_err(nix_err)?;
+            for &(_, key) in BUTTONS.iter() {
+                ioctl::ui_set_keybit(self.fd, key as ioctl::ioctl_param_type).map_err(nix_err)?;
+            }
+            for &(abs, min, max, fuzz, flat) in axes.iter() {
+                ioctl::ui_set_absbit(self.fd, abs as ioctl::ioctl_param_type).map_err(nix_err)?;
+                dev.absmin[abs as usize] = min;
+                dev.absmax[abs as usize] = max;
+                dev.absfuzz[abs as usize] = fuzz;
+                dev.absflat[abs as usize] = flat;
+            }
+
+            let size = mem::size_of::<uinput_user_dev>();
+            let n = c::write(self.fd, &dev as *const _ as *const c::c_void, size);
+            if n != size as isize {
+                return Err(io::Error::last_os_error());
+            }
+
+            ioctl::ui_dev_create(self.fd).map_err(nix_err)?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn send(&self, report: &Report) -> io::Result<()> {
+        let event = |type_, code, value| input_event {
+            type_,
+            code,
+            value,
+            ..Default::default()
+        };
+        let pressed = |bit| report.buttons & bit != 0;
+        let hat = |negative, positive| i32::from(pressed(positive)) - i32::from(pressed(negative));
+
+        let mut events: Vec<_> = BUTTONS
+            .iter()
+            .map(|&(bit, key)| event(EV_KEY, key, i32::from(pressed(bit))))
+            .collect();
+        // Positive Y of evdev is down.
+        events.extend_from_slice(&[
+            event(EV_ABS, ABS_X, i32::from(report.thumb_lx)),
+            event(EV_ABS, ABS_Y, -i32::from(report.thumb_ly)),
+            event(EV_ABS, ABS_RX, i32::from(report.thumb_rx)),
+            event(EV_ABS, ABS_RY, -i32::from(report.thumb_ry)),
+            event(EV_ABS, ABS_Z, i32::from(report.left_trigger)),
+            event(EV_ABS, ABS_RZ, i32::from(report.right_trigger)),
+            event(EV_ABS, ABS_HAT0X, hat(xusb::DPAD_LEFT, xusb::DPAD_RIGHT)),
+            event(EV_ABS, ABS_HAT0Y, hat(xusb::DPAD_UP, xusb::DPAD_DOWN)),
+            event(EV_SYN, SYN_REPORT, 0),
+        ]);
+
+        // Kernel drops events that don't change state.
+        let size = mem::size_of::<input_event>() * events.len();
+        let n = unsafe { c::write(self.fd, events.as_ptr() as *const c::c_void, size) };
+        if n != size as isize {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for VirtualDevice {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = ioctl::ui_dev_destroy(self.fd);
+            c::close(self.fd);
+        }
+    }
+}
+
+fn nix_err(e: nix::Error) -> io::Error {
+    match e.as_errno() {
+        Some(errno) => io::Error::from_raw_os_error(errno as i32),
+        None => io::Error::new(io::ErrorKind::InvalidInput, e),
+    }
+}
@@ -7,10 +7,14 @@
 
 use super::io_kit::*;
 use super::FfDevice;
-use crate::{AxisInfo, Event, EventType, PlatformError, PowerInfo};
+use crate::utils::{self, Watchdog, WatchdogStatus};
+use crate::{
+    AxisInfo, BackendErrorKind, BackendInfo, ErrorQueue, Event, EventType, PlatformError, PollMode,
+    PowerInfo, RawWaitHandle,
+};
 use uuid::Uuid;
 
-use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop};
+use core_foundation::runloop::{kCFRunLoopDefaultMode, CFRunLoop, CFRunLoopRunInMode};
 use io_kit_sys::hid::base::{IOHIDDeviceRef, IOHIDValueRef};
 use io_kit_sys::hid::usage_tables::{
     kHIDPage_GenericDesktop, kHIDUsage_GD_GamePad, kHIDUsage_GD_Joystick,
@@ -19,70 +23,192 @@ use io_kit_sys::hid::usage_tables::{
 use io_kit_sys::ret::IOReturn;
 use vec_map::VecMap;
 
+use std::collections::VecDeque;
+use std::error::Error as StdError;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io;
 use std::os::raw::c_void;
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::sync::{Arc, Mutex};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
+use std::time::Duration;
+
+/// How long (in seconds) event thread waits for events before checking if it should exit.
+const RUN_LOOP_TIMEOUT: f64 = 0.1;
 
 #[derive(Debug)]
 pub struct Gilrs {
     gamepads: Vec<Gamepad>,
     device_infos: Arc<Mutex<Vec<DeviceInfo>>>,
     rx: Receiver<(Event, Option<IOHIDDevice>)>,
+    /// Tells event thread to exit. `None` when backend is stopped.
+    stop_flag: Option<Arc<AtomicBool>>,
+    watchdog: Watchdog,
+    is_dead: bool,
+    pending_events: VecDeque<Event>,
+    /// Event received by `wait()`, returned by next call to `next_event()`.
+    received: Option<(Event, Option<IOHIDDevice>)>,
+    is_suspended: bool,
+    errors: ErrorQueue,
 }
 
 impl Gilrs {
-    pub(crate) fn new() -> Result<Self, PlatformError> {
+    pub(crate) fn new(errors: ErrorQueue) -> Result<Self, PlatformError> {
         let gamepads = Vec::new();
         let device_infos = Arc::new(Mutex::new(Vec::new()));
 
         let (tx, rx) = mpsc::channel();
-        Self::spawn_thread(tx, device_infos.clone());
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let mut watchdog = Watchdog::new();
+        Self::spawn_thread(
+            tx,
+            device_infos.clone(),
+            stop_flag.clone(),
+            watchdog.heartbeat(),
+            errors.clone(),
+        )
+        .map_err(|e| PlatformError::Other(Box::new(e)))?;
 
         Ok(Gilrs {
             gamepads,
             device_infos,
             rx,
+            stop_flag: Some(stop_flag),
+            watchdog,
+            is_dead: false,
+            pending_events: VecDeque::new(),
+            received: None,
+            is_suspended: false,
+            errors,
         })
     }
 
     fn spawn_thread(
         tx: Sender<(Event, Option<IOHIDDevice>)>,
         device_infos: Arc<Mutex<Vec<DeviceInfo>>>,
-    ) {
-        thread::spawn(move || unsafe {
-            let mut manager = match IOHIDManager::new() {
-                Some(manager) => manager,
-                None => {
-                    error!("Failed to create IOHIDManager object");
-                    return;
+        stop_flag: Arc<AtomicBool>,
+        heartbeat: Arc<AtomicUsize>,
+        errors: ErrorQueue,
+    ) -> Result<(), Error> {
+        // Thread reports whether it managed to create IOHIDManager, so failure is returned from
+        // `new()` or `restart()` instead of leaving backend that never emits events.
+        let (init_tx, init_rx) = mpsc::channel();
+        let builder = thread::Builder::new().name("gilrs-iokit".to_owned());
+        builder
+            .spawn(move || unsafe {
+                let mut manager = match IOHIDManager::new() {
+                    Some(manager) => manager,
+                    None => {
+                        error!("Failed to create IOHIDManager object");
+                        let _ = init_tx.send(false);
+                        return;
+                    }
+                };
+                let _ = init_tx.send(true);
+
+                manager.schedule_with_run_loop(CFRunLoop::get_current(), kCFRunLoopDefaultMode);
+
+                // Context must outlive run loop, callbacks keep raw pointer to it.
+                let context: CallbackContext = (tx, device_infos, errors);
+                let context_ptr = &context as *const _ as *mut c_void;
+                manager.register_device_matching_callback(device_matching_cb, context_ptr);
+                manager.register_device_removal_callback(device_removal_cb, context_ptr);
+                manager.register_input_value_callback(input_value_cb, context_ptr);
+
+                while !stop_flag.load(Ordering::SeqCst) {
+                    heartbeat.fetch_add(1, Ordering::Relaxed);
+                    // Return regularly to check if backend was stopped.
+                    CFRunLoopRunInMode(kCFRunLoopDefaultMode, RUN_LOOP_TIMEOUT, 0);
                 }
-            };
 
-            manager.schedule_with_run_loop(CFRunLoop::get_current(), kCFRunLoopDefaultMode);
+                manager.unschedule_from_run_loop(CFRunLoop::get_current(), kCFRunLoopDefaultMode);
+            })
+            .map_err(Error::ThreadSpawn)?;
+
+        match init_rx.recv() {
+            Ok(true) => Ok(()),
+            _ => Err(Error::HidManager),
+        }
+    }
 
-            let context = &(tx.clone(), device_infos.clone()) as *const _ as *mut c_void;
-            manager.register_device_matching_callback(device_matching_cb, context);
+    pub(crate) fn stop(&mut self) {
+        match self.stop_flag.take() {
+            Some(flag) => flag.store(true, Ordering::SeqCst),
+            None => return,
+        }
 
-            let context = &(tx.clone(), device_infos.clone()) as *const _ as *mut c_void;
-            manager.register_device_removal_callback(device_removal_cb, context);
+        // Events that are still in channel come from thread that is going to exit, drop them.
+        let (_, rx) = mpsc::channel();
+        self.rx = rx;
 
-            let context = &(tx, device_infos) as *const _ as *mut c_void;
-            manager.register_input_value_callback(input_value_cb, context);
+        let mut device_infos = lock_ignore_poison(&self.device_infos);
+        for (id, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if gamepad.is_connected {
+                gamepad.is_connected = false;
+                if let Some(info) = device_infos.get_mut(id) {
+                    info.is_connected = false;
+                }
+                self.pending_events
+                    .push_back(Event::new(id, EventType::Disconnected));
+            }
+        }
+    }
 
-            CFRunLoop::run_current();
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn StdError + Send + Sync>> {
+        if self.stop_flag.is_some() {
+            return Ok(());
+        }
 
-            manager.unschedule_from_run_loop(CFRunLoop::get_current(), kCFRunLoopDefaultMode);
-        });
+        // IOHIDManager calls matching callback for all devices that are already connected, so
+        // they will be reported again.
+        let (tx, rx) = mpsc::channel();
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        Self::spawn_thread(
+            tx,
+            self.device_infos.clone(),
+            stop_flag.clone(),
+            self.watchdog.heartbeat(),
+            self.errors.clone(),
+        )?;
+        self.rx = rx;
+        self.stop_flag = Some(stop_flag);
+        self.is_dead = false;
+
+        Ok(())
+    }
+
+    // IOKit doesn't let us read current state of all elements cheaply, so events are left in
+    // channel and delivered after resuming. That's enough to end up with correct state.
+    pub(crate) fn suspend(&mut self) {
+        self.is_suspended = true;
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.is_suspended = false;
     }
 
     pub(crate) fn next_event(&mut self) -> Option<Event> {
-        match self.rx.try_recv().ok() {
-            Some((event, Some(device))) => {
+        if let Some(event) = self.pending_events.pop_front() {
+            return Some(event);
+        }
+
+        if self.is_suspended {
+            return None;
+        }
+
+        let received = match self.received.take() {
+            Some(ev) => Ok(ev),
+            None => self.rx.try_recv(),
+        };
+
+        match received {
+            Ok((event, Some(device))) => {
                 if event.event == EventType::Connected {
-                    if self.gamepads.get(event.id).is_some() {
-                        self.gamepads[event.id].is_connected = true;
+                    if let Some(gamepad) = self.gamepads.get_mut(event.id) {
+                        gamepad.device = device;
+                        gamepad.is_connected = true;
                     } else {
                         match Gamepad::open(device) {
                             Some(gamepad) => {
@@ -90,6 +216,12 @@ impl Gilrs {
                             }
                             None => {
                                 error!("Failed to open gamepad: {:?}", event.id);
+                                self.errors.push(
+                                    Some(event.id),
+                                    BackendErrorKind::DeviceOpen,
+                                    "device is not a supported gamepad or doesn't report \
+                                     required properties",
+                                );
                                 return None;
                             }
                         };
@@ -97,7 +229,7 @@ impl Gilrs {
                 }
                 Some(event)
             }
-            Some((event, None)) => {
+            Ok((event, None)) => {
                 if event.event == EventType::Disconnected {
                     match self.gamepads.get_mut(event.id) {
                         Some(gamepad) => {
@@ -118,10 +250,83 @@ impl Gilrs {
                 }
                 Some(event)
             }
-            None => None,
+            Err(e) => {
+                self.check_watchdog(e == TryRecvError::Disconnected);
+                self.pending_events.pop_front()
+            }
+        }
+    }
+
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        if !self.pending_events.is_empty() || self.received.is_some() || self.is_suspended {
+            return;
+        }
+
+        let timeout = timeout.map_or(utils::WAIT_SLICE, |t| t.min(utils::WAIT_SLICE));
+        // Errors are handled by watchdog in `next_event()`.
+        self.received = self.rx.recv_timeout(timeout).ok();
+    }
+
+    pub(crate) fn set_poll_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn set_poll_mode(&mut self, _mode: PollMode) {}
+
+    pub(crate) fn set_connection_check_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        None
+    }
+
+    pub(crate) fn refresh(&mut self) {}
+
+    pub(crate) fn set_event_queue_capacity(&mut self, _capacity: Option<usize>) {}
+
+    /// Respawns event thread if it died or one of callbacks hangs. Connected gamepads are
+    /// reported as disconnected and then connected again when new thread enumerates devices.
+    fn check_watchdog(&mut self, exited: bool) {
+        if self.stop_flag.is_none() {
+            return;
+        }
+
+        match self.watchdog.check(exited) {
+            WatchdogStatus::Alive => (),
+            WatchdogStatus::Restart(reason) => {
+                warn!("Restarting IOKit thread: {}", reason);
+                self.errors.push(None, BackendErrorKind::Watchdog, reason);
+                self.stop();
+                if let Err(e) = self.restart() {
+                    self.errors
+                        .push(None, BackendErrorKind::Watchdog, e.to_string());
+                    self.is_dead = true;
+                }
+            }
+            WatchdogStatus::Dead => {
+                error!("IOKit thread keeps failing, giving up.");
+                self.errors.push(
+                    None,
+                    BackendErrorKind::Watchdog,
+                    "event thread keeps failing after restart",
+                );
+                self.stop();
+                self.is_dead = true;
+                // Start from scratch if application calls `restart()`.
+                self.watchdog = Watchdog::new();
+            }
         }
     }
 
+    pub(crate) fn is_alive(&self) -> bool {
+        !self.is_dead
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        BackendInfo::new("IOKit", None)
+    }
+
     pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
         self.gamepads.get(id)
     }
@@ -132,6 +337,15 @@ impl Gilrs {
     }
 }
 
+impl Drop for Gilrs {
+    fn drop(&mut self) {
+        // Event thread exits on next run loop iteration.
+        if let Some(flag) = self.stop_flag.take() {
+            flag.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Gamepad {
     name: String,
@@ -144,6 +358,7 @@ pub struct Gamepad {
     axes: Vec<EvCode>,
     buttons: Vec<EvCode>,
     is_connected: bool,
+    device: IOHIDDevice,
 }
 
 impl Gamepad {
@@ -226,8 +441,10 @@ impl Gamepad {
             axes: Vec::with_capacity(8),
             buttons: Vec::with_capacity(16),
             is_connected: true,
+            device,
         };
-        gamepad.collect_axes_and_buttons(&device.get_elements());
+        let elements = gamepad.device.get_elements();
+        gamepad.collect_axes_and_buttons(&elements);
 
         Some(gamepad)
     }
@@ -296,13 +513,58 @@ impl Gamepad {
     }
 
     pub fn power_info(&self) -> PowerInfo {
-        PowerInfo::Unknown
+        if !self.is_connected {
+            return PowerInfo::Unknown;
+        }
+
+        // IOKit doesn't report charging state for HID devices, so assume that gamepad with battery
+        // is being charged when it's connected by cable. Wireless gamepad is always discharging,
+        // even with full battery.
+        let is_usb = self.device.get_bustype() == Some(0x03);
+
+        match self.device.get_battery_percent() {
+            Some(100) if is_usb => PowerInfo::Charged,
+            Some(lvl) if is_usb => PowerInfo::Charging(lvl),
+            Some(lvl) => PowerInfo::Discharging(lvl),
+            None if is_usb => PowerInfo::Wired,
+            None => {
+                trace!("Failed to get battery level of {}", self.name);
+
+                PowerInfo::Unknown
+            }
+        }
     }
 
     pub fn is_ff_supported(&self) -> bool {
         false
     }
 
+    pub fn is_y_axis_reversed(&self) -> bool {
+        super::IS_Y_AXIS_REVERSED
+    }
+
+    pub fn has_mic_led(&self) -> bool {
+        false
+    }
+
+    pub fn set_mic_led(&self, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "microphone LED is not supported on this platform",
+        ))
+    }
+
+    pub fn is_exclusive_supported(&self) -> bool {
+        false
+    }
+
+    pub fn set_exclusive(&self, _exclusive: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "exclusive access is not supported on this platform",
+        ))
+    }
+
     /// Creates Ffdevice corresponding to this gamepad.
     pub fn ff_device(&self) -> Option<FfDevice> {
         Some(FfDevice)
@@ -396,6 +658,25 @@ impl Gamepad {
     }
 }
 
+#[derive(Debug)]
+enum Error {
+    ThreadSpawn(io::Error),
+    HidManager,
+}
+
+impl StdError for Error {}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Error::ThreadSpawn(e) => {
+                f.write_fmt(format_args!("Failed to spawn IOKit thread {}", e))
+            }
+            Error::HidManager => f.write_str("Failed to create IOHIDManager object"),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct DeviceInfo {
     entry_id: u64,
@@ -571,18 +852,100 @@ pub mod native_ev_codes {
         page: super::PAGE_BUTTON,
         usage: super::USAGE_BTN_DPAD_RIGHT,
     };
+
+    // Back buttons of Steam Deck, only reported by Steam Input backend.
+    #[cfg(feature = "steam")]
+    pub const BTN_L4: EvCode = EvCode {
+        page: super::PAGE_BUTTON,
+        usage: super::USAGE_BTN_DPAD_RIGHT + 1,
+    };
+    #[cfg(feature = "steam")]
+    pub const BTN_R4: EvCode = EvCode {
+        page: super::PAGE_BUTTON,
+        usage: super::USAGE_BTN_DPAD_RIGHT + 2,
+    };
+    #[cfg(feature = "steam")]
+    pub const BTN_L5: EvCode = EvCode {
+        page: super::PAGE_BUTTON,
+        usage: super::USAGE_BTN_DPAD_RIGHT + 3,
+    };
+    #[cfg(feature = "steam")]
+    pub const BTN_R5: EvCode = EvCode {
+        page: super::PAGE_BUTTON,
+        usage: super::USAGE_BTN_DPAD_RIGHT + 4,
+    };
+
+    // Touchpad click and microphone button of Sony controllers, only reported by HID drivers.
+    #[cfg(feature = "hidapi")]
+    pub const BTN_TOUCHPAD: EvCode = EvCode {
+        page: super::PAGE_BUTTON,
+        usage: super::USAGE_BTN_DPAD_RIGHT + 5,
+    };
+    #[cfg(feature = "hidapi")]
+    pub const BTN_MISC1: EvCode = EvCode {
+        page: super::PAGE_BUTTON,
+        usage: super::USAGE_BTN_DPAD_RIGHT + 6,
+    };
+
+    // Codes of elements mapped to part of other element's axis, never reported by backend.
+    pub const SPLIT_AXES: [EvCode; 4] = [
+        EvCode {
+            page: super::PAGE_BUTTON,
+            usage: super::USAGE_BTN_DPAD_RIGHT + 7,
+        },
+        EvCode {
+            page: super::PAGE_BUTTON,
+            usage: super::USAGE_BTN_DPAD_RIGHT + 8,
+        },
+        EvCode {
+            page: super::PAGE_BUTTON,
+            usage: super::USAGE_BTN_DPAD_RIGHT + 9,
+        },
+        EvCode {
+            page: super::PAGE_BUTTON,
+            usage: super::USAGE_BTN_DPAD_RIGHT + 10,
+        },
+    ];
+}
+
+type CallbackContext = (
+    Sender<(Event, Option<IOHIDDevice>)>,
+    Arc<Mutex<Vec<DeviceInfo>>>,
+    ErrorQueue,
+);
+
+/// Runs body of IOKit callback. Unwinding into C code aborts the process, so panics are caught
+/// here and reported through the error queue instead.
+fn catch_callback_panic<F: FnOnce()>(context: *mut c_void, callback: &str, f: F) {
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+        let (_, _, errors): &CallbackContext = unsafe { &*(context as *mut _) };
+        let msg = utils::panic_message(&*payload);
+        error!("Panic in {} callback: {}", callback, msg);
+        errors.push(None, BackendErrorKind::Other, format!("panic: {}", msg));
+    }
+}
+
+/// Callback that panicked while holding the lock poisons it. Recover instead of panicking in
+/// every callback that follows.
+fn lock_ignore_poison(device_infos: &Mutex<Vec<DeviceInfo>>) -> MutexGuard<'_, Vec<DeviceInfo>> {
+    device_infos
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
 }
 
 extern "C" fn device_matching_cb(
     context: *mut c_void,
     _result: IOReturn,
-    _sender: *mut c_void,
+    sender: *mut c_void,
     value: IOHIDDeviceRef,
 ) {
-    let (tx, device_infos): &(
-        Sender<(Event, Option<IOHIDDevice>)>,
-        Arc<Mutex<Vec<DeviceInfo>>>,
-    ) = unsafe { &*(context as *mut _) };
+    catch_callback_panic(context, "device_matching", || {
+        device_matching(context, sender, value)
+    });
+}
+
+fn device_matching(context: *mut c_void, _sender: *mut c_void, value: IOHIDDeviceRef) {
+    let (tx, device_infos, _): &CallbackContext = unsafe { &*(context as *mut _) };
     let device = match IOHIDDevice::new(value) {
         Some(device) => device,
         None => {
@@ -607,15 +970,20 @@ extern "C" fn device_matching_cb(
         }
     };
 
-    let mut device_infos = device_infos.lock().unwrap();
+    let mut device_infos = lock_ignore_poison(device_infos);
     let id = match device_infos
         .iter()
-        .position(|info| info.entry_id == entry_id && info.is_connected)
+        .position(|info| info.entry_id == entry_id)
     {
-        Some(id) => {
+        Some(id) if device_infos[id].is_connected => {
             info!("Device is already registered: {:?}", entry_id);
             id
         }
+        // Device was seen before the backend was restarted, keep its id.
+        Some(id) => {
+            device_infos[id].is_connected = true;
+            id
+        }
         None => {
             let location_id = match device.get_location_id() {
                 Some(location_id) => location_id,
@@ -640,13 +1008,16 @@ extern "C" fn device_matching_cb(
 extern "C" fn device_removal_cb(
     context: *mut c_void,
     _result: IOReturn,
-    _sender: *mut c_void,
+    sender: *mut c_void,
     value: IOHIDDeviceRef,
 ) {
-    let (tx, device_infos): &(
-        Sender<(Event, Option<IOHIDDevice>)>,
-        Arc<Mutex<Vec<DeviceInfo>>>,
-    ) = unsafe { &*(context as *mut _) };
+    catch_callback_panic(context, "device_removal", || {
+        device_removal(context, sender, value)
+    });
+}
+
+fn device_removal(context: *mut c_void, _sender: *mut c_void, value: IOHIDDeviceRef) {
+    let (tx, device_infos, _): &CallbackContext = unsafe { &*(context as *mut _) };
 
     let device = match IOHIDDevice::new(value) {
         Some(device) => device,
@@ -664,7 +1035,7 @@ extern "C" fn device_removal_cb(
         }
     };
 
-    let device_infos = device_infos.lock().unwrap();
+    let device_infos = lock_ignore_poison(device_infos);
     let id = match device_infos
         .iter()
         .position(|info| info.location_id == location_id && info.is_connected)
@@ -685,10 +1056,13 @@ extern "C" fn input_value_cb(
     sender: *mut c_void,
     value: IOHIDValueRef,
 ) {
-    let (tx, device_infos): &(
-        Sender<(Event, Option<IOHIDDevice>)>,
-        Arc<Mutex<Vec<DeviceInfo>>>,
-    ) = unsafe { &*(context as *mut _) };
+    catch_callback_panic(context, "input_value", || {
+        input_value(context, sender, value)
+    });
+}
+
+fn input_value(context: *mut c_void, sender: *mut c_void, value: IOHIDValueRef) {
+    let (tx, device_infos, _): &CallbackContext = unsafe { &*(context as *mut _) };
 
     let device = match IOHIDDevice::new(sender as _) {
         Some(device) => device,
@@ -714,7 +1088,7 @@ extern "C" fn input_value_cb(
         }
     };
 
-    let device_infos = device_infos.lock().unwrap();
+    let device_infos = lock_ignore_poison(device_infos);
     let id = match device_infos
         .iter()
         .position(|info| info.entry_id == entry_id && info.is_connected)
@@ -779,46 +1153,25 @@ extern "C" fn input_value_cb(
             let _ = tx.send((event, None));
         }
     } else if IOHIDElement::is_hat(type_, page, usage) {
-        // Hat switch values are reported with a range of usually 8 numbers (sometimes 4). The logic
-        // below uses the reported min/max values of that range to map that onto a range of 0-7 for
-        // the directions (and any other value indicates the center position). Lucky for us, they
-        // always start with "up" as the lowest number and proceed clockwise. See similar handling
-        // here https://github.com/spurious/SDL-mirror/blob/094b2f68dd7fc9af167f905e10625e103a131459/src/joystick/darwin/SDL_sysjoystick.c#L976-L1028
+        // Hat switch values are reported with a range of usually 8 numbers (sometimes 4), starting
+        // with "up" as the lowest number and proceeding clockwise. Any other value indicates the
+        // center position. See similar handling here https://github.com/spurious/SDL-mirror/blob/094b2f68dd7fc9af167f905e10625e103a131459/src/joystick/darwin/SDL_sysjoystick.c#L976-L1028
         //
-        //          up
-        //       7  0  1
-        //        \ | /
-        // left 6 - ? - 2 right       (After mapping)
-        //        / | \
-        //       5  4  3
-        //         down
-        let range = element.get_logical_max() - element.get_logical_min() + 1;
-        let shifted_value = value.get_value() - element.get_logical_min();
-        let dpad_value = match range {
-            4 => shifted_value * 2, // 4-position hat switch - scale it up to 8
-            8 => shifted_value,     // 8-position hat switch - no adjustment necessary
-            _ => -1, // Neither 4 nor 8 positions, we don't know what to do - default to centered
-        };
-        // At this point, the value should be normalized to the 0-7 directional values (or center
-        // for any other value). The dpad is a hat switch on macOS, but on other platforms dpads are
-        // either buttons or a pair of axes that get converted to button events by the
-        // `axis_dpad_to_button` filter.  We will emulate axes here and let that filter do the
-        // button conversion, because it is safer and easier than making separate logic for button
-        // conversion that may diverge in subtle ways from the axis conversion logic.  The most
-        // practical outcome of this conversion is that there are extra "released" axis events for
-        // the unused axis. For example, pressing just "up" will also give you a "released" event
-        // for either the left or right button, even if it wasn't pressed before pressing "up".
-        let x_axis_value = match dpad_value {
-            5 | 6 | 7 => -1, // left
-            1 | 2 | 3 => 1,  // right
-            _ => 0,
-        };
-        // Since we're emulating an inverted macOS gamepad axis, down is positive and up is negative
-        let y_axis_value = match dpad_value {
-            3 | 4 | 5 => 1,  // down
-            0 | 1 | 7 => -1, // up
-            _ => 0,
-        };
+        // The dpad is a hat switch on macOS, but on other platforms dpads are either buttons or a
+        // pair of axes that get converted to button events by the `axis_dpad_to_button` filter.
+        // We will emulate axes here and let that filter do the button conversion, because it is
+        // safer and easier than making separate logic for button conversion that may diverge in
+        // subtle ways from the axis conversion logic.  The most practical outcome of this
+        // conversion is that there are extra "released" axis events for the unused axis. For
+        // example, pressing just "up" will also give you a "released" event for either the left or
+        // right button, even if it wasn't pressed before pressing "up".
+        //
+        // Since we're emulating an inverted macOS gamepad axis, down is positive and up is negative.
+        let (x_axis_value, y_axis_value) = utils::hat_to_axes(
+            value.get_value() as i32,
+            element.get_logical_min() as i32,
+            element.get_logical_max() as i32,
+        );
 
         let x_axis_event = Event::new(
             id,
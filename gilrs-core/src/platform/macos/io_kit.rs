@@ -47,6 +47,10 @@ pub struct IOHIDManager(IOHIDManagerRef);
 
 pub type CFMutableArrayRef = *mut __CFArray;
 
+// Not part of the public IOKit headers, but set by the HID drivers of most wireless gamepads.
+const kIOHIDBatteryPercentKey: *const c_char =
+    b"BatteryPercent\0" as *const [u8; 15] as *const c_char;
+
 extern "C" {
     pub fn CFArrayCreateMutable(
         allocator: CFAllocatorRef,
@@ -221,6 +225,16 @@ impl IOHIDDevice {
         }
     }
 
+    pub fn get_battery_percent(&self) -> Option<u8> {
+        match self.get_number_property(kIOHIDBatteryPercentKey) {
+            Some(percent) => match percent.to_i32() {
+                Some(percent) if percent >= 0 => Some(percent.min(100) as u8),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
     pub fn get_transport_key(&self) -> Option<String> {
         match self.get_string_property(kIOHIDTransportKey) {
             Some(transport_key) => Some(transport_key.to_string()),
@@ -16,12 +16,78 @@
 //! * A constant which define whether Y axis of sticks points upwards or downwards
 //! * A module with the platform-specific constants for common gamepad buttons
 //!   called `native_ev_codes`
+//!
+//! With `hidapi` or `steam` feature these types are provided by wrappers of native backend.
+//! `drivers` adds gamepads read by vendor HID drivers and `steam` replaces everything with Steam
+//! Input when game runs under Steam, so their `Gamepad` is a trait object (see `any` module).
+//! Gamepads of all backends implement `BackendGamepad` (see `native` module).
 
 #![allow(clippy::module_inception)]
 
+mod native;
+
+#[cfg(not(all(
+    any(feature = "hidapi", feature = "steam"),
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+)))]
 pub use self::platform::*;
 
-#[cfg(target_os = "linux")]
+#[cfg(all(
+    any(feature = "hidapi", feature = "steam"),
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+mod any;
+
+// Combines native backend with gamepads opened by HID drivers.
+#[cfg(all(
+    feature = "hidapi",
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+mod drivers;
+#[cfg(all(
+    feature = "hidapi",
+    not(feature = "steam"),
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+pub use self::drivers::*;
+
+// Wraps backend above and replaces it with Steam Input when game runs under Steam.
+#[cfg(all(
+    feature = "steam",
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+mod steam;
+#[cfg(all(
+    feature = "steam",
+    feature = "hidapi",
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+use self::drivers as base;
+#[cfg(all(
+    feature = "steam",
+    not(feature = "hidapi"),
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+use self::platform as base;
+#[cfg(all(
+    feature = "steam",
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+pub use self::steam::*;
+
+// Joysticks connected to GPIO are evdev devices, their backend is part of Linux backend.
+#[cfg(all(feature = "gpio", target_os = "linux"))]
+pub use self::platform::{GpioBackend, GpioGamepad};
+
+// Virtual gamepads don't depend on backend used for input.
+#[cfg(all(
+    feature = "virtual-gamepad",
+    any(target_os = "linux", target_os = "windows")
+))]
+pub use self::platform::VirtualDevice;
+
+// FreeBSD and DragonFly BSD provide evdev and libudev compatible API, so they share Linux backend.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "dragonfly"))]
 #[path = "linux/mod.rs"]
 mod platform;
 
@@ -33,14 +99,27 @@ mod platform;
 #[path = "windows/mod.rs"]
 mod platform;
 
+#[cfg(any(target_os = "ios", target_os = "tvos"))]
+#[path = "ios/mod.rs"]
+mod platform;
+
+#[cfg(target_os = "android")]
+#[path = "android/mod.rs"]
+mod platform;
+
 #[cfg(target_arch = "wasm32")]
 #[path = "wasm/mod.rs"]
 mod platform;
 
 #[cfg(all(
     not(target_os = "linux"),
+    not(target_os = "freebsd"),
+    not(target_os = "dragonfly"),
     not(target_os = "macos"),
     not(target_os = "windows"),
+    not(target_os = "android"),
+    not(target_os = "ios"),
+    not(target_os = "tvos"),
     not(target_arch = "wasm32")
 ))]
 #[path = "default/mod.rs"]
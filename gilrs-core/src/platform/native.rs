@@ -0,0 +1,78 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Implementation of backend traits for types of native backend.
+
+use super::platform as native;
+use crate::{AxisInfo, BackendFfDevice, BackendGamepad, EvCode, FfDevice, PowerInfo};
+
+use std::io;
+use std::time::Duration;
+
+impl BackendGamepad for native::Gamepad {
+    fn name(&self) -> &str {
+        native::Gamepad::name(self)
+    }
+
+    fn uuid(&self) -> [u8; 16] {
+        *native::Gamepad::uuid(self).as_bytes()
+    }
+
+    fn is_connected(&self) -> bool {
+        native::Gamepad::is_connected(self)
+    }
+
+    fn buttons(&self) -> &[EvCode] {
+        EvCode::from_native(native::Gamepad::buttons(self))
+    }
+
+    fn axes(&self) -> &[EvCode] {
+        EvCode::from_native(native::Gamepad::axes(self))
+    }
+
+    fn axis_info(&self, nec: EvCode) -> Option<&AxisInfo> {
+        native::Gamepad::axis_info(self, nec.0)
+    }
+
+    fn power_info(&self) -> PowerInfo {
+        native::Gamepad::power_info(self)
+    }
+
+    fn is_ff_supported(&self) -> bool {
+        native::Gamepad::is_ff_supported(self)
+    }
+
+    fn is_y_axis_reversed(&self) -> bool {
+        native::Gamepad::is_y_axis_reversed(self)
+    }
+
+    fn ff_device(&self) -> Option<FfDevice> {
+        native::Gamepad::ff_device(self).map(FfDevice::new)
+    }
+
+    fn is_exclusive_supported(&self) -> bool {
+        native::Gamepad::is_exclusive_supported(self)
+    }
+
+    fn set_exclusive(&self, exclusive: bool) -> io::Result<()> {
+        native::Gamepad::set_exclusive(self, exclusive)
+    }
+
+    fn has_mic_led(&self) -> bool {
+        native::Gamepad::has_mic_led(self)
+    }
+
+    fn set_mic_led(&self, on: bool) -> io::Result<()> {
+        native::Gamepad::set_mic_led(self, on)
+    }
+}
+
+impl BackendFfDevice for native::FfDevice {
+    fn set_ff_state(&mut self, strong: u16, weak: u16, min_duration: Duration) -> io::Result<()> {
+        native::FfDevice::set_ff_state(self, strong, weak, min_duration)
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Steamworks flat API, loaded at runtime so games that don't ship steam_api library still work.
+//!
+//! Library and symbol names passed to functions of this module must end with `\0`.
+
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::mem;
+use std::os::raw::{c_char, c_int, c_void};
+
+pub type InputHandle = u64;
+pub type ActionSetHandle = u64;
+pub type ActionHandle = u64;
+
+/// `STEAM_INPUT_MAX_COUNT`
+pub const MAX_COUNT: usize = 16;
+/// `STEAM_INPUT_HANDLE_ALL_CONTROLLERS`
+pub const ALL_CONTROLLERS: InputHandle = !0;
+
+/// `InputDigitalActionData_t`
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct DigitalActionData {
+    pub state: bool,
+    pub active: bool,
+}
+
+/// `InputAnalogActionData_t`
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct AnalogActionData {
+    pub mode: c_int,
+    pub x: f32,
+    pub y: f32,
+    pub active: bool,
+}
+
+#[cfg(all(windows, target_pointer_width = "64"))]
+pub(super) const LIBRARY_NAME: &str = "steam_api64.dll\0";
+#[cfg(all(windows, target_pointer_width = "32"))]
+pub(super) const LIBRARY_NAME: &str = "steam_api.dll\0";
+#[cfg(target_os = "linux")]
+pub(super) const LIBRARY_NAME: &str = "libsteam_api.so\0";
+#[cfg(target_os = "macos")]
+pub(super) const LIBRARY_NAME: &str = "libsteam_api.dylib\0";
+
+type Input = *mut c_void;
+
+/// Opened `ISteamInput` interface. Dropping it shuts down Steam Input, but not Steamworks API,
+/// which may be used by the game.
+pub struct Api {
+    input: Input,
+    shutdown: unsafe extern "C" fn(Input) -> bool,
+    run_frame: unsafe extern "C" fn(Input, bool),
+    get_connected_controllers: unsafe extern "C" fn(Input, *mut InputHandle) -> c_int,
+    get_action_set_handle: unsafe extern "C" fn(Input, *const c_char) -> ActionSetHandle,
+    activate_action_set: unsafe extern "C" fn(Input, InputHandle, ActionSetHandle),
+    get_digital_action_handle: unsafe extern "C" fn(Input, *const c_char) -> ActionHandle,
+    get_digital_action_data:
+        unsafe extern "C" fn(Input, InputHandle, ActionHandle) -> DigitalActionData,
+    get_analog_action_handle: unsafe extern "C" fn(Input, *const c_char) -> ActionHandle,
+    get_analog_action_data:
+        unsafe extern "C" fn(Input, InputHandle, ActionHandle) -> AnalogActionData,
+    get_input_type_for_handle: unsafe extern "C" fn(Input, InputHandle) -> c_int,
+    trigger_vibration: unsafe extern "C" fn(Input, InputHandle, u16, u16),
+    /// Also drives impulse motors in triggers. Missing in older versions of Steamworks SDK.
+    trigger_vibration_extended:
+        Option<unsafe extern "C" fn(Input, InputHandle, u16, u16, u16, u16)>,
+}
+
+// Functions are only called from thread that owns `Gilrs`.
+unsafe impl Send for Api {}
+
+impl Debug for Api {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("Api").field("input", &self.input).finish()
+    }
+}
+
+impl Api {
+    /// Returns `None` if steam_api library can't be loaded, Steam isn't running or the game
+    /// wasn't started by Steam.
+    pub fn load() -> Option<Self> {
+        unsafe {
+            let lib = open(LIBRARY_NAME);
+            if lib.is_null() {
+                debug!("Failed to load {}", LIBRARY_NAME.trim_end_matches('\0'));
+                return None;
+            }
+
+            let accessor: unsafe extern "C" fn() -> Input =
+                symbol(lib, "SteamAPI_SteamInput_v006\0")
+                    .or_else(|| symbol(lib, "SteamAPI_SteamInput_v005\0"))?;
+            let mut input = accessor();
+            if input.is_null() {
+                // Game didn't initialize Steamworks API itself.
+                let is_running: unsafe extern "C" fn() -> bool =
+                    symbol(lib, "SteamAPI_IsSteamRunning\0")?;
+                if !is_running() || !init(lib) {
+                    debug!("Steam is not running or game was not started by Steam.");
+                    return None;
+                }
+                input = accessor();
+                if input.is_null() {
+                    return None;
+                }
+            }
+
+            let api = Api {
+                input,
+                shutdown: symbol(lib, "SteamAPI_ISteamInput_Shutdown\0")?,
+                run_frame: symbol(lib, "SteamAPI_ISteamInput_RunFrame\0")?,
+                get_connected_controllers: symbol(
+                    lib,
+                    "SteamAPI_ISteamInput_GetConnectedControllers\0",
+                )?,
+                get_action_set_handle: symbol(lib, "SteamAPI_ISteamInput_GetActionSetHandle\0")?,
+                activate_action_set: symbol(lib, "SteamAPI_ISteamInput_ActivateActionSet\0")?,
+                get_digital_action_handle: symbol(
+                    lib,
+                    "SteamAPI_ISteamInput_GetDigitalActionHandle\0",
+                )?,
+                get_digital_action_data: symbol(
+                    lib,
+                    "SteamAPI_ISteamInput_GetDigitalActionData\0",
+                )?,
+                get_analog_action_handle: symbol(
+                    lib,
+                    "SteamAPI_ISteamInput_GetAnalogActionHandle\0",
+                )?,
+                get_analog_action_data: symbol(lib, "SteamAPI_ISteamInput_GetAnalogActionData\0")?,
+                get_input_type_for_handle: symbol(
+                    lib,
+                    "SteamAPI_ISteamInput_GetInputTypeForHandle\0",
+                )?,
+                trigger_vibration: symbol(lib, "SteamAPI_ISteamInput_TriggerVibration\0")?,
+                trigger_vibration_extended: symbol(
+                    lib,
+                    "SteamAPI_ISteamInput_TriggerVibrationExtended\0",
+                ),
+            };
+
+            // `RunFrame()` is called by `Gilrs`, so it doesn't depend on game calling
+            // `SteamAPI_RunCallbacks()`.
+            let init_input: unsafe extern "C" fn(Input, bool) -> bool =
+                symbol(lib, "SteamAPI_ISteamInput_Init\0")?;
+            if !init_input(input, true) {
+                warn!("Failed to initialize Steam Input.");
+                // Don't shut down Steam Input we didn't initialize.
+                mem::forget(api);
+                return None;
+            }
+
+            Some(api)
+        }
+    }
+
+    pub fn run_frame(&self) {
+        unsafe { (self.run_frame)(self.input, false) }
+    }
+
+    pub fn connected_controllers(&self) -> Vec<InputHandle> {
+        let mut handles = [0; MAX_COUNT];
+        let count = unsafe { (self.get_connected_controllers)(self.input, handles.as_mut_ptr()) };
+        handles[..(count.max(0) as usize).min(MAX_COUNT)].to_vec()
+    }
+
+    /// `name` must end with `\0`. Returns 0 if action set doesn't exist or configuration is not
+    /// loaded yet.
+    pub fn action_set_handle(&self, name: &[u8]) -> ActionSetHandle {
+        debug_assert_eq!(name.last(), Some(&0));
+        unsafe { (self.get_action_set_handle)(self.input, name.as_ptr() as *const c_char) }
+    }
+
+    pub fn activate_action_set(&self, controller: InputHandle, set: ActionSetHandle) {
+        unsafe { (self.activate_action_set)(self.input, controller, set) }
+    }
+
+    /// `name` must end with `\0`. Returns 0 if action doesn't exist or configuration is not
+    /// loaded yet.
+    pub fn digital_action_handle(&self, name: &str) -> ActionHandle {
+        debug_assert!(name.ends_with('\0'));
+        unsafe { (self.get_digital_action_handle)(self.input, name.as_ptr() as *const c_char) }
+    }
+
+    pub fn digital_action_data(
+        &self,
+        controller: InputHandle,
+        action: ActionHandle,
+    ) -> DigitalActionData {
+        unsafe { (self.get_digital_action_data)(self.input, controller, action) }
+    }
+
+    /// `name` must end with `\0`. Returns 0 if action doesn't exist or configuration is not
+    /// loaded yet.
+    pub fn analog_action_handle(&self, name: &str) -> ActionHandle {
+        debug_assert!(name.ends_with('\0'));
+        unsafe { (self.get_analog_action_handle)(self.input, name.as_ptr() as *const c_char) }
+    }
+
+    pub fn analog_action_data(
+        &self,
+        controller: InputHandle,
+        action: ActionHandle,
+    ) -> AnalogActionData {
+        unsafe { (self.get_analog_action_data)(self.input, controller, action) }
+    }
+
+    /// Returns `ESteamInputType` of controller.
+    pub fn input_type(&self, controller: InputHandle) -> c_int {
+        unsafe { (self.get_input_type_for_handle)(self.input, controller) }
+    }
+
+    pub fn trigger_vibration(&self, controller: InputHandle, left: u16, right: u16) {
+        unsafe { (self.trigger_vibration)(self.input, controller, left, right) }
+    }
+
+    pub fn has_trigger_vibration(&self) -> bool {
+        self.trigger_vibration_extended.is_some()
+    }
+
+    /// Like `trigger_vibration()`, but also sets speed of impulse motors in triggers. Trigger
+    /// speeds are ignored if Steamworks SDK doesn't support them.
+    pub fn trigger_vibration_extended(
+        &self,
+        controller: InputHandle,
+        left: u16,
+        right: u16,
+        left_trigger: u16,
+        right_trigger: u16,
+    ) {
+        match self.trigger_vibration_extended {
+            Some(f) => unsafe {
+                f(
+                    self.input,
+                    controller,
+                    left,
+                    right,
+                    left_trigger,
+                    right_trigger,
+                )
+            },
+            None => self.trigger_vibration(controller, left, right),
+        }
+    }
+}
+
+impl Drop for Api {
+    fn drop(&mut self) {
+        unsafe {
+            (self.shutdown)(self.input);
+        }
+    }
+}
+
+/// Initializes Steamworks API. `SteamAPI_InitFlat()` replaced `SteamAPI_Init()` in SDK 1.58.
+unsafe fn init(lib: *mut c_void) -> bool {
+    let init_flat: Option<unsafe extern "C" fn(*mut [c_char; 1024]) -> c_int> =
+        symbol(lib, "SteamAPI_InitFlat\0");
+    if let Some(init_flat) = init_flat {
+        let mut err = [0; 1024];
+        // k_ESteamAPIInitResult_OK
+        return init_flat(&mut err) == 0;
+    }
+
+    let init: Option<unsafe extern "C" fn() -> bool> = symbol(lib, "SteamAPI_Init\0");
+    match init {
+        Some(init) => init(),
+        None => false,
+    }
+}
+
+/// Returns `None` if library doesn't export `name`. `T` must be function pointer.
+unsafe fn symbol<T: Copy>(lib: *mut c_void, name: &str) -> Option<T> {
+    debug_assert!(name.ends_with('\0'));
+    debug_assert_eq!(mem::size_of::<T>(), mem::size_of::<*mut c_void>());
+
+    let ptr = find(lib, name);
+    if ptr.is_null() {
+        debug!("steam_api doesn't export {}", name.trim_end_matches('\0'));
+        None
+    } else {
+        Some(mem::transmute_copy(&ptr))
+    }
+}
+
+#[cfg(unix)]
+unsafe fn open(name: &str) -> *mut c_void {
+    // Returns already loaded library if game links to it.
+    libc::dlopen(name.as_ptr() as *const c_char, libc::RTLD_NOW)
+}
+
+#[cfg(unix)]
+unsafe fn find(lib: *mut c_void, name: &str) -> *mut c_void {
+    libc::dlsym(lib, name.as_ptr() as *const c_char)
+}
+
+#[cfg(windows)]
+unsafe fn open(name: &str) -> *mut c_void {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::libloaderapi::LoadLibraryW;
+
+    let name: Vec<u16> = OsStr::new(name).encode_wide().collect();
+    LoadLibraryW(name.as_ptr()) as *mut c_void
+}
+
+#[cfg(windows)]
+unsafe fn find(lib: *mut c_void, name: &str) -> *mut c_void {
+    use winapi::um::libloaderapi::GetProcAddress;
+
+    GetProcAddress(lib as _, name.as_ptr() as *const c_char) as *mut c_void
+}
@@ -0,0 +1,80 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::api::InputHandle;
+use crate::BackendFfDevice;
+
+use std::io;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+/// Speeds of all motors of one controller.
+#[derive(Copy, Clone, Debug, Default)]
+pub(super) struct Vibration {
+    pub controller: InputHandle,
+    pub strong: u16,
+    pub weak: u16,
+    pub left_trigger: u16,
+    pub right_trigger: u16,
+}
+
+/// Steam Input vibration. Force feedback runs on its own thread, but Steamworks API is only
+/// called by `Gilrs`, so motor speeds are sent to it and applied in next poll.
+#[derive(Debug)]
+pub struct Device {
+    state: Vibration,
+    trigger_motors: bool,
+    tx: Sender<Vibration>,
+}
+
+impl Device {
+    pub(super) fn new(
+        controller: InputHandle,
+        trigger_motors: bool,
+        tx: Sender<Vibration>,
+    ) -> Self {
+        Device {
+            state: Vibration {
+                controller,
+                ..Default::default()
+            },
+            trigger_motors,
+            tx,
+        }
+    }
+
+    fn send(&self) {
+        // Fails only if `Gilrs` was dropped.
+        let _ = self.tx.send(self.state);
+    }
+}
+
+impl BackendFfDevice for Device {
+    /// Sets magnitude for strong and weak ff motors.
+    fn set_ff_state(&mut self, strong: u16, weak: u16, _min_duration: Duration) -> io::Result<()> {
+        self.state.strong = strong;
+        self.state.weak = weak;
+        self.send();
+        Ok(())
+    }
+
+    fn has_trigger_motors(&self) -> bool {
+        self.trigger_motors
+    }
+
+    fn set_trigger_state(
+        &mut self,
+        left: u16,
+        right: u16,
+        _min_duration: Duration,
+    ) -> io::Result<()> {
+        self.state.left_trigger = left;
+        self.state.right_trigger = right;
+        self.send();
+        Ok(())
+    }
+}
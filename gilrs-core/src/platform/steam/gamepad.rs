@@ -0,0 +1,481 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::api::{self, ActionHandle, ActionSetHandle, Api, InputHandle};
+use super::ff::{self, Vibration};
+use super::{native_ev_codes, EvCode};
+use crate::utils;
+use crate::{AxisInfo, BackendGamepad, Event, EventType, FfDevice, PowerInfo};
+
+use std::collections::VecDeque;
+use std::error;
+use std::ffi::CString;
+use std::io;
+use std::os::raw::c_int;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(8);
+const DEFAULT_ACTION_SET: &str = "gamepad";
+const AXIS_MAX: i32 = 32767;
+
+/// Names of digital actions in game's action set. Actions missing from configuration are
+/// skipped.
+const DIGITAL_ACTIONS: [(&str, EvCode); 19] = {
+    use self::native_ev_codes::*;
+
+    [
+        ("south\0", BTN_SOUTH),
+        ("east\0", BTN_EAST),
+        ("north\0", BTN_NORTH),
+        ("west\0", BTN_WEST),
+        ("left_bumper\0", BTN_LT),
+        ("right_bumper\0", BTN_RT),
+        ("select\0", BTN_SELECT),
+        ("start\0", BTN_START),
+        ("mode\0", BTN_MODE),
+        ("left_thumb\0", BTN_LTHUMB),
+        ("right_thumb\0", BTN_RTHUMB),
+        ("dpad_up\0", BTN_DPAD_UP),
+        ("dpad_down\0", BTN_DPAD_DOWN),
+        ("dpad_left\0", BTN_DPAD_LEFT),
+        ("dpad_right\0", BTN_DPAD_RIGHT),
+        ("l4\0", BTN_L4),
+        ("r4\0", BTN_R4),
+        ("l5\0", BTN_L5),
+        ("r5\0", BTN_R5),
+    ]
+};
+
+/// Names of analog actions in game's action set. Sticks report both axes, triggers only X.
+const ANALOG_ACTIONS: [(&str, EvCode, Option<EvCode>); 4] = {
+    use self::native_ev_codes::*;
+
+    [
+        ("left_stick\0", AXIS_LSTICKX, Some(AXIS_LSTICKY)),
+        ("right_stick\0", AXIS_RSTICKX, Some(AXIS_RSTICKY)),
+        ("left_trigger\0", AXIS_LT2, None),
+        ("right_trigger\0", AXIS_RT2, None),
+    ]
+};
+
+#[derive(Debug)]
+pub struct Gilrs {
+    api: Api,
+    gamepads: Vec<Gamepad>,
+    events: VecDeque<Event>,
+    action_set_name: CString,
+    action_set: ActionSetHandle,
+    digital: [ActionHandle; 19],
+    analog: [ActionHandle; 4],
+    ff_tx: Sender<Vibration>,
+    ff_rx: Receiver<Vibration>,
+    poll_interval: Duration,
+    suspended: bool,
+    stopped: bool,
+}
+
+impl Gilrs {
+    /// Returns `None` if Steam Input is not available.
+    pub(super) fn new() -> Option<Self> {
+        let api = Api::load()?;
+        let (ff_tx, ff_rx) = mpsc::channel();
+
+        let mut gilrs = Gilrs {
+            api,
+            gamepads: Vec::new(),
+            events: VecDeque::new(),
+            action_set_name: CString::new(DEFAULT_ACTION_SET).unwrap(),
+            action_set: 0,
+            digital: [0; 19],
+            analog: [0; 4],
+            ff_tx,
+            ff_rx,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            suspended: false,
+            stopped: false,
+        };
+        gilrs.poll();
+
+        Some(gilrs)
+    }
+
+    pub(super) fn next_event(&mut self) -> Option<Event> {
+        if self.events.is_empty() {
+            self.poll();
+        }
+
+        self.events.pop_front()
+    }
+
+    pub(super) fn wait(&mut self, timeout: Option<Duration>) {
+        let timeout = timeout.unwrap_or(utils::WAIT_SLICE);
+        let start = Instant::now();
+
+        loop {
+            if self.events.is_empty() {
+                self.poll();
+            }
+
+            let elapsed = start.elapsed();
+            if !self.events.is_empty() || elapsed >= timeout {
+                return;
+            }
+
+            thread::sleep(self.poll_interval.min(timeout - elapsed));
+        }
+    }
+
+    pub(super) fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+    }
+
+    /// Changes action set that is activated for all controllers. Handles of actions are looked
+    /// up again, so the new set must use the same action names.
+    pub(super) fn set_action_set(&mut self, name: &str) {
+        match CString::new(name) {
+            Ok(name) => {
+                self.action_set_name = name;
+                self.action_set = 0;
+                self.digital = [0; 19];
+                self.analog = [0; 4];
+            }
+            Err(_) => warn!("Steam Input action set name {:?} contains NUL byte", name),
+        }
+    }
+
+    pub(super) fn stop(&mut self) {
+        if self.stopped {
+            return;
+        }
+
+        self.stopped = true;
+        for (id, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if gamepad.is_connected {
+                gamepad.is_connected = false;
+                self.events
+                    .push_back(Event::new(id, EventType::Disconnected));
+            }
+        }
+    }
+
+    pub(super) fn suspend(&mut self) {
+        self.suspended = true;
+    }
+
+    pub(super) fn resume(&mut self) {
+        self.suspended = false;
+    }
+
+    pub(super) fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        self.stopped = false;
+        self.poll();
+
+        Ok(())
+    }
+
+    pub(super) fn gamepad(&self, id: usize) -> Option<&Gamepad> {
+        self.gamepads.get(id)
+    }
+
+    /// Returns index greater than index of last connected gamepad.
+    pub(super) fn last_gamepad_hint(&self) -> usize {
+        self.gamepads.len()
+    }
+
+    /// Updates Steam Input state, applies pending vibration and compares connected controllers
+    /// with known gamepads.
+    fn poll(&mut self) {
+        if self.stopped || self.suspended {
+            return;
+        }
+
+        self.api.run_frame();
+
+        while let Ok(v) = self.ff_rx.try_recv() {
+            self.api.trigger_vibration_extended(
+                v.controller,
+                v.strong,
+                v.weak,
+                v.left_trigger,
+                v.right_trigger,
+            );
+        }
+
+        self.resolve_handles();
+        if self.action_set != 0 {
+            self.api
+                .activate_action_set(api::ALL_CONTROLLERS, self.action_set);
+        }
+
+        let current = self.api.connected_controllers();
+
+        for (id, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if gamepad.is_connected && !current.contains(&gamepad.controller) {
+                gamepad.is_connected = false;
+                self.events
+                    .push_back(Event::new(id, EventType::Disconnected));
+            }
+        }
+
+        for controller in current {
+            let id = match self
+                .gamepads
+                .iter()
+                .position(|g| g.controller == controller)
+            {
+                Some(id) => id,
+                None => {
+                    self.gamepads
+                        .push(Gamepad::new(controller, self.ff_tx.clone()));
+                    self.gamepads.len() - 1
+                }
+            };
+
+            let gamepad = &mut self.gamepads[id];
+            if !gamepad.is_connected {
+                let input_type = self.api.input_type(controller);
+                gamepad.trigger_motors =
+                    input_type == XBOX_ONE_CONTROLLER && self.api.has_trigger_vibration();
+                gamepad.connect(type_name(input_type), &self.digital, &self.analog);
+                self.events.push_back(Event::new(id, EventType::Connected));
+            }
+
+            gamepad.collect_events(&self.api, id, &mut self.events);
+        }
+    }
+
+    /// Action handles are 0 until Steam loads game's input configuration.
+    fn resolve_handles(&mut self) {
+        if self.action_set == 0 {
+            self.action_set = self
+                .api
+                .action_set_handle(self.action_set_name.as_bytes_with_nul());
+        }
+
+        for (handle, &(name, _)) in self.digital.iter_mut().zip(DIGITAL_ACTIONS.iter()) {
+            if *handle == 0 {
+                *handle = self.api.digital_action_handle(name);
+            }
+        }
+
+        for (handle, &(name, _, _)) in self.analog.iter_mut().zip(ANALOG_ACTIONS.iter()) {
+            if *handle == 0 {
+                *handle = self.api.analog_action_handle(name);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Gamepad {
+    name: &'static str,
+    controller: InputHandle,
+    is_connected: bool,
+    buttons: Vec<EvCode>,
+    button_actions: Vec<ActionHandle>,
+    pressed: Vec<bool>,
+    axes: Vec<EvCode>,
+    /// Action and whether axis is its Y component.
+    axis_actions: Vec<(ActionHandle, bool)>,
+    values: Vec<i32>,
+    /// Xbox One controller with impulse motors in triggers.
+    trigger_motors: bool,
+    ff_tx: Sender<Vibration>,
+}
+
+impl Gamepad {
+    fn new(controller: InputHandle, ff_tx: Sender<Vibration>) -> Self {
+        Gamepad {
+            name: "",
+            controller,
+            is_connected: false,
+            buttons: Vec::new(),
+            button_actions: Vec::new(),
+            pressed: Vec::new(),
+            axes: Vec::new(),
+            axis_actions: Vec::new(),
+            values: Vec::new(),
+            trigger_motors: false,
+            ff_tx,
+        }
+    }
+
+    /// Gamepad has elements for actions that exist in loaded configuration.
+    fn connect(&mut self, name: &'static str, digital: &[ActionHandle], analog: &[ActionHandle]) {
+        self.name = name;
+
+        self.buttons.clear();
+        self.button_actions.clear();
+        for (&handle, &(_, code)) in digital.iter().zip(DIGITAL_ACTIONS.iter()) {
+            if handle != 0 {
+                self.buttons.push(code);
+                self.button_actions.push(handle);
+            }
+        }
+
+        self.axes.clear();
+        self.axis_actions.clear();
+        for (&handle, &(_, x, y)) in analog.iter().zip(ANALOG_ACTIONS.iter()) {
+            if handle != 0 {
+                self.axes.push(x);
+                self.axis_actions.push((handle, false));
+                if let Some(y) = y {
+                    self.axes.push(y);
+                    self.axis_actions.push((handle, true));
+                }
+            }
+        }
+
+        self.pressed = vec![false; self.buttons.len()];
+        self.values = vec![0; self.axes.len()];
+        self.is_connected = true;
+    }
+
+    fn collect_events(&mut self, api: &Api, id: usize, events: &mut VecDeque<Event>) {
+        for (idx, &action) in self.button_actions.iter().enumerate() {
+            let data = api.digital_action_data(self.controller, action);
+            let pressed = data.active && data.state;
+            if self.pressed[idx] != pressed {
+                self.pressed[idx] = pressed;
+                let code = crate::EvCode(self.buttons[idx]);
+                let ev = if pressed {
+                    EventType::ButtonPressed(code)
+                } else {
+                    EventType::ButtonReleased(code)
+                };
+                events.push_back(Event::new(id, ev));
+            }
+        }
+
+        for (idx, &(action, is_y)) in self.axis_actions.iter().enumerate() {
+            let data = api.analog_action_data(self.controller, action);
+            let value = match (data.active, is_y) {
+                (false, _) => 0.0,
+                (true, false) => data.x,
+                (true, true) => data.y,
+            };
+            let value = (value * AXIS_MAX as f32) as i32;
+            if self.values[idx] != value {
+                self.values[idx] = value;
+                let ev = EventType::AxisValueChanged(value, crate::EvCode(self.axes[idx]));
+                events.push_back(Event::new(id, ev));
+            }
+        }
+    }
+}
+
+impl BackendGamepad for Gamepad {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Steam Input doesn't expose vendor and product IDs.
+    fn uuid(&self) -> [u8; 16] {
+        [0; 16]
+    }
+
+    fn is_connected(&self) -> bool {
+        self.is_connected
+    }
+
+    fn power_info(&self) -> PowerInfo {
+        PowerInfo::Unknown
+    }
+
+    fn is_ff_supported(&self) -> bool {
+        true
+    }
+
+    /// Steam Input reports positive Y for up.
+    fn is_y_axis_reversed(&self) -> bool {
+        false
+    }
+
+    fn ff_device(&self) -> Option<FfDevice> {
+        let device = ff::Device::new(self.controller, self.trigger_motors, self.ff_tx.clone());
+        Some(FfDevice::new(device))
+    }
+
+    fn is_exclusive_supported(&self) -> bool {
+        false
+    }
+
+    fn set_exclusive(&self, _exclusive: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "exclusive access is not supported by Steam Input",
+        ))
+    }
+
+    fn has_mic_led(&self) -> bool {
+        false
+    }
+
+    fn set_mic_led(&self, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "microphone LED is not supported by Steam Input",
+        ))
+    }
+
+    fn buttons(&self) -> &[crate::EvCode] {
+        crate::EvCode::from_native(&self.buttons)
+    }
+
+    fn axes(&self) -> &[crate::EvCode] {
+        crate::EvCode::from_native(&self.axes)
+    }
+
+    fn axis_info(&self, nec: crate::EvCode) -> Option<&AxisInfo> {
+        let nec = nec.0;
+        if !self.axes.contains(&nec) {
+            None
+        } else if nec == native_ev_codes::AXIS_LT2 || nec == native_ev_codes::AXIS_RT2 {
+            Some(&TRIGGER_INFO)
+        } else {
+            Some(&STICK_INFO)
+        }
+    }
+}
+
+/// `k_ESteamInputType_XBoxOneController`, the only type with impulse motors in triggers.
+const XBOX_ONE_CONTROLLER: c_int = 3;
+
+/// Name for `ESteamInputType`.
+fn type_name(input_type: c_int) -> &'static str {
+    match input_type {
+        1 => "Steam Controller",
+        2 => "Xbox 360 Controller",
+        3 => "Xbox One Controller",
+        4 => "Generic XInput Controller",
+        5 => "PS4 Controller",
+        6 => "Apple MFi Controller",
+        7 => "Android Controller",
+        8 => "Switch Joy-Con Pair",
+        9 => "Switch Joy-Con",
+        10 => "Switch Pro Controller",
+        11 => "Mobile Touch",
+        12 => "PS3 Controller",
+        13 => "PS5 Controller",
+        14 => "Steam Deck",
+        _ => "Steam Input Controller",
+    }
+}
+
+const STICK_INFO: AxisInfo = AxisInfo {
+    min: -AXIS_MAX,
+    max: AXIS_MAX,
+    deadzone: None,
+};
+
+const TRIGGER_INFO: AxisInfo = AxisInfo {
+    min: 0,
+    max: AXIS_MAX,
+    deadzone: None,
+};
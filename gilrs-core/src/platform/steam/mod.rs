@@ -0,0 +1,177 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Steam Input backend.
+//!
+//! When game runs under Steam, Steam takes over gamepads and exposes them as virtual devices
+//! configured by user, so native backend would see the same gamepad twice. If Steamworks API and
+//! Steam Input can be initialized, this backend is used instead of native one. Otherwise all calls
+//! are forwarded to native backend.
+//!
+//! Both backends share native `EvCode`, so mappings and `native_ev_codes` work the same way.
+
+mod api;
+mod ff;
+mod gamepad;
+
+use super::base;
+use crate::{BackendInfo, ErrorQueue, Event, PlatformError, PollMode, RawWaitHandle};
+
+use std::error;
+use std::time::Duration;
+
+pub use super::any::Gamepad;
+pub use super::base::{native_ev_codes, EvCode, IS_Y_AXIS_REVERSED};
+
+#[derive(Debug)]
+pub enum Gilrs {
+    Native(Box<base::Gilrs>),
+    Steam(Box<gamepad::Gilrs>),
+}
+
+impl Gilrs {
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn new(errors: ErrorQueue) -> Result<Self, PlatformError> {
+        if let Some(gilrs) = gamepad::Gilrs::new() {
+            info!("Using Steam Input backend.");
+            return Ok(Gilrs::Steam(Box::new(gilrs)));
+        }
+
+        base::Gilrs::new(errors).map(|gilrs| Gilrs::Native(Box::new(gilrs)))
+    }
+
+    pub(crate) fn next_event(&mut self) -> Option<Event> {
+        match *self {
+            Gilrs::Native(ref mut gilrs) => gilrs.next_event(),
+            Gilrs::Steam(ref mut gilrs) => gilrs.next_event(),
+        }
+    }
+
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        match *self {
+            Gilrs::Native(ref mut gilrs) => gilrs.wait(timeout),
+            Gilrs::Steam(ref mut gilrs) => gilrs.wait(timeout),
+        }
+    }
+
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        match *self {
+            Gilrs::Native(ref gilrs) => gilrs.raw_wait_handle(),
+            Gilrs::Steam(_) => None,
+        }
+    }
+
+    pub(crate) fn set_poll_interval(&mut self, interval: Duration) {
+        match *self {
+            Gilrs::Native(ref mut gilrs) => gilrs.set_poll_interval(interval),
+            Gilrs::Steam(ref mut gilrs) => gilrs.set_poll_interval(interval),
+        }
+    }
+
+    pub(crate) fn set_poll_mode(&mut self, mode: PollMode) {
+        if let Gilrs::Native(ref mut gilrs) = *self {
+            gilrs.set_poll_mode(mode)
+        }
+    }
+
+    pub(crate) fn set_connection_check_interval(&mut self, interval: Duration) {
+        if let Gilrs::Native(ref mut gilrs) = *self {
+            gilrs.set_connection_check_interval(interval)
+        }
+    }
+
+    pub(crate) fn set_event_queue_capacity(&mut self, capacity: Option<usize>) {
+        if let Gilrs::Native(ref mut gilrs) = *self {
+            gilrs.set_event_queue_capacity(capacity)
+        }
+    }
+
+    pub(crate) fn set_steam_action_set(&mut self, name: &str) {
+        if let Gilrs::Steam(ref mut gilrs) = *self {
+            gilrs.set_action_set(name)
+        }
+    }
+
+    pub(crate) fn is_steam_input(&self) -> bool {
+        match *self {
+            Gilrs::Native(_) => false,
+            Gilrs::Steam(_) => true,
+        }
+    }
+
+    pub(crate) fn stop(&mut self) {
+        match *self {
+            Gilrs::Native(ref mut gilrs) => gilrs.stop(),
+            Gilrs::Steam(ref mut gilrs) => gilrs.stop(),
+        }
+    }
+
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync>> {
+        match *self {
+            Gilrs::Native(ref mut gilrs) => gilrs.restart(),
+            Gilrs::Steam(ref mut gilrs) => gilrs.restart(),
+        }
+    }
+
+    pub(crate) fn is_alive(&self) -> bool {
+        match *self {
+            Gilrs::Native(ref gilrs) => gilrs.is_alive(),
+            Gilrs::Steam(_) => true,
+        }
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        match *self {
+            Gilrs::Native(ref gilrs) => gilrs.is_waiting_for_activation(),
+            Gilrs::Steam(_) => false,
+        }
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        match *self {
+            Gilrs::Native(ref gilrs) => gilrs.backend_info(),
+            Gilrs::Steam(_) => BackendInfo::new(
+                "Steam Input",
+                Some(api::LIBRARY_NAME.trim_end_matches('\0')),
+            ),
+        }
+    }
+
+    pub(crate) fn suspend(&mut self) {
+        match *self {
+            Gilrs::Native(ref mut gilrs) => gilrs.suspend(),
+            Gilrs::Steam(ref mut gilrs) => gilrs.suspend(),
+        }
+    }
+
+    pub(crate) fn resume(&mut self) {
+        match *self {
+            Gilrs::Native(ref mut gilrs) => gilrs.resume(),
+            Gilrs::Steam(ref mut gilrs) => gilrs.resume(),
+        }
+    }
+
+    pub(crate) fn refresh(&mut self) {
+        if let Gilrs::Native(ref mut gilrs) = *self {
+            gilrs.refresh()
+        }
+    }
+
+    pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
+        match *self {
+            Gilrs::Native(ref gilrs) => gilrs.gamepad(id).map(|gp| gp as &Gamepad),
+            Gilrs::Steam(ref gilrs) => gilrs.gamepad(id).map(|gp| gp as &Gamepad),
+        }
+    }
+
+    pub fn last_gamepad_hint(&self) -> usize {
+        match *self {
+            Gilrs::Native(ref gilrs) => gilrs.last_gamepad_hint(),
+            Gilrs::Steam(ref gilrs) => gilrs.last_gamepad_hint(),
+        }
+    }
+}
@@ -5,11 +5,209 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::io;
 use std::time::Duration;
+use std::u16::MAX as U16_MAX;
+
+#[cfg(feature = "wasm-bindgen")]
+use crate::BackendErrorKind;
+use crate::ErrorQueue;
+
+#[cfg(feature = "wasm-bindgen")]
+use js_sys::{Array, Function, Object, Reflect};
+#[cfg(not(feature = "wasm-bindgen"))]
+use stdweb::{js, web::Gamepad as WebGamepad, Value};
+#[cfg(feature = "wasm-bindgen")]
+use wasm_bindgen_rs::{JsCast, JsValue};
+#[cfg(feature = "wasm-bindgen")]
+use web_sys::Gamepad as WebGamepad;
 
 #[derive(Debug)]
-pub struct Device;
+pub struct Device {
+    gamepad: WebGamepad,
+    is_playing: bool,
+    #[cfg_attr(not(feature = "wasm-bindgen"), allow(dead_code))]
+    errors: ErrorQueue,
+}
 
 impl Device {
-    pub fn set_ff_state(&mut self, _strong: u16, _weak: u16, _min_duration: Duration) {}
+    pub(crate) fn new(gamepad: WebGamepad, errors: ErrorQueue) -> Self {
+        Device {
+            gamepad,
+            is_playing: false,
+            errors,
+        }
+    }
+
+    pub fn set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        min_duration: Duration,
+    ) -> io::Result<()> {
+        // Don't bother browser with effects that wouldn't change anything.
+        if strong == 0 && weak == 0 && !self.is_playing {
+            return Ok(());
+        }
+        self.is_playing = strong != 0 || weak != 0;
+
+        let strong = f64::from(strong) / f64::from(U16_MAX);
+        let weak = f64::from(weak) / f64::from(U16_MAX);
+        let duration = min_duration.as_millis() as f64;
+
+        #[cfg(feature = "wasm-bindgen")]
+        {
+            if let Err(e) = play_effect(&self.gamepad, strong, weak, duration) {
+                debug!("Failed to play haptic effect: {:?}", e);
+                self.errors.push(
+                    Some(self.gamepad.index() as usize),
+                    BackendErrorKind::ForceFeedback,
+                    format!("{:?}", e),
+                );
+                return Err(io::Error::new(io::ErrorKind::Other, format!("{:?}", e)));
+            }
+        }
+
+        #[cfg(not(feature = "wasm-bindgen"))]
+        play_effect(&self.gamepad, strong, weak, duration);
+
+        Ok(())
+    }
+}
+
+/// Returns true if browser exposes any haptic actuator for this gamepad.
+///
+/// Chromium based browsers implement `Gamepad.vibrationActuator` with "dual-rumble" effect, while
+/// Firefox only has `Gamepad.hapticActuators`, which can do simple `pulse()`.
+///
+/// Haptics are experimental and only enabled with `wasm-ff` feature.
+pub(crate) fn is_supported(gamepad: &WebGamepad) -> bool {
+    if !cfg!(feature = "wasm-ff") {
+        return false;
+    }
+
+    #[cfg(feature = "wasm-bindgen")]
+    {
+        dual_rumble_actuator(gamepad).is_some() || pulse_actuator(gamepad).is_some()
+    }
+
+    #[cfg(not(feature = "wasm-bindgen"))]
+    {
+        let supported = js! {
+            var gamepad = @{gamepad.as_ref()};
+            var actuator = gamepad.vibrationActuator;
+
+            if (actuator && typeof actuator.playEffect === "function") {
+                var effects = Array.isArray(actuator.effects) ? actuator.effects : [];
+
+                return actuator.type === "dual-rumble" || effects.indexOf("dual-rumble") >= 0;
+            }
+
+            return !!gamepad.hapticActuators && gamepad.hapticActuators.length > 0;
+        };
+
+        match supported {
+            Value::Bool(supported) => supported,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "wasm-bindgen")]
+fn play_effect(
+    gamepad: &WebGamepad,
+    strong: f64,
+    weak: f64,
+    duration: f64,
+) -> Result<(), JsValue> {
+    if let Some((actuator, play)) = dual_rumble_actuator(gamepad) {
+        let params = Object::new();
+        let _ = Reflect::set(&params, &"duration".into(), &duration.into());
+        let _ = Reflect::set(&params, &"startDelay".into(), &0f64.into());
+        let _ = Reflect::set(&params, &"strongMagnitude".into(), &strong.into());
+        let _ = Reflect::set(&params, &"weakMagnitude".into(), &weak.into());
+
+        play.call2(&actuator, &"dual-rumble".into(), &params)?;
+    } else if let Some((actuator, pulse)) = pulse_actuator(gamepad) {
+        pulse.call2(&actuator, &strong.max(weak).into(), &duration.into())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "wasm-bindgen"))]
+fn play_effect(gamepad: &WebGamepad, strong: f64, weak: f64, duration: f64) {
+    js! { @(no_return)
+        var gamepad = @{gamepad.as_ref()};
+        var duration = @{duration};
+        var strong = @{strong};
+        var weak = @{weak};
+        var actuator = gamepad.vibrationActuator;
+
+        if (actuator && typeof actuator.playEffect === "function") {
+            actuator.playEffect("dual-rumble", {
+                duration: duration,
+                startDelay: 0,
+                strongMagnitude: strong,
+                weakMagnitude: weak
+            });
+        } else if (gamepad.hapticActuators && gamepad.hapticActuators.length > 0) {
+            gamepad.hapticActuators[0].pulse(Math.max(strong, weak), duration);
+        }
+    }
+}
+
+/// Returns `Gamepad.vibrationActuator` and its `playEffect()` method if actuator supports
+/// "dual-rumble" effect.
+#[cfg(feature = "wasm-bindgen")]
+fn dual_rumble_actuator(gamepad: &WebGamepad) -> Option<(JsValue, Function)> {
+    let actuator = Reflect::get(gamepad, &"vibrationActuator".into()).ok()?;
+    if actuator.is_undefined() || actuator.is_null() {
+        return None;
+    }
+
+    let play = Reflect::get(&actuator, &"playEffect".into())
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?;
+
+    let type_ = Reflect::get(&actuator, &"type".into())
+        .ok()
+        .and_then(|ty| ty.as_string());
+    // Newer versions of the spec replaced `type` with list of supported effects.
+    let effects = Reflect::get(&actuator, &"effects".into())
+        .ok()
+        .and_then(|effects| effects.dyn_into::<Array>().ok());
+    let has_dual_rumble = type_.as_deref() == Some("dual-rumble")
+        || effects.map_or(false, |effects| {
+            effects
+                .iter()
+                .any(|eff| eff.as_string().as_deref() == Some("dual-rumble"))
+        });
+
+    if has_dual_rumble {
+        Some((actuator, play))
+    } else {
+        None
+    }
+}
+
+/// Returns first element of `Gamepad.hapticActuators` and its `pulse()` method.
+#[cfg(feature = "wasm-bindgen")]
+fn pulse_actuator(gamepad: &WebGamepad) -> Option<(JsValue, Function)> {
+    let actuators = Reflect::get(gamepad, &"hapticActuators".into())
+        .ok()?
+        .dyn_into::<Array>()
+        .ok()?;
+    let actuator = actuators.get(0);
+    if actuator.is_undefined() || actuator.is_null() {
+        return None;
+    }
+
+    let pulse = Reflect::get(&actuator, &"pulse".into())
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?;
+
+    Some((actuator, pulse))
 }
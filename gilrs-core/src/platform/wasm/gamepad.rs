@@ -5,8 +5,12 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use super::ff;
 use super::FfDevice;
-use crate::{AxisInfo, Event, EventType, PlatformError, PowerInfo};
+use crate::{
+    AxisInfo, BackendInfo, ErrorQueue, Event, EventType, PlatformError, PollMode, PowerInfo,
+    RawWaitHandle,
+};
 use uuid::Uuid;
 
 use std::collections::VecDeque;
@@ -15,29 +19,100 @@ use stdweb::web::{Gamepad as WebGamepad, GamepadMappingType};
 #[cfg(feature = "wasm-bindgen")]
 use web_sys::{Gamepad as WebGamepad, GamepadButton, GamepadMappingType};
 
+use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::i32::MAX as I32_MAX;
+use std::io;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct Gilrs {
     gamepads: Vec<Gamepad>,
     event_cache: VecDeque<Event>,
+    is_stopped: bool,
+    is_suspended: bool,
+    /// Browsers don't expose any gamepad until user presses button on one of them.
+    is_activated: bool,
+    errors: ErrorQueue,
 }
 
 impl Gilrs {
-    pub(crate) fn new() -> Result<Self, PlatformError> {
+    pub(crate) fn new(errors: ErrorQueue) -> Result<Self, PlatformError> {
         Ok({
             Gilrs {
                 gamepads: Vec::new(),
                 event_cache: VecDeque::new(),
+                is_stopped: false,
+                is_suspended: false,
+                is_activated: false,
+                errors,
             }
         })
     }
 
+    pub(crate) fn stop(&mut self) {
+        if self.is_stopped {
+            return;
+        }
+
+        self.is_stopped = true;
+        for gamepad in self.gamepads.drain(..) {
+            self.event_cache
+                .push_back(Event::new(gamepad.index(), EventType::Disconnected));
+        }
+    }
+
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // There are no resources to acquire, gamepads will be reported as connected on next
+        // poll.
+        self.is_stopped = false;
+
+        Ok(())
+    }
+
+    // Browser is not polled while suspended, so first poll after resuming compares current state
+    // with the last one we have seen.
+    pub(crate) fn suspend(&mut self) {
+        self.is_suspended = true;
+    }
+
+    pub(crate) fn resume(&mut self) {
+        self.is_suspended = false;
+    }
+
+    pub(crate) fn is_alive(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        !self.is_activated
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        BackendInfo::new("Gamepad API", None)
+    }
+
+    /// Browsers don't allow blocking, events are only updated when `next_event()` is called.
+    pub(crate) fn wait(&mut self, _timeout: Option<Duration>) {}
+
+    pub(crate) fn set_poll_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn set_poll_mode(&mut self, _mode: PollMode) {}
+
+    pub(crate) fn set_connection_check_interval(&mut self, _interval: Duration) {}
+
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        None
+    }
+
+    pub(crate) fn refresh(&mut self) {}
+
+    pub(crate) fn set_event_queue_capacity(&mut self, _capacity: Option<usize>) {}
+
     pub(crate) fn next_event(&mut self) -> Option<Event> {
         // Don't duplicate the work of checking the diff between the old and new gamepads if
         // there are still events to return
-        if !self.event_cache.is_empty() {
+        if !self.event_cache.is_empty() || self.is_stopped || self.is_suspended {
             return self.event_cache.pop_front();
         }
 
@@ -59,7 +134,11 @@ impl Gilrs {
             }
         });
 
-        let new_gamepads: Vec<_> = gamepads.flatten().map(Gamepad::new).collect();
+        let errors = &self.errors;
+        let new_gamepads: Vec<_> = gamepads
+            .flatten()
+            .map(|gamepad| Gamepad::new(gamepad, errors.clone()))
+            .collect();
         let mut old_index = 0;
         let mut new_index = 0;
 
@@ -128,6 +207,12 @@ impl Gilrs {
             }
         }
 
+        if !self.is_activated && !new_gamepads.is_empty() {
+            self.is_activated = true;
+        }
+
+        // Gamepads that appear after user gesture are found here, because we re-enumerate them on
+        // every poll.
         self.gamepads = new_gamepads;
         self.event_cache.pop_front()
     }
@@ -171,10 +256,11 @@ pub struct Gamepad {
     gamepad: WebGamepad,
     name: String,
     mapping: Mapping,
+    errors: ErrorQueue,
 }
 
 impl Gamepad {
-    fn new(gamepad: WebGamepad) -> Gamepad {
+    fn new(gamepad: WebGamepad, errors: ErrorQueue) -> Gamepad {
         let name = gamepad.id();
 
         let buttons = gamepad.buttons();
@@ -229,6 +315,7 @@ impl Gamepad {
             gamepad,
             name,
             mapping,
+            errors,
         }
     }
 
@@ -253,11 +340,41 @@ impl Gamepad {
     }
 
     pub fn is_ff_supported(&self) -> bool {
+        ff::is_supported(&self.gamepad)
+    }
+
+    pub fn is_y_axis_reversed(&self) -> bool {
+        super::IS_Y_AXIS_REVERSED
+    }
+
+    pub fn has_mic_led(&self) -> bool {
         false
     }
 
+    pub fn set_mic_led(&self, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "microphone LED is not supported on this platform",
+        ))
+    }
+
+    pub fn is_exclusive_supported(&self) -> bool {
+        false
+    }
+
+    pub fn set_exclusive(&self, _exclusive: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "exclusive access is not supported on this platform",
+        ))
+    }
+
     pub fn ff_device(&self) -> Option<FfDevice> {
-        None
+        if self.is_ff_supported() {
+            Some(FfDevice::new(self.gamepad.clone(), self.errors.clone()))
+        } else {
+            None
+        }
     }
 
     pub fn buttons(&self) -> &[EvCode] {
@@ -346,6 +463,9 @@ pub mod native_ev_codes {
     pub const BTN_DPAD_LEFT: EvCode = EvCode(29);
     pub const BTN_DPAD_RIGHT: EvCode = EvCode(30);
 
+    // Codes of elements mapped to part of other element's axis, never reported by backend.
+    pub const SPLIT_AXES: [EvCode; 4] = [EvCode(252), EvCode(253), EvCode(254), EvCode(255)];
+
     pub(super) static BUTTONS: [EvCode; 17] = [
         BTN_SOUTH,
         BTN_EAST,
@@ -5,34 +5,73 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use rusty_xinput::{self, XInputUsageError};
+use crate::{BackendErrorKind, ErrorQueue};
+use rusty_xinput::{XInputHandle, XInputUsageError};
+use std::io;
 use std::time::Duration;
 
 #[derive(Debug)]
 pub struct Device {
     id: u32,
+    xinput: XInputHandle,
+    errors: ErrorQueue,
+    // Effects are updated every few milliseconds, so report only first error in a row.
+    is_failing: bool,
 }
 
 impl Device {
-    pub(crate) fn new(id: u32) -> Self {
-        Device { id }
+    pub(crate) fn new(id: u32, xinput: XInputHandle, errors: ErrorQueue) -> Self {
+        Device {
+            id,
+            xinput,
+            errors,
+            is_failing: false,
+        }
     }
 
-    pub fn set_ff_state(&mut self, strong: u16, weak: u16, _min_duration: Duration) {
-        match rusty_xinput::xinput_set_state(self.id, strong, weak) {
-            Ok(()) => (),
+    pub fn set_ff_state(
+        &mut self,
+        strong: u16,
+        weak: u16,
+        _min_duration: Duration,
+    ) -> io::Result<()> {
+        match self.xinput.set_state(self.id, strong, weak) {
+            Ok(()) => {
+                self.is_failing = false;
+                Ok(())
+            }
             Err(XInputUsageError::DeviceNotConnected) => {
                 error!(
                     "Failed to change FF state – gamepad with id {} is no longer connected.",
                     self.id
                 );
+                self.report_error(None, "gamepad is no longer connected".to_owned());
+                Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    "gamepad is no longer connected",
+                ))
             }
             Err(err) => {
                 error!(
                     "Failed to change FF state – unknown error. ID = {}, error = {:?}.",
                     self.id, err
                 );
+                let description = format!("{:?}", err);
+                self.report_error(super::gamepad::error_code(&err), description.clone());
+                Err(io::Error::new(io::ErrorKind::Other, description))
             }
         }
     }
+
+    fn report_error(&mut self, code: Option<i32>, description: String) {
+        if !self.is_failing {
+            self.is_failing = true;
+            self.errors.push_with_code(
+                Some(self.id as usize),
+                BackendErrorKind::ForceFeedback,
+                code,
+                description,
+            );
+        }
+    }
 }
@@ -5,18 +5,43 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use super::hid::{self, HidGamepads, HidInfo};
+use super::notify::DeviceNotifications;
 use super::FfDevice;
-use crate::{AxisInfo, Event, EventType, PlatformError, PowerInfo};
+use crate::utils::{self, Watchdog, WatchdogStatus};
+use crate::{
+    AxisInfo, BackendErrorKind, BackendInfo, ErrorQueue, Event, EventType, PlatformError, PollMode,
+    PowerInfo, RawWaitHandle,
+};
 
+use std::cmp;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
 use std::error::Error as StdError;
+use std::ffi::{OsStr, OsString};
 use std::fmt::{Display, Formatter, Result as FmtResult};
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::time::Duration;
-use std::{mem, thread, u16, u32};
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::io::RawHandle;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SendError, Sender, TryRecvError};
+use std::sync::{Arc, Mutex, Once};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use std::{mem, ptr, thread, u16, u32, u64};
 
-use rusty_xinput::XInputLoadingFailure;
-use rusty_xinput::{self, BatteryLevel, BatteryType, XInputState, XInputUsageError};
+use rusty_xinput::{
+    BatteryLevel, BatteryType, XInputHandle, XInputOptionalFnUsageError, XInputState,
+    XInputUsageError, XINPUT_CAPABILITIES_EX,
+};
 use uuid::Uuid;
+use winapi::shared::minwindef::{DWORD, FALSE, FARPROC, TRUE};
+use winapi::shared::winerror::ERROR_SUCCESS;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::libloaderapi::{GetProcAddress, LoadLibraryW};
+use winapi::um::synchapi::{CreateEventW, ResetEvent, SetEvent};
+use winapi::um::winnt::{HANDLE, LPCSTR};
 use winapi::um::xinput::{
     XINPUT_GAMEPAD as XGamepad, XINPUT_GAMEPAD_A, XINPUT_GAMEPAD_B, XINPUT_GAMEPAD_BACK,
     XINPUT_GAMEPAD_DPAD_DOWN, XINPUT_GAMEPAD_DPAD_LEFT, XINPUT_GAMEPAD_DPAD_RIGHT,
@@ -27,53 +52,847 @@ use winapi::um::xinput::{
 
 // Chosen by dice roll ;)
 const EVENT_THREAD_SLEEP_TIME: u64 = 10;
-const ITERATIONS_TO_CHECK_IF_CONNECTED: u64 = 100;
+const CONNECTION_CHECK_INTERVAL: u64 = 1000;
+/// XInput may report new controller a bit after device arrival notification, so connections are
+/// checked every `RECHECK_INTERVAL` ms for `RECHECK_TIME` ms after each notification.
+const RECHECK_INTERVAL: u64 = 100;
+const RECHECK_TIME: u64 = 2000;
+/// Longest sleep of idle event thread, it has to be well below watchdog's timeout.
+const MAX_IDLE_SLEEP_TIME: u64 = 500;
 
 const MAX_XINPUT_CONTROLLERS: usize = 4;
+/// XInput DLLs in order of preference. xinput1_4.dll ships with Windows 8 and newer, older versions
+/// come with DirectX runtime.
+const XINPUT_DLLS: [&str; 3] = ["xinput1_4.dll", "xinput1_3.dll", "xinput9_1_0.dll"];
+
+/// How long `Gamepad::set_exclusive()` waits for event thread to reopen the device.
+const SET_EXCLUSIVE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Name of XInput controller whose product string is unknown.
+const XINPUT_NAME: &str = "Xbox Controller";
+
+/// Undocumented `XInputGetCapabilitiesEx`, the only XInput function that reports vendor and
+/// product ID. It's exported as ordinal 108 by xinput1_4.dll (Windows 8 and newer).
+type GetCapabilitiesEx =
+    unsafe extern "system" fn(DWORD, DWORD, DWORD, *mut XINPUT_CAPABILITIES_EX) -> DWORD;
+
+fn load_get_capabilities_ex() -> Option<GetCapabilitiesEx> {
+    unsafe {
+        let name: Vec<u16> = OsStr::new("xinput1_4.dll")
+            .encode_wide()
+            .chain(Some(0))
+            .collect();
+        // Never unloaded, same as the DLL loaded by rusty_xinput.
+        let module = LoadLibraryW(name.as_ptr());
+        if module.is_null() {
+            return None;
+        }
+
+        let f = GetProcAddress(module, 108 as LPCSTR);
+        if f.is_null() {
+            None
+        } else {
+            Some(mem::transmute::<FARPROC, GetCapabilitiesEx>(f))
+        }
+    }
+}
+
+/// Loads first available XInput DLL. Returns `None` if none of them exists, for example on Wine
+/// without XInput or stripped-down Windows.
+fn load_xinput() -> Option<(XInputHandle, &'static str)> {
+    for &dll in XINPUT_DLLS.iter() {
+        match XInputHandle::load(dll) {
+            Ok(handle) => {
+                debug!("Loaded {}", dll);
+                return Some((handle, dll));
+            }
+            Err(e) => trace!("Failed to load {}: {:?}", dll, e),
+        }
+    }
+
+    None
+}
+
+/// Polling settings in milliseconds, shared with event thread.
+#[derive(Debug)]
+struct PollSettings {
+    /// Time between polls while any gamepad is connected.
+    interval: AtomicU64,
+    /// Time between polls while no gamepad is connected, 0 if it's the same as `interval`.
+    idle_interval: AtomicU64,
+    /// Time between checks of disconnected XInput slots and HID devices.
+    check_interval: AtomicU64,
+}
+
+impl PollSettings {
+    fn new() -> Self {
+        PollSettings {
+            interval: AtomicU64::new(EVENT_THREAD_SLEEP_TIME),
+            idle_interval: AtomicU64::new(0),
+            check_interval: AtomicU64::new(CONNECTION_CHECK_INTERVAL),
+        }
+    }
+
+    fn sleep_time(&self, any_connected: bool) -> Duration {
+        let interval = self.interval.load(Ordering::Relaxed);
+        let ms = match self.idle_interval.load(Ordering::Relaxed) {
+            idle if idle > 0 && !any_connected => idle,
+            _ => interval,
+        };
+
+        Duration::from_millis(ms)
+    }
+}
+
+/// Sending half of event channel that discards input events when too many are waiting.
+///
+/// Number of discarded events is reported with `Overflow` event before next event of the same
+/// gamepad. `Connected` and `Disconnected` are never discarded.
+pub(super) struct EventSender {
+    tx: Sender<Event>,
+    queued: Arc<AtomicUsize>,
+    /// 0 means unlimited.
+    capacity: Arc<AtomicUsize>,
+    /// Indexed by gamepad id.
+    dropped: Vec<usize>,
+    signal: Option<Arc<WaitEvent>>,
+}
+
+impl EventSender {
+    pub(super) fn send(&mut self, ev: Event) -> Result<(), SendError<Event>> {
+        if ev.id >= self.dropped.len() {
+            self.dropped.resize(ev.id + 1, 0);
+        }
+
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        let is_connection = ev.event == EventType::Connected || ev.event == EventType::Disconnected;
+        if !is_connection && capacity != 0 && self.queued.load(Ordering::SeqCst) >= capacity {
+            self.dropped[ev.id] += 1;
+            return Ok(());
+        }
+
+        let dropped = mem::replace(&mut self.dropped[ev.id], 0);
+        if dropped > 0 {
+            self.push(Event::new(ev.id, EventType::Overflow(dropped)))?;
+        }
+
+        self.push(ev)
+    }
+
+    fn push(&mut self, ev: Event) -> Result<(), SendError<Event>> {
+        self.queued.fetch_add(1, Ordering::SeqCst);
+        self.tx.send(ev)?;
+        if let Some(ref signal) = self.signal {
+            signal.set();
+        }
+
+        Ok(())
+    }
+}
+
+/// Manual-reset event object, set while there are events in channel.
+#[derive(Debug)]
+struct WaitEvent(HANDLE);
+
+// Event objects can be used from any thread.
+unsafe impl Send for WaitEvent {}
+unsafe impl Sync for WaitEvent {}
+
+impl WaitEvent {
+    fn new() -> Option<Self> {
+        let handle = unsafe { CreateEventW(ptr::null_mut(), TRUE, FALSE, ptr::null()) };
+        if handle.is_null() {
+            warn!(
+                "Failed to create event object: {}",
+                io::Error::last_os_error()
+            );
+            None
+        } else {
+            Some(WaitEvent(handle))
+        }
+    }
+
+    fn set(&self) {
+        unsafe {
+            SetEvent(self.0);
+        }
+    }
+
+    fn reset(&self) {
+        unsafe {
+            ResetEvent(self.0);
+        }
+    }
+}
+
+impl Drop for WaitEvent {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+/// Source of keys that identify subscribers of event thread.
+static NEXT_SUBSCRIBER_KEY: AtomicUsize = AtomicUsize::new(0);
+
+/// `Gilrs` instance that receives events from shared event thread.
+pub(super) struct Subscriber {
+    key: usize,
+    tx: EventSender,
+    /// Sent before `Connected` event of HID gamepad.
+    hid_tx: Sender<(usize, HidInfo)>,
+    suspended: Arc<AtomicBool>,
+    poll_settings: Arc<PollSettings>,
+    rescan: Arc<AtomicBool>,
+    errors: ErrorQueue,
+    /// State of XInput controllers that was last reported to this subscriber.
+    connected: [bool; MAX_XINPUT_CONTROLLERS],
+    prev_states: [XState; MAX_XINPUT_CONTROLLERS],
+}
+
+impl Subscriber {
+    fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::SeqCst)
+    }
+}
+
+/// All subscribers of event thread.
+pub(super) struct Broadcast {
+    subscribers: Vec<Subscriber>,
+}
+
+impl Broadcast {
+    /// Adds subscriber and reports HID gamepads that are already open to it.
+    fn add(&mut self, mut sub: Subscriber, hid: &HidGamepads) {
+        for (id, info) in hid.connected() {
+            let _ = sub.hid_tx.send((id, info.clone()));
+            let _ = sub.tx.send(Event::new(id, EventType::Connected));
+        }
+        self.subscribers.push(sub);
+    }
+
+    fn remove(&mut self, key: usize) {
+        self.subscribers.retain(|sub| sub.key != key);
+    }
+
+    /// Sends event of HID gamepad to all subscribers. Suspended subscribers get only `Connected`
+    /// and `Disconnected`, input from before `resume()` would be stale.
+    pub(super) fn send(&mut self, ev: Event) {
+        let is_connection = ev.event == EventType::Connected || ev.event == EventType::Disconnected;
+        for sub in self.subscribers.iter_mut() {
+            if is_connection || !sub.is_suspended() {
+                let _ = sub.tx.send(ev);
+            }
+        }
+    }
+
+    pub(super) fn send_hid_info(&mut self, id: usize, info: &HidInfo) {
+        for sub in self.subscribers.iter() {
+            let _ = sub.hid_tx.send((id, info.clone()));
+        }
+    }
+
+    fn push_error(&self, id: Option<usize>, kind: BackendErrorKind, code: Option<i32>, msg: &str) {
+        for sub in self.subscribers.iter() {
+            sub.errors.push_with_code(id, kind, code, msg);
+        }
+    }
+
+    /// Returns `true` if there is no subscriber that isn't suspended.
+    fn is_suspended(&self) -> bool {
+        self.subscribers.iter().all(Subscriber::is_suspended)
+    }
+
+    /// Returns `true` if any subscriber asked for connection check. Requests of all subscribers
+    /// are cleared.
+    fn take_rescan(&self) -> bool {
+        let mut rescan = false;
+        for sub in self.subscribers.iter() {
+            if sub.rescan.swap(false, Ordering::SeqCst) {
+                rescan = true;
+            }
+        }
+
+        rescan
+    }
+
+    /// Shortest poll interval requested by subscribers.
+    fn sleep_time(&self, any_connected: bool) -> Duration {
+        self.subscribers
+            .iter()
+            .map(|sub| sub.poll_settings.sleep_time(any_connected))
+            .min()
+            .unwrap_or_else(|| Duration::from_millis(EVENT_THREAD_SLEEP_TIME))
+    }
+
+    /// Shortest connection check interval requested by subscribers.
+    fn check_interval(&self) -> Duration {
+        let ms = self
+            .subscribers
+            .iter()
+            .map(|sub| sub.poll_settings.check_interval.load(Ordering::Relaxed))
+            .min()
+            .unwrap_or(CONNECTION_CHECK_INTERVAL);
+
+        Duration::from_millis(ms)
+    }
+
+    /// Sends changes of XInput controllers to subscribers that are not suspended. Each
+    /// subscriber is compared with state it has seen, so after resume or subscribing it gets
+    /// events for everything that changed in the meantime.
+    fn sync_xinput(
+        &mut self,
+        connected: &[bool; MAX_XINPUT_CONTROLLERS],
+        states: &[XState; MAX_XINPUT_CONTROLLERS],
+    ) {
+        for sub in self.subscribers.iter_mut() {
+            if sub.is_suspended() {
+                continue;
+            }
+
+            for (id, state) in states.iter().enumerate() {
+                if !connected[id] {
+                    if sub.connected[id] {
+                        sub.connected[id] = false;
+                        let _ = sub.tx.send(Event::new(id, EventType::Disconnected));
+                    }
+                    continue;
+                }
+
+                if !sub.connected[id] {
+                    sub.connected[id] = true;
+                    let _ = sub.tx.send(Event::new(id, EventType::Connected));
+                }
+                if state.dwPacketNumber != sub.prev_states[id].dwPacketNumber {
+                    Gilrs::compare_state(
+                        id,
+                        &state.Gamepad,
+                        &sub.prev_states[id].Gamepad,
+                        &mut sub.tx,
+                    );
+                    sub.prev_states[id] = *state;
+                }
+            }
+        }
+    }
+}
+
+enum Command {
+    Subscribe(Box<Subscriber>),
+    Unsubscribe(usize),
+    SetExclusive {
+        id: usize,
+        exclusive: bool,
+        reply: Sender<io::Result<()>>,
+    },
+}
+
+/// Event thread shared by all `Gilrs` instances in process, so XInput and HID gamepads are
+/// polled only once no matter how many instances exist. Each instance gets full event stream.
+#[derive(Debug)]
+struct Poller {
+    commands: Mutex<Sender<Command>>,
+    stop_flag: Arc<AtomicBool>,
+    heartbeat: Arc<AtomicUsize>,
+}
+
+/// Running event thread and number of its subscribers.
+#[derive(Debug)]
+struct SharedPoller {
+    poller: Arc<Poller>,
+    thread: Option<JoinHandle<()>>,
+    refs: usize,
+}
+
+fn shared_poller() -> &'static Mutex<Option<SharedPoller>> {
+    static INIT: Once = Once::new();
+    static mut SHARED: *const Mutex<Option<SharedPoller>> = ptr::null();
+
+    unsafe {
+        INIT.call_once(|| SHARED = Box::into_raw(Box::new(Mutex::new(None))));
+        &*SHARED
+    }
+}
+
+impl Poller {
+    /// Adds subscriber to running event thread or spawns new thread if there is none.
+    fn subscribe(
+        sub: Subscriber,
+        xinput: Option<XInputHandle>,
+        known: Vec<OsString>,
+    ) -> io::Result<Arc<Poller>> {
+        let mut shared = shared_poller().lock().unwrap_or_else(|e| e.into_inner());
+        let mut command = Command::Subscribe(Box::new(sub));
+        if let Some(ref mut running) = *shared {
+            match running.poller.send(command) {
+                Ok(()) => {
+                    running.refs += 1;
+                    return Ok(running.poller.clone());
+                }
+                // Thread exited, its other subscribers will notice and subscribe again.
+                Err(SendError(c)) => command = c,
+            }
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let _ = tx.send(command);
+        let poller = Arc::new(Poller {
+            commands: Mutex::new(tx),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            heartbeat: Arc::new(AtomicUsize::new(0)),
+        });
+        let thread = Gilrs::spawn_thread(
+            rx,
+            xinput,
+            HidGamepads::new(MAX_XINPUT_CONTROLLERS, known),
+            poller.stop_flag.clone(),
+            poller.heartbeat.clone(),
+        )?;
+        *shared = Some(SharedPoller {
+            poller: poller.clone(),
+            thread: Some(thread),
+            refs: 1,
+        });
+
+        Ok(poller)
+    }
+
+    /// Removes subscriber. Thread exits when its last subscriber is removed and, if `join` is
+    /// true, this function waits until it does.
+    fn unsubscribe(this: &Arc<Poller>, key: usize, join: bool) {
+        let _ = this.send(Command::Unsubscribe(key));
+
+        let mut shared = shared_poller().lock().unwrap_or_else(|e| e.into_inner());
+        let is_last = match *shared {
+            Some(ref mut running) if Arc::ptr_eq(&running.poller, this) => {
+                running.refs -= 1;
+                running.refs == 0
+            }
+            // Thread was abandoned.
+            _ => false,
+        };
+        if !is_last {
+            return;
+        }
+
+        this.stop_flag.store(true, Ordering::SeqCst);
+        let thread = shared.take().and_then(|running| running.thread);
+        drop(shared);
+
+        if let Some(thread) = thread {
+            if join && thread.join().is_err() {
+                error!("XInput thread panicked.");
+            }
+        }
+    }
+
+    /// Tells thread that exited or hangs in XInput call to stop, so the next subscriber spawns
+    /// a new one. Thread is not joined.
+    fn abandon(this: &Arc<Poller>) {
+        this.stop_flag.store(true, Ordering::SeqCst);
+
+        let mut shared = shared_poller().lock().unwrap_or_else(|e| e.into_inner());
+        let is_current = match *shared {
+            Some(ref running) => Arc::ptr_eq(&running.poller, this),
+            None => false,
+        };
+        if is_current {
+            *shared = None;
+        }
+    }
+
+    fn send(&self, command: Command) -> Result<(), SendError<Command>> {
+        self.commands
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .send(command)
+    }
+}
 
 #[derive(Debug)]
 pub struct Gilrs {
-    gamepads: [Gamepad; MAX_XINPUT_CONTROLLERS],
+    /// XInput controllers followed by HID gamepads, in order of their ids.
+    gamepads: Vec<Gamepad>,
     rx: Receiver<Event>,
+    /// Sent by event thread before `Connected` event of HID gamepad.
+    hid_rx: Receiver<(usize, HidInfo)>,
+    /// Event thread shared with other instances. `None` when backend is stopped.
+    poller: Option<Arc<Poller>>,
+    /// Identifies this instance among subscribers of event thread.
+    key: usize,
+    /// Shared with event thread, which doesn't send input events while it's set and doesn't
+    /// poll gamepads if all its subscribers are suspended.
+    suspended: Arc<AtomicBool>,
+    poll_settings: Arc<PollSettings>,
+    /// Interval set by `set_poll_interval()`, used when `poll_mode` isn't `LowLatency`.
+    poll_interval: u64,
+    poll_mode: PollMode,
+    /// Tells event thread to check connection state of all controllers in next iteration.
+    rescan: Arc<AtomicBool>,
+    /// Number of events sent by event thread and not yet received.
+    queued: Arc<AtomicUsize>,
+    /// Shared with event thread, 0 means unlimited.
+    queue_capacity: Arc<AtomicUsize>,
+    wait_event: Option<Arc<WaitEvent>>,
+    watchdog: Watchdog,
+    is_dead: bool,
+    pending_events: VecDeque<Event>,
+    /// Event received by `wait()`, returned by next call to `next_event()`.
+    received: Option<Event>,
+    errors: ErrorQueue,
+    /// `None` if no XInput DLL was found, only HID gamepads are reported then.
+    xinput: Option<XInputHandle>,
+    xinput_dll: Option<&'static str>,
+    get_capabilities_ex: Option<GetCapabilitiesEx>,
 }
 
 impl Gilrs {
-    pub(crate) fn new() -> Result<Self, PlatformError> {
-        match rusty_xinput::dynamic_load_xinput() {
-            Ok(()) => (),
-            Err(XInputLoadingFailure::AlreadyLoading)
-            | Err(XInputLoadingFailure::AlreadyActive) => (),
-            Err(e) => return Err(PlatformError::Other(Box::new(Error::FailedToLoadDll(e)))),
-        }
-
-        let mut gamepads: [Gamepad; MAX_XINPUT_CONTROLLERS] = Default::default();
+    pub(crate) fn new(errors: ErrorQueue) -> Result<Self, PlatformError> {
+        let (xinput, xinput_dll) = match load_xinput() {
+            Some((handle, dll)) => (Some(handle), Some(dll)),
+            None => {
+                warn!("XInput is not available, only HID gamepads will be reported.");
+                (None, None)
+            }
+        };
+        let get_capabilities_ex = xinput.as_ref().and_then(|_| load_get_capabilities_ex());
+        let mut gamepads = Vec::with_capacity(MAX_XINPUT_CONTROLLERS);
         let mut connected: [bool; MAX_XINPUT_CONTROLLERS] = Default::default();
 
         // Iterate through each controller ID and set connected state
-        for id in 0..MAX_XINPUT_CONTROLLERS {
-            gamepads[id] = Gamepad::new(id as u32);
-            connected[id] = gamepads[id].is_connected;
+        for (id, connected) in connected.iter_mut().enumerate() {
+            let mut gamepad = Gamepad::new(id as u32, xinput.clone(), errors.clone());
+            if gamepad.is_connected {
+                gamepad.identify(get_capabilities_ex);
+            }
+            *connected = gamepad.is_connected;
+            gamepads.push(gamepad);
         }
 
+        // Channels are created when subscribing to event thread.
+        let (_, rx) = mpsc::channel();
+        let (_, hid_rx) = mpsc::channel();
+        let poll_settings = Arc::new(PollSettings::new());
+        let mut gilrs = Gilrs {
+            gamepads,
+            rx,
+            hid_rx,
+            poller: None,
+            key: 0,
+            suspended: Arc::new(AtomicBool::new(false)),
+            poll_interval: poll_settings.interval.load(Ordering::Relaxed),
+            poll_settings,
+            poll_mode: PollMode::Fixed,
+            rescan: Arc::new(AtomicBool::new(false)),
+            queued: Arc::new(AtomicUsize::new(0)),
+            queue_capacity: Arc::new(AtomicUsize::new(0)),
+            wait_event: WaitEvent::new().map(Arc::new),
+            watchdog: Watchdog::new(),
+            is_dead: false,
+            pending_events: VecDeque::new(),
+            received: None,
+            errors,
+            xinput,
+            xinput_dll,
+            get_capabilities_ex,
+        };
+        // HID gamepads are found by event thread, they will be reported with `Connected` event.
+        gilrs
+            .subscribe(connected, Vec::new())
+            .map_err(|e| PlatformError::Other(Box::new(Error::ThreadSpawn(e))))?;
+
+        Ok(gilrs)
+    }
+
+    /// Subscribes to event thread, which is spawned if no other instance is using it. XInput
+    /// controllers that are not marked in `connected` are reported with `Connected` event.
+    fn subscribe(
+        &mut self,
+        connected: [bool; MAX_XINPUT_CONTROLLERS],
+        known: Vec<OsString>,
+    ) -> io::Result<()> {
         let (tx, rx) = mpsc::channel();
-        Self::spawn_thread(tx, connected);
+        let (hid_tx, hid_rx) = mpsc::channel();
+        // Old channel was dropped together with events that were counted.
+        let queued = Arc::new(AtomicUsize::new(0));
+        let key = NEXT_SUBSCRIBER_KEY.fetch_add(1, Ordering::Relaxed);
+        let subscriber = Subscriber {
+            key,
+            tx: EventSender {
+                tx,
+                queued: queued.clone(),
+                capacity: self.queue_capacity.clone(),
+                dropped: Vec::new(),
+                signal: self.wait_event.clone(),
+            },
+            hid_tx,
+            suspended: self.suspended.clone(),
+            poll_settings: self.poll_settings.clone(),
+            rescan: self.rescan.clone(),
+            errors: self.errors.clone(),
+            connected,
+            prev_states: unsafe { mem::zeroed() },
+        };
+        let poller = Poller::subscribe(subscriber, self.xinput.clone(), known)?;
+
+        self.watchdog.watch(poller.heartbeat.clone());
+        self.rx = rx;
+        self.hid_rx = hid_rx;
+        self.queued = queued;
+        self.key = key;
+        self.poller = Some(poller);
+        self.is_dead = false;
+
+        Ok(())
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.stop_thread(true);
+    }
+
+    /// Unsubscribes from event thread. If this was its last subscriber, thread exits and, if
+    /// `join` is true, waits until it does. Thread that hangs in XInput call is only detached.
+    fn stop_thread(&mut self, join: bool) {
+        match self.poller.take() {
+            Some(poller) => Poller::unsubscribe(&poller, self.key, join),
+            None => return,
+        }
+
+        // Events that are still in channel come from thread that is going to exit, drop them.
+        let (_, rx) = mpsc::channel();
+        self.rx = rx;
+        let (_, hid_rx) = mpsc::channel();
+        self.hid_rx = hid_rx;
+
+        for (id, gamepad) in self.gamepads.iter_mut().enumerate() {
+            if gamepad.is_connected {
+                gamepad.is_connected = false;
+                self.pending_events
+                    .push_back(Event::new(id, EventType::Disconnected));
+            }
+        }
+    }
+
+    pub(crate) fn restart(&mut self) -> Result<(), Box<dyn StdError + Send + Sync>> {
+        if self.poller.is_some() {
+            return Ok(());
+        }
 
-        // Coerce gamepads vector to slice
-        Ok(Gilrs { gamepads, rx })
+        // All gamepads were reported as disconnected, event thread will send `Connected` events
+        // for gamepads that are still available. Known HID gamepads keep their ids if new thread
+        // is spawned.
+        let known = self.gamepads[MAX_XINPUT_CONTROLLERS..]
+            .iter()
+            .filter_map(|g| g.hid.as_ref().map(|hid| hid.path.clone()))
+            .collect::<Vec<OsString>>();
+        self.subscribe([false; MAX_XINPUT_CONTROLLERS], known)?;
+
+        Ok(())
+    }
+
+    // Thread compares state with the last one it has seen, so after resuming it will emit events
+    // for all changes that happened while suspended.
+    pub(crate) fn suspend(&mut self) {
+        self.suspended.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn resume(&mut self) {
+        // Controllers could be plugged or unplugged while suspended.
+        self.rescan.store(true, Ordering::SeqCst);
+        self.suspended.store(false, Ordering::SeqCst);
+    }
+
+    pub(crate) fn refresh(&mut self) {
+        self.rescan.store(true, Ordering::SeqCst);
     }
 
     pub(crate) fn next_event(&mut self) -> Option<Event> {
-        let ev = self.rx.try_recv().ok();
+        if let Some(ev) = self.pending_events.pop_front() {
+            return Some(ev);
+        }
+
+        let received = match self.received.take() {
+            Some(ev) => Ok(ev),
+            None => self.try_recv(),
+        };
 
-        if let Some(ev) = ev {
-            match ev.event {
-                EventType::Connected => self.gamepads[ev.id].is_connected = true,
-                EventType::Disconnected => self.gamepads[ev.id].is_connected = false,
-                _ => (),
+        let ev = match received {
+            Ok(ev) => ev,
+            Err(e) => {
+                self.check_watchdog(e == TryRecvError::Disconnected);
+                return self.pending_events.pop_front();
+            }
+        };
+
+        match ev.event {
+            EventType::Connected => {
+                self.update_hid_gamepads();
+                if ev.id < MAX_XINPUT_CONTROLLERS {
+                    // Slot could be taken by different controller than before.
+                    self.gamepads[ev.id].identify(self.get_capabilities_ex);
+                }
+                self.gamepads[ev.id].is_connected = true;
+            }
+            EventType::Disconnected => self.gamepads[ev.id].is_connected = false,
+            _ => (),
+        }
+
+        Some(ev)
+    }
+
+    /// Adds or updates HID gamepads described by event thread.
+    fn update_hid_gamepads(&mut self) {
+        while let Ok((id, info)) = self.hid_rx.try_recv() {
+            let gamepad =
+                Gamepad::from_hid(id as u32, info, self.poller.clone(), self.errors.clone());
+            match id.cmp(&self.gamepads.len()) {
+                cmp::Ordering::Less => self.gamepads[id] = gamepad,
+                cmp::Ordering::Equal => self.gamepads.push(gamepad),
+                cmp::Ordering::Greater => {
+                    // Event thread shared with other instances doesn't report gamepads that
+                    // were disconnected before this instance subscribed.
+                    self.gamepads.resize_with(id, Gamepad::default);
+                    self.gamepads.push(gamepad);
+                }
             }
         }
+    }
+
+    fn try_recv(&mut self) -> Result<Event, TryRecvError> {
+        let mut received = self.rx.try_recv();
+        if received == Err(TryRecvError::Empty) {
+            if let Some(ref wait_event) = self.wait_event {
+                // Check again after reset, event thread could send something in the meantime.
+                wait_event.reset();
+                received = self.rx.try_recv();
+                if received.is_ok() {
+                    wait_event.set();
+                }
+            }
+        }
+
+        if received.is_ok() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        received
+    }
+
+    pub(crate) fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        self.wait_event
+            .as_ref()
+            .map(|e| RawWaitHandle::Handle(e.0 as RawHandle))
+    }
+
+    pub(crate) fn wait(&mut self, timeout: Option<Duration>) {
+        if !self.pending_events.is_empty() || self.received.is_some() {
+            return;
+        }
+
+        let timeout = timeout.map_or(utils::WAIT_SLICE, |t| t.min(utils::WAIT_SLICE));
+        // Errors are handled by watchdog in `next_event()`.
+        self.received = self.rx.recv_timeout(timeout).ok();
+        if self.received.is_some() {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    pub(crate) fn set_event_queue_capacity(&mut self, capacity: Option<usize>) {
+        self.queue_capacity
+            .store(capacity.map_or(0, |c| c.max(1)), Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_poll_interval(&mut self, interval: Duration) {
+        let ms = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+        self.poll_interval = ms.max(1);
+        self.update_poll_settings();
+    }
+
+    pub(crate) fn set_poll_mode(&mut self, mode: PollMode) {
+        self.poll_mode = mode;
+        self.update_poll_settings();
+    }
+
+    pub(crate) fn set_connection_check_interval(&mut self, interval: Duration) {
+        let ms = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+        self.poll_settings
+            .check_interval
+            .store(ms, Ordering::Relaxed);
+    }
+
+    fn update_poll_settings(&mut self) {
+        let interval = match self.poll_mode {
+            PollMode::LowLatency => 1,
+            _ => self.poll_interval,
+        };
+        let idle_interval = match self.poll_mode {
+            PollMode::Adaptive { idle } => {
+                let ms = u64::try_from(idle.as_millis()).unwrap_or(u64::MAX);
+                // 0 means that idle interval is the same as the normal one.
+                ms.min(MAX_IDLE_SLEEP_TIME)
+            }
+            _ => 0,
+        };
+
+        self.poll_settings
+            .interval
+            .store(interval, Ordering::Relaxed);
+        self.poll_settings
+            .idle_interval
+            .store(idle_interval, Ordering::Relaxed);
+    }
+
+    /// Respawns event thread if it died or hangs in XInput call. Connected gamepads are reported
+    /// as disconnected and then connected again by the new thread.
+    fn check_watchdog(&mut self, exited: bool) {
+        let poller = match self.poller {
+            Some(ref poller) => poller.clone(),
+            None => return,
+        };
+
+        match self.watchdog.check(exited) {
+            WatchdogStatus::Alive => (),
+            WatchdogStatus::Restart(reason) => {
+                warn!("Restarting XInput thread: {}", reason);
+                self.errors.push(None, BackendErrorKind::Watchdog, reason);
+                // Other instances will subscribe to the respawned thread when their watchdog
+                // notices that the old one is gone.
+                Poller::abandon(&poller);
+                self.stop_thread(false);
+                if let Err(e) = self.restart() {
+                    self.errors
+                        .push(None, BackendErrorKind::Watchdog, e.to_string());
+                    self.is_dead = true;
+                }
+            }
+            WatchdogStatus::Dead => {
+                error!("XInput thread keeps failing, giving up.");
+                self.errors.push(
+                    None,
+                    BackendErrorKind::Watchdog,
+                    "event thread keeps failing after restart",
+                );
+                self.stop_thread(false);
+                self.is_dead = true;
+                // Start from scratch if application calls `restart()`.
+                self.watchdog = Watchdog::new();
+            }
+        }
+    }
 
-        ev
+    pub(crate) fn is_alive(&self) -> bool {
+        !self.is_dead
+    }
+
+    pub(crate) fn is_waiting_for_activation(&self) -> bool {
+        false
+    }
+
+    pub(crate) fn backend_info(&self) -> BackendInfo {
+        match self.xinput_dll {
+            Some(dll) => BackendInfo::new("XInput", Some(dll)),
+            None => BackendInfo::new("HID", None),
+        }
     }
 
     pub fn gamepad(&self, id: usize) -> Option<&Gamepad> {
@@ -84,53 +903,145 @@ impl Gilrs {
         self.gamepads.len()
     }
 
-    fn spawn_thread(tx: Sender<Event>, connected: [bool; MAX_XINPUT_CONTROLLERS]) {
-        thread::spawn(move || unsafe {
-            // Issue #70 fix - Maintain a prev_state per controller id. Otherwise the loop will compare the prev_state of a different controller.
-            let mut prev_states: [XState; MAX_XINPUT_CONTROLLERS] =
+    fn spawn_thread(
+        commands: Receiver<Command>,
+        xinput: Option<XInputHandle>,
+        mut hid: HidGamepads,
+        stop_flag: Arc<AtomicBool>,
+        heartbeat: Arc<AtomicUsize>,
+    ) -> io::Result<JoinHandle<()>> {
+        let builder = thread::Builder::new().name("gilrs-xinput".to_owned());
+        builder.spawn(move || unsafe {
+            let mut subscribers = Broadcast {
+                subscribers: Vec::new(),
+            };
+            // Last state read from each controller, subscribers compare it with their own.
+            let mut states: [XState; MAX_XINPUT_CONTROLLERS] =
                 [mem::zeroed::<XState>(); MAX_XINPUT_CONTROLLERS];
-            let mut connected = connected;
-            let mut counter = 0;
-
-            loop {
-                for id in 0..MAX_XINPUT_CONTROLLERS {
-                    if *connected.get_unchecked(id)
-                        || counter % ITERATIONS_TO_CHECK_IF_CONNECTED == 0
-                    {
-                        match rusty_xinput::xinput_get_state(id as u32) {
-                            Ok(XInputState { raw: state }) => {
-                                if !connected[id] {
-                                    connected[id] = true;
-                                    let _ = tx.send(Event::new(id, EventType::Connected));
-                                }
+            let mut connected = [false; MAX_XINPUT_CONTROLLERS];
+            // `None` forces connection check in next iteration.
+            let mut last_check: Option<Instant> = None;
+            let mut recheck_until: Option<Instant> = None;
+            // Without notifications connections are checked every `check_interval`.
+            let mut notifications = match DeviceNotifications::new() {
+                Ok(notifications) => Some(notifications),
+                Err(e) => {
+                    warn!("Failed to register for device notifications: {}", e);
+                    None
+                }
+            };
+
+            while !stop_flag.load(Ordering::SeqCst) {
+                heartbeat.fetch_add(1, Ordering::Relaxed);
+
+                while let Ok(command) = commands.try_recv() {
+                    match command {
+                        Command::Subscribe(sub) => subscribers.add(*sub, &hid),
+                        Command::Unsubscribe(key) => subscribers.remove(key),
+                        Command::SetExclusive {
+                            id,
+                            exclusive,
+                            reply,
+                        } => {
+                            let _ = reply.send(hid.set_exclusive(id, exclusive, &mut subscribers));
+                        }
+                    }
+                }
+
+                if let Some(notifications) = notifications.as_mut() {
+                    if notifications.poll() {
+                        last_check = None;
+                        recheck_until = Some(Instant::now() + Duration::from_millis(RECHECK_TIME));
+                    }
+                }
+
+                if subscribers.is_suspended() {
+                    thread::sleep(subscribers.sleep_time(true));
+                    continue;
+                }
 
-                                if state.dwPacketNumber != prev_states[id].dwPacketNumber {
-                                    Self::compare_state(
-                                        id,
-                                        &state.Gamepad,
-                                        &prev_states[id].Gamepad,
-                                        &tx,
-                                    );
-                                    prev_states[id] = state;
+                if subscribers.take_rescan() {
+                    last_check = None;
+                }
+                let rechecking = match recheck_until {
+                    Some(until) => Instant::now() < until,
+                    None => false,
+                };
+                let check_connections = match last_check {
+                    None => true,
+                    Some(time) if rechecking => {
+                        time.elapsed() >= Duration::from_millis(RECHECK_INTERVAL)
+                    }
+                    Some(_) if notifications.is_some() => false,
+                    Some(time) => time.elapsed() >= subscribers.check_interval(),
+                };
+                if check_connections {
+                    last_check = Some(Instant::now());
+                }
+
+                let poll = panic::catch_unwind(AssertUnwindSafe(|| {
+                    // Without XInput DLL only HID gamepads are polled.
+                    if let Some(ref xinput) = xinput {
+                        for id in 0..MAX_XINPUT_CONTROLLERS {
+                            if *connected.get_unchecked(id) || check_connections {
+                                match xinput.get_state(id as u32) {
+                                    Ok(XInputState { raw: state }) => {
+                                        if !connected[id] {
+                                            info!("XInput gamepad {} connected.", id);
+                                            connected[id] = true;
+                                        }
+                                        states[id] = state;
+                                    }
+                                    Err(XInputUsageError::DeviceNotConnected) if connected[id] => {
+                                        info!("XInput gamepad {} disconnected.", id);
+                                        connected[id] = false;
+                                    }
+                                    Err(XInputUsageError::DeviceNotConnected) => (),
+                                    Err(e) => {
+                                        error!("Failed to get gamepad state: {:?}", e);
+                                        subscribers.push_error(
+                                            Some(id),
+                                            BackendErrorKind::Poll,
+                                            error_code(&e),
+                                            &format!("{:?}", e),
+                                        );
+                                    }
                                 }
                             }
-                            Err(XInputUsageError::DeviceNotConnected) if connected[id] => {
-                                connected[id] = false;
-                                let _ = tx.send(Event::new(id, EventType::Disconnected));
-                            }
-                            Err(XInputUsageError::DeviceNotConnected) => (),
-                            Err(e) => error!("Failed to get gamepad state: {:?}", e),
                         }
                     }
+
+                    if check_connections {
+                        hid.scan(&mut subscribers);
+                    }
+                    hid.poll(&mut subscribers);
+                }));
+
+                // Don't let a panic kill the thread. Report it and pretend that all gamepads were
+                // disconnected, they will be detected again on next connection check.
+                if let Err(payload) = poll {
+                    let msg = utils::panic_message(&*payload);
+                    error!("XInput polling panicked: {}", msg);
+                    subscribers.push_error(
+                        None,
+                        BackendErrorKind::Other,
+                        None,
+                        &format!("panic: {}", msg),
+                    );
+
+                    connected = [false; MAX_XINPUT_CONTROLLERS];
+                    hid.disconnect_all(&mut subscribers);
+                    last_check = None;
                 }
+                subscribers.sync_xinput(&connected, &states);
 
-                counter = counter.wrapping_add(1);
-                thread::sleep(Duration::from_millis(EVENT_THREAD_SLEEP_TIME));
+                let any_connected = connected.iter().any(|&c| c) || !hid.is_empty();
+                thread::sleep(subscribers.sleep_time(any_connected));
             }
-        });
+        })
     }
 
-    fn compare_state(id: usize, g: &XGamepad, pg: &XGamepad, tx: &Sender<Event>) {
+    fn compare_state(id: usize, g: &XGamepad, pg: &XGamepad, tx: &mut EventSender) {
         if g.bLeftTrigger != pg.bLeftTrigger {
             let _ = tx.send(Event::new(
                 id,
@@ -356,34 +1267,95 @@ impl Gilrs {
     }
 }
 
+impl Drop for Gilrs {
+    fn drop(&mut self) {
+        self.stop_thread(true);
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Gamepad {
+    name: String,
     uuid: Uuid,
     id: u32,
     is_connected: bool,
+    errors: ErrorQueue,
+    /// `None` for HID gamepads and if XInput is not available.
+    xinput: Option<XInputHandle>,
+    /// `None` for XInput controllers.
+    hid: Option<HidInfo>,
+    /// Event thread that owns HID gamepad and reopens it in `set_exclusive()`.
+    poller: Option<Arc<Poller>>,
 }
 
 impl Gamepad {
-    fn new(id: u32) -> Gamepad {
-        let is_connected = {
-            if rusty_xinput::xinput_get_state(id).is_ok() {
-                true
-            } else {
-                false
-            }
+    fn new(id: u32, xinput: Option<XInputHandle>, errors: ErrorQueue) -> Gamepad {
+        let is_connected = match xinput {
+            Some(ref xinput) => xinput.get_state(id).is_ok(),
+            None => false,
         };
 
         let gamepad = Gamepad {
+            name: XINPUT_NAME.to_owned(),
             uuid: Uuid::nil(),
             id,
             is_connected,
+            errors,
+            xinput,
+            hid: None,
+            poller: None,
         };
 
         gamepad
     }
 
+    fn from_hid(
+        id: u32,
+        info: HidInfo,
+        poller: Option<Arc<Poller>>,
+        errors: ErrorQueue,
+    ) -> Gamepad {
+        Gamepad {
+            name: info.name.clone(),
+            uuid: info.uuid,
+            id,
+            is_connected: false,
+            errors,
+            xinput: None,
+            hid: Some(info),
+            poller,
+        }
+    }
+
+    /// Sets name and UUID of XInput controller from its vendor and product ID. They stay
+    /// generic if IDs are not available (before Windows 8 or with some third party drivers).
+    fn identify(&mut self, get_capabilities_ex: Option<GetCapabilitiesEx>) {
+        self.name = XINPUT_NAME.to_owned();
+        self.uuid = Uuid::nil();
+
+        let get_capabilities_ex = match get_capabilities_ex {
+            Some(f) => f,
+            None => return,
+        };
+        let caps = unsafe {
+            let mut caps: XINPUT_CAPABILITIES_EX = mem::zeroed();
+            if get_capabilities_ex(1, self.id, 0, &mut caps) != ERROR_SUCCESS {
+                return;
+            }
+            caps
+        };
+        if caps.vendor_id == 0 && caps.product_id == 0 {
+            return;
+        }
+
+        self.uuid = hid::create_uuid(caps.vendor_id, caps.product_id);
+        if let Some(name) = hid::xinput_product_name(caps.vendor_id, caps.product_id) {
+            self.name = name;
+        }
+    }
+
     pub fn name(&self) -> &str {
-        "Xbox Controller"
+        &self.name
     }
 
     pub fn uuid(&self) -> Uuid {
@@ -395,7 +1367,12 @@ impl Gamepad {
     }
 
     pub fn power_info(&self) -> PowerInfo {
-        match rusty_xinput::xinput_get_gamepad_battery_information(self.id) {
+        let xinput = match self.xinput {
+            Some(ref xinput) => xinput,
+            None => return PowerInfo::Unknown,
+        };
+
+        match xinput.get_gamepad_battery_information(self.id) {
             Ok(binfo) => match binfo.battery_type {
                 BatteryType::WIRED => PowerInfo::Wired,
                 BatteryType::ALKALINE | BatteryType::NIMH => {
@@ -420,6 +1397,16 @@ impl Gamepad {
             },
             Err(e) => {
                 debug!("Failed to get battery info: {:?}", e);
+                let code = match e {
+                    XInputOptionalFnUsageError::UnknownError(code) => Some(code as i32),
+                    _ => None,
+                };
+                self.errors.push_with_code(
+                    Some(self.id as usize),
+                    BackendErrorKind::PowerInfo,
+                    code,
+                    format!("{:?}", e),
+                );
 
                 PowerInfo::Unknown
             }
@@ -427,25 +1414,92 @@ impl Gamepad {
     }
 
     pub fn is_ff_supported(&self) -> bool {
-        true
+        self.xinput.is_some()
+    }
+
+    /// HID devices report Y axes pointing down, unlike XInput.
+    pub fn is_y_axis_reversed(&self) -> bool {
+        self.hid.is_some()
+    }
+
+    pub fn has_mic_led(&self) -> bool {
+        false
+    }
+
+    pub fn set_mic_led(&self, _on: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "microphone LED is not supported on this platform",
+        ))
+    }
+
+    /// Only HID gamepads can be opened exclusively, XInput doesn't have such option.
+    pub fn is_exclusive_supported(&self) -> bool {
+        self.hid.is_some() && self.poller.is_some()
+    }
+
+    /// HID gamepad is reopened without sharing, which prevents applications that open it later
+    /// from reading it. Devices are shared by all `Gilrs` instances in process, so they all
+    /// keep receiving its input.
+    pub fn set_exclusive(&self, exclusive: bool) -> io::Result<()> {
+        let poller = match (&self.hid, &self.poller) {
+            (Some(_), Some(poller)) => poller,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "exclusive access is not supported for XInput controllers",
+                ))
+            }
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let command = Command::SetExclusive {
+            id: self.id as usize,
+            exclusive,
+            reply: tx,
+        };
+        if poller.send(command).is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "event thread is not running",
+            ));
+        }
+
+        rx.recv_timeout(SET_EXCLUSIVE_TIMEOUT).unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "event thread did not respond",
+            ))
+        })
     }
 
     pub fn ff_device(&self) -> Option<FfDevice> {
-        Some(FfDevice::new(self.id))
+        self.xinput
+            .clone()
+            .map(|xinput| FfDevice::new(self.id, xinput, self.errors.clone()))
     }
 
     pub fn buttons(&self) -> &[EvCode] {
-        &native_ev_codes::BUTTONS
+        match self.hid {
+            Some(ref hid) => &hid.buttons,
+            None => &native_ev_codes::BUTTONS,
+        }
     }
 
     pub fn axes(&self) -> &[EvCode] {
-        &native_ev_codes::AXES
+        match self.hid {
+            Some(ref hid) => &hid.axes,
+            None => &native_ev_codes::AXES,
+        }
     }
 
     pub(crate) fn axis_info(&self, nec: EvCode) -> Option<&AxisInfo> {
-        native_ev_codes::AXES_INFO
-            .get(nec.0 as usize)
-            .and_then(|o| o.as_ref())
+        let axes_info = match self.hid {
+            Some(ref hid) => &hid.axes_info[..],
+            None => &native_ev_codes::AXES_INFO[..],
+        };
+
+        axes_info.get(nec.0 as usize).and_then(|o| o.as_ref())
     }
 }
 
@@ -454,12 +1508,20 @@ fn is_mask_eq(l: u16, r: u16, mask: u16) -> bool {
     (l & mask != 0) == (r & mask != 0)
 }
 
+/// Returns error code that Windows returned, if any.
+pub(super) fn error_code(err: &XInputUsageError) -> Option<i32> {
+    match *err {
+        XInputUsageError::UnknownError(code) => Some(code as i32),
+        _ => None,
+    }
+}
+
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
 
 #[cfg_attr(feature="serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct EvCode(u8);
+pub struct EvCode(pub(super) u8);
 
 impl EvCode {
     pub fn into_u32(self) -> u32 {
@@ -475,7 +1537,7 @@ impl Display for EvCode {
 
 #[derive(Debug)]
 enum Error {
-    FailedToLoadDll(XInputLoadingFailure),
+    ThreadSpawn(io::Error),
 }
 
 impl StdError for Error {}
@@ -483,8 +1545,8 @@ impl StdError for Error {}
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         match self {
-            Error::FailedToLoadDll(e) => {
-                f.write_fmt(format_args!("Failed to load XInput DLL {:?}", e))
+            Error::ThreadSpawn(e) => {
+                f.write_fmt(format_args!("Failed to spawn XInput thread {}", e))
             }
         }
     }
@@ -536,6 +1598,25 @@ pub mod native_ev_codes {
     pub const BTN_DPAD_LEFT: EvCode = EvCode(29);
     pub const BTN_DPAD_RIGHT: EvCode = EvCode(30);
 
+    // Back buttons of Steam Deck, only reported by Steam Input backend. Codes follow HID buttons.
+    #[cfg(feature = "steam")]
+    pub const BTN_L4: EvCode = EvCode(192);
+    #[cfg(feature = "steam")]
+    pub const BTN_R4: EvCode = EvCode(193);
+    #[cfg(feature = "steam")]
+    pub const BTN_L5: EvCode = EvCode(194);
+    #[cfg(feature = "steam")]
+    pub const BTN_R5: EvCode = EvCode(195);
+
+    // Touchpad click and microphone button of Sony controllers, only reported by HID drivers.
+    #[cfg(feature = "hidapi")]
+    pub const BTN_TOUCHPAD: EvCode = EvCode(196);
+    #[cfg(feature = "hidapi")]
+    pub const BTN_MISC1: EvCode = EvCode(197);
+
+    // Codes of elements mapped to part of other element's axis, never reported by backend.
+    pub const SPLIT_AXES: [EvCode; 4] = [EvCode(198), EvCode(199), EvCode(200), EvCode(201)];
+
     pub(super) static BUTTONS: [EvCode; 15] = [
         BTN_SOUTH,
         BTN_EAST,
@@ -0,0 +1,809 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HID joysticks and gamepads that are not available through XInput, like DualShock, DualSense
+//! or Switch Pro controllers.
+//!
+//! Devices are polled by XInput thread. Input reports are read with overlapped I/O and parsed
+//! with `HidP_*` functions, so no device specific code is needed.
+
+use super::gamepad::{native_ev_codes as nec, Broadcast, EvCode};
+use crate::{utils, AxisInfo, Event, EventType};
+
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::{mem, ptr, slice};
+
+use uuid::Uuid;
+use winapi::shared::hidpi::{
+    HidP_GetButtonCaps, HidP_GetCaps, HidP_GetUsageValue, HidP_GetUsages, HidP_GetValueCaps,
+    HidP_Input, HidP_MaxUsageListLength, HIDP_BUTTON_CAPS, HIDP_CAPS, HIDP_STATUS_SUCCESS,
+    HIDP_VALUE_CAPS, PHIDP_PREPARSED_DATA,
+};
+use winapi::shared::hidsdi::{
+    HidD_FreePreparsedData, HidD_GetAttributes, HidD_GetHidGuid, HidD_GetPreparsedData,
+    HidD_GetProductString, HIDD_ATTRIBUTES,
+};
+use winapi::shared::minwindef::{DWORD, FALSE, TRUE, ULONG};
+use winapi::shared::winerror::{ERROR_IO_INCOMPLETE, ERROR_IO_PENDING};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::fileapi::{CreateFileW, ReadFile, OPEN_EXISTING};
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::ioapiset::{CancelIo, GetOverlappedResult};
+use winapi::um::minwinbase::OVERLAPPED;
+use winapi::um::setupapi::{
+    SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInterfaces, SetupDiGetClassDevsW,
+    SetupDiGetDeviceInterfaceDetailW, DIGCF_DEVICEINTERFACE, DIGCF_PRESENT,
+    SP_DEVICE_INTERFACE_DATA, SP_DEVICE_INTERFACE_DETAIL_DATA_W,
+};
+use winapi::um::winbase::FILE_FLAG_OVERLAPPED;
+use winapi::um::winnt::{FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, HANDLE, PCHAR};
+
+/// Native code of first HID button. Codes below are used by XInput and axes.
+const BUTTON_BASE: u8 = 64;
+const MAX_BUTTONS: usize = 128;
+/// Native code of first axis that doesn't correspond to any XInput axis (slider, dial, wheel).
+const EXTRA_AXIS_BASE: u8 = 32;
+const MAX_EXTRA_AXES: usize = 32;
+/// Size of `HidInfo::axes_info`.
+const AXES_INFO_LEN: usize = (EXTRA_AXIS_BASE as usize) + MAX_EXTRA_AXES;
+/// Limit of reports processed in one poll, so a chatty device can't starve others.
+const MAX_REPORTS_PER_POLL: usize = 32;
+
+const USAGE_PAGE_GENERIC: u16 = 0x01;
+const USAGE_PAGE_BUTTON: u16 = 0x09;
+const USAGE_JOYSTICK: u16 = 0x04;
+const USAGE_GAMEPAD: u16 = 0x05;
+const USAGE_X: u16 = 0x30;
+const USAGE_Y: u16 = 0x31;
+const USAGE_Z: u16 = 0x32;
+const USAGE_RX: u16 = 0x33;
+const USAGE_RY: u16 = 0x34;
+const USAGE_RZ: u16 = 0x35;
+const USAGE_SLIDER: u16 = 0x36;
+const USAGE_DIAL: u16 = 0x37;
+const USAGE_WHEEL: u16 = 0x38;
+const USAGE_HAT: u16 = 0x39;
+
+/// Description of HID gamepad, sent to `Gilrs` before its `Connected` event.
+#[derive(Debug, Clone)]
+pub(super) struct HidInfo {
+    /// Device interface path, used to give reconnected device the same id.
+    pub path: OsString,
+    pub name: String,
+    pub uuid: Uuid,
+    pub buttons: Vec<EvCode>,
+    pub axes: Vec<EvCode>,
+    /// Indexed by `EvCode`.
+    pub axes_info: Vec<Option<AxisInfo>>,
+}
+
+/// HID gamepads polled by XInput thread. Gamepad at index `i` has id `first_id + i`.
+pub(super) struct HidGamepads {
+    first_id: usize,
+    paths: Vec<OsString>,
+    devices: Vec<Option<Device>>,
+    /// Description of each gamepad that was opened, reported to `Gilrs` instances that
+    /// subscribe later.
+    infos: Vec<Option<HidInfo>>,
+    /// Paths of present devices that are not gamepads or can't be opened.
+    ignored: Vec<OsString>,
+}
+
+impl HidGamepads {
+    /// `known` are paths of gamepads that already have an id, for example from before thread was
+    /// restarted.
+    pub fn new(first_id: usize, known: Vec<OsString>) -> Self {
+        HidGamepads {
+            first_id,
+            devices: known.iter().map(|_| None).collect(),
+            infos: known.iter().map(|_| None).collect(),
+            paths: known,
+            ignored: Vec::new(),
+        }
+    }
+
+    /// Opens gamepads that were connected since last scan.
+    pub fn scan(&mut self, tx: &mut Broadcast) {
+        let present = device_paths();
+        self.ignored.retain(|path| present.contains(path));
+
+        for path in present {
+            let idx = self.paths.iter().position(|p| *p == path);
+            let is_open = match idx {
+                Some(idx) => self.devices[idx].is_some(),
+                None => false,
+            };
+            if is_open || self.ignored.contains(&path) {
+                continue;
+            }
+
+            // XInput compatible devices have "IG_" in path. They are already handled by XInput,
+            // which also supports their force feedback.
+            if path.to_string_lossy().to_uppercase().contains("IG_") {
+                self.ignored.push(path);
+                continue;
+            }
+
+            let (device, info) = match unsafe { Device::open(&path, false) } {
+                Some(opened) => opened,
+                None => {
+                    self.ignored.push(path);
+                    continue;
+                }
+            };
+
+            let idx = match idx {
+                Some(idx) => idx,
+                None => {
+                    self.paths.push(path);
+                    self.devices.push(None);
+                    self.infos.push(None);
+                    self.paths.len() - 1
+                }
+            };
+            let id = self.first_id + idx;
+
+            info!("HID gamepad {} ({}) connected.", id, info.name);
+            tx.send_hid_info(id, &info);
+            self.devices[idx] = Some(device);
+            self.infos[idx] = Some(info);
+            tx.send(Event::new(id, EventType::Connected));
+        }
+    }
+
+    /// Reads pending input reports of all gamepads.
+    pub fn poll(&mut self, tx: &mut Broadcast) {
+        for (idx, slot) in self.devices.iter_mut().enumerate() {
+            let id = self.first_id + idx;
+            let alive = match slot {
+                Some(device) => unsafe { device.poll(id, tx) },
+                None => continue,
+            };
+
+            if !alive {
+                info!("HID gamepad {} disconnected.", id);
+                *slot = None;
+                tx.send(Event::new(id, EventType::Disconnected));
+            }
+        }
+    }
+
+    /// Reopens gamepad with or without sharing. Exclusive open fails if other application already
+    /// has the device open, gamepad stays shared then. Without sharing, applications that open
+    /// the device later fail to do so, processes that use Raw Input still receive its reports.
+    pub unsafe fn set_exclusive(
+        &mut self,
+        id: usize,
+        exclusive: bool,
+        tx: &mut Broadcast,
+    ) -> io::Result<()> {
+        let idx = id.wrapping_sub(self.first_id);
+        let was_exclusive = match self.devices.get(idx) {
+            Some(Some(device)) => device.exclusive,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "gamepad is not connected",
+                ))
+            }
+        };
+        if was_exclusive == exclusive {
+            return Ok(());
+        }
+
+        // Our own handle would make exclusive open fail.
+        self.devices[idx] = None;
+        if let Some((device, _)) = Device::open(&self.paths[idx], exclusive) {
+            self.devices[idx] = Some(device);
+            return Ok(());
+        }
+
+        let err = io::Error::last_os_error();
+        match Device::open(&self.paths[idx], was_exclusive) {
+            Some((device, _)) => self.devices[idx] = Some(device),
+            None => {
+                info!("HID gamepad {} disconnected.", id);
+                tx.send(Event::new(id, EventType::Disconnected));
+            }
+        }
+
+        Err(err)
+    }
+
+    /// Returns ids and descriptions of open gamepads.
+    pub fn connected(&self) -> impl Iterator<Item = (usize, &HidInfo)> {
+        let first_id = self.first_id;
+        self.devices
+            .iter()
+            .zip(self.infos.iter())
+            .enumerate()
+            .filter_map(move |(idx, (device, info))| match (device, info) {
+                (Some(_), Some(info)) => Some((first_id + idx, info)),
+                _ => None,
+            })
+    }
+
+    /// Returns `true` if no HID gamepad is open.
+    pub fn is_empty(&self) -> bool {
+        self.devices.iter().all(Option::is_none)
+    }
+
+    /// Closes all gamepads. They will be opened again by next scan.
+    pub fn disconnect_all(&mut self, tx: &mut Broadcast) {
+        for (idx, slot) in self.devices.iter_mut().enumerate() {
+            if slot.take().is_some() {
+                tx.send(Event::new(self.first_id + idx, EventType::Disconnected));
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Value {
+    usage: u16,
+    code: EvCode,
+    min: i32,
+    max: i32,
+    bits: u16,
+}
+
+impl Value {
+    fn new(caps: &HIDP_VALUE_CAPS, usage: u16, code: EvCode) -> Self {
+        let bits = caps.BitSize;
+        let min = caps.LogicalMin;
+        let mut max = caps.LogicalMax;
+        // Some devices declare unsigned maximum that doesn't fit in signed field, like 255 in
+        // 8 bits, which is then read as -1.
+        if max < min && bits > 0 && bits < 32 {
+            max = ((1u32 << bits) - 1) as i32;
+        }
+
+        Value {
+            usage,
+            code,
+            min,
+            max,
+            bits,
+        }
+    }
+
+    fn info(&self) -> AxisInfo {
+        AxisInfo {
+            min: self.min,
+            max: self.max,
+            deadzone: None,
+        }
+    }
+
+    /// Sign extends value if logical range is signed.
+    fn decode(&self, raw: ULONG) -> i32 {
+        if self.min < 0 && self.bits > 0 && self.bits < 32 && raw & (1 << (self.bits - 1)) != 0 {
+            (raw as i64 - (1i64 << self.bits)) as i32
+        } else {
+            raw as i32
+        }
+    }
+}
+
+struct Device {
+    handle: HANDLE,
+    preparsed: PHIDP_PREPARSED_DATA,
+    /// Button usages, index is button number.
+    buttons: Vec<u16>,
+    pressed: Vec<bool>,
+    /// Buffer for `HidP_GetUsages()`.
+    usages: Vec<u16>,
+    axes: Vec<Value>,
+    values: Vec<i32>,
+    hat: Option<Value>,
+    hat_state: (i32, i32),
+    report: Vec<u8>,
+    // Boxed, because system writes to it until read completes.
+    overlapped: Box<OVERLAPPED>,
+    reading: bool,
+    /// Opened without sharing.
+    exclusive: bool,
+}
+
+// Handles and preparsed data can be used from any thread.
+unsafe impl Send for Device {}
+
+impl Device {
+    /// Returns `None` if device can't be opened or it's not joystick or gamepad.
+    unsafe fn open(path: &OsStr, exclusive: bool) -> Option<(Self, HidInfo)> {
+        let wide: Vec<u16> = path.encode_wide().chain(Some(0)).collect();
+        let share_mode = if exclusive {
+            0
+        } else {
+            FILE_SHARE_READ | FILE_SHARE_WRITE
+        };
+        let handle = CreateFileW(
+            wide.as_ptr(),
+            GENERIC_READ,
+            share_mode,
+            ptr::null_mut(),
+            OPEN_EXISTING,
+            FILE_FLAG_OVERLAPPED,
+            ptr::null_mut(),
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return None;
+        }
+
+        // From now on `Drop` releases resources on early return.
+        let mut device = Device {
+            handle,
+            preparsed: ptr::null_mut(),
+            buttons: Vec::new(),
+            pressed: Vec::new(),
+            usages: Vec::new(),
+            axes: Vec::new(),
+            values: Vec::new(),
+            hat: None,
+            hat_state: (0, 0),
+            report: Vec::new(),
+            overlapped: Box::new(mem::zeroed()),
+            reading: false,
+            exclusive,
+        };
+
+        if HidD_GetPreparsedData(handle, &mut device.preparsed) == 0 {
+            return None;
+        }
+
+        let mut caps: HIDP_CAPS = mem::zeroed();
+        if HidP_GetCaps(device.preparsed, &mut caps) != HIDP_STATUS_SUCCESS {
+            return None;
+        }
+        if caps.UsagePage != USAGE_PAGE_GENERIC
+            || (caps.Usage != USAGE_JOYSTICK && caps.Usage != USAGE_GAMEPAD)
+        {
+            return None;
+        }
+
+        device.find_buttons(caps.NumberInputButtonCaps);
+        device.find_values(caps.NumberInputValueCaps);
+        if device.buttons.is_empty() && device.axes.is_empty() {
+            return None;
+        }
+
+        let max_usages =
+            HidP_MaxUsageListLength(HidP_Input, USAGE_PAGE_BUTTON, device.preparsed) as usize;
+        device.usages = vec![0; max_usages.max(device.buttons.len())];
+        device.pressed = vec![false; device.buttons.len()];
+        device.values = device.axes.iter().map(|v| v.min).collect();
+        device.report = vec![0; caps.InputReportByteLength as usize];
+
+        let mut attributes: HIDD_ATTRIBUTES = mem::zeroed();
+        attributes.Size = mem::size_of::<HIDD_ATTRIBUTES>() as ULONG;
+        if HidD_GetAttributes(handle, &mut attributes) == 0 {
+            return None;
+        }
+        let vendor = attributes.VendorID;
+        let product = attributes.ProductID;
+
+        let name = product_string(handle)
+            .unwrap_or_else(|| format!("HID Gamepad {:04x}:{:04x}", vendor, product));
+
+        let mut axes: Vec<_> = device.axes.iter().map(|v| v.code).collect();
+        let mut axes_info = vec![None; AXES_INFO_LEN];
+        for value in &device.axes {
+            axes_info[value.code.0 as usize] = Some(value.info());
+        }
+        if device.hat.is_some() {
+            let info = AxisInfo {
+                min: -1,
+                max: 1,
+                deadzone: None,
+            };
+            axes.push(nec::AXIS_DPADX);
+            axes.push(nec::AXIS_DPADY);
+            axes_info[nec::AXIS_DPADX.0 as usize] = Some(info);
+            axes_info[nec::AXIS_DPADY.0 as usize] = Some(info);
+        }
+
+        let info = HidInfo {
+            path: path.to_owned(),
+            name,
+            uuid: create_uuid(vendor, product),
+            buttons: (0..device.buttons.len())
+                .map(|i| EvCode(BUTTON_BASE + i as u8))
+                .collect(),
+            axes,
+            axes_info,
+        };
+
+        if !device.start_read() {
+            return None;
+        }
+
+        debug!(
+            "Opened HID device {:?}: buttons: {}, axes: {:?}, hat: {}",
+            path,
+            device.buttons.len(),
+            info.axes,
+            device.hat.is_some()
+        );
+
+        Some((device, info))
+    }
+
+    unsafe fn find_buttons(&mut self, count: u16) {
+        let mut len = count;
+        let mut caps: Vec<HIDP_BUTTON_CAPS> = vec![mem::zeroed(); len as usize];
+        if len == 0
+            || HidP_GetButtonCaps(HidP_Input, caps.as_mut_ptr(), &mut len, self.preparsed)
+                != HIDP_STATUS_SUCCESS
+        {
+            return;
+        }
+
+        for cap in &caps[..len as usize] {
+            if cap.UsagePage != USAGE_PAGE_BUTTON {
+                continue;
+            }
+
+            if cap.IsRange != 0 {
+                let range = cap.u.Range();
+                self.buttons.extend(range.UsageMin..=range.UsageMax);
+            } else {
+                self.buttons.push(cap.u.NotRange().Usage);
+            }
+        }
+
+        self.buttons.sort_unstable();
+        self.buttons.dedup();
+        self.buttons.truncate(MAX_BUTTONS);
+    }
+
+    unsafe fn find_values(&mut self, count: u16) {
+        let mut len = count;
+        let mut caps: Vec<HIDP_VALUE_CAPS> = vec![mem::zeroed(); len as usize];
+        if len == 0
+            || HidP_GetValueCaps(HidP_Input, caps.as_mut_ptr(), &mut len, self.preparsed)
+                != HIDP_STATUS_SUCCESS
+        {
+            return;
+        }
+
+        let mut usages = Vec::new();
+        for cap in &caps[..len as usize] {
+            if cap.UsagePage != USAGE_PAGE_GENERIC {
+                continue;
+            }
+
+            if cap.IsRange != 0 {
+                let range = cap.u.Range();
+                usages.extend((range.UsageMin..=range.UsageMax).map(|u| (u, cap)));
+            } else {
+                usages.push((cap.u.NotRange().Usage, cap));
+            }
+        }
+        // Keep order of SDL mappings, which index axes by usage.
+        usages.sort_by_key(|&(usage, _)| usage);
+        usages.dedup_by_key(|&mut (usage, _)| usage);
+
+        let mut extra = 0;
+        for (usage, cap) in usages {
+            let code = match usage {
+                USAGE_X => nec::AXIS_LSTICKX,
+                USAGE_Y => nec::AXIS_LSTICKY,
+                USAGE_Z => nec::AXIS_LEFTZ,
+                USAGE_RX => nec::AXIS_RSTICKX,
+                USAGE_RY => nec::AXIS_RSTICKY,
+                USAGE_RZ => nec::AXIS_RIGHTZ,
+                USAGE_SLIDER | USAGE_DIAL | USAGE_WHEEL if extra < MAX_EXTRA_AXES => {
+                    extra += 1;
+                    EvCode(EXTRA_AXIS_BASE + extra as u8 - 1)
+                }
+                USAGE_HAT if self.hat.is_none() => {
+                    self.hat = Some(Value::new(cap, usage, nec::AXIS_DPADX));
+                    continue;
+                }
+                _ => continue,
+            };
+
+            self.axes.push(Value::new(cap, usage, code));
+        }
+    }
+
+    /// Starts reading next input report. Returns false if device is gone.
+    unsafe fn start_read(&mut self) -> bool {
+        *self.overlapped = mem::zeroed();
+        let res = ReadFile(
+            self.handle,
+            self.report.as_mut_ptr() as *mut _,
+            self.report.len() as DWORD,
+            ptr::null_mut(),
+            &mut *self.overlapped,
+        );
+
+        // Read can also complete immediately, result is then available from
+        // `GetOverlappedResult()` anyway.
+        if res == FALSE && GetLastError() != ERROR_IO_PENDING {
+            return false;
+        }
+        self.reading = true;
+
+        true
+    }
+
+    /// Processes completed input reports. Returns false if device is gone.
+    unsafe fn poll(&mut self, id: usize, tx: &mut Broadcast) -> bool {
+        for _ in 0..MAX_REPORTS_PER_POLL {
+            if !self.reading && !self.start_read() {
+                return false;
+            }
+
+            let mut len = 0;
+            if GetOverlappedResult(self.handle, &mut *self.overlapped, &mut len, FALSE) == FALSE {
+                return GetLastError() == ERROR_IO_INCOMPLETE;
+            }
+            self.reading = false;
+            self.process_report(id, len, tx);
+        }
+
+        true
+    }
+
+    unsafe fn process_report(&mut self, id: usize, len: DWORD, tx: &mut Broadcast) {
+        let report = self.report.as_mut_ptr() as PCHAR;
+
+        let mut count = self.usages.len() as ULONG;
+        if !self.buttons.is_empty()
+            && HidP_GetUsages(
+                HidP_Input,
+                USAGE_PAGE_BUTTON,
+                0,
+                self.usages.as_mut_ptr(),
+                &mut count,
+                self.preparsed,
+                report,
+                len,
+            ) == HIDP_STATUS_SUCCESS
+        {
+            let usages = &self.usages[..count as usize];
+            for (idx, usage) in self.buttons.iter().enumerate() {
+                let pressed = usages.contains(usage);
+                if pressed != self.pressed[idx] {
+                    self.pressed[idx] = pressed;
+                    let code = crate::EvCode(EvCode(BUTTON_BASE + idx as u8));
+                    let event = if pressed {
+                        EventType::ButtonPressed(code)
+                    } else {
+                        EventType::ButtonReleased(code)
+                    };
+                    tx.send(Event::new(id, event));
+                }
+            }
+        }
+
+        for (idx, axis) in self.axes.iter().enumerate() {
+            // Fails if value is not part of this report, for devices with multiple report IDs.
+            let val = match self.read_value(axis, report, len) {
+                Some(val) => val,
+                None => continue,
+            };
+
+            if val != self.values[idx] {
+                self.values[idx] = val;
+                tx.send(Event::new(
+                    id,
+                    EventType::AxisValueChanged(val, crate::EvCode(axis.code)),
+                ));
+            }
+        }
+
+        if let Some(hat) = self.hat {
+            let val = match self.read_value(&hat, report, len) {
+                Some(val) => val,
+                None => return,
+            };
+
+            let (x, y) = utils::hat_to_axes(val, hat.min, hat.max);
+
+            if x != self.hat_state.0 {
+                let event = EventType::AxisValueChanged(x, crate::native_ev_codes::AXIS_DPADX);
+                tx.send(Event::new(id, event));
+            }
+            if y != self.hat_state.1 {
+                let event = EventType::AxisValueChanged(y, crate::native_ev_codes::AXIS_DPADY);
+                tx.send(Event::new(id, event));
+            }
+            self.hat_state = (x, y);
+        }
+    }
+
+    unsafe fn read_value(&self, value: &Value, report: PCHAR, len: DWORD) -> Option<i32> {
+        let mut raw = 0;
+        let status = HidP_GetUsageValue(
+            HidP_Input,
+            USAGE_PAGE_GENERIC,
+            0,
+            value.usage,
+            &mut raw,
+            self.preparsed,
+            report,
+            len,
+        );
+
+        if status == HIDP_STATUS_SUCCESS {
+            Some(value.decode(raw))
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        unsafe {
+            if self.reading {
+                // Wait until read is cancelled, system could still write to our buffers.
+                let mut len = 0;
+                CancelIo(self.handle);
+                GetOverlappedResult(self.handle, &mut *self.overlapped, &mut len, TRUE);
+            }
+            if !self.preparsed.is_null() {
+                HidD_FreePreparsedData(self.preparsed);
+            }
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Returns trimmed product string of HID device or `None` if it's empty.
+unsafe fn product_string(handle: HANDLE) -> Option<String> {
+    let mut name = [0u16; 128];
+    if HidD_GetProductString(
+        handle,
+        name.as_mut_ptr() as *mut _,
+        mem::size_of_val(&name) as ULONG,
+    ) == 0
+    {
+        return None;
+    }
+
+    let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    let name = String::from_utf16_lossy(&name[..len]);
+    if name.trim().is_empty() {
+        None
+    } else {
+        Some(name.trim().to_owned())
+    }
+}
+
+/// Returns product string of XInput compatible HID device with given vendor and product ID.
+///
+/// XInput doesn't tell which HID device belongs to which slot, but the product string is the
+/// same for all devices with the same IDs, so the first one is good enough.
+pub(super) fn xinput_product_name(vendor: u16, product: u16) -> Option<String> {
+    let ids = format!("VID_{:04X}&PID_{:04X}", vendor, product);
+
+    device_paths().into_iter().find_map(|path| {
+        let upper = path.to_string_lossy().to_uppercase();
+        if !upper.contains("IG_") || !upper.contains(&ids) {
+            return None;
+        }
+
+        unsafe {
+            // XInput driver may have the device open exclusively, but attributes and strings
+            // can be read without any access rights.
+            let wide: Vec<u16> = path.encode_wide().chain(Some(0)).collect();
+            let handle = CreateFileW(
+                wide.as_ptr(),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                0,
+                ptr::null_mut(),
+            );
+            if handle == INVALID_HANDLE_VALUE {
+                return None;
+            }
+            let name = product_string(handle);
+            CloseHandle(handle);
+
+            name
+        }
+    })
+}
+
+/// Returns paths of all present HID devices.
+fn device_paths() -> Vec<OsString> {
+    let mut paths = Vec::new();
+
+    unsafe {
+        let mut guid = mem::zeroed();
+        HidD_GetHidGuid(&mut guid);
+
+        let set = SetupDiGetClassDevsW(
+            &guid,
+            ptr::null(),
+            ptr::null_mut(),
+            DIGCF_PRESENT | DIGCF_DEVICEINTERFACE,
+        );
+        if set == INVALID_HANDLE_VALUE {
+            warn!("Failed to enumerate HID devices.");
+            return paths;
+        }
+
+        for index in 0.. {
+            let mut data: SP_DEVICE_INTERFACE_DATA = mem::zeroed();
+            data.cbSize = mem::size_of::<SP_DEVICE_INTERFACE_DATA>() as DWORD;
+            if SetupDiEnumDeviceInterfaces(set, ptr::null_mut(), &guid, index, &mut data) == FALSE {
+                break;
+            }
+
+            let mut size = 0;
+            SetupDiGetDeviceInterfaceDetailW(
+                set,
+                &mut data,
+                ptr::null_mut(),
+                0,
+                &mut size,
+                ptr::null_mut(),
+            );
+            if size == 0 {
+                continue;
+            }
+
+            // u32 for alignment of `cbSize`.
+            let mut buf = vec![0u32; size as usize / 4 + 1];
+            let detail = buf.as_mut_ptr() as *mut SP_DEVICE_INTERFACE_DETAIL_DATA_W;
+            (*detail).cbSize = mem::size_of::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>() as DWORD;
+            if SetupDiGetDeviceInterfaceDetailW(
+                set,
+                &mut data,
+                detail,
+                size,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            ) == FALSE
+            {
+                continue;
+            }
+
+            let path = (*detail).DevicePath.as_ptr();
+            let max_len = (size as usize - mem::size_of::<DWORD>()) / 2;
+            let path = slice::from_raw_parts(path, max_len);
+            let len = path.iter().position(|&c| c == 0).unwrap_or(max_len);
+            paths.push(OsString::from_wide(&path[..len]));
+        }
+
+        SetupDiDestroyDeviceInfoList(set);
+    }
+
+    paths
+}
+
+/// Creates SDL compatible UUID of USB device.
+pub(super) fn create_uuid(vendor: u16, product: u16) -> Uuid {
+    let vendor = vendor.to_le_bytes();
+    let product = product.to_le_bytes();
+    Uuid::from_bytes([
+        0x03, 0x00, 0x00, 0x00, vendor[0], vendor[1], 0x00, 0x00, product[0], product[1], 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sdl_uuid() {
+        // DualShock 4, as in SDL_GameControllerDB.
+        assert_eq!(
+            create_uuid(0x054c, 0x05c4).to_simple().to_string(),
+            "030000004c050000c405000000000000"
+        );
+    }
+}
@@ -6,9 +6,15 @@
 // copied, modified, or distributed except according to those terms.
 mod ff;
 mod gamepad;
+mod hid;
+mod notify;
+#[cfg(feature = "virtual-gamepad")]
+mod vigem;
 
 pub use self::ff::Device as FfDevice;
 pub use self::gamepad::{native_ev_codes, EvCode, Gamepad, Gilrs};
+#[cfg(feature = "virtual-gamepad")]
+pub use self::vigem::VirtualDevice;
 
 pub const NAME: &'static str = "Windows";
 pub const IS_Y_AXIS_REVERSED: bool = false;
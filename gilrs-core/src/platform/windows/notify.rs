@@ -0,0 +1,144 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Hidden message-only window that receives `WM_DEVICECHANGE` for all device interfaces, so
+//! event thread doesn't have to probe disconnected XInput slots periodically.
+
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::io;
+use std::mem;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+
+use winapi::shared::basetsd::LONG_PTR;
+use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::shared::winerror::ERROR_CLASS_ALREADY_EXISTS;
+use winapi::um::dbt::{
+    DBT_DEVICEARRIVAL, DBT_DEVICEREMOVECOMPLETE, DBT_DEVTYP_DEVICEINTERFACE,
+    DEV_BROADCAST_DEVICEINTERFACE_W,
+};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetWindowLongPtrW,
+    PeekMessageW, RegisterClassExW, RegisterDeviceNotificationW, SetWindowLongPtrW,
+    UnregisterDeviceNotification, DEVICE_NOTIFY_ALL_INTERFACE_CLASSES, DEVICE_NOTIFY_WINDOW_HANDLE,
+    GWLP_USERDATA, HDEVNOTIFY, HWND_MESSAGE, MSG, PM_REMOVE, WM_DEVICECHANGE, WNDCLASSEXW,
+};
+
+#[derive(Debug)]
+pub(super) struct DeviceNotifications {
+    hwnd: HWND,
+    notify: HDEVNOTIFY,
+    /// Set by window procedure, pointer to it is stored in window's `GWLP_USERDATA`.
+    changed: Box<Cell<bool>>,
+}
+
+impl DeviceNotifications {
+    /// Creates window owned by current thread. Messages are only dispatched by `poll()`, which
+    /// must be called from the same thread.
+    pub fn new() -> io::Result<Self> {
+        unsafe {
+            let class_name = wide("gilrs-device-notifications");
+            let instance = GetModuleHandleW(ptr::null());
+
+            let mut class: WNDCLASSEXW = mem::zeroed();
+            class.cbSize = mem::size_of::<WNDCLASSEXW>() as UINT;
+            class.lpfnWndProc = Some(wnd_proc);
+            class.hInstance = instance;
+            class.lpszClassName = class_name.as_ptr();
+            if RegisterClassExW(&class) == 0 && GetLastError() != ERROR_CLASS_ALREADY_EXISTS {
+                return Err(io::Error::last_os_error());
+            }
+
+            let hwnd = CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                ptr::null(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                ptr::null_mut(),
+                instance,
+                ptr::null_mut(),
+            );
+            if hwnd.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let changed = Box::new(Cell::new(false));
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, &*changed as *const _ as LONG_PTR);
+
+            let mut filter: DEV_BROADCAST_DEVICEINTERFACE_W = mem::zeroed();
+            filter.dbcc_size = mem::size_of::<DEV_BROADCAST_DEVICEINTERFACE_W>() as u32;
+            filter.dbcc_devicetype = DBT_DEVTYP_DEVICEINTERFACE;
+            let notify = RegisterDeviceNotificationW(
+                hwnd as _,
+                &mut filter as *mut _ as *mut _,
+                DEVICE_NOTIFY_WINDOW_HANDLE | DEVICE_NOTIFY_ALL_INTERFACE_CLASSES,
+            );
+            if notify.is_null() {
+                let err = io::Error::last_os_error();
+                DestroyWindow(hwnd);
+                return Err(err);
+            }
+
+            Ok(DeviceNotifications {
+                hwnd,
+                notify,
+                changed,
+            })
+        }
+    }
+
+    /// Dispatches pending messages. Returns `true` if any device was added or removed since
+    /// last call.
+    pub fn poll(&mut self) -> bool {
+        unsafe {
+            let mut msg: MSG = mem::zeroed();
+            while PeekMessageW(&mut msg, self.hwnd, 0, 0, PM_REMOVE) != 0 {
+                DispatchMessageW(&msg);
+            }
+        }
+
+        self.changed.replace(false)
+    }
+}
+
+impl Drop for DeviceNotifications {
+    fn drop(&mut self) {
+        unsafe {
+            UnregisterDeviceNotification(self.notify);
+            DestroyWindow(self.hwnd);
+        }
+    }
+}
+
+unsafe extern "system" fn wnd_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if msg == WM_DEVICECHANGE && (wparam == DBT_DEVICEARRIVAL || wparam == DBT_DEVICEREMOVECOMPLETE)
+    {
+        let changed = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const Cell<bool>;
+        if !changed.is_null() {
+            (*changed).set(true);
+        }
+    }
+
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(Some(0)).collect()
+}
@@ -0,0 +1,208 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Virtual Xbox 360 controller created by ViGEmBus driver. `ViGEmClient.dll` is loaded at
+//! runtime, so applications don't have to ship it if they don't use virtual gamepads.
+
+use crate::virtual_gamepad::Report;
+
+use winapi::shared::minwindef::HMODULE;
+use winapi::um::libloaderapi::{FreeLibrary, GetProcAddress, LoadLibraryW};
+
+use std::ffi::OsStr;
+use std::io;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::os::windows::ffi::OsStrExt;
+
+const VIGEM_CLIENT_DLL: &str = "ViGEmClient.dll";
+const VIGEM_ERROR_NONE: u32 = 0x2000_0000;
+const VIGEM_ERROR_BUS_NOT_FOUND: u32 = 0xE000_0001;
+
+/// `XUSB_REPORT`, passed by value.
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct XusbReport {
+    buttons: u16,
+    left_trigger: u8,
+    right_trigger: u8,
+    thumb_lx: i16,
+    thumb_ly: i16,
+    thumb_rx: i16,
+    thumb_ry: i16,
+}
+
+type Alloc = unsafe extern "C" fn() -> *mut c_void;
+type Free = unsafe extern "C" fn(*mut c_void);
+type ClientCall = unsafe extern "C" fn(*mut c_void) -> u32;
+type TargetCall = unsafe extern "C" fn(*mut c_void, *mut c_void) -> u32;
+type Update = unsafe extern "C" fn(*mut c_void, *mut c_void, XusbReport) -> u32;
+
+#[derive(Copy, Clone)]
+struct Api {
+    alloc: Alloc,
+    free: Free,
+    connect: ClientCall,
+    disconnect: Free,
+    target_x360_alloc: Alloc,
+    target_free: Free,
+    target_add: TargetCall,
+    target_remove: TargetCall,
+    target_x360_update: Update,
+}
+
+#[derive(Debug)]
+pub struct VirtualDevice {
+    lib: HMODULE,
+    api: Api,
+    client: *mut c_void,
+    target: *mut c_void,
+}
+
+// ViGEmClient functions can be called from any thread.
+unsafe impl Send for VirtualDevice {}
+
+impl VirtualDevice {
+    pub fn new(_name: &str) -> io::Result<Self> {
+        unsafe {
+            let name: Vec<u16> = OsStr::new(VIGEM_CLIENT_DLL)
+                .encode_wide()
+                .chain(Some(0))
+                .collect();
+            let lib = LoadLibraryW(name.as_ptr());
+            if lib.is_null() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("failed to load {}", VIGEM_CLIENT_DLL),
+                ));
+            }
+
+            let api = match Api::load(lib) {
+                Some(api) => api,
+                None => {
+                    FreeLibrary(lib);
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("{} doesn't export required functions", VIGEM_CLIENT_DLL),
+                    ));
+                }
+            };
+
+            // Drop frees everything that was allocated.
+            let mut device = VirtualDevice {
+                lib,
+                api,
+                client: (api.alloc)(),
+                target: (api.target_x360_alloc)(),
+            };
+            if device.client.is_null() || device.target.is_null() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "failed to allocate ViGEm client",
+                ));
+            }
+
+            if let Err(e) = check((api.connect)(device.client)) {
+                // Disconnecting client that is not connected is not allowed.
+                (api.free)(device.client);
+                device.client = std::ptr::null_mut();
+                return Err(e);
+            }
+            check((api.target_add)(device.client, device.target))?;
+            info!("Created virtual gamepad with ViGEm");
+
+            Ok(device)
+        }
+    }
+
+    pub(crate) fn send(&self, report: &Report) -> io::Result<()> {
+        let report = XusbReport {
+            buttons: report.buttons,
+            left_trigger: report.left_trigger,
+            right_trigger: report.right_trigger,
+            thumb_lx: report.thumb_lx,
+            thumb_ly: report.thumb_ly,
+            thumb_rx: report.thumb_rx,
+            thumb_ry: report.thumb_ry,
+        };
+
+        check(unsafe { (self.api.target_x360_update)(self.client, self.target, report) })
+    }
+}
+
+impl Drop for VirtualDevice {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.client.is_null() {
+                if !self.target.is_null() {
+                    // Fails if target wasn't added.
+                    let _ = (self.api.target_remove)(self.client, self.target);
+                }
+                (self.api.disconnect)(self.client);
+                (self.api.free)(self.client);
+            }
+            if !self.target.is_null() {
+                (self.api.target_free)(self.target);
+            }
+            FreeLibrary(self.lib);
+        }
+    }
+}
+
+impl Api {
+    unsafe fn load(lib: HMODULE) -> Option<Self> {
+        Some(Api {
+            alloc: symbol(lib, "vigem_alloc\0")?,
+            free: symbol(lib, "vigem_free\0")?,
+            connect: symbol(lib, "vigem_connect\0")?,
+            disconnect: symbol(lib, "vigem_disconnect\0")?,
+            target_x360_alloc: symbol(lib, "vigem_target_x360_alloc\0")?,
+            target_free: symbol(lib, "vigem_target_free\0")?,
+            target_add: symbol(lib, "vigem_target_add\0")?,
+            target_remove: symbol(lib, "vigem_target_remove\0")?,
+            target_x360_update: symbol(lib, "vigem_target_x360_update\0")?,
+        })
+    }
+}
+
+impl std::fmt::Debug for Api {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Api")
+    }
+}
+
+/// Returns `None` if library doesn't export `name`. `T` must be function pointer.
+unsafe fn symbol<T: Copy>(lib: HMODULE, name: &str) -> Option<T> {
+    debug_assert!(name.ends_with('\0'));
+    debug_assert_eq!(mem::size_of::<T>(), mem::size_of::<*mut c_void>());
+
+    let ptr = GetProcAddress(lib, name.as_ptr() as *const c_char);
+    if ptr.is_null() {
+        debug!(
+            "{} doesn't export {}",
+            VIGEM_CLIENT_DLL,
+            name.trim_end_matches('\0')
+        );
+        None
+    } else {
+        Some(mem::transmute_copy(&ptr))
+    }
+}
+
+fn check(err: u32) -> io::Result<()> {
+    match err {
+        VIGEM_ERROR_NONE => Ok(()),
+        VIGEM_ERROR_BUS_NOT_FOUND => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "ViGEmBus driver is not installed",
+        )),
+        err => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("ViGEm error {:#x}", err),
+        )),
+    }
+}
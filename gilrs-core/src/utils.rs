@@ -1,4 +1,17 @@
+use std::any::Any;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
 use std::time::SystemTime;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+/// Longest time `wait()` blocks on backends with event thread, so that watchdog is still checked
+/// regularly.
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(dead_code)]
+pub(crate) const WAIT_SLICE: Duration = Duration::from_millis(500);
 
 /// Returns true if nth bit in array is 1.
 #[allow(dead_code)]
@@ -6,6 +19,47 @@ pub(crate) fn test_bit(n: u16, array: &[u8]) -> bool {
     (array[(n / 8) as usize] >> (n % 8)) & 1 != 0
 }
 
+/// Hat switch positions clockwise from north, as `(x, y)` with y pointing down.
+const HAT_DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+
+/// Converts value of HID hat switch (POV) with logical range `min..=max` to d-pad axes, as
+/// `(x, y)` with y pointing down. Hats report 8 or 4 positions clockwise from north, values
+/// outside of logical range mean that hat is centered. Hats with other number of positions are
+/// always centered.
+#[allow(dead_code)]
+pub(crate) fn hat_to_axes(value: i32, min: i32, max: i32) -> (i32, i32) {
+    if value < min || value > max {
+        return (0, 0);
+    }
+
+    let position = (value - min) as usize;
+    match max - min + 1 {
+        8 => HAT_DIRECTIONS[position],
+        4 => HAT_DIRECTIONS[position * 2],
+        _ => (0, 0),
+    }
+}
+
+/// Returns message of panic payload obtained from `std::panic::catch_unwind()`.
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "unknown panic payload"
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn time_now() -> SystemTime {
     SystemTime::now()
@@ -22,3 +76,146 @@ pub fn time_now() -> SystemTime {
     let offset = Duration::from_millis(Date::now() as u64);
     SystemTime::UNIX_EPOCH + offset
 }
+
+/// Detects that backend thread died or stopped making progress.
+///
+/// Thread increments heartbeat counter on every iteration, `Gilrs` calls `check()` when polling
+/// for events.
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(dead_code)]
+#[derive(Debug)]
+pub(crate) struct Watchdog {
+    heartbeat: Arc<AtomicUsize>,
+    last_beat: usize,
+    last_beat_time: Instant,
+    last_restart: Option<Instant>,
+    /// Number of restarts after which thread didn't make any progress.
+    failed_restarts: u32,
+}
+
+/// Result of `Watchdog::check()`.
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(dead_code)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum WatchdogStatus {
+    Alive,
+    /// Thread should be restarted because of given reason.
+    Restart(&'static str),
+    /// Restarting thread doesn't help, give up.
+    Dead,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(dead_code)]
+impl Watchdog {
+    /// Thread is considered hung if heartbeat doesn't change for this long.
+    const TIMEOUT: Duration = Duration::from_secs(2);
+    /// Don't restart threads in a loop if they keep failing right after start.
+    const MIN_RESTART_INTERVAL: Duration = Duration::from_secs(5);
+    const MAX_FAILED_RESTARTS: u32 = 3;
+
+    pub(crate) fn new() -> Self {
+        Watchdog {
+            heartbeat: Arc::new(AtomicUsize::new(0)),
+            last_beat: 0,
+            last_beat_time: Instant::now(),
+            last_restart: None,
+            failed_restarts: 0,
+        }
+    }
+
+    /// Returns counter that should be passed to newly spawned thread. Resets the watchdog.
+    pub(crate) fn heartbeat(&mut self) -> Arc<AtomicUsize> {
+        let heartbeat = Arc::new(AtomicUsize::new(0));
+        self.watch(heartbeat.clone());
+
+        heartbeat
+    }
+
+    /// Starts watching thread that is already running, for example one shared with other
+    /// instances. Resets the watchdog.
+    pub(crate) fn watch(&mut self, heartbeat: Arc<AtomicUsize>) {
+        self.last_beat = heartbeat.load(Ordering::Relaxed);
+        self.last_beat_time = Instant::now();
+        self.heartbeat = heartbeat;
+    }
+
+    /// Returns whether thread should be restarted. `exited` should be true when thread is known
+    /// to be dead, for example because it dropped its end of the channel.
+    pub(crate) fn check(&mut self, exited: bool) -> WatchdogStatus {
+        let now = Instant::now();
+        let beat = self.heartbeat.load(Ordering::Relaxed);
+        if beat != self.last_beat {
+            self.last_beat = beat;
+            self.last_beat_time = now;
+            self.failed_restarts = 0;
+        }
+
+        let reason = if exited {
+            "event thread exited"
+        } else if now.duration_since(self.last_beat_time) > Self::TIMEOUT {
+            "event thread stopped responding"
+        } else {
+            return WatchdogStatus::Alive;
+        };
+
+        match self.last_restart {
+            Some(time) if now.duration_since(time) < Self::MIN_RESTART_INTERVAL => {
+                WatchdogStatus::Alive
+            }
+            _ if self.failed_restarts >= Self::MAX_FAILED_RESTARTS => WatchdogStatus::Dead,
+            _ => {
+                self.last_restart = Some(now);
+                self.failed_restarts += 1;
+                WatchdogStatus::Restart(reason)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::{hat_to_axes, Watchdog, WatchdogStatus};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn hat() {
+        assert_eq!(hat_to_axes(0, 0, 7), (0, -1));
+        assert_eq!(hat_to_axes(3, 0, 7), (1, 1));
+        // Null state of hat with range 1–8.
+        assert_eq!(hat_to_axes(0, 1, 8), (0, 0));
+        assert_eq!(hat_to_axes(8, 1, 8), (-1, -1));
+        // 4-position hat.
+        assert_eq!(hat_to_axes(3, 0, 3), (-1, 0));
+        assert_eq!(hat_to_axes(15, 0, 3), (0, 0));
+        assert_eq!(hat_to_axes(2, 0, 5), (0, 0));
+    }
+
+    #[test]
+    fn watchdog_restarts_exited_thread_once() {
+        let mut watchdog = Watchdog::new();
+        let heartbeat = watchdog.heartbeat();
+        heartbeat.fetch_add(1, Ordering::Relaxed);
+
+        assert_eq!(watchdog.check(false), WatchdogStatus::Alive);
+        assert_eq!(
+            watchdog.check(true),
+            WatchdogStatus::Restart("event thread exited")
+        );
+        // Thread exited again right after it was restarted.
+        let _ = watchdog.heartbeat();
+        assert_eq!(watchdog.check(true), WatchdogStatus::Alive);
+    }
+
+    #[test]
+    fn watchdog_gives_up() {
+        let mut watchdog = Watchdog::new();
+        for _ in 0..Watchdog::MAX_FAILED_RESTARTS {
+            watchdog.last_restart = None;
+            assert_ne!(watchdog.check(true), WatchdogStatus::Dead);
+        }
+
+        watchdog.last_restart = None;
+        assert_eq!(watchdog.check(true), WatchdogStatus::Dead);
+    }
+}
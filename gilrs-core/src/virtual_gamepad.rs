@@ -0,0 +1,171 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Gamepads created by application and exposed to other applications.
+
+use crate::native_ev_codes as nec;
+use crate::platform::VirtualDevice;
+use crate::EvCode;
+
+use std::io;
+
+const STICK_MAX: f32 = 32767.0;
+const TRIGGER_MAX: f32 = 255.0;
+
+// Bits of `Report::buttons`.
+pub(crate) const DPAD_UP: u16 = 0x0001;
+pub(crate) const DPAD_DOWN: u16 = 0x0002;
+pub(crate) const DPAD_LEFT: u16 = 0x0004;
+pub(crate) const DPAD_RIGHT: u16 = 0x0008;
+pub(crate) const START: u16 = 0x0010;
+pub(crate) const BACK: u16 = 0x0020;
+pub(crate) const LEFT_THUMB: u16 = 0x0040;
+pub(crate) const RIGHT_THUMB: u16 = 0x0080;
+pub(crate) const LEFT_SHOULDER: u16 = 0x0100;
+pub(crate) const RIGHT_SHOULDER: u16 = 0x0200;
+pub(crate) const GUIDE: u16 = 0x0400;
+pub(crate) const A: u16 = 0x1000;
+pub(crate) const B: u16 = 0x2000;
+pub(crate) const X: u16 = 0x4000;
+pub(crate) const Y: u16 = 0x8000;
+
+/// State of Xbox 360 controller, the layout of virtual gamepad on all platforms. Bits of
+/// `buttons` are the same as in XInput, positive Y of sticks is up.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct Report {
+    pub buttons: u16,
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+    pub thumb_lx: i16,
+    pub thumb_ly: i16,
+    pub thumb_rx: i16,
+    pub thumb_ry: i16,
+}
+
+/// Gamepad created by application that other applications see as Xbox 360 controller. It's
+/// implemented with uinput on Linux and [ViGEm](https://github.com/nefarius/ViGEmBus) on
+/// Windows.
+///
+/// Input is set with codes from `native_ev_codes`, as if it was reported by native gamepad, and
+/// sent to OS by `sync()`. Gamepad is removed when `VirtualGamepad` is dropped.
+///
+/// ```no_run
+/// use gilrs_core::{native_ev_codes as nec, VirtualGamepad};
+///
+/// let mut gamepad = VirtualGamepad::new("Remapped gamepad").unwrap();
+/// gamepad.set_button(nec::BTN_SOUTH, true);
+/// gamepad.set_axis(nec::AXIS_LSTICKX, -0.5);
+/// gamepad.sync().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct VirtualGamepad {
+    device: VirtualDevice,
+    report: Report,
+    sent: Option<Report>,
+}
+
+impl VirtualGamepad {
+    /// Creates new virtual gamepad. On Linux user needs write access to `/dev/uinput`. On
+    /// Windows ViGEmBus driver has to be installed and `ViGEmClient.dll` is loaded at runtime,
+    /// `name` is ignored.
+    pub fn new(name: &str) -> io::Result<Self> {
+        Ok(VirtualGamepad {
+            device: VirtualDevice::new(name)?,
+            report: Report::default(),
+            sent: None,
+        })
+    }
+
+    /// Sets state of button. `nec` is one of `native_ev_codes::BTN_*`. Xbox 360 controller
+    /// doesn't have `BTN_C`, `BTN_Z` and other extra buttons, they are ignored. `BTN_LT2` and
+    /// `BTN_RT2` fully press or release triggers.
+    pub fn set_button(&mut self, nec: EvCode, pressed: bool) {
+        let trigger = if pressed { TRIGGER_MAX as u8 } else { 0 };
+        if nec == nec::BTN_LT2 {
+            self.report.left_trigger = trigger;
+        } else if nec == nec::BTN_RT2 {
+            self.report.right_trigger = trigger;
+        } else if let Some(bit) = button_bit(nec) {
+            if pressed {
+                self.report.buttons |= bit;
+            } else {
+                self.report.buttons &= !bit;
+            }
+        }
+    }
+
+    /// Sets value of axis. `nec` is one of `native_ev_codes::AXIS_*`. Values of sticks are from
+    /// -1.0 to 1.0 (positive Y is up), values of triggers (`AXIS_LT2`, `AXIS_RT2`, `AXIS_LEFTZ`
+    /// and `AXIS_RIGHTZ`) from 0.0 to 1.0. D-pad axes press d-pad buttons. Other axes are
+    /// ignored.
+    pub fn set_axis(&mut self, nec: EvCode, value: f32) {
+        let stick = (value.max(-1.0).min(1.0) * STICK_MAX) as i16;
+        let trigger = (value.max(0.0).min(1.0) * TRIGGER_MAX).round() as u8;
+
+        if nec == nec::AXIS_LSTICKX {
+            self.report.thumb_lx = stick;
+        } else if nec == nec::AXIS_LSTICKY {
+            self.report.thumb_ly = stick;
+        } else if nec == nec::AXIS_RSTICKX {
+            self.report.thumb_rx = stick;
+        } else if nec == nec::AXIS_RSTICKY {
+            self.report.thumb_ry = stick;
+        } else if nec == nec::AXIS_LT2 || nec == nec::AXIS_LEFTZ {
+            self.report.left_trigger = trigger;
+        } else if nec == nec::AXIS_RT2 || nec == nec::AXIS_RIGHTZ {
+            self.report.right_trigger = trigger;
+        } else if nec == nec::AXIS_DPADX {
+            self.set_button(nec::BTN_DPAD_LEFT, value < -0.5);
+            self.set_button(nec::BTN_DPAD_RIGHT, value > 0.5);
+        } else if nec == nec::AXIS_DPADY {
+            self.set_button(nec::BTN_DPAD_DOWN, value < -0.5);
+            self.set_button(nec::BTN_DPAD_UP, value > 0.5);
+        }
+    }
+
+    /// Releases all buttons and centers all axes. Call `sync()` to send new state.
+    pub fn reset(&mut self) {
+        self.report = Report::default();
+    }
+
+    /// Sends current state to OS, if it changed since last call.
+    pub fn sync(&mut self) -> io::Result<()> {
+        if self.sent == Some(self.report) {
+            return Ok(());
+        }
+
+        self.device.send(&self.report)?;
+        self.sent = Some(self.report);
+
+        Ok(())
+    }
+}
+
+fn button_bit(nec: EvCode) -> Option<u16> {
+    let buttons = [
+        (nec::BTN_SOUTH, A),
+        (nec::BTN_EAST, B),
+        (nec::BTN_WEST, X),
+        (nec::BTN_NORTH, Y),
+        (nec::BTN_LT, LEFT_SHOULDER),
+        (nec::BTN_RT, RIGHT_SHOULDER),
+        (nec::BTN_SELECT, BACK),
+        (nec::BTN_START, START),
+        (nec::BTN_MODE, GUIDE),
+        (nec::BTN_LTHUMB, LEFT_THUMB),
+        (nec::BTN_RTHUMB, RIGHT_THUMB),
+        (nec::BTN_DPAD_UP, DPAD_UP),
+        (nec::BTN_DPAD_DOWN, DPAD_DOWN),
+        (nec::BTN_DPAD_LEFT, DPAD_LEFT),
+        (nec::BTN_DPAD_RIGHT, DPAD_RIGHT),
+    ];
+
+    buttons
+        .iter()
+        .find(|&&(code, _)| code == nec)
+        .map(|&(_, bit)| bit)
+}
@@ -27,7 +27,7 @@ fn main() {
                 with_delay: duration * 3,
                 ..Default::default()
             },
-            envelope: Default::default(),
+            ..Default::default()
         })
         .add_effect(BaseEffect {
             kind: BaseEffectType::Weak { magnitude: 60_000 },
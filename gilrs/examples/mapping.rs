@@ -0,0 +1,83 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Creates mapping by asking user to press each button and move each stick, then prints it in
+//! SDL2 format.
+
+use gilrs::{Axis, Button, EventType, Gilrs, Mapping};
+
+use std::process;
+
+enum Element {
+    Button(Button),
+    Axis(Axis),
+}
+
+const ELEMENTS: &[(&str, Element)] = &[
+    ("South (A)", Element::Button(Button::South)),
+    ("East (B)", Element::Button(Button::East)),
+    ("West (X)", Element::Button(Button::West)),
+    ("North (Y)", Element::Button(Button::North)),
+    ("Select", Element::Button(Button::Select)),
+    ("Start", Element::Button(Button::Start)),
+    ("left bumper", Element::Button(Button::LeftTrigger)),
+    ("right bumper", Element::Button(Button::RightTrigger)),
+    ("left stick to the right", Element::Axis(Axis::LeftStickX)),
+    ("left stick down", Element::Axis(Axis::LeftStickY)),
+    ("right stick to the right", Element::Axis(Axis::RightStickX)),
+    ("right stick down", Element::Axis(Axis::RightStickY)),
+];
+
+fn main() {
+    env_logger::init();
+
+    let mut gilrs = match Gilrs::new() {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("Failed to create gilrs context: {}", e);
+            process::exit(-1);
+        }
+    };
+
+    let mut data = Mapping::new();
+    let mut gamepad = None;
+
+    for (name, element) in ELEMENTS {
+        println!("Press {}", name);
+
+        loop {
+            let ev = match gilrs.next_event_blocking(None) {
+                Some(ev) => ev,
+                None => continue,
+            };
+            // Use only the first gamepad that sends input.
+            if *gamepad.get_or_insert(ev.id) != ev.id {
+                continue;
+            }
+
+            match (element, ev.event) {
+                (&Element::Button(btn), EventType::ButtonPressed(_, code)) => {
+                    data.insert_btn(code, btn);
+                    break;
+                }
+                (&Element::Axis(axis), EventType::AxisChanged(_, value, code))
+                    if value.abs() > 0.8 =>
+                {
+                    data.insert_axis(code, axis);
+                    break;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let id = gamepad.unwrap();
+    match gilrs.set_mapping(id.into(), &data, None) {
+        Ok(sdl) => println!("Mapping applied. SDL2 mapping:\n{}", sdl),
+        Err(e) => eprintln!("Failed to set mapping: {}", e),
+    }
+}
@@ -116,6 +116,57 @@ impl FilterFn for Jitter {
     }
 }
 
+/// Like `Jitter`, but threshold of each axis is `factor` times its
+/// [noise floor](../../struct.Gamepad.html#method.noise_floor), and not less than `min_threshold`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AdaptiveJitter {
+    pub min_threshold: f32,
+    pub factor: f32,
+}
+
+impl AdaptiveJitter {
+    /// Creates new `AdaptiveJitter` filter with minimal threshold set to 0.01 and factor set to 2.
+    pub fn new() -> Self {
+        AdaptiveJitter {
+            min_threshold: 0.01,
+            factor: 2.0,
+        }
+    }
+}
+
+impl Default for AdaptiveJitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FilterFn for AdaptiveJitter {
+    fn filter(&self, ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
+        match ev {
+            Some(Event {
+                event: EventType::AxisChanged(_, val, axis),
+                id,
+                ..
+            }) => {
+                let gamepad = gilrs.gamepad(id);
+                let threshold = gamepad
+                    .noise_floor(axis)
+                    .map(|floor| floor * self.factor)
+                    .unwrap_or(0.0)
+                    .max(self.min_threshold);
+
+                match gamepad.state().axis_data(axis) {
+                    Some(data) if val != 0.0 && (val - data.value()).abs() < threshold => {
+                        Some(Event::new(id, EventType::Dropped))
+                    }
+                    _ => ev,
+                }
+            }
+            _ => ev,
+        }
+    }
+}
+
 fn apply_deadzone(x: f32, y: f32, threshold: f32) -> (f32, f32) {
     let magnitude = utils::clamp((x * x + y * y).sqrt(), 0.0, 1.0);
     if magnitude <= threshold {
@@ -133,6 +184,7 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
             event: EventType::AxisChanged(axis, val, nec),
             id,
             time,
+            seq,
         }) => {
             let threshold = match gilrs.gamepad(id).deadzone(nec) {
                 Some(t) => t,
@@ -152,6 +204,7 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
                     Event {
                         id,
                         time,
+                        seq,
                         event: EventType::AxisChanged(axis, val.0, nec),
                     }
                 })
@@ -164,6 +217,7 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
                     Event {
                         id,
                         time,
+                        seq,
                         event: EventType::AxisChanged(axis, val, nec),
                     }
                 })
@@ -173,6 +227,7 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
             event: EventType::ButtonChanged(btn, val, nec),
             id,
             time,
+            seq,
         }) => {
             let gp = &gilrs.gamepad(id);
             let threshold = match gp.deadzone(nec) {
@@ -187,6 +242,7 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
                 Event {
                     id,
                     time,
+                    seq,
                     event: EventType::ButtonChanged(btn, val, nec),
                 }
             })
@@ -199,12 +255,13 @@ pub fn deadzone(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
 ///
 /// This filter will do nothing if gamepad have dpad buttons (to prevent double events for same
 /// element) and if standard `NativeEvCode` for dpads is used by some other buttons. It will always
-/// try to map if SDL mappings contains mappings for all four hats.
+/// try to map if SDL mappings contains mappings for all four hats. Events have code of virtual
+/// d-pad button and button it's mapped to, so hat can also be mapped to other buttons.
 pub fn axis_dpad_to_button(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event> {
     use gilrs_core::native_ev_codes as necs;
 
     fn can_map(gp: &Gamepad<'_>) -> bool {
-        let hats_mapped = gp.mapping().hats_mapped();
+        let hats_mapped = gp.raw_mapping().hats_mapped();
         if hats_mapped == 0b0000_1111 {
             true
         } else if hats_mapped == 0 {
@@ -219,75 +276,63 @@ pub fn axis_dpad_to_button(ev: Option<Event>, gilrs: &mut Gilrs) -> Option<Event
         }
     }
 
-    match ev {
+    let (id, time, seq, val, negative, positive) = match ev {
         Some(Event {
             event: EventType::AxisChanged(Axis::DPadX, val, _),
             id,
             time,
-        }) if can_map(&gilrs.gamepad(id)) => Some(if val == 1.0 {
-            Event {
-                id,
-                time,
-                event: EventType::ButtonPressed(Button::DPadRight, Code(necs::BTN_DPAD_RIGHT)),
-            }
-        } else if val == -1.0 {
-            Event {
-                id,
-                time,
-                event: EventType::ButtonPressed(Button::DPadLeft, Code(necs::BTN_DPAD_LEFT)),
-            }
-        } else if gilrs
-            .gamepad(id)
-            .state()
-            .is_pressed(Code(necs::BTN_DPAD_RIGHT))
-        {
-            Event {
-                id,
-                time,
-                event: EventType::ButtonReleased(Button::DPadRight, Code(necs::BTN_DPAD_RIGHT)),
-            }
-        } else {
-            Event {
-                id,
-                time,
-                event: EventType::ButtonReleased(Button::DPadLeft, Code(necs::BTN_DPAD_LEFT)),
-            }
-        }),
+            seq,
+        }) if can_map(&gilrs.gamepad(id)) => (
+            id,
+            time,
+            seq,
+            val,
+            (necs::BTN_DPAD_LEFT, Button::DPadLeft),
+            (necs::BTN_DPAD_RIGHT, Button::DPadRight),
+        ),
         Some(Event {
             event: EventType::AxisChanged(Axis::DPadY, val, _),
             id,
             time,
-        }) if can_map(&gilrs.gamepad(id)) => Some(if val == 1.0 {
-            Event {
-                id,
-                time,
-                event: EventType::ButtonPressed(Button::DPadUp, Code(necs::BTN_DPAD_UP)),
-            }
-        } else if val == -1.0 {
-            Event {
-                id,
-                time,
-                event: EventType::ButtonPressed(Button::DPadDown, Code(necs::BTN_DPAD_DOWN)),
-            }
-        } else if gilrs
-            .gamepad(id)
-            .state()
-            .is_pressed(Code(necs::BTN_DPAD_UP))
-        {
-            Event {
-                id,
-                time,
-                event: EventType::ButtonReleased(Button::DPadUp, Code(necs::BTN_DPAD_UP)),
-            }
-        } else {
-            Event {
-                id,
-                time,
-                event: EventType::ButtonReleased(Button::DPadDown, Code(necs::BTN_DPAD_DOWN)),
-            }
-        }),
-        _ => ev,
-    }
+            seq,
+        }) if can_map(&gilrs.gamepad(id)) => (
+            id,
+            time,
+            seq,
+            val,
+            (necs::BTN_DPAD_DOWN, Button::DPadDown),
+            (necs::BTN_DPAD_UP, Button::DPadUp),
+        ),
+        _ => return ev,
+    };
+
+    let gamepad = gilrs.gamepad(id);
+    // Button mapped to virtual d-pad button by SDL mapping or d-pad button by default.
+    let button = |(nec, default)| match gamepad.axis_or_btn_name(Code(nec)) {
+        Some(AxisOrBtn::Btn(btn)) => (btn, Code(nec)),
+        _ => (default, Code(nec)),
+    };
+
+    let event = if val == 1.0 {
+        let (btn, nec) = button(positive);
+        EventType::ButtonPressed(btn, nec)
+    } else if val == -1.0 {
+        let (btn, nec) = button(negative);
+        EventType::ButtonPressed(btn, nec)
+    } else if gamepad.state().is_pressed(Code(positive.0)) {
+        let (btn, nec) = button(positive);
+        EventType::ButtonReleased(btn, nec)
+    } else {
+        let (btn, nec) = button(negative);
+        EventType::ButtonReleased(btn, nec)
+    };
+
+    Some(Event {
+        id,
+        time,
+        seq,
+        event,
+    })
 }
 
 /// Repeats pressed keys.
@@ -336,6 +381,7 @@ impl FilterFn for Repeat {
                                     id,
                                     event: EventType::ButtonRepeated(btn_name, nec),
                                     time: btn_data.timestamp() + self.after,
+                                    seq: 0,
                                 });
                             }
                             (true, true, Ok(dur)) if dur >= self.every => {
@@ -348,6 +394,7 @@ impl FilterFn for Repeat {
                                     id,
                                     event: EventType::ButtonRepeated(btn_name, nec),
                                     time: btn_data.timestamp() + self.every,
+                                    seq: 0,
                                 });
                             }
                             _ => (),
@@ -385,6 +432,8 @@ where
 
 impl Filter for Option<Event> {
     fn filter_ev<F: FilterFn>(&self, filter: &F, gilrs: &mut Gilrs) -> Option<Event> {
+        enter_span!("gilrs::filter", filter = std::any::type_name::<F>());
+
         let e = filter.filter(*self, gilrs);
         debug_assert!(
             !(self.is_some() && e.is_none()),
@@ -397,6 +446,8 @@ impl Filter for Option<Event> {
 
 impl Filter for Event {
     fn filter_ev<F: FilterFn>(&self, filter: &F, gilrs: &mut Gilrs) -> Option<Event> {
+        enter_span!("gilrs::filter", filter = std::any::type_name::<F>());
+
         let e = filter.filter(Some(*self), gilrs);
         debug_assert!(
             !e.is_none(),
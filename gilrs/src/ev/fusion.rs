@@ -0,0 +1,235 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::gamepad::MotionData;
+
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+/// Longest gap between motion readings that is integrated. Longer gaps (gamepad reconnected or
+/// stopped reporting) only restart integration.
+const MAX_STEP: f32 = 0.1;
+
+/// Parameters of sensor fusion that combines gyroscope and accelerometer readings into
+/// `Orientation`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FusionConfig {
+    /// Angular velocity in rad/s below which gyroscope readings are treated as zero, removing
+    /// noise and drift of gamepad at rest.
+    pub gyro_deadzone: f32,
+    /// Time constant of low-pass filter applied to gyroscope readings. Zero disables smoothing.
+    pub gyro_smoothing: Duration,
+    /// How fast accelerometer corrects tilt drift of orientation, in 1/s. Zero uses only
+    /// gyroscope.
+    pub accel_correction: f32,
+}
+
+impl FusionConfig {
+    /// Creates new `FusionConfig` with gyro deadzone set to 0.01 rad/s, gyro smoothing set to
+    /// 10ms and accelerometer correction set to 0.5.
+    pub fn new() -> Self {
+        FusionConfig {
+            gyro_deadzone: 0.01,
+            gyro_smoothing: Duration::from_millis(10),
+            accel_correction: 0.5,
+        }
+    }
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Orientation of gamepad computed from its motion sensors.
+///
+/// Axes follow the same convention as `MotionData`. World's Y axis points up, its X and Z axes
+/// are the gamepad's ones from the first reading, so only pitch and roll are absolute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct Orientation {
+    /// Unit quaternion `[w, x, y, z]` rotating vectors from gamepad's to world's coordinates.
+    pub quaternion: [f32; 4],
+    /// Rotation around gamepad's X, Y and Z axes in radians since last `Gilrs::inc()`, after
+    /// deadzone and smoothing.
+    pub delta: [f32; 3],
+}
+
+/// Fuses motion readings of one gamepad.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SensorFusion {
+    orientation: Option<Orientation>,
+    gyro: [f32; 3],
+    last: Option<SystemTime>,
+}
+
+impl SensorFusion {
+    pub(crate) fn update(&mut self, motion: MotionData, time: SystemTime, config: &FusionConfig) {
+        let dt = self
+            .last
+            .replace(time)
+            .and_then(|last| time.duration_since(last).ok())
+            .map(|dt| dt.as_secs_f32());
+        let orientation = self.orientation.get_or_insert(Orientation {
+            quaternion: [1.0, 0.0, 0.0, 0.0],
+            delta: [0.0; 3],
+        });
+        let dt = match dt {
+            Some(dt) if dt <= MAX_STEP => dt,
+            _ => {
+                self.gyro = [0.0; 3];
+                return;
+            }
+        };
+
+        let magnitude = length(motion.gyro);
+        let gyro = if magnitude < config.gyro_deadzone {
+            [0.0; 3]
+        } else {
+            motion.gyro
+        };
+        let tau = config.gyro_smoothing.as_secs_f32();
+        let alpha = if tau > 0.0 { dt / (tau + dt) } else { 1.0 };
+        let filtered = self.gyro.iter_mut().zip(&gyro);
+        for ((smoothed, raw), delta) in filtered.zip(&mut orientation.delta) {
+            *smoothed += (raw - *smoothed) * alpha;
+            *delta += *smoothed * dt;
+        }
+
+        // Mahony filter: turn towards orientation in which measured gravity points up.
+        let q = orientation.quaternion;
+        let mut omega = self.gyro;
+        let accel_len = length(motion.accel);
+        if accel_len > 0.0 {
+            let measured = scale(motion.accel, 1.0 / accel_len);
+            let estimated = rotate(conjugate(q), [0.0, 1.0, 0.0]);
+            let error = cross(measured, estimated);
+            for axis in 0..3 {
+                omega[axis] += error[axis] * config.accel_correction;
+            }
+        }
+
+        let half_angle = length(omega) * dt / 2.0;
+        let step = if half_angle > 0.0 {
+            let axis = scale(omega, half_angle.sin() / length(omega));
+            [half_angle.cos(), axis[0], axis[1], axis[2]]
+        } else {
+            [1.0, 0.0, 0.0, 0.0]
+        };
+        orientation.quaternion = normalize(multiply(q, step));
+    }
+
+    pub(crate) fn orientation(&self) -> Option<Orientation> {
+        self.orientation
+    }
+
+    pub(crate) fn reset_delta(&mut self) {
+        if let Some(ref mut orientation) = self.orientation {
+            orientation.delta = [0.0; 3];
+        }
+    }
+}
+
+fn length(v: [f32; 3]) -> f32 {
+    (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+}
+
+fn scale(v: [f32; 3], s: f32) -> [f32; 3] {
+    [v[0] * s, v[1] * s, v[2] * s]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn multiply(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [
+        a[0] * b[0] - a[1] * b[1] - a[2] * b[2] - a[3] * b[3],
+        a[0] * b[1] + a[1] * b[0] + a[2] * b[3] - a[3] * b[2],
+        a[0] * b[2] - a[1] * b[3] + a[2] * b[0] + a[3] * b[1],
+        a[0] * b[3] + a[1] * b[2] - a[2] * b[1] + a[3] * b[0],
+    ]
+}
+
+fn conjugate(q: [f32; 4]) -> [f32; 4] {
+    [q[0], -q[1], -q[2], -q[3]]
+}
+
+fn normalize(q: [f32; 4]) -> [f32; 4] {
+    let len = (q[0] * q[0] + q[1] * q[1] + q[2] * q[2] + q[3] * q[3]).sqrt();
+    [q[0] / len, q[1] / len, q[2] / len, q[3] / len]
+}
+
+fn rotate(q: [f32; 4], v: [f32; 3]) -> [f32; 3] {
+    let r = multiply(multiply(q, [0.0, v[0], v[1], v[2]]), conjugate(q));
+    [r[1], r[2], r[3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{conjugate, rotate, FusionConfig, SensorFusion};
+    use crate::gamepad::MotionData;
+
+    use std::f32::consts::PI;
+    use std::time::{Duration, SystemTime};
+
+    fn assert_close(a: &[f32], b: &[f32]) {
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).abs() < 0.01, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn fusion() {
+        let config = FusionConfig {
+            gyro_smoothing: Duration::from_millis(0),
+            ..FusionConfig::new()
+        };
+        let start = SystemTime::UNIX_EPOCH;
+        let mut fusion = SensorFusion::default();
+        assert_eq!(fusion.orientation(), None);
+
+        // Quarter turn around Y axis, gravity stays on Y.
+        let turn = MotionData {
+            accel: [0.0, 9.8, 0.0],
+            gyro: [0.0, PI / 2.0, 0.0],
+        };
+        for i in 0..=100 {
+            fusion.update(turn, start + Duration::from_millis(i * 10), &config);
+        }
+        let orientation = fusion.orientation().unwrap();
+        let half = (PI / 4.0).cos();
+        assert_close(&orientation.quaternion, &[half, 0.0, half, 0.0]);
+        assert_close(&orientation.delta, &[0.0, PI / 2.0, 0.0]);
+        fusion.reset_delta();
+        assert_eq!(fusion.orientation().unwrap().delta, [0.0; 3]);
+
+        // Noise below deadzone is ignored, accelerometer corrects tilt.
+        let tilted = MotionData {
+            accel: [9.8, 0.0, 0.0],
+            gyro: [0.005, 0.0, -0.005],
+        };
+        for i in 0..2000 {
+            fusion.update(
+                tilted,
+                start + Duration::from_millis(2000 + i * 10),
+                &config,
+            );
+        }
+        let orientation = fusion.orientation().unwrap();
+        assert_eq!(orientation.delta, [0.0; 3]);
+        let up = rotate(conjugate(orientation.quaternion), [0.0, 1.0, 0.0]);
+        assert_close(&up, &[1.0, 0.0, 0.0]);
+    }
+}
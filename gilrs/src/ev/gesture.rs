@@ -0,0 +1,233 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::gamepad::TouchData;
+
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
+/// Touchpad gesture, reported by `EventType::Gesture` after the last finger leaves touchpad.
+/// Recognition is enabled by `GilrsBuilder::set_touch_gestures()`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum Gesture {
+    /// One finger moved across touchpad in this direction.
+    Swipe(SwipeDirection),
+    /// Swipe that started at the opposite edge of touchpad, for example swipe to the right that
+    /// started at left edge.
+    EdgeSwipe(SwipeDirection),
+    /// Two fingers touched touchpad and left it without moving.
+    TwoFingerTap,
+}
+
+/// Direction of `Gesture::Swipe` and `Gesture::EdgeSwipe`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum SwipeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Parameters of touchpad gesture recognition. Distances are fractions of touchpad's width or
+/// height.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GestureConfig {
+    /// Shortest distance finger has to move for swipe.
+    pub swipe_distance: f32,
+    /// Longest distance fingers can move during tap.
+    pub tap_distance: f32,
+    /// Width of area at touchpad's edges where edge swipes start.
+    pub edge: f32,
+    /// Longest time from the first touch to the last finger leaving touchpad.
+    pub max_duration: Duration,
+}
+
+impl GestureConfig {
+    /// Creates new `GestureConfig` with swipe distance set to 0.3, tap distance set to 0.05,
+    /// edge set to 0.1 and max duration set to 500ms.
+    pub fn new() -> Self {
+        GestureConfig {
+            swipe_distance: 0.3,
+            tap_distance: 0.05,
+            edge: 0.1,
+            max_duration: Duration::from_millis(500),
+        }
+    }
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Contact {
+    finger: u8,
+    touching: bool,
+    start: (f32, f32),
+    last: (f32, f32),
+    /// Longest distance from `start`.
+    max_distance: f32,
+}
+
+/// Tracks touches of one gamepad.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GestureRecognizer {
+    /// Every finger that touched touchpad since it was free.
+    contacts: Vec<Contact>,
+    started: Option<SystemTime>,
+}
+
+impl GestureRecognizer {
+    /// Updates touches and returns gesture if this was the last finger that left touchpad.
+    pub(crate) fn update(
+        &mut self,
+        touch: TouchData,
+        time: SystemTime,
+        config: &GestureConfig,
+    ) -> Option<Gesture> {
+        let pos = (touch.x, touch.y);
+        match self.contacts.iter_mut().find(|c| c.finger == touch.finger) {
+            Some(contact) => {
+                contact.touching = touch.touching;
+                contact.last = pos;
+                let (dx, dy) = (pos.0 - contact.start.0, pos.1 - contact.start.1);
+                contact.max_distance = contact.max_distance.max((dx * dx + dy * dy).sqrt());
+            }
+            // Finger that leaves without touching was tracked by previous gesture.
+            None if touch.touching => {
+                self.started.get_or_insert(time);
+                self.contacts.push(Contact {
+                    finger: touch.finger,
+                    touching: true,
+                    start: pos,
+                    last: pos,
+                    max_distance: 0.0,
+                });
+            }
+            None => (),
+        }
+
+        if self.contacts.is_empty() || self.contacts.iter().any(|c| c.touching) {
+            return None;
+        }
+
+        let started = self.started.take();
+        let contacts = std::mem::take(&mut self.contacts);
+        let in_time = match started.map(|started| time.duration_since(started)) {
+            Some(Ok(duration)) => duration <= config.max_duration,
+            _ => true,
+        };
+        if !in_time {
+            return None;
+        }
+
+        let tap = contacts
+            .iter()
+            .all(|c| c.max_distance <= config.tap_distance);
+        match contacts[..] {
+            [_, _] if tap => Some(Gesture::TwoFingerTap),
+            [contact] => swipe(&contact, config),
+            _ => None,
+        }
+    }
+}
+
+fn swipe(contact: &Contact, config: &GestureConfig) -> Option<Gesture> {
+    let (dx, dy) = (
+        contact.last.0 - contact.start.0,
+        contact.last.1 - contact.start.1,
+    );
+    let (x, y) = contact.start;
+    // Y grows downwards.
+    let (direction, distance, from_edge) = if dx.abs() >= dy.abs() {
+        if dx > 0.0 {
+            (SwipeDirection::Right, dx, x <= config.edge)
+        } else {
+            (SwipeDirection::Left, -dx, x >= 1.0 - config.edge)
+        }
+    } else if dy > 0.0 {
+        (SwipeDirection::Down, dy, y <= config.edge)
+    } else {
+        (SwipeDirection::Up, -dy, y >= 1.0 - config.edge)
+    };
+
+    if distance < config.swipe_distance {
+        None
+    } else if from_edge {
+        Some(Gesture::EdgeSwipe(direction))
+    } else {
+        Some(Gesture::Swipe(direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gesture, GestureConfig, GestureRecognizer, SwipeDirection};
+    use crate::gamepad::TouchData;
+
+    use std::time::{Duration, SystemTime};
+
+    fn touch(finger: u8, touching: bool, x: f32, y: f32) -> TouchData {
+        TouchData {
+            finger,
+            touching,
+            x,
+            y,
+        }
+    }
+
+    #[test]
+    fn gestures() {
+        let config = GestureConfig::new();
+        let start = SystemTime::UNIX_EPOCH;
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut recognizer = GestureRecognizer::default();
+        let mut update = |data, ms| recognizer.update(data, at(ms), &config);
+
+        assert_eq!(update(touch(0, true, 0.5, 0.5), 0), None);
+        assert_eq!(update(touch(0, true, 0.7, 0.55), 50), None);
+        assert_eq!(
+            update(touch(0, false, 0.9, 0.55), 100),
+            Some(Gesture::Swipe(SwipeDirection::Right))
+        );
+
+        assert_eq!(update(touch(0, true, 0.5, 0.95), 1000), None);
+        assert_eq!(
+            update(touch(0, false, 0.5, 0.4), 1100),
+            Some(Gesture::EdgeSwipe(SwipeDirection::Up))
+        );
+
+        // Too short movement, then too slow swipe.
+        assert_eq!(update(touch(0, true, 0.5, 0.5), 2000), None);
+        assert_eq!(update(touch(0, false, 0.6, 0.5), 2100), None);
+        assert_eq!(update(touch(0, true, 0.5, 0.5), 3000), None);
+        assert_eq!(update(touch(0, false, 0.1, 0.5), 4000), None);
+
+        // Fingers can leave in any order.
+        assert_eq!(update(touch(0, true, 0.3, 0.5), 5000), None);
+        assert_eq!(update(touch(1, true, 0.6, 0.5), 5020), None);
+        assert_eq!(update(touch(0, false, 0.31, 0.5), 5100), None);
+        assert_eq!(
+            update(touch(1, false, 0.6, 0.51), 5120),
+            Some(Gesture::TwoFingerTap)
+        );
+
+        // Two fingers that moved are not a tap.
+        assert_eq!(update(touch(0, true, 0.3, 0.5), 6000), None);
+        assert_eq!(update(touch(1, true, 0.6, 0.5), 6000), None);
+        assert_eq!(update(touch(1, false, 0.9, 0.5), 6100), None);
+        assert_eq!(update(touch(0, false, 0.3, 0.5), 6100), None);
+    }
+}
@@ -8,14 +8,26 @@
 //! Gamepad state and other event related functionality.
 
 pub mod filter;
+mod fusion;
+mod gesture;
 pub mod state;
 
+pub(crate) use self::fusion::SensorFusion;
+pub use self::fusion::{FusionConfig, Orientation};
+pub(crate) use self::gesture::GestureRecognizer;
+pub use self::gesture::{Gesture, GestureConfig, SwipeDirection};
+
 use std::{
     fmt::{Display, Formatter, Result as FmtResult},
     time::SystemTime,
 };
 
-use crate::{constants::*, gamepad::GamepadId, utils};
+use crate::{
+    constants::*,
+    ff::EffectId,
+    gamepad::{GamepadId, MotionData, TouchData},
+    utils,
+};
 
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
@@ -55,6 +67,12 @@ pub struct Event {
     pub event: EventType,
     /// Time when event was emitted.
     pub time: SystemTime,
+    /// Sequence number assigned by `Gilrs::next_event()`. It's increased by one for every
+    /// returned event, so it can be used to detect reordered or duplicated events when merging
+    /// them with other input sources. Events that were not returned by `next_event()` (for
+    /// example created with `Event::new()` or by filters applied afterwards) have it set to 0.
+    #[cfg_attr(feature = "serde-serialize", serde(default))]
+    pub seq: u64,
 }
 
 impl Event {
@@ -64,6 +82,7 @@ impl Event {
             id,
             event,
             time: utils::time_now(),
+            seq: 0,
         }
     }
 
@@ -95,12 +114,35 @@ pub enum EventType {
     /// Value of axis has changed. Value can be in range [-1.0, 1.0].
     AxisChanged(Axis, f32, Code),
     /// Gamepad has been connected. If gamepad's UUID doesn't match one of disconnected gamepads,
-    /// newly connected gamepad will get new ID.
+    /// newly connected gamepad will get new ID. Its name, UUID and USB IDs are available from
+    /// `Gilrs::connected_info()`, even if it has been disconnected right after connecting.
     Connected,
     /// Gamepad has been disconnected. Disconnected gamepad will not generate any new events.
     Disconnected,
     /// There was an `Event`, but it was dropped by one of filters. You should ignore it.
     Dropped,
+    /// No input has been received from connected gamepad for longer than timeout set with
+    /// `GilrsBuilder::set_control_timeout()`. Next event from this gamepad means that control has
+    /// been regained.
+    ControlLost,
+    /// Platform backend discarded this number of events from gamepad, because its queue was full
+    /// (see `GilrsBuilder::set_event_queue_capacity()`). Cached state may be outdated until gamepad
+    /// reports new values.
+    Overflow(usize),
+    /// New reading of gamepad's accelerometer and gyroscope. Only gamepads opened by HID drivers
+    /// (`hidapi` feature) report it.
+    Motion(MotionData),
+    /// Finger touched, moved on or left gamepad's touchpad. Only gamepads opened by HID drivers
+    /// (`hidapi` feature) report it. Click of touchpad is reported as `Button::Unknown` with
+    /// `native_ev_codes::BTN_TOUCHPAD` code.
+    Touch(TouchData),
+    /// Gesture recognized from `Touch` events, reported after the `Touch` event that completed
+    /// it. Only emitted if enabled by `GilrsBuilder::set_touch_gestures()`.
+    Gesture(Gesture),
+    /// Force feedback effect finished playing on this gamepad, because its `Repeat::For`
+    /// elapsed or gamepad didn't reconnect within 3 seconds after disconnecting. It's not
+    /// emitted for effects stopped with `Effect::stop()` or dropped while playing.
+    ForceFeedbackEffectCompleted(EffectId),
 }
 
 #[repr(u16)]
@@ -134,6 +176,10 @@ pub enum Button {
     DPadDown = BTN_DPAD_DOWN,
     DPadLeft = BTN_DPAD_LEFT,
     DPadRight = BTN_DPAD_RIGHT,
+    // Extra
+    /// Additional button, like the microphone button on DualSense or share button on Xbox Series
+    /// controllers. Corresponds to SDL's `misc1`.
+    Misc1 = BTN_MISC1,
 
     Unknown = BTN_UNKNOWN,
 }
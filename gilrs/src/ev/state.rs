@@ -9,7 +9,7 @@ use crate::ev::Code;
 
 use fnv::FnvHashMap;
 
-use std::collections::hash_map;
+use std::collections::{hash_map, vec_deque, VecDeque};
 use std::iter::Iterator;
 use std::time::SystemTime;
 
@@ -20,6 +20,8 @@ pub struct GamepadState {
     buttons: FnvHashMap<Code, ButtonData>,
     // Indexed by EvCode (nec)
     axes: FnvHashMap<Code, AxisData>,
+    // Indexed by EvCode (nec). Only used when axis history is enabled.
+    axis_history: FnvHashMap<Code, VecDeque<(SystemTime, f32)>>,
 }
 
 impl GamepadState {
@@ -27,6 +29,7 @@ impl GamepadState {
         GamepadState {
             buttons: FnvHashMap::default(),
             axes: FnvHashMap::default(),
+            axis_history: FnvHashMap::default(),
         }
     }
 
@@ -69,6 +72,13 @@ impl GamepadState {
         self.axes.get(&axis)
     }
 
+    /// Returns iterator over last values of axis and when they changed, from oldest to newest.
+    ///
+    /// History is only recorded if it was enabled with `GilrsBuilder::set_axis_history()`.
+    pub fn axis_history(&self, axis: Code) -> AxisHistoryIter<'_> {
+        AxisHistoryIter(self.axis_history.get(&axis).map(|h| h.iter()))
+    }
+
     pub(crate) fn set_btn_pressed(
         &mut self,
         btn: Code,
@@ -120,6 +130,17 @@ impl GamepadState {
     pub(crate) fn update_axis(&mut self, axis: Code, data: AxisData) {
         self.axes.insert(axis, data);
     }
+
+    pub(crate) fn push_axis_history(&mut self, axis: Code, data: AxisData, len: usize) {
+        let history = self
+            .axis_history
+            .entry(axis)
+            .or_insert_with(|| VecDeque::with_capacity(len));
+        if history.len() >= len {
+            history.pop_front();
+        }
+        history.push_back((data.timestamp(), data.value()));
+    }
 }
 
 /// Iterator over `ButtonData`.
@@ -128,6 +149,23 @@ pub struct ButtonDataIter<'a>(hash_map::Iter<'a, Code, ButtonData>);
 /// Iterator over `AxisData`.
 pub struct AxisDataIter<'a>(hash_map::Iter<'a, Code, AxisData>);
 
+/// Iterator over axis history returned by `GamepadState::axis_history()`.
+pub struct AxisHistoryIter<'a>(Option<vec_deque::Iter<'a, (SystemTime, f32)>>);
+
+impl<'a> AxisHistoryIter<'a> {
+    pub(crate) fn empty() -> Self {
+        AxisHistoryIter(None)
+    }
+}
+
+impl<'a> Iterator for AxisHistoryIter<'a> {
+    type Item = (SystemTime, f32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.as_mut().and_then(|it| it.next()).cloned()
+    }
+}
+
 impl<'a> Iterator for ButtonDataIter<'a> {
     type Item = (Code, &'a ButtonData);
 
@@ -228,3 +266,43 @@ impl AxisData {
         self.last_event_ts
     }
 }
+
+/// Combined state of trigger that can report both analog value and digital full-pull click (for
+/// example GameCube controller).
+#[derive(Clone, Copy, Debug)]
+pub struct TriggerData<'a> {
+    pub(crate) analog: Option<&'a ButtonData>,
+    pub(crate) click: Option<&'a ButtonData>,
+}
+
+impl<'a> TriggerData<'a> {
+    /// Returns analog value of trigger. If trigger has only digital button, returns 1.0 when it is
+    /// pressed and 0.0 otherwise.
+    pub fn value(&self) -> f32 {
+        match (self.analog, self.click) {
+            (Some(analog), _) => analog.value(),
+            (None, Some(click)) if click.is_pressed() => 1.0,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns `true` if trigger is pulled past press threshold or clicked.
+    pub fn is_pressed(&self) -> bool {
+        self.analog.map(ButtonData::is_pressed).unwrap_or(false) || self.is_clicked()
+    }
+
+    /// Returns `true` if digital full-pull button is pressed.
+    pub fn is_clicked(&self) -> bool {
+        self.click.map(ButtonData::is_pressed).unwrap_or(false)
+    }
+
+    /// Returns state of analog part of the trigger.
+    pub fn analog(&self) -> Option<&'a ButtonData> {
+        self.analog
+    }
+
+    /// Returns state of digital part of the trigger.
+    pub fn click(&self) -> Option<&'a ButtonData> {
+        self.click
+    }
+}
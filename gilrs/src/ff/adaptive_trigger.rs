@@ -0,0 +1,219 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Resistance of adaptive triggers, like on DualSense.
+//!
+//! This module is available with `adaptive-triggers` feature, which enables HID drivers
+//! (`hidapi` feature). Trigger travel is divided into 10 zones, from 0 (released) to 9 (fully
+//! pressed). Effect stays active until it's replaced or gamepad disconnects.
+//!
+//! ```no_run
+//! use gilrs::ff::adaptive_trigger::{Trigger, TriggerEffect};
+//! use gilrs::Gilrs;
+//!
+//! let gilrs = Gilrs::new().unwrap();
+//! for (_, gamepad) in gilrs.gamepads() {
+//!     if gamepad.supports_adaptive_triggers() {
+//!         let gun = TriggerEffect::Weapon {
+//!             start: 4,
+//!             end: 6,
+//!             strength: 8,
+//!         };
+//!         gamepad.set_trigger_effect(Trigger::Right, gun).unwrap();
+//!     }
+//! }
+//! ```
+
+use crate::GamepadId;
+
+pub use gilrs_core::Trigger;
+
+use std::error;
+use std::fmt::{self, Display};
+use std::io;
+
+/// Number of zones of trigger travel.
+const ZONES: usize = 10;
+
+/// Effect of adaptive trigger. Values out of documented ranges are clamped.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TriggerEffect {
+    /// No resistance.
+    Off,
+    /// Constant resistance from `start` zone (0–9) to the end of travel. `strength` is from 1
+    /// to 8.
+    Feedback { start: u8, strength: u8 },
+    /// Resistance curve, strength (0–8, 0 is no resistance) of each zone.
+    Curve { strengths: [u8; 10] },
+    /// Resistance between `start` (2–7) and `end` (`start + 1`–8) zones that suddenly gives
+    /// way, like trigger of a gun. `strength` is from 1 to 8.
+    Weapon { start: u8, end: u8, strength: u8 },
+    /// Resistance between `start` (0–7) and `end` (`start + 1`–8) zones, after which
+    /// trigger is pushed back with `snap` force (1–8), like string of a bow. `strength` is from 1
+    /// to 8.
+    Bow {
+        start: u8,
+        end: u8,
+        strength: u8,
+        snap: u8,
+    },
+    /// Trigger vibrates from `start` zone (0–9) to the end of travel. `amplitude` is from 1 to 8,
+    /// `frequency` in Hz.
+    Vibration {
+        start: u8,
+        amplitude: u8,
+        frequency: u8,
+    },
+}
+
+impl TriggerEffect {
+    /// Encodes effect as mode of DualSense followed by its 10 parameters.
+    pub(crate) fn to_report(self) -> [u8; 11] {
+        let mut report = [0; 11];
+        match self {
+            TriggerEffect::Off => report[0] = 0x05,
+            TriggerEffect::Feedback { start, strength } => {
+                let mut strengths = [0; ZONES];
+                for s in &mut strengths[start.min(9) as usize..] {
+                    *s = strength;
+                }
+                return TriggerEffect::Curve { strengths }.to_report();
+            }
+            TriggerEffect::Curve { strengths } => {
+                let mut active = 0u16;
+                let mut forces = 0u32;
+                for (zone, &strength) in strengths.iter().enumerate() {
+                    if strength > 0 {
+                        active |= 1 << zone;
+                        forces |= u32::from(strength.min(8) - 1) << (3 * zone);
+                    }
+                }
+                report[0] = 0x21;
+                report[1..3].copy_from_slice(&active.to_le_bytes());
+                report[3..7].copy_from_slice(&forces.to_le_bytes());
+            }
+            TriggerEffect::Weapon {
+                start,
+                end,
+                strength,
+            } => {
+                let start = limit(start, 2, 7);
+                let end = limit(end, start + 1, 8);
+                let zones: u16 = (1 << start) | (1 << end);
+                report[0] = 0x25;
+                report[1..3].copy_from_slice(&zones.to_le_bytes());
+                report[3] = level(strength);
+            }
+            TriggerEffect::Bow {
+                start,
+                end,
+                strength,
+                snap,
+            } => {
+                let start = start.min(7);
+                let end = limit(end, start + 1, 8);
+                let zones: u16 = (1 << start) | (1 << end);
+                let forces = u16::from(level(strength)) | (u16::from(level(snap)) << 3);
+                report[0] = 0x22;
+                report[1..3].copy_from_slice(&zones.to_le_bytes());
+                report[3..5].copy_from_slice(&forces.to_le_bytes());
+            }
+            TriggerEffect::Vibration {
+                start,
+                amplitude,
+                frequency,
+            } => {
+                let mut active = 0u16;
+                let mut amplitudes = 0u32;
+                for zone in start.min(9) as usize..ZONES {
+                    active |= 1 << zone;
+                    amplitudes |= u32::from(level(amplitude)) << (3 * zone);
+                }
+                report[0] = 0x26;
+                report[1..3].copy_from_slice(&active.to_le_bytes());
+                report[3..7].copy_from_slice(&amplitudes.to_le_bytes());
+                report[9] = frequency;
+            }
+        }
+
+        report
+    }
+}
+
+/// Converts strength from 1–8 to 3 bits used by DualSense.
+fn level(strength: u8) -> u8 {
+    limit(strength, 1, 8) - 1
+}
+
+// `Ord::clamp()` requires Rust 1.50.
+fn limit(value: u8, min: u8, max: u8) -> u8 {
+    value.max(min).min(max)
+}
+
+/// Error returned by `Gamepad::set_trigger_effect()`.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum TriggerError {
+    /// Gamepad is not connected.
+    Disconnected(GamepadId),
+    /// Gamepad doesn't have adaptive triggers or they are not handled by HID driver.
+    NotSupported(GamepadId),
+    /// HID driver failed to send the effect.
+    Io(io::Error),
+}
+
+impl Display for TriggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerError::Disconnected(id) => write!(f, "Gamepad {} is disconnected.", id),
+            TriggerError::NotSupported(id) => {
+                write!(f, "Gamepad {} doesn't have adaptive triggers.", id)
+            }
+            TriggerError::Io(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for TriggerError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            TriggerError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TriggerEffect;
+
+    #[test]
+    fn encode() {
+        assert_eq!(
+            TriggerEffect::Off.to_report(),
+            [5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        // Zones 8 and 9 with strength 8 (7 in 3 bits).
+        let feedback = TriggerEffect::Feedback {
+            start: 8,
+            strength: 8,
+        };
+        assert_eq!(
+            feedback.to_report(),
+            [0x21, 0x00, 0x03, 0x00, 0x00, 0x00, 0x3f, 0, 0, 0, 0]
+        );
+
+        let weapon = TriggerEffect::Weapon {
+            start: 0,
+            end: 0,
+            strength: 20,
+        };
+        assert_eq!(weapon.to_report(), [0x25, 0x0c, 0, 7, 0, 0, 0, 0, 0, 0, 0]);
+    }
+}
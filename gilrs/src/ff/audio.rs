@@ -0,0 +1,136 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Rumble generated from audio.
+//!
+//! This module is available with `audio-haptics` feature.
+//! [`AudioHaptics`](struct.AudioHaptics.html) converts mono PCM samples to
+//! [`Pattern`](../struct.Pattern.html) with one keyframe for every force feedback tick.
+//! Audio is split by crossover filter: energy of low frequencies drives strong (low frequency)
+//! motor and energy of high frequencies weak motor.
+//!
+//! ```
+//! use gilrs::ff::audio::AudioHaptics;
+//! use gilrs::ff::{EffectBuilder, Repeat};
+//!
+//! // 0.5 s of 60 Hz sine.
+//! let samples = (0..24_000).map(|i| (i as f32 * 60.0 * 2.0 * std::f32::consts::PI / 48_000.0).sin());
+//! let pattern = AudioHaptics::new(48_000).pattern(samples);
+//!
+//! let mut builder = EffectBuilder::new();
+//! builder
+//!     .add_pattern(&pattern)
+//!     .repeat(Repeat::For(pattern.duration()));
+//! ```
+
+use super::pattern::Pattern;
+use super::time::tick_duration;
+
+use std::f32::consts::PI;
+
+/// Converts PCM samples to rumble [`Pattern`](../struct.Pattern.html).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AudioHaptics {
+    sample_rate: u32,
+    crossover: f32,
+    gain: f32,
+}
+
+impl AudioHaptics {
+    /// Creates converter for samples with given rate. Crossover is at 150 Hz and gain is 1.0.
+    pub fn new(sample_rate: u32) -> Self {
+        AudioHaptics {
+            sample_rate: sample_rate.max(1),
+            crossover: 150.0,
+            gain: 1.0,
+        }
+    }
+
+    /// Changes frequency, in Hz, that separates audio played by strong and weak motor.
+    pub fn crossover(mut self, frequency: f32) -> Self {
+        self.crossover = frequency.max(1.0);
+        self
+    }
+
+    /// Changes gain applied to both motors. With gain 1.0, full-scale sine wave plays at full
+    /// magnitude.
+    pub fn gain(mut self, gain: f32) -> Self {
+        self.gain = gain.max(0.0);
+        self
+    }
+
+    /// Converts mono samples, from -1.0 to 1.0, to pattern. Pattern ends with zero magnitude
+    /// after the last sample.
+    pub fn pattern<I: IntoIterator<Item = f32>>(&self, samples: I) -> Pattern {
+        let tick = tick_duration();
+        let window = (self.sample_rate as u64 * u64::from(tick) / 1000).max(1) as usize;
+        // One-pole low-pass filter, high frequencies are the rest of signal.
+        let alpha = 1.0 - (-2.0 * PI * self.crossover / self.sample_rate as f32).exp();
+        // RMS of full-scale sine wave is 1/sqrt(2).
+        let scale = self.gain * 2f32.sqrt();
+
+        let mut pattern = Pattern::new();
+        let mut low = 0.0;
+        let mut energy = (0.0, 0.0);
+        let mut count = 0;
+        let mut time_ms = 0;
+
+        for sample in samples {
+            low += alpha * (sample - low);
+            let high = sample - low;
+            energy.0 += low * low;
+            energy.1 += high * high;
+            count += 1;
+
+            if count == window {
+                let rms = |energy: f32| (energy / count as f32).sqrt() * scale;
+                pattern = pattern.keyframe(time_ms, rms(energy.0), rms(energy.1));
+                time_ms += tick;
+                energy = (0.0, 0.0);
+                count = 0;
+            }
+        }
+
+        if count > 0 {
+            let rms = |energy: f32| (energy / count as f32).sqrt() * scale;
+            pattern = pattern.keyframe(time_ms, rms(energy.0), rms(energy.1));
+            time_ms += tick;
+        }
+        if time_ms > 0 {
+            pattern = pattern.keyframe(time_ms, 0.0, 0.0);
+        }
+
+        pattern
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioHaptics;
+
+    use std::f32::consts::PI;
+
+    #[test]
+    fn crossover() {
+        let sine = |frequency: f32| {
+            (0..8000).map(move |i| (i as f32 * frequency * 2.0 * PI / 8000.0).sin())
+        };
+        let haptics = AudioHaptics::new(8000);
+
+        // 1 s of audio, 20 ticks and final keyframe.
+        let low = haptics.pattern(sine(20.0));
+        assert_eq!(low.keyframes().len(), 21);
+        let k = low.keyframes()[10];
+        assert!(k.strong > 0.8 && k.weak < 0.3, "{:?}", k);
+
+        let high = haptics.pattern(sine(1000.0));
+        let k = high.keyframes()[10];
+        assert!(k.strong < 0.3 && k.weak > 0.8, "{:?}", k);
+
+        assert!(haptics.pattern(Vec::new()).keyframes().is_empty());
+    }
+}
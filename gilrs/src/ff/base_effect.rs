@@ -5,26 +5,33 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::f32::consts::PI;
 use std::ops::Mul;
 
 use super::time::Ticks;
+use crate::utils;
 
 /// Kind of [`BaseEffect`](struct.BaseEffect.html).
 ///
-/// Currently base effect support only xinput model of force feedback, which means that  gamepad
-/// have weak and strong motor.
+/// Base effect follows xinput model of force feedback, which means that gamepad have weak and
+/// strong motor. Xbox One controllers also have impulse motors in triggers; on gamepads without
+/// them `LeftTrigger` is played by strong motor and `RightTrigger` by weak motor.
 #[derive(Copy, Clone, PartialEq, Debug)]
 #[non_exhaustive]
 pub enum BaseEffectType {
     Weak { magnitude: u16 },
     Strong { magnitude: u16 },
+    LeftTrigger { magnitude: u16 },
+    RightTrigger { magnitude: u16 },
 }
 
 impl BaseEffectType {
-    fn magnitude(&self) -> u16 {
+    pub(super) fn magnitude(&self) -> u16 {
         match *self {
             BaseEffectType::Weak { magnitude } => magnitude,
             BaseEffectType::Strong { magnitude } => magnitude,
+            BaseEffectType::LeftTrigger { magnitude } => magnitude,
+            BaseEffectType::RightTrigger { magnitude } => magnitude,
         }
     }
 }
@@ -37,6 +44,8 @@ impl Mul<f32> for BaseEffectType {
         match self {
             BaseEffectType::Weak { .. } => BaseEffectType::Weak { magnitude: mg },
             BaseEffectType::Strong { .. } => BaseEffectType::Strong { magnitude: mg },
+            BaseEffectType::LeftTrigger { .. } => BaseEffectType::LeftTrigger { magnitude: mg },
+            BaseEffectType::RightTrigger { .. } => BaseEffectType::RightTrigger { magnitude: mg },
         }
     }
 }
@@ -57,17 +66,20 @@ pub struct BaseEffect {
     pub kind: BaseEffectType,
     /// Defines playback duration and delays between each repetition.
     pub scheduling: Replay,
-    // TODO: maybe allow other f(t)?
     /// Basic attenuation function.
     pub envelope: Envelope,
+    /// Shape of magnitude during playback, multiplied with envelope.
+    pub waveform: Waveform,
 }
 
 impl BaseEffect {
-    /// Returns `Weak` or `Strong` after applying envelope.
+    /// Returns `kind` after applying envelope and waveform.
     pub(super) fn magnitude_at(&self, ticks: Ticks) -> BaseEffectType {
         if let Some(wrapped) = self.scheduling.wrap(ticks) {
-            let att =
-                self.scheduling.at(wrapped) * self.envelope.at(wrapped, self.scheduling.play_for);
+            let play_for = self.scheduling.play_for;
+            let att = self.scheduling.at(wrapped)
+                * self.envelope.at(wrapped, play_for)
+                * self.waveform.at(wrapped, play_for);
             self.kind * att
         } else {
             self.kind * 0.0
@@ -75,6 +87,70 @@ impl BaseEffect {
     }
 }
 
+/// Shape of [`BaseEffect`](struct.BaseEffect.html) magnitude over time.
+///
+/// Waveforms are synthesized in software and sampled every tick, so periods shorter than 2 ticks
+/// (100 ms) are not reproduced. `phase` is offset from the start of period, from 0.0 to 1.0.
+/// Periodic waveforms go from 0 to full magnitude and start from playback of base effect, so
+/// they restart with each repetition.
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[non_exhaustive]
+pub enum Waveform {
+    /// Full magnitude during whole playback.
+    Constant,
+    /// Sine wave, starts from half of magnitude and goes up.
+    Sine { period: Ticks, phase: f32 },
+    /// Full magnitude for first half of period, zero for second half.
+    Square { period: Ticks, phase: f32 },
+    /// Rises from zero to full magnitude in first half of period and falls back in second half.
+    Triangle { period: Ticks, phase: f32 },
+    /// Changes linearly from `start` to `end` fraction of magnitude (0.0 to 1.0) during
+    /// `Replay::play_for`.
+    Ramp { start: f32, end: f32 },
+}
+
+impl Waveform {
+    pub(super) fn at(&self, ticks: Ticks, dur: Ticks) -> f32 {
+        let position = |period: Ticks, phase: f32| {
+            if period.0 == 0 {
+                None
+            } else {
+                let x = ticks.0 as f32 / period.0 as f32 + phase;
+                Some(x - x.floor())
+            }
+        };
+
+        let value = match *self {
+            Waveform::Constant => 1.0,
+            Waveform::Sine { period, phase } => position(period, phase)
+                .map(|x| 0.5 + 0.5 * (2.0 * PI * x).sin())
+                .unwrap_or(1.0),
+            Waveform::Square { period, phase } => position(period, phase)
+                .map(|x| if x < 0.5 { 1.0 } else { 0.0 })
+                .unwrap_or(1.0),
+            Waveform::Triangle { period, phase } => position(period, phase)
+                .map(|x| 1.0 - (2.0 * x - 1.0).abs())
+                .unwrap_or(1.0),
+            Waveform::Ramp { start, end } => {
+                // Last tick of playback plays `end`.
+                let last = dur.0.saturating_sub(1).max(1);
+                let t = (ticks.0 as f32 / last as f32).min(1.0);
+                start + (end - start) * t
+            }
+        };
+
+        utils::clamp(value, 0.0, 1.0)
+    }
+}
+
+// `#[default]` on enum variants requires Rust 1.62.
+#[allow(clippy::derivable_impls)]
+impl Default for Waveform {
+    fn default() -> Self {
+        Waveform::Constant
+    }
+}
+
 // TODO: Image with "envelope"
 #[derive(Copy, Clone, PartialEq, Debug, Default)]
 /// Envelope shaped attenuation(time) function.
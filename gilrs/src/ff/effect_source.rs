@@ -11,6 +11,7 @@ use std::{fmt, u16};
 
 use super::base_effect::{BaseEffect, BaseEffectType};
 use super::time::{Repeat, Ticks};
+use super::MixMode;
 
 use vec_map::VecMap;
 
@@ -246,8 +247,8 @@ pub(super) enum EffectState {
 #[derive(Clone, PartialEq, Debug)]
 pub(crate) struct EffectSource {
     base_effects: Vec<BaseEffect>,
-    // TODO: Use bitset
-    pub(super) devices: VecMap<()>,
+    /// Gain of effect on each gamepad.
+    pub(super) devices: VecMap<f32>,
     pub(super) repeat: Repeat,
     pub(super) distance_model: DistanceModel,
     pub(super) position: [f32; 3],
@@ -258,7 +259,7 @@ pub(crate) struct EffectSource {
 impl EffectSource {
     pub(super) fn new(
         base_effects: Vec<BaseEffect>,
-        devices: VecMap<()>,
+        devices: VecMap<f32>,
         repeat: Repeat,
         dist_model: DistanceModel,
         position: [f32; 3],
@@ -275,6 +276,13 @@ impl EffectSource {
         }
     }
 
+    pub(super) fn is_playing(&self) -> bool {
+        match self.state {
+            EffectState::Playing { .. } => true,
+            EffectState::Stopped => false,
+        }
+    }
+
     pub(super) fn combine_base_effects(&mut self, ticks: Ticks, actor_pos: [f32; 3]) -> Magnitude {
         let ticks = match self.state {
             EffectState::Playing { since } => {
@@ -308,22 +316,67 @@ impl EffectSource {
                 BaseEffectType::Weak { magnitude } => {
                     final_magnitude.weak = final_magnitude.weak.saturating_add(magnitude)
                 }
+                BaseEffectType::LeftTrigger { magnitude } => {
+                    final_magnitude.left_trigger =
+                        final_magnitude.left_trigger.saturating_add(magnitude)
+                }
+                BaseEffectType::RightTrigger { magnitude } => {
+                    final_magnitude.right_trigger =
+                        final_magnitude.right_trigger.saturating_add(magnitude)
+                }
             };
         }
         final_magnitude * attenuation
     }
 }
 
-/// (strong, weak) pair.
+/// Magnitudes of rumble motors and impulse motors in triggers.
 #[derive(Copy, Clone, Debug)]
 pub(super) struct Magnitude {
     pub strong: u16,
     pub weak: u16,
+    pub left_trigger: u16,
+    pub right_trigger: u16,
 }
 
 impl Magnitude {
     pub fn zero() -> Self {
-        Magnitude { strong: 0, weak: 0 }
+        Magnitude {
+            strong: 0,
+            weak: 0,
+            left_trigger: 0,
+            right_trigger: 0,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.strong == 0 && self.weak == 0 && self.left_trigger == 0 && self.right_trigger == 0
+    }
+
+    /// Adds trigger magnitudes to rumble motors on the same side, for gamepads without trigger
+    /// motors. Strong motor is in the left grip.
+    pub fn fold_triggers(mut self) -> Self {
+        self.strong = self.strong.saturating_add(self.left_trigger);
+        self.weak = self.weak.saturating_add(self.right_trigger);
+        self.left_trigger = 0;
+        self.right_trigger = 0;
+
+        self
+    }
+
+    /// Combines magnitudes of two effects played at the same time.
+    pub fn mix(mut self, other: Magnitude, mode: MixMode) -> Self {
+        match mode {
+            MixMode::Sum => self += other,
+            MixMode::Max => {
+                self.strong = self.strong.max(other.strong);
+                self.weak = self.weak.max(other.weak);
+                self.left_trigger = self.left_trigger.max(other.left_trigger);
+                self.right_trigger = self.right_trigger.max(other.right_trigger);
+            }
+        }
+
+        self
     }
 }
 
@@ -332,19 +385,20 @@ impl Mul<f32> for Magnitude {
 
     fn mul(self, rhs: f32) -> Self::Output {
         debug_assert!(rhs >= 0.0);
-        let strong = self.strong as f32 * rhs;
-        let strong = if strong > u16::MAX as f32 {
-            u16::MAX
-        } else {
-            strong as u16
-        };
-        let weak = self.weak as f32 * rhs;
-        let weak = if weak > u16::MAX as f32 {
-            u16::MAX
-        } else {
-            weak as u16
+        let scale = |magnitude: u16| {
+            let magnitude = magnitude as f32 * rhs;
+            if magnitude > u16::MAX as f32 {
+                u16::MAX
+            } else {
+                magnitude as u16
+            }
         };
-        Magnitude { strong, weak }
+        Magnitude {
+            strong: scale(self.strong),
+            weak: scale(self.weak),
+            left_trigger: scale(self.left_trigger),
+            right_trigger: scale(self.right_trigger),
+        }
     }
 }
 
@@ -352,6 +406,8 @@ impl AddAssign for Magnitude {
     fn add_assign(&mut self, rhs: Magnitude) {
         self.strong = self.strong.saturating_add(rhs.strong);
         self.weak = self.weak.saturating_add(rhs.weak);
+        self.left_trigger = self.left_trigger.saturating_add(rhs.left_trigger);
+        self.right_trigger = self.right_trigger.saturating_add(rhs.right_trigger);
     }
 }
 
@@ -0,0 +1,135 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use crate::gamepad::GamepadId;
+use crate::utils;
+
+use std::f32;
+use std::iter::FromIterator;
+
+use vec_map::VecMap;
+
+/// Gamepads that effects are played on together, for example all players or one team. Each
+/// gamepad has its own gain, multiplied with gain of effect.
+///
+/// Unlike `Effect::set_gamepads()`, group can contain disconnected gamepads and gamepads without
+/// force feedback, effects are played only on other gamepads.
+///
+/// ```
+/// use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Group};
+/// use gilrs::Gilrs;
+///
+/// let mut gilrs = Gilrs::new().unwrap();
+/// let mut all_players: Group = gilrs.gamepads().map(|(id, _)| id).collect();
+/// if let Some((id, _)) = gilrs.gamepads().next() {
+///     // First player prefers weaker rumble.
+///     all_players.set_gain(id, 0.5);
+/// }
+///
+/// let effect = EffectBuilder::new()
+///     .add_effect(BaseEffect {
+///         kind: BaseEffectType::Strong { magnitude: 60_000 },
+///         ..Default::default()
+///     })
+///     .finish(&mut gilrs)
+///     .unwrap();
+/// effect.play_on(&all_players).unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Group {
+    gamepads: VecMap<f32>,
+}
+
+impl Group {
+    /// Creates empty group.
+    pub fn new() -> Self {
+        Group {
+            gamepads: VecMap::new(),
+        }
+    }
+
+    /// Adds gamepad with gain 1.0. Gain of gamepad that is already in the group is not changed.
+    pub fn add(&mut self, id: GamepadId) -> &mut Self {
+        self.gamepads.entry(id.0).or_insert(1.0);
+        self
+    }
+
+    pub fn remove(&mut self, id: GamepadId) -> &mut Self {
+        self.gamepads.remove(id.0);
+        self
+    }
+
+    /// Changes gain of gamepad, adding it to the group if needed. `gain` will be clamped to
+    /// \[0.0, f32::MAX\].
+    pub fn set_gain(&mut self, id: GamepadId, gain: f32) -> &mut Self {
+        self.gamepads
+            .insert(id.0, utils::clamp(gain, 0.0, f32::MAX));
+        self
+    }
+
+    /// Returns gain of gamepad or `None` if it's not in the group.
+    pub fn gain(&self, id: GamepadId) -> Option<f32> {
+        self.gamepads.get(id.0).cloned()
+    }
+
+    pub fn contains(&self, id: GamepadId) -> bool {
+        self.gamepads.contains_key(id.0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.gamepads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gamepads.is_empty()
+    }
+
+    /// Returns iterator over gamepads in the group and their gains.
+    pub fn iter(&self) -> impl Iterator<Item = (GamepadId, f32)> + '_ {
+        self.gamepads
+            .iter()
+            .map(|(id, &gain)| (GamepadId(id), gain))
+    }
+
+    pub(super) fn gains(&self) -> &VecMap<f32> {
+        &self.gamepads
+    }
+}
+
+impl FromIterator<GamepadId> for Group {
+    fn from_iter<I: IntoIterator<Item = GamepadId>>(iter: I) -> Self {
+        let mut group = Group::new();
+        for id in iter {
+            group.add(id);
+        }
+
+        group
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Group;
+    use crate::GamepadId;
+
+    #[test]
+    fn gains() {
+        let mut group: Group = vec![GamepadId(0), GamepadId(2)].into_iter().collect();
+        group
+            .set_gain(GamepadId(2), 0.5)
+            .set_gain(GamepadId(3), -1.0);
+        group.add(GamepadId(2)).remove(GamepadId(0));
+
+        assert_eq!(
+            group.iter().collect::<Vec<_>>(),
+            [(GamepadId(2), 0.5), (GamepadId(3), 0.0)]
+        );
+        assert_eq!(group.gain(GamepadId(0)), None);
+        assert!(group.contains(GamepadId(3)));
+        assert_eq!(group.len(), 2);
+    }
+}
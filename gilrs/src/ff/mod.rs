@@ -5,9 +5,6 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-// This code is not used on wasm
-#![cfg_attr(target_arch = "wasm32", allow(dead_code))]
-
 //! Force feedback module.
 //!
 //! To use force feedback, you have to create one or more [`Effect`s](struct.Effect.html). Each
@@ -16,6 +13,10 @@
 //! [`DistanceModel`](enum.DistanceModel.html). Final strength of effect is based on saturating sum
 //! (to `u16::MAX`) of all base effects and time from the start of playback, attenuation from
 //! distance between effect source and listener (represented by gamepad) and effect's gain.
+//! Effects played on the same gamepad are summed too, unless other
+//! [`MixMode`](enum.MixMode.html) is set.
+//!
+//! If gamepad disconnects and reconnects within 3 seconds, effects playing on it resume.
 //!
 //! See also [`Gilrs::set_listener_position()`](../struct.Gilrs.html#method.set_listener_position)
 //! and [`Gamepad::is_ff_supported()`](../struct.Gamepad.html#method.is_ff_supported).
@@ -37,7 +38,7 @@
 //!     .add_effect(BaseEffect {
 //!         kind: BaseEffectType::Strong { magnitude: 60_000 },
 //!         scheduling: Replay { play_for: duration, with_delay: duration * 3, ..Default::default() },
-//!         envelope: Default::default(),
+//!         ..Default::default()
 //!     })
 //!     .add_effect(BaseEffect {
 //!         kind: BaseEffectType::Weak { magnitude: 60_000 },
@@ -52,20 +53,28 @@
 //!
 //! See [`examples/ff_pos.rs`](https://gitlab.com/gilrs-project/gilrs/blob/v0.8.1/examples/ff_pos.rs) for
 //! more advanced example.
+#[cfg(feature = "adaptive-triggers")]
+pub mod adaptive_trigger;
+#[cfg(feature = "audio-haptics")]
+pub mod audio;
 mod base_effect;
 mod effect_source;
+mod group;
+mod pattern;
 pub(crate) mod server;
 mod time;
 
-pub use self::base_effect::{BaseEffect, BaseEffectType, Envelope, Replay};
+pub use self::base_effect::{BaseEffect, BaseEffectType, Envelope, Replay, Waveform};
 pub use self::effect_source::{DistanceModel, DistanceModelError};
-#[allow(unused_imports)]
-pub(crate) use self::time::TICK_DURATION;
+pub use self::group::Group;
+pub use self::pattern::{Interpolation, Keyframe, Pattern, PatternError};
+pub use self::server::Diagnostics;
+pub(crate) use self::time::{set_tick_duration, DEFAULT_TICK_DURATION, MAX_TICK_DURATION};
 pub use self::time::{Repeat, Ticks};
 
 use std::error::Error as StdError;
 use std::hash::{Hash, Hasher};
-use std::sync::mpsc::{SendError, Sender};
+use std::sync::mpsc::{Receiver, SendError, Sender, TryRecvError};
 use std::{f32, fmt};
 
 use self::effect_source::EffectSource;
@@ -75,6 +84,9 @@ use crate::utils;
 
 use vec_map::VecMap;
 
+#[cfg(feature = "serde-serialize")]
+use serde::{Deserialize, Serialize};
+
 /// Handle to force feedback effect.
 ///
 /// `Effect` represents force feedback effect that can be played on one or more gamepads. It uses a
@@ -87,6 +99,13 @@ pub struct Effect {
     tx: Sender<Message>,
 }
 
+/// Identifies [`Effect`](struct.Effect.html) in
+/// [`EventType::ForceFeedbackEffectCompleted`](../enum.EventType.html). Returned by
+/// `Effect::id()`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct EffectId(pub(crate) usize);
+
 impl PartialEq for Effect {
     fn eq(&self, other: &Effect) -> bool {
         self.id == other.id
@@ -118,9 +137,17 @@ impl Drop for Effect {
 }
 
 impl Effect {
+    /// Returns ID of effect, the same for all clones of it.
+    pub fn id(&self) -> EffectId {
+        EffectId(self.id)
+    }
+
     /// Plays effect on all associated gamepads.
     pub fn play(&self) -> Result<(), Error> {
-        self.tx.send(Message::Play { id: self.id })?;
+        self.tx.send(Message::Play {
+            id: self.id,
+            time: utils::time_now(),
+        })?;
 
         Ok(())
     }
@@ -149,7 +176,7 @@ impl Effect {
             {
                 return Err(Error::FfNotSupported(dev));
             } else {
-                gamepads.insert(dev.0, ());
+                gamepads.insert(dev.0, 1.0);
             }
         }
 
@@ -182,6 +209,23 @@ impl Effect {
         }
     }
 
+    /// Changes gamepads that are associated with effect to gamepads of `group`, using their
+    /// gains. Gamepads that are disconnected or don't support force feedback are ignored.
+    pub fn set_group(&self, group: &Group) -> Result<(), Error> {
+        self.tx.send(Message::SetGamepads {
+            id: self.id,
+            gamepads: group.gains().clone(),
+        })?;
+
+        Ok(())
+    }
+
+    /// Plays effect on gamepads of `group`. Same as `set_group()` followed by `play()`.
+    pub fn play_on(&self, group: &Group) -> Result<(), Error> {
+        self.set_group(group)?;
+        self.play()
+    }
+
     /// Changes what should happen to effect when it ends.
     pub fn set_repeat(&self, repeat: Repeat) -> Result<(), Error> {
         self.tx.send(Message::SetRepeat {
@@ -230,7 +274,9 @@ impl Effect {
 #[derive(Clone, PartialEq, Debug)]
 pub struct EffectBuilder {
     base_effects: Vec<BaseEffect>,
-    devices: VecMap<()>,
+    devices: VecMap<f32>,
+    /// Gamepads added by `add_group()`, not checked by `finish()`.
+    group_devices: VecMap<f32>,
     repeat: Repeat,
     dist_model: DistanceModel,
     position: [f32; 3],
@@ -245,6 +291,7 @@ impl EffectBuilder {
         EffectBuilder {
             base_effects: Vec::new(),
             devices: VecMap::new(),
+            group_devices: VecMap::new(),
             repeat: Repeat::Infinitely,
             dist_model: DistanceModel::None,
             position: [0.0, 0.0, 0.0],
@@ -258,22 +305,37 @@ impl EffectBuilder {
         self
     }
 
+    /// Adds base effects that play [`Pattern`](struct.Pattern.html). Pattern starts with the
+    /// effect and repeats after its duration.
+    pub fn add_pattern(&mut self, pattern: &Pattern) -> &mut Self {
+        self.base_effects.extend(pattern.base_effects());
+        self
+    }
+
     /// Changes gamepads that are associated with effect. Effect will be only played on gamepads
     /// from last call to this function.
     pub fn gamepads(&mut self, ids: &[GamepadId]) -> &mut Self {
         for dev in ids {
-            self.devices.insert(dev.0, ());
+            self.devices.insert(dev.0, 1.0);
         }
         self
     }
 
     /// Adds gamepad to the list of gamepads associated with effect.
     pub fn add_gamepad(&mut self, gamepad: &Gamepad<'_>) -> &mut Self {
-        self.devices.insert(gamepad.id().0, ());
+        self.devices.insert(gamepad.id().0, 1.0);
 
         self
     }
 
+    /// Adds gamepads of `group` to the list of gamepads associated with effect, using their
+    /// gains. Unlike other gamepads, they are not checked by `finish()`.
+    pub fn add_group(&mut self, group: &Group) -> &mut Self {
+        self.group_devices
+            .extend(group.gains().iter().map(|(id, &gain)| (id, gain)));
+        self
+    }
+
     /// Changes what should happen to effect when it ends.
     pub fn repeat(&mut self, repeat: Repeat) -> &mut Self {
         self.repeat = repeat;
@@ -321,9 +383,11 @@ impl EffectBuilder {
 
         self.dist_model.validate()?;
 
+        let mut devices = self.devices.clone();
+        devices.extend(self.group_devices.iter().map(|(id, &gain)| (id, gain)));
         let effect = EffectSource::new(
             self.base_effects.clone(),
-            self.devices.clone(),
+            devices,
             self.repeat,
             self.dist_model,
             self.position,
@@ -339,6 +403,60 @@ impl EffectBuilder {
     }
 }
 
+/// Force feedback motor of gamepad.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Actuator {
+    /// Strong (low frequency) rumble motor.
+    Strong,
+    /// Weak (high frequency) rumble motor.
+    Weak,
+    /// Impulse motor in left trigger.
+    LeftTrigger,
+    /// Impulse motor in right trigger.
+    RightTrigger,
+}
+
+/// Actuator test started by [`Gamepad::test_actuators()`](../struct.Gamepad.html#method.test_actuators).
+#[derive(Debug)]
+pub struct ActuatorTest {
+    rx: Receiver<Vec<Actuator>>,
+    result: Option<Vec<Actuator>>,
+}
+
+impl ActuatorTest {
+    pub(crate) fn new(rx: Receiver<Vec<Actuator>>) -> Self {
+        ActuatorTest { rx, result: None }
+    }
+
+    /// Returns motors whose pulse was sent to gamepad without error, in the order they were
+    /// pulsed, or `None` if test is still running. Test that was interrupted, for example by
+    /// `Gilrs::stop_all_ff()` or disconnecting gamepad, only reports motors pulsed until then.
+    pub fn result(&mut self) -> Option<&[Actuator]> {
+        if self.result.is_none() {
+            match self.rx.try_recv() {
+                Ok(actuators) => self.result = Some(actuators),
+                Err(TryRecvError::Disconnected) => self.result = Some(Vec::new()),
+                Err(TryRecvError::Empty) => (),
+            }
+        }
+
+        self.result.as_ref().map(|actuators| &actuators[..])
+    }
+}
+
+/// How magnitudes of effects that play at the same time on one gamepad are combined. Used by
+/// [`Gilrs::set_ff_mix_mode()`](../struct.Gilrs.html#method.set_ff_mix_mode).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MixMode {
+    /// Magnitudes are added, saturating at `u16::MAX`. Weak effects are still felt while strong
+    /// one plays, but many effects together easily saturate motors.
+    Sum,
+    /// Each motor plays the strongest magnitude of all effects.
+    Max,
+}
+
 /// Basic error type in force feedback module.
 #[derive(Copy, Clone, Debug, PartialEq)]
 #[non_exhaustive]
@@ -404,6 +522,8 @@ impl From<DistanceModelError> for Error {
 mod tests {
     use super::*;
 
+    use std::u16;
+
     #[test]
     fn envelope() {
         let env = Envelope {
@@ -449,4 +569,46 @@ mod tests {
         assert_eq!(replay.at(Ticks(60)), 0.0);
         assert_eq!(replay.at(Ticks(70)), 0.0);
     }
+
+    #[test]
+    fn waveform() {
+        let dur = Ticks(9);
+        let square = Waveform::Square {
+            period: Ticks(4),
+            phase: 0.0,
+        };
+        assert_eq!(square.at(Ticks(1), dur), 1.0);
+        assert_eq!(square.at(Ticks(2), dur), 0.0);
+        assert_eq!(square.at(Ticks(4), dur), 1.0);
+
+        let triangle = Waveform::Triangle {
+            period: Ticks(4),
+            phase: 0.25,
+        };
+        assert_eq!(triangle.at(Ticks(0), dur), 0.5);
+        assert_eq!(triangle.at(Ticks(1), dur), 1.0);
+
+        let ramp = Waveform::Ramp {
+            start: 1.0,
+            end: 0.0,
+        };
+        assert_eq!(ramp.at(Ticks(0), dur), 1.0);
+        assert_eq!(ramp.at(Ticks(4), dur), 0.5);
+        assert_eq!(ramp.at(Ticks(8), dur), 0.0);
+    }
+
+    #[test]
+    fn fold_triggers() {
+        let magnitude = effect_source::Magnitude {
+            strong: 100,
+            left_trigger: 50,
+            right_trigger: u16::MAX,
+            ..effect_source::Magnitude::zero()
+        }
+        .fold_triggers();
+
+        assert_eq!(magnitude.strong, 150);
+        assert_eq!(magnitude.weak, u16::MAX);
+        assert!(magnitude.left_trigger == 0 && magnitude.right_trigger == 0);
+    }
 }
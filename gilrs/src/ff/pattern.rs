@@ -0,0 +1,300 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use super::base_effect::{BaseEffect, BaseEffectType, Replay, Waveform};
+use super::time::Ticks;
+use crate::utils;
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::u16;
+
+/// How magnitude changes between keyframes of [`Pattern`](struct.Pattern.html).
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Magnitude changes linearly to the next keyframe.
+    Linear,
+    /// Magnitude of keyframe is held until the next keyframe.
+    Step,
+}
+
+/// Magnitudes of strong and weak motor, from 0.0 to 1.0, at given time from the start of
+/// pattern.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Keyframe {
+    pub time_ms: u32,
+    pub strong: f32,
+    pub weak: f32,
+}
+
+/// Timeline of rumble keyframes, usually authored in a file so it can be changed without
+/// recompiling the game.
+///
+/// Pattern is played by adding it to [`EffectBuilder`](struct.EffectBuilder.html), which turns
+/// each part between two keyframes into base effects. With default `Repeat::Infinitely`, pattern
+/// loops; use `Repeat::For(pattern.duration())` to play it once.
+///
+/// Text format has one keyframe per line: time in milliseconds, strong and weak magnitude.
+/// Keyframes must be sorted by time. Optional `interpolation` line selects `linear` (default) or
+/// `step` interpolation, `#` starts a comment.
+///
+/// ```
+/// use gilrs::ff::{EffectBuilder, Pattern, Repeat};
+///
+/// let heartbeat = Pattern::parse(
+///     "
+///     interpolation = linear
+///     ## ms  strong  weak
+///     0     0.0     0.0
+///     60    1.0     0.2
+///     160   0.0     0.0
+///     260   0.6     0.1
+///     400   0.0     0.0
+///     ",
+/// )
+/// .unwrap();
+///
+/// let mut builder = EffectBuilder::new();
+/// builder
+///     .add_pattern(&heartbeat)
+///     .repeat(Repeat::For(heartbeat.duration()));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    keyframes: Vec<Keyframe>,
+    interpolation: Interpolation,
+}
+
+impl Pattern {
+    /// Creates empty pattern with linear interpolation.
+    pub fn new() -> Self {
+        Pattern {
+            keyframes: Vec::new(),
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    /// Adds keyframe. Magnitudes are clamped to \[0.0, 1.0\], time must not be lower than
+    /// time of previous keyframe.
+    pub fn keyframe(mut self, time_ms: u32, strong: f32, weak: f32) -> Self {
+        debug_assert!(self.keyframes.last().map_or(0, |k| k.time_ms) <= time_ms);
+        self.keyframes.push(Keyframe {
+            time_ms,
+            strong: utils::clamp(strong, 0.0, 1.0),
+            weak: utils::clamp(weak, 0.0, 1.0),
+        });
+        self
+    }
+
+    /// Changes interpolation between keyframes.
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Returns time of the last keyframe.
+    pub fn duration(&self) -> Ticks {
+        Ticks::from_ms(self.keyframes.last().map_or(0, |k| k.time_ms))
+    }
+
+    /// Parses pattern from text format described above.
+    pub fn parse(s: &str) -> Result<Self, PatternError> {
+        let mut pattern = Pattern::new();
+
+        for (idx, line) in s.lines().enumerate() {
+            let err = |reason| PatternError::InvalidLine {
+                line: idx + 1,
+                reason,
+            };
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.contains('=') {
+                let mut kv = line.splitn(2, '=');
+                let key = kv.next().unwrap().trim();
+                let value = kv.next().unwrap().trim();
+                if key != "interpolation" {
+                    return Err(err("unknown key"));
+                }
+                pattern.interpolation = match value {
+                    "linear" => Interpolation::Linear,
+                    "step" => Interpolation::Step,
+                    _ => return Err(err("unknown interpolation")),
+                };
+                continue;
+            }
+
+            let mut values = line.split_whitespace();
+            let time_ms = values
+                .next()
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| err("invalid time"))?;
+            let strong = values
+                .next()
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| err("invalid strong magnitude"))?;
+            let weak = values
+                .next()
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| err("invalid weak magnitude"))?;
+            if values.next().is_some() {
+                return Err(err("expected time, strong and weak magnitude"));
+            }
+            if let Some(last) = pattern.keyframes.last() {
+                if last.time_ms > time_ms {
+                    return Err(err("keyframes are not sorted by time"));
+                }
+            }
+
+            pattern = pattern.keyframe(time_ms, strong, weak);
+        }
+
+        Ok(pattern)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PatternError> {
+        let s = fs::read_to_string(path).map_err(PatternError::Io)?;
+
+        Pattern::parse(&s)
+    }
+
+    /// Converts pattern to base effects, two for every part between keyframes.
+    pub(super) fn base_effects(&self) -> Vec<BaseEffect> {
+        let period = self.duration();
+        let mut effects = Vec::new();
+
+        for pair in self.keyframes.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let start = Ticks::from_ms(from.time_ms);
+            let play_for = Ticks::from_ms(to.time_ms) - start;
+            if play_for == Ticks(0) {
+                continue;
+            }
+
+            let scheduling = Replay {
+                after: start,
+                play_for,
+                with_delay: period - play_for,
+            };
+            let motors = [
+                (
+                    BaseEffectType::Strong {
+                        magnitude: u16::MAX,
+                    },
+                    from.strong,
+                    to.strong,
+                ),
+                (
+                    BaseEffectType::Weak {
+                        magnitude: u16::MAX,
+                    },
+                    from.weak,
+                    to.weak,
+                ),
+            ];
+            for &(kind, from, to) in &motors {
+                let end = match self.interpolation {
+                    Interpolation::Linear => to,
+                    Interpolation::Step => from,
+                };
+                if from == 0.0 && end == 0.0 {
+                    continue;
+                }
+                effects.push(BaseEffect {
+                    kind,
+                    scheduling,
+                    waveform: Waveform::Ramp { start: from, end },
+                    ..Default::default()
+                });
+            }
+        }
+
+        effects
+    }
+}
+
+impl Default for Pattern {
+    fn default() -> Self {
+        Pattern::new()
+    }
+}
+
+/// Error returned when parsing or loading [`Pattern`](struct.Pattern.html).
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum PatternError {
+    /// Failed to read the file.
+    Io(io::Error),
+    /// Pattern is not valid. `line` starts from 1.
+    InvalidLine { line: usize, reason: &'static str },
+}
+
+impl Error for PatternError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PatternError::Io(e) => Some(e),
+            PatternError::InvalidLine { .. } => None,
+        }
+    }
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternError::Io(_) => f.write_str("failed to read pattern file"),
+            PatternError::InvalidLine { line, reason } => {
+                write!(f, "invalid pattern, line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pattern, PatternError};
+    use crate::ff::time::Ticks;
+
+    use std::u16;
+
+    #[test]
+    fn parse_and_play() {
+        let pattern =
+            Pattern::parse("interpolation = step\n0 1.0 0\n100 0.5 0 # two ticks\n200 0 0")
+                .unwrap();
+        assert_eq!(pattern.keyframes().len(), 3);
+        assert_eq!(pattern.duration(), Ticks(4));
+
+        let effects = pattern.base_effects();
+        assert_eq!(effects.len(), 2);
+        let at = |ticks| {
+            effects
+                .iter()
+                .map(|effect| effect.magnitude_at(Ticks(ticks)).magnitude())
+                .sum::<u16>()
+        };
+        assert_eq!(at(0), u16::MAX);
+        assert_eq!(at(2), u16::MAX / 2);
+        // Pattern loops.
+        assert_eq!(at(5), u16::MAX);
+
+        match Pattern::parse("0 0 0\n\n10 0.5\n") {
+            Err(PatternError::InvalidLine { line: 3, .. }) => (),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
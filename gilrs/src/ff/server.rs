@@ -6,14 +6,25 @@
 // copied, modified, or distributed except according to those terms.
 
 use super::effect_source::{DistanceModel, EffectSource, EffectState, Magnitude};
-use super::time::{Repeat, Ticks, TICK_DURATION};
+use super::time::{tick_duration, Repeat, Ticks};
+use super::{Actuator, MixMode};
 
+use std::fmt::{self, Debug};
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
 use std::ops::{Deref, DerefMut};
+#[cfg(not(target_arch = "wasm32"))]
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use std::time::{Duration, SystemTime};
+use std::u16;
 
 use crate::gamepad::GamepadId;
+use crate::utils;
 use gilrs_core::FfDevice;
 
 use vec_map::VecMap;
@@ -32,6 +43,8 @@ pub(crate) enum Message {
     },
     Play {
         id: usize,
+        /// When `Effect::play()` was called.
+        time: SystemTime,
     },
     Stop {
         id: usize,
@@ -49,7 +62,7 @@ pub(crate) enum Message {
     },
     SetGamepads {
         id: usize,
-        gamepads: VecMap<()>,
+        gamepads: VecMap<f32>,
     },
     AddGamepad {
         id: usize,
@@ -71,6 +84,28 @@ pub(crate) enum Message {
         id: usize,
         gain: f32,
     },
+    /// Plays constant magnitude on device, replacing previous rumble.
+    Rumble {
+        id: usize,
+        strong: u16,
+        weak: u16,
+        duration: Ticks,
+    },
+    /// Pulses each motor of device, one after another, and sends motors whose pulse succeeded
+    /// when test ends.
+    TestActuators {
+        id: usize,
+        result: Sender<Vec<Actuator>>,
+    },
+    SetMixMode {
+        mode: MixMode,
+    },
+    /// Silences all devices without changing state of effects.
+    SetMuted {
+        muted: bool,
+    },
+    /// Stops all effects, rumbles and actuator tests.
+    StopAll,
 }
 
 impl Message {
@@ -90,6 +125,52 @@ struct Device {
     inner: FfDevice,
     position: [f32; 3],
     gain: f32,
+    /// Magnitude of `Gamepad::rumble()` and tick when it ends.
+    rumble: Option<(Magnitude, Ticks)>,
+    /// Running actuator test.
+    test: Option<RunningTest>,
+    /// Whether non-zero magnitude was played in the last tick.
+    is_playing: bool,
+}
+
+/// Actuator test started by `Message::TestActuators`.
+#[derive(Debug)]
+struct RunningTest {
+    started: Ticks,
+    /// Actuators in the order they are pulsed.
+    actuators: Vec<Actuator>,
+    succeeded: Vec<Actuator>,
+    failed: Vec<Actuator>,
+    result: Sender<Vec<Actuator>>,
+}
+
+impl RunningTest {
+    fn record(&mut self, actuator: Actuator, ok: bool) {
+        if !ok {
+            self.succeeded.retain(|&a| a != actuator);
+            if !self.failed.contains(&actuator) {
+                self.failed.push(actuator);
+            }
+        } else if !self.succeeded.contains(&actuator) && !self.failed.contains(&actuator) {
+            self.succeeded.push(actuator);
+        }
+    }
+
+    /// Sends actuators whose pulse was written without error. Tests that are interrupted only
+    /// report actuators pulsed until then.
+    fn finish(self) {
+        // Fails only if `gilrs::ff::ActuatorTest` was dropped.
+        let _ = self.result.send(self.succeeded);
+    }
+}
+
+/// State of disconnected device, restored if it reconnects within `RECONNECT_GRACE_MS`.
+#[derive(Debug)]
+struct Disconnected {
+    position: [f32; 3],
+    gain: f32,
+    rumble: Option<(Magnitude, Ticks)>,
+    since: Ticks,
 }
 
 struct Effect {
@@ -136,19 +217,106 @@ impl From<FfDevice> for Device {
             inner,
             position: [0.0, 0.0, 0.0],
             gain: 1.0,
+            rumble: None,
+            test: None,
+            is_playing: false,
         }
     }
 }
 
-pub(crate) fn run(rx: Receiver<Message>) {
-    let mut effects = VecMap::<Effect>::new();
-    let mut devices = VecMap::<Device>::new();
-    let sleep_dur = Duration::from_millis(TICK_DURATION.into());
-    let mut tick = Ticks(0);
+/// Gamepad and effect ID of effect that finished playing on that gamepad.
+pub(crate) type Completed = (usize, usize);
 
-    loop {
-        let t1 = Instant::now();
-        while let Ok(ev) = rx.try_recv() {
+/// Counters describing timing of force feedback thread. See
+/// [`Gilrs::ff_diagnostics()`](../struct.Gilrs.html#method.ff_diagnostics).
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Diagnostics {
+    ticks: u64,
+    missed_ticks: u64,
+    latency_samples: u32,
+    total_latency: Duration,
+    max_latency: Duration,
+}
+
+impl Diagnostics {
+    /// Number of ticks played by force feedback thread.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Number of ticks that weren't played on time, because previous tick took longer than
+    /// tick duration.
+    pub fn missed_ticks(&self) -> u64 {
+        self.missed_ticks
+    }
+
+    /// Average time from `Effect::play()` to the first write of motor state. Zero if no effect
+    /// was played yet.
+    pub fn average_latency(&self) -> Duration {
+        if self.latency_samples == 0 {
+            Duration::from_secs(0)
+        } else {
+            self.total_latency / self.latency_samples
+        }
+    }
+
+    /// The longest time from `Effect::play()` to the first write of motor state.
+    pub fn max_latency(&self) -> Duration {
+        self.max_latency
+    }
+
+    fn record_latency(&mut self, latency: Duration) {
+        self.latency_samples += 1;
+        self.total_latency += latency;
+        self.max_latency = self.max_latency.max(latency);
+    }
+}
+
+pub(crate) type SharedDiagnostics = Arc<Mutex<Diagnostics>>;
+
+/// Holds state of all effects and devices. It's normally owned by a separate thread (see
+/// `run()`), but on wasm, which doesn't have threads, `Gilrs` updates it when polling for events.
+pub(crate) struct FfServer {
+    rx: Receiver<Message>,
+    completed: Sender<Completed>,
+    effects: VecMap<Effect>,
+    devices: VecMap<Device>,
+    disconnected: VecMap<Disconnected>,
+    /// Effects that didn't write motor state since `Message::Play`, with time they were played.
+    pending_latency: VecMap<SystemTime>,
+    diagnostics: SharedDiagnostics,
+    tick: Ticks,
+    muted: bool,
+    mix_mode: MixMode,
+    #[cfg(target_arch = "wasm32")]
+    last_update: SystemTime,
+}
+
+impl FfServer {
+    fn new(
+        rx: Receiver<Message>,
+        completed: Sender<Completed>,
+        diagnostics: SharedDiagnostics,
+    ) -> Self {
+        FfServer {
+            rx,
+            completed,
+            effects: VecMap::new(),
+            devices: VecMap::new(),
+            disconnected: VecMap::new(),
+            pending_latency: VecMap::new(),
+            diagnostics,
+            tick: Ticks(0),
+            muted: false,
+            mix_mode: MixMode::Sum,
+            #[cfg(target_arch = "wasm32")]
+            last_update: utils::time_now(),
+        }
+    }
+
+    /// Handles all pending messages and plays one tick of combined effects.
+    fn update(&mut self) {
+        while let Ok(ev) = self.rx.try_recv() {
             if ev.use_trace_level() {
                 trace!("New ff event: {:?}", ev);
             } else {
@@ -157,37 +325,60 @@ pub(crate) fn run(rx: Receiver<Message>) {
 
             match ev {
                 Message::Create { id, effect } => {
-                    effects.insert(id, (*effect).into());
+                    self.effects.insert(id, (*effect).into());
                 }
-                Message::Play { id } => {
-                    if let Some(effect) = effects.get_mut(id) {
-                        effect.source.state = EffectState::Playing { since: tick }
+                Message::Play { id, time } => {
+                    if let Some(effect) = self.effects.get_mut(id) {
+                        effect.source.state = EffectState::Playing { since: self.tick };
+                        self.pending_latency.insert(id, time);
                     } else {
                         error!("{:?} with wrong ID", ev);
                     }
                 }
                 Message::Stop { id } => {
-                    if let Some(effect) = effects.get_mut(id) {
+                    if let Some(effect) = self.effects.get_mut(id) {
                         effect.source.state = EffectState::Stopped
                     } else {
                         error!("{:?} with wrong ID", ev);
                     }
                 }
                 Message::Open { id, device } => {
-                    devices.insert(id, device.into());
+                    let mut device = Device::from(device);
+                    if let Some(state) = self.disconnected.remove(id) {
+                        debug!(
+                            "Force feedback device {} reconnected, resuming effects.",
+                            id
+                        );
+                        device.position = state.position;
+                        device.gain = state.gain;
+                        device.rumble = state.rumble;
+                    }
+                    self.devices.insert(id, device);
                 }
                 Message::Close { id } => {
-                    devices.remove(id);
+                    // Effects keep playing, so they resume if device reconnects soon.
+                    if let Some(mut device) = self.devices.remove(id) {
+                        if let Some(test) = device.test.take() {
+                            test.finish();
+                        }
+                        let state = Disconnected {
+                            position: device.position,
+                            gain: device.gain,
+                            rumble: device.rumble,
+                            since: self.tick,
+                        };
+                        self.disconnected.insert(id, state);
+                    }
                 }
                 Message::SetListenerPosition { id, position } => {
-                    if let Some(device) = devices.get_mut(id) {
+                    if let Some(device) = self.devices.get_mut(id) {
                         device.position = position;
                     } else {
                         error!("{:?} with wrong ID", ev);
                     }
                 }
                 Message::HandleCloned { id } => {
-                    if let Some(effect) = effects.get_mut(id) {
+                    if let Some(effect) = self.effects.get_mut(id) {
                         effect.inc();
                     } else {
                         error!("{:?} with wrong ID", ev);
@@ -195,7 +386,7 @@ pub(crate) fn run(rx: Receiver<Message>) {
                 }
                 Message::HandleDropped { id } => {
                     let mut drop = false;
-                    if let Some(effect) = effects.get_mut(id) {
+                    if let Some(effect) = self.effects.get_mut(id) {
                         if effect.dec() == 0 {
                             drop = true;
                         }
@@ -204,98 +395,687 @@ pub(crate) fn run(rx: Receiver<Message>) {
                     }
 
                     if drop {
-                        effects.remove(id);
+                        self.effects.remove(id);
+                        self.pending_latency.remove(id);
                     }
                 }
                 Message::SetGamepads { id, gamepads } => {
-                    if let Some(eff) = effects.get_mut(id) {
+                    if let Some(eff) = self.effects.get_mut(id) {
                         eff.source.devices = gamepads;
                     } else {
                         error!("Invalid effect id {} when changing gamepads.", id);
                     }
                 }
                 Message::AddGamepad { id, gamepad_id } => {
-                    if let Some(eff) = effects.get_mut(id) {
-                        eff.source.devices.insert(gamepad_id.0, ());
+                    if let Some(eff) = self.effects.get_mut(id) {
+                        eff.source.devices.insert(gamepad_id.0, 1.0);
                     } else {
                         error!("Invalid effect id {} when changing gamepads.", id);
                     }
                 }
                 Message::SetRepeat { id, repeat } => {
-                    if let Some(eff) = effects.get_mut(id) {
+                    if let Some(eff) = self.effects.get_mut(id) {
                         eff.source.repeat = repeat;
                     } else {
                         error!("Invalid effect id {} when changing repeat mode.", id);
                     }
                 }
                 Message::SetDistanceModel { id, model } => {
-                    if let Some(eff) = effects.get_mut(id) {
+                    if let Some(eff) = self.effects.get_mut(id) {
                         eff.source.distance_model = model;
                     } else {
                         error!("Invalid effect id {} when changing distance model.", id);
                     }
                 }
                 Message::SetPosition { id, position } => {
-                    if let Some(eff) = effects.get_mut(id) {
+                    if let Some(eff) = self.effects.get_mut(id) {
                         eff.source.position = position;
                     } else {
                         error!("Invalid effect id {}.", id);
                     }
                 }
                 Message::SetGain { id, gain } => {
-                    if let Some(eff) = effects.get_mut(id) {
+                    if let Some(eff) = self.effects.get_mut(id) {
                         eff.source.gain = gain;
                     } else {
                         error!("Invalid effect id {} when changing effect gain.", id);
                     }
                 }
+                Message::Rumble {
+                    id,
+                    strong,
+                    weak,
+                    duration,
+                } => {
+                    if let Some(device) = self.devices.get_mut(id) {
+                        let magnitude = Magnitude {
+                            strong,
+                            weak,
+                            ..Magnitude::zero()
+                        };
+                        device.rumble = Some((magnitude, self.tick + duration));
+                    } else {
+                        error!("{:?} with wrong ID", ev);
+                    }
+                }
+                Message::TestActuators { id, result } => {
+                    if let Some(device) = self.devices.get_mut(id) {
+                        let mut actuators = vec![Actuator::Strong, Actuator::Weak];
+                        if device.inner.has_trigger_motors() {
+                            actuators.extend(&[Actuator::LeftTrigger, Actuator::RightTrigger]);
+                        }
+                        let test = RunningTest {
+                            started: self.tick,
+                            actuators,
+                            succeeded: Vec::new(),
+                            failed: Vec::new(),
+                            result,
+                        };
+                        if self.muted {
+                            test.finish();
+                        } else if let Some(previous) = device.test.replace(test) {
+                            previous.finish();
+                        }
+                    } else {
+                        error!("TestActuators with wrong ID {}", id);
+                    }
+                }
+                Message::SetMixMode { mode } => self.mix_mode = mode,
+                Message::SetMuted { muted } => {
+                    if muted && !self.muted {
+                        for (_, dev) in self.devices.iter_mut() {
+                            if let Some(test) = dev.test.take() {
+                                test.finish();
+                            }
+                            let _ = dev.inner.set_ff_state(0, 0, Duration::from_secs(0));
+                        }
+                    }
+                    self.muted = muted;
+                }
+                Message::StopAll => self.stop_all(),
+            }
+        }
+
+        self.expire_disconnected();
+
+        if !self.muted {
+            let playing: Vec<_> = self
+                .effects
+                .iter()
+                .filter(|(_, effect)| effect.is_playing())
+                .map(|(id, _)| id)
+                .collect();
+
+            combine_and_play(
+                &mut self.effects,
+                &mut self.devices,
+                self.tick,
+                self.mix_mode,
+            );
+            self.record_latency();
+
+            // Effects are only stopped by `Message::Stop` or when their `Repeat::For` elapses.
+            for id in playing {
+                let effect = &self.effects[id];
+                if effect.is_playing() {
+                    continue;
+                }
+                let devices = self.devices.keys().chain(self.disconnected.keys());
+                for dev_id in devices {
+                    if effect.devices.contains_key(dev_id) {
+                        let _ = self.completed.send((dev_id, id));
+                    }
+                }
+            }
+        }
+        self.tick.inc();
+        self.diagnostics.lock().unwrap().ticks += 1;
+    }
+
+    /// Records latency of effects that wrote motor state for the first time in this tick.
+    fn record_latency(&mut self) {
+        let now = utils::time_now();
+        let mut done = Vec::new();
+        for (id, &time) in self.pending_latency.iter() {
+            let effect = match self.effects.get(id) {
+                Some(effect) if effect.is_playing() => effect,
+                _ => {
+                    done.push(id);
+                    continue;
+                }
+            };
+            let written = self
+                .devices
+                .iter()
+                .any(|(dev_id, dev)| dev.is_playing && effect.devices.contains_key(dev_id));
+            if written {
+                let latency = now.duration_since(time).unwrap_or_default();
+                self.diagnostics.lock().unwrap().record_latency(latency);
+                done.push(id);
+            }
+        }
+
+        for id in done {
+            self.pending_latency.remove(id);
+        }
+    }
+
+    fn record_missed_ticks(&self, missed: u64) {
+        self.diagnostics.lock().unwrap().missed_ticks += missed;
+    }
+
+    fn stop_all(&mut self) {
+        for (_, effect) in self.effects.iter_mut() {
+            effect.source.state = EffectState::Stopped;
+        }
+        self.pending_latency.clear();
+
+        for (_, dev) in self.devices.iter_mut() {
+            dev.rumble = None;
+            if let Some(test) = dev.test.take() {
+                test.finish();
             }
+            dev.is_playing = false;
+            if dev.inner.has_trigger_motors() {
+                let _ = dev.inner.set_trigger_state(0, 0, Duration::from_secs(0));
+            }
+            let _ = dev.inner.set_ff_state(0, 0, Duration::from_secs(0));
+        }
+        for (_, state) in self.disconnected.iter_mut() {
+            state.rumble = None;
         }
+    }
 
-        combine_and_play(&mut effects, &mut devices, tick);
+    /// Forgets devices that didn't reconnect within grace period. Effects playing on them are
+    /// reported as completed.
+    fn expire_disconnected(&mut self) {
+        let grace = Ticks::from_ms(RECONNECT_GRACE_MS);
+        let tick = self.tick;
+        let expired: Vec<_> = self
+            .disconnected
+            .iter()
+            .filter(|(_, state)| tick - state.since >= grace)
+            .map(|(id, _)| id)
+            .collect();
+
+        for id in expired {
+            self.disconnected.remove(id);
+            for (effect_id, effect) in self.effects.iter() {
+                if effect.is_playing() && effect.devices.contains_key(id) {
+                    // Fails only if `Gilrs` was dropped.
+                    let _ = self.completed.send((id, effect_id));
+                }
+            }
+        }
+    }
+
+    /// Calls `update()` once for every tick that elapsed since the last call.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) fn poll(&mut self) {
+        let tick_dur = Duration::from_millis(tick_duration().into());
+        let now = utils::time_now();
+        let mut updates = 0;
+
+        while now
+            .duration_since(self.last_update)
+            .map(|dur| dur >= tick_dur)
+            .unwrap_or(false)
+        {
+            self.update();
+            self.last_update += tick_dur;
+            updates += 1;
+        }
+
+        // Only one tick is played on time, others are catching up.
+        if updates > 1 {
+            self.record_missed_ticks(updates - 1);
+        }
+    }
+}
+
+impl Debug for FfServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FfServer")
+            .field("devices", &self.devices)
+            .field("tick", &self.tick)
+            .field("muted", &self.muted)
+            .finish()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run(rx: Receiver<Message>, completed: Sender<Completed>, diagnostics: SharedDiagnostics) {
+    let mut server = FfServer::new(rx, completed, diagnostics);
+
+    loop {
+        let sleep_dur = Duration::from_millis(tick_duration().into());
+        let t1 = Instant::now();
+        // Effects and devices may be left in inconsistent state, but it's still better than
+        // losing force feedback for all gamepads until application restarts.
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| server.update())) {
+            error!(
+                "Force feedback server panicked: {}",
+                utils::panic_message(&*payload)
+            );
+        }
 
         let dur = Instant::now().duration_since(t1);
         if dur > sleep_dur {
             // TODO: Should we add dur - sleep_dur to next iteration's dur?
             warn!(
                 "One iteration of a force feedback loop took more than {}ms!",
-                TICK_DURATION
+                sleep_dur.as_millis()
             );
+            server.record_missed_ticks((dur.as_nanos() / sleep_dur.as_nanos()) as u64);
         } else {
             thread::sleep(sleep_dur - dur);
         }
-        tick.inc();
     }
 }
 
-pub(crate) fn init() -> Sender<Message> {
-    let (tx, _rx) = mpsc::channel();
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn init(
+    diagnostics: SharedDiagnostics,
+) -> io::Result<(Sender<Message>, Receiver<Completed>)> {
+    let (tx, rx) = mpsc::channel();
+    let (completed_tx, completed_rx) = mpsc::channel();
+    thread::Builder::new()
+        .name("gilrs-ff".to_owned())
+        .spawn(move || run(rx, completed_tx, diagnostics))?;
+
+    Ok((tx, completed_rx))
+}
+
+// Wasm doesn't support threads, so server has to be updated by `Gilrs`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn init(
+    diagnostics: SharedDiagnostics,
+) -> (Sender<Message>, FfServer, Receiver<Completed>) {
+    let (tx, rx) = mpsc::channel();
+    let (completed_tx, completed_rx) = mpsc::channel();
 
-    // Wasm doesn't support threads and force feedback
-    #[cfg(not(target_arch = "wasm32"))]
-    thread::spawn(move || run(_rx));
+    let server = FfServer::new(rx, completed_tx, diagnostics);
 
-    tx
+    (tx, server, completed_rx)
 }
 
-fn combine_and_play(effects: &mut VecMap<Effect>, devices: &mut VecMap<Device>, tick: Ticks) {
+fn combine_and_play(
+    effects: &mut VecMap<Effect>,
+    devices: &mut VecMap<Device>,
+    tick: Ticks,
+    mode: MixMode,
+) {
     for (dev_id, dev) in devices {
         let mut magnitude = Magnitude::zero();
         for (_, ref mut effect) in effects.iter_mut() {
-            if effect.devices.contains_key(dev_id) {
-                magnitude += effect.combine_base_effects(tick, dev.position);
+            if let Some(&gain) = effect.devices.get(dev_id) {
+                let effect = effect.combine_base_effects(tick, dev.position) * gain;
+                magnitude = magnitude.mix(effect, mode);
             }
         }
+        if let Some((rumble, end)) = dev.rumble {
+            if tick < end {
+                magnitude = magnitude.mix(rumble, mode);
+            } else {
+                dev.rumble = None;
+            }
+        }
+        let mut pulsed = None;
+        let step = dev
+            .test
+            .as_ref()
+            .map(|test| actuator_test_step(tick - test.started, &test.actuators));
+        match step {
+            Some(Some(actuator)) => {
+                magnitude = actuator.map_or_else(Magnitude::zero, actuator_test_magnitude);
+                pulsed = actuator;
+            }
+            Some(None) => {
+                if let Some(test) = dev.test.take() {
+                    test.finish();
+                }
+            }
+            None => (),
+        }
+
+        // Don't keep writing zero magnitude to idle devices, so other `Gilrs` instances
+        // in the same process can play effects on them.
+        let is_playing = !magnitude.is_zero();
+        if !is_playing && !dev.is_playing {
+            continue;
+        }
+        dev.is_playing = is_playing;
+
+        let min_duration = Duration::from_millis(u64::from(tick_duration()) * 2);
+        let triggers = if dev.inner.has_trigger_motors() {
+            dev.inner.set_trigger_state(
+                magnitude.left_trigger,
+                magnitude.right_trigger,
+                min_duration,
+            )
+        } else {
+            magnitude = magnitude.fold_triggers();
+            Ok(())
+        };
+
         trace!(
             "({:?}) Setting ff state of {:?} to {:?}",
             tick,
             dev,
             magnitude
         );
-        dev.inner.set_ff_state(
-            magnitude.strong,
-            magnitude.weak,
-            Duration::from_millis(u64::from(TICK_DURATION) * 2),
+        let rumble = dev
+            .inner
+            .set_ff_state(magnitude.strong, magnitude.weak, min_duration);
+
+        if let (Some(actuator), Some(test)) = (pulsed, dev.test.as_mut()) {
+            let ok = match actuator {
+                Actuator::Strong | Actuator::Weak => rumble.is_ok(),
+                Actuator::LeftTrigger | Actuator::RightTrigger => triggers.is_ok(),
+            };
+            test.record(actuator, ok);
+        }
+    }
+}
+
+/// Returns actuator pulsed by actuator test `elapsed` ticks after it started, `Some(None)` during
+/// pause between pulses or `None` if test has ended.
+fn actuator_test_step(elapsed: Ticks, actuators: &[Actuator]) -> Option<Option<Actuator>> {
+    let pulse = Ticks::from_ms(ACTUATOR_TEST_PULSE_MS).0.max(1);
+    let step = (elapsed.0 / pulse) as usize;
+
+    if step + 1 >= actuators.len() * 2 {
+        None
+    } else if step % 2 == 1 {
+        Some(None)
+    } else {
+        Some(Some(actuators[step / 2]))
+    }
+}
+
+/// Returns magnitude that pulses `actuator` at full strength.
+fn actuator_test_magnitude(actuator: Actuator) -> Magnitude {
+    let mut magnitude = Magnitude::zero();
+    match actuator {
+        Actuator::Strong => magnitude.strong = u16::MAX,
+        Actuator::Weak => magnitude.weak = u16::MAX,
+        Actuator::LeftTrigger => magnitude.left_trigger = u16::MAX,
+        Actuator::RightTrigger => magnitude.right_trigger = u16::MAX,
+    }
+    magnitude
+}
+
+/// Duration of each pulse and pause between them in actuator test.
+const ACTUATOR_TEST_PULSE_MS: u32 = 400;
+
+/// How long state of disconnected device is kept. Bluetooth gamepads often reconnect within a
+/// second.
+const RECONNECT_GRACE_MS: u32 = 3000;
+
+#[cfg(test)]
+mod tests {
+    use super::{FfServer, Message};
+    use crate::ff::base_effect::{BaseEffect, BaseEffectType};
+    use crate::ff::effect_source::{DistanceModel, EffectSource};
+    use crate::ff::time::{Repeat, Ticks};
+    use crate::ff::Actuator;
+    use crate::utils;
+    use gilrs_core::{BackendFfDevice, FfDevice};
+
+    use std::io;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use std::u16;
+
+    use vec_map::VecMap;
+
+    #[derive(Debug)]
+    struct Dummy;
+
+    impl BackendFfDevice for Dummy {
+        fn set_ff_state(
+            &mut self,
+            _strong: u16,
+            _weak: u16,
+            _min_duration: Duration,
+        ) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Has trigger motors, but its rumble motors can't be written.
+    #[derive(Debug)]
+    struct BrokenRumble;
+
+    impl BackendFfDevice for BrokenRumble {
+        fn set_ff_state(
+            &mut self,
+            _strong: u16,
+            _weak: u16,
+            _min_duration: Duration,
+        ) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "rumble failed"))
+        }
+
+        fn has_trigger_motors(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn completed() {
+        let (tx, rx) = mpsc::channel();
+        let (completed_tx, completed) = mpsc::channel();
+        let mut server = FfServer::new(rx, completed_tx, Default::default());
+
+        let mut devices = VecMap::new();
+        devices.insert(1, 1.0);
+        devices.insert(2, 1.0);
+        let effect = EffectSource::new(
+            vec![BaseEffect::default()],
+            devices,
+            Repeat::For(Ticks(2)),
+            DistanceModel::None,
+            [0.0; 3],
+            1.0,
+        );
+        for &id in &[1, 2] {
+            let device = FfDevice::new(Dummy);
+            tx.send(Message::Open { id, device }).unwrap();
+        }
+        tx.send(Message::Create {
+            id: 7,
+            effect: Box::new(effect),
+        })
+        .unwrap();
+        tx.send(Message::Play {
+            id: 7,
+            time: utils::time_now(),
+        })
+        .unwrap();
+        server.update();
+        assert!(completed.try_recv().is_err());
+
+        // Effect completes on both devices, including disconnected one within grace period.
+        tx.send(Message::Close { id: 2 }).unwrap();
+        server.update();
+        assert!(completed.try_recv().is_err());
+
+        server.update();
+        server.update();
+        assert_eq!(completed.try_recv(), Ok((1, 7)));
+        assert_eq!(completed.try_recv(), Ok((2, 7)));
+        assert!(completed.try_recv().is_err());
+    }
+
+    #[test]
+    fn reconnect() {
+        let (tx, rx) = mpsc::channel();
+        let (completed_tx, completed) = mpsc::channel();
+        let mut server = FfServer::new(rx, completed_tx, Default::default());
+
+        let mut devices = VecMap::new();
+        devices.insert(0, 1.0);
+        let effect = EffectSource::new(
+            vec![BaseEffect::default()],
+            devices,
+            Repeat::Infinitely,
+            DistanceModel::None,
+            [0.0; 3],
+            1.0,
+        );
+        tx.send(Message::Open {
+            id: 0,
+            device: FfDevice::new(Dummy),
+        })
+        .unwrap();
+        tx.send(Message::SetListenerPosition {
+            id: 0,
+            position: [1.0, 2.0, 3.0],
+        })
+        .unwrap();
+        tx.send(Message::Create {
+            id: 0,
+            effect: Box::new(effect),
+        })
+        .unwrap();
+        tx.send(Message::Play {
+            id: 0,
+            time: utils::time_now(),
+        })
+        .unwrap();
+        tx.send(Message::Close { id: 0 }).unwrap();
+        server.update();
+
+        // Reconnected within grace period.
+        tx.send(Message::Open {
+            id: 0,
+            device: FfDevice::new(Dummy),
+        })
+        .unwrap();
+        server.update();
+        assert_eq!(server.devices[0].position, [1.0, 2.0, 3.0]);
+        assert!(completed.try_recv().is_err());
+
+        tx.send(Message::Close { id: 0 }).unwrap();
+        for _ in 0..=Ticks::from_ms(super::RECONNECT_GRACE_MS).0 {
+            server.update();
+        }
+        assert_eq!(completed.try_recv(), Ok((0, 0)));
+        assert!(server.disconnected.is_empty());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let (tx, rx) = mpsc::channel();
+        let (completed_tx, _completed) = mpsc::channel();
+        let mut server = FfServer::new(rx, completed_tx, Default::default());
+
+        let mut devices = VecMap::new();
+        devices.insert(0, 1.0);
+        let base = BaseEffect {
+            kind: BaseEffectType::Strong {
+                magnitude: u16::MAX,
+            },
+            ..Default::default()
+        };
+        let effect = EffectSource::new(
+            vec![base],
+            devices,
+            Repeat::Infinitely,
+            DistanceModel::None,
+            [0.0; 3],
+            1.0,
+        );
+        tx.send(Message::Open {
+            id: 0,
+            device: FfDevice::new(Dummy),
+        })
+        .unwrap();
+        tx.send(Message::Create {
+            id: 0,
+            effect: Box::new(effect),
+        })
+        .unwrap();
+        tx.send(Message::Play {
+            id: 0,
+            time: utils::time_now() - Duration::from_millis(10),
+        })
+        .unwrap();
+        server.update();
+        server.update();
+
+        let diagnostics = *server.diagnostics.lock().unwrap();
+        assert_eq!(diagnostics.ticks(), 2);
+        assert_eq!(diagnostics.missed_ticks(), 0);
+        // Latency is recorded only for the first write.
+        assert!(diagnostics.max_latency() >= Duration::from_millis(10));
+        assert_eq!(diagnostics.average_latency(), diagnostics.max_latency());
+
+        tx.send(Message::StopAll).unwrap();
+        server.update();
+        assert!(!server.effects[0].is_playing());
+        assert!(!server.devices[0].is_playing);
+    }
+
+    #[test]
+    fn test_actuators() {
+        let (tx, rx) = mpsc::channel();
+        let (completed_tx, _completed) = mpsc::channel();
+        let mut server = FfServer::new(rx, completed_tx, Default::default());
+
+        tx.send(Message::Open {
+            id: 0,
+            device: FfDevice::new(Dummy),
+        })
+        .unwrap();
+        tx.send(Message::Open {
+            id: 1,
+            device: FfDevice::new(BrokenRumble),
+        })
+        .unwrap();
+        let (rumble_tx, rumble) = mpsc::channel();
+        tx.send(Message::TestActuators {
+            id: 0,
+            result: rumble_tx,
+        })
+        .unwrap();
+        let (triggers_tx, triggers) = mpsc::channel();
+        tx.send(Message::TestActuators {
+            id: 1,
+            result: triggers_tx,
+        })
+        .unwrap();
+
+        // Two pulses and one pause.
+        let pulse = Ticks::from_ms(super::ACTUATOR_TEST_PULSE_MS).0;
+        for _ in 0..=pulse * 3 {
+            server.update();
+        }
+        assert_eq!(
+            rumble.try_recv(),
+            Ok(vec![Actuator::Strong, Actuator::Weak])
         );
+        assert!(triggers.try_recv().is_err());
+
+        for _ in 0..pulse * 4 {
+            server.update();
+        }
+        assert_eq!(
+            triggers.try_recv(),
+            Ok(vec![Actuator::LeftTrigger, Actuator::RightTrigger])
+        );
+
+        // Interrupted test reports only actuators pulsed until then.
+        let (tx_stopped, stopped) = mpsc::channel();
+        tx.send(Message::TestActuators {
+            id: 0,
+            result: tx_stopped,
+        })
+        .unwrap();
+        server.update();
+        tx.send(Message::StopAll).unwrap();
+        server.update();
+        assert_eq!(stopped.try_recv(), Ok(vec![Actuator::Strong]));
     }
 }
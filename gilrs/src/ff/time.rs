@@ -6,17 +6,33 @@
 // copied, modified, or distributed except according to those terms.
 
 use std::ops::{Add, AddAssign, Mul, MulAssign, Rem, Sub, SubAssign};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
 
 use crate::utils;
 
-pub(crate) const TICK_DURATION: u32 = 50;
+/// Default duration of one tick in milliseconds.
+pub(crate) const DEFAULT_TICK_DURATION: u32 = 50;
+/// Longest tick that can be set with `GilrsBuilder::set_ff_tick_duration()`.
+pub(crate) const MAX_TICK_DURATION: u32 = 50;
+
+// Global, because `Ticks` are created without access to `Gilrs`.
+static TICK_DURATION: AtomicU32 = AtomicU32::new(DEFAULT_TICK_DURATION);
+
+/// Returns duration of one tick in milliseconds.
+pub(crate) fn tick_duration() -> u32 {
+    TICK_DURATION.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_tick_duration(ms: u32) {
+    TICK_DURATION.store(ms, Ordering::Relaxed);
+}
 
 /// Represents duration.
 ///
 /// This type is only useful as input parameter for other functions in force feedback module. To
 /// create it, use `from_ms()` method. Keep in mind that `Ticks` **is not precise** representation
-/// of time.
+/// of time. One tick lasts 50 ms, unless changed by `GilrsBuilder::set_ff_tick_duration()`.
 ///
 /// # Example
 ///
@@ -35,7 +51,7 @@ pub struct Ticks(pub(super) u32);
 
 impl Ticks {
     pub fn from_ms(dur: u32) -> Self {
-        Ticks(utils::ceil_div(dur, TICK_DURATION))
+        Ticks(utils::ceil_div(dur, tick_duration()))
     }
 
     pub(super) fn inc(&mut self) {
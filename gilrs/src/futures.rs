@@ -0,0 +1,161 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Asynchronous stream of events.
+//!
+//! This module is available with `futures` feature. [`EventStream`](struct.EventStream.html)
+//! implements `futures_core::Stream`, so it can be used with any executor.
+//!
+//! ```no_run
+//! use gilrs::futures::EventStream;
+//! use gilrs::GilrsBuilder;
+//!
+//! let mut events = EventStream::new(GilrsBuilder::new()).unwrap();
+//! // In async function, using `StreamExt` from `futures` crate:
+//! // while let Some(ev) = events.next().await {
+//! //     println!("{:?}", ev);
+//! // }
+//! ```
+
+use crate::{Error, Event, GilrsBuilder};
+
+use futures_core::Stream;
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+/// How long event thread waits for events before checking if stream was dropped.
+const WAIT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Default)]
+struct Shared {
+    waker: Mutex<Option<Waker>>,
+    closed: AtomicBool,
+}
+
+impl Shared {
+    fn wake(&self) {
+        let waker = match self.waker.lock() {
+            Ok(mut waker) => waker.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Stream of events from all gamepads.
+///
+/// `Gilrs` created from builder lives on separate thread that waits for events and wakes the
+/// task when they arrive, so cached gamepad state is not available. Events are processed as by
+/// `Gilrs::next_event()`, including default filters if they are enabled.
+///
+/// Stream ends when platform backend stops working. On platforms that are not supported it ends
+/// immediately.
+#[derive(Debug)]
+pub struct EventStream {
+    rx: Receiver<Event>,
+    shared: Arc<Shared>,
+}
+
+impl EventStream {
+    /// Creates `Gilrs` from `builder` on new thread and returns stream of its events.
+    ///
+    /// Errors from `GilrsBuilder::build()` are returned as `Error::Other` (except
+    /// `Error::NotImplemented`, see above).
+    // `Error` is big only because of `NotImplemented`, which is never returned here.
+    #[allow(clippy::result_large_err)]
+    pub fn new(builder: GilrsBuilder) -> Result<Self, Error> {
+        let (tx, rx) = mpsc::channel();
+        let (init_tx, init_rx) = mpsc::channel();
+        let shared = Arc::new(Shared::default());
+        let thread_shared = shared.clone();
+
+        thread::Builder::new()
+            .name("gilrs-stream".to_owned())
+            .spawn(move || {
+                let mut gilrs = match builder.build() {
+                    Ok(gilrs) => gilrs,
+                    Err(Error::NotImplemented(_)) => {
+                        let _ = init_tx.send(Ok(()));
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = init_tx.send(Err(e.to_string()));
+                        return;
+                    }
+                };
+                let _ = init_tx.send(Ok(()));
+
+                while !thread_shared.closed.load(Ordering::SeqCst) {
+                    let ev = match gilrs.next_event_blocking(Some(WAIT_TIMEOUT)) {
+                        Some(ev) => ev,
+                        None => match gilrs.try_next_event() {
+                            Ok(Some(ev)) => ev,
+                            Ok(None) => continue,
+                            Err(_) => break,
+                        },
+                    };
+
+                    if tx.send(ev).is_err() {
+                        break;
+                    }
+                    thread_shared.wake();
+                }
+
+                // Disconnect channel before waking, so stream sees its end.
+                drop(tx);
+                thread_shared.wake();
+            })
+            .map_err(|e| Error::Other(Box::new(e)))?;
+
+        match init_rx.recv() {
+            Ok(Ok(())) => Ok(EventStream { rx, shared }),
+            Ok(Err(msg)) => Err(Error::Other(msg.into())),
+            Err(_) => Err(Error::Other("event thread exited unexpectedly".into())),
+        }
+    }
+
+    fn try_recv(&self) -> Option<Poll<Option<Event>>> {
+        match self.rx.try_recv() {
+            Ok(ev) => Some(Poll::Ready(Some(ev))),
+            Err(TryRecvError::Disconnected) => Some(Poll::Ready(None)),
+            Err(TryRecvError::Empty) => None,
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        if let Some(poll) = self.try_recv() {
+            return poll;
+        }
+
+        match self.shared.waker.lock() {
+            Ok(mut waker) => *waker = Some(cx.waker().clone()),
+            Err(poisoned) => *poisoned.into_inner() = Some(cx.waker().clone()),
+        }
+
+        // Event could be sent before waker was stored.
+        self.try_recv().unwrap_or(Poll::Pending)
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::SeqCst);
+    }
+}
@@ -7,31 +7,41 @@
 
 use crate::{
     ev::{
-        state::{AxisData, ButtonData, GamepadState},
-        Axis, AxisOrBtn, Button, Code, Event, EventType,
+        state::{AxisData, AxisHistoryIter, ButtonData, GamepadState, TriggerData},
+        Axis, AxisOrBtn, Button, Code, Event, EventType, FusionConfig, GestureConfig,
+        GestureRecognizer, Orientation, SensorFusion,
     },
     ff::{
+        self,
         server::{self, Message},
-        Error as FfError,
+        ActuatorTest, EffectId, Error as FfError, MixMode, Ticks,
     },
-    mapping::{Mapping, MappingData, MappingDb},
+    mapping::{Mapping, MappingData, MappingDb, MappingOrigin, ResolvedMapping},
+    quirks::{Quirk, QuirkDb, QuirksError, QuirksFile},
     utils, MappingError,
 };
 
+pub use gilrs_core::BackendErrorKind;
 use gilrs_core::{
-    self, AxisInfo, Error as PlatformError, Event as RawEvent, EventType as RawEventType,
+    self, AxisInfo, Backend, DummyBackend, Error as PlatformError, Event as RawEvent,
+    EventType as RawEventType,
 };
 
 use uuid::Uuid;
 
+use fnv::FnvHashMap;
+
 use std::{
     collections::VecDeque,
-    error,
+    env, error,
     fmt::{self, Display},
-    sync::mpsc::Sender,
+    io, mem,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant, SystemTime},
 };
 
-pub use gilrs_core::PowerInfo;
+pub use gilrs_core::{BackendInfo, MotionData, PollMode, PowerInfo, RawWaitHandle, TouchData};
 
 #[cfg(feature = "serde-serialize")]
 use serde::{Deserialize, Serialize};
@@ -126,12 +136,25 @@ const DEFAULT_DEADZONE: f32 = 0.1;
 ///     gilrs.inc();
 /// #   break;
 /// }
+/// ```
+///
+/// # Multiple instances
+///
+/// It's possible to create more than one `Gilrs` in the same process, for example when plugin uses
+/// its own instance. Every instance has its own backend, so each of them receives all events and
+/// keeps its own gamepad IDs, mappings and state. Events consumed by one instance are not removed
+/// from others.
 ///
+/// Force feedback effects from all instances are sent to the same devices. Instance that doesn't
+/// play anything doesn't touch gamepad's motors, but if two instances play effects on the same
+/// gamepad at once, on Windows only effect of one of them will be felt.
 #[derive(Debug)]
 pub struct Gilrs {
     inner: gilrs_core::Gilrs,
     next_id: usize,
     tx: Sender<Message>,
+    ff_completed: Receiver<server::Completed>,
+    ff_diagnostics: server::SharedDiagnostics,
     counter: u64,
     mappings: MappingDb,
     default_filters: bool,
@@ -139,7 +162,30 @@ pub struct Gilrs {
     axis_to_btn_pressed: f32,
     axis_to_btn_released: f32,
     update_state: bool,
+    axis_history_len: usize,
+    control_timeout: Option<Duration>,
+    zero_axes_on_control_lost: bool,
+    nintendo_layout: NintendoLayout,
+    ff_enabled: bool,
+    adaptive_jitter: bool,
+    deadzone: Option<f32>,
+    dpad_axis_events: bool,
+    touch_gestures: Option<GestureConfig>,
+    motion_fusion: Option<FusionConfig>,
+    // Events created from already filtered events, returned without filtering.
+    translated_events: VecDeque<Event>,
+    // Sequence number of next returned event.
+    next_seq: u64,
+    // Events skipped by `next_event_for()`. They are already processed and are returned first.
+    deferred_events: VecDeque<Event>,
+    listener: Option<Listener>,
     gamepads_data: Vec<GamepadData>,
+    metrics: Metrics,
+    suspended_at: Option<SystemTime>,
+    quirks: QuirkDb,
+    quirks_file: Option<QuirksFile>,
+    #[cfg(target_arch = "wasm32")]
+    ff_server: server::FfServer,
 }
 
 impl Gilrs {
@@ -153,20 +199,29 @@ impl Gilrs {
     /// returned. This function will not block current thread and should be safe
     /// to call in async context.
     pub fn next_event(&mut self) -> Option<Event> {
-        use crate::ev::filter::{axis_dpad_to_button, deadzone, Filter, Jitter};
+        use crate::ev::filter::{axis_dpad_to_button, deadzone, AdaptiveJitter, Filter, Jitter};
 
-        let ev = if self.default_filters {
+        if let Some(ev) = self.deferred_events.pop_front() {
+            return Some(ev);
+        }
+
+        let ev = if let Some(ev) = self.translated_events.pop_front() {
+            Some(ev)
+        } else if self.default_filters {
             let jitter_filter = Jitter::new();
+            let adaptive_jitter_filter = AdaptiveJitter::new();
             loop {
-                let ev = self
-                    .next_event_priv()
-                    .filter_ev(&axis_dpad_to_button, self)
-                    .filter_ev(&jitter_filter, self)
-                    .filter_ev(&deadzone, self);
+                let ev = self.next_event_priv().filter_ev(&axis_dpad_to_button, self);
+                let ev = if self.adaptive_jitter {
+                    ev.filter_ev(&adaptive_jitter_filter, self)
+                } else {
+                    ev.filter_ev(&jitter_filter, self)
+                };
+                let ev = ev.filter_ev(&deadzone, self);
 
                 // Skip all dropped events, there is no reason to return them
                 match ev {
-                    Some(ev) if ev.is_dropped() => (),
+                    Some(ev) if ev.is_dropped() => self.metrics.events_filtered += 1,
                     _ => break ev,
                 }
             }
@@ -174,43 +229,684 @@ impl Gilrs {
             self.next_event_priv()
         };
 
+        let ev = ev.map(|mut ev| {
+            ev.seq = self.next_seq;
+            self.next_seq += 1;
+            ev
+        });
+
         if self.update_state {
             if let Some(ref ev) = ev {
                 self.update(ev);
             }
         }
 
+        if self.dpad_axis_events {
+            if let Some(ref ev) = ev {
+                self.translate_dpad(ev);
+            }
+        }
+
+        if let (Some(config), Some(ev)) = (self.touch_gestures, ev.as_ref()) {
+            self.recognize_gesture(ev, &config);
+        }
+
+        if let (Some(config), Some(ev)) = (self.motion_fusion, ev.as_ref()) {
+            if let EventType::Motion(motion) = ev.event {
+                if let Some(data) = self.gamepads_data.get_mut(ev.id.0) {
+                    data.fusion.update(motion, ev.time, &config);
+                }
+            }
+        }
+
+        if let (Some(listener), Some(ev)) = (self.listener.as_mut(), ev.as_ref()) {
+            (listener.0)(ev);
+        }
+
+        if ev.is_some() {
+            self.metrics.events_delivered += 1;
+        }
+
+        ev
+    }
+
+    /// Queues `DPadX` or `DPadY` axis event for d-pad button event if gamepad doesn't report
+    /// d-pad both as buttons and axes.
+    fn translate_dpad(&mut self, ev: &Event) {
+        use gilrs_core::native_ev_codes as necs;
+
+        let (btn, pressed) = match ev.event {
+            EventType::ButtonPressed(btn, _) => (btn, true),
+            EventType::ButtonReleased(btn, _) => (btn, false),
+            _ => return,
+        };
+        let (axis, positive, negative, default_code) = match btn {
+            Button::DPadRight | Button::DPadLeft => (
+                Axis::DPadX,
+                Button::DPadRight,
+                Button::DPadLeft,
+                necs::AXIS_DPADX,
+            ),
+            Button::DPadUp | Button::DPadDown => (
+                Axis::DPadY,
+                Button::DPadUp,
+                Button::DPadDown,
+                necs::AXIS_DPADY,
+            ),
+            _ => return,
+        };
+
+        let gamepad = self.gamepad(ev.id);
+        let code = gamepad.axis_code(axis);
+        if code.is_some() && gamepad.button_code(positive).is_some() {
+            return;
+        }
+
+        let value = if pressed {
+            if btn == positive {
+                1.0
+            } else {
+                -1.0
+            }
+        } else {
+            // Other direction may still be held.
+            let other = if btn == positive { negative } else { positive };
+            if !gamepad.is_pressed(other) {
+                0.0
+            } else if other == positive {
+                1.0
+            } else {
+                -1.0
+            }
+        };
+
+        self.translated_events.push_back(Event {
+            id: ev.id,
+            time: ev.time,
+            seq: 0,
+            event: EventType::AxisChanged(axis, value, code.unwrap_or(Code(default_code))),
+        });
+    }
+
+    /// Queues `Gesture` event if touch event completed one.
+    fn recognize_gesture(&mut self, ev: &Event, config: &GestureConfig) {
+        let touch = match ev.event {
+            EventType::Touch(touch) => touch,
+            _ => return,
+        };
+        let gesture = match self.gamepads_data.get_mut(ev.id.0) {
+            Some(data) => data.gestures.update(touch, ev.time, config),
+            None => return,
+        };
+
+        if let Some(gesture) = gesture {
+            self.translated_events.push_back(Event {
+                id: ev.id,
+                time: ev.time,
+                seq: 0,
+                event: EventType::Gesture(gesture),
+            });
+        }
+    }
+
+    /// Like [`next_event()`](#method.next_event), but if there is no pending event, blocks current
+    /// thread until one arrives or `timeout` passes. `None` means no timeout.
+    ///
+    /// Returns immediately if `Gilrs` is suspended or platform backend died. On wasm this function
+    /// never blocks and behaves like `next_event()`.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// loop {
+    ///     match gilrs.next_event_blocking(Some(Duration::from_secs(1))) {
+    ///         Some(ev) => println!("{:?}", ev),
+    ///         None => println!("No input for a second"),
+    ///     }
+    /// }
+    /// ```
+    pub fn next_event_blocking(&mut self, timeout: Option<Duration>) -> Option<Event> {
+        // Can't block on wasm. Return early, because `Instant` is not supported there either.
+        if cfg!(target_arch = "wasm32") {
+            return self.next_event();
+        }
+
+        // `Instant`, unlike system time, is not affected by changes of the clock.
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            if let Some(ev) = self.next_event() {
+                return Some(ev);
+            }
+
+            if self.is_suspended() || !self.inner.is_alive() {
+                return None;
+            }
+
+            let remaining = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(remaining) if remaining > Duration::new(0, 0) => Some(remaining),
+                    _ => return None,
+                },
+                None => None,
+            };
+            // Wake up in time to report `ControlLost`.
+            let wait = match (remaining, self.control_timeout) {
+                (Some(r), Some(c)) => Some(r.min(c)),
+                (r, c) => r.or(c),
+            };
+
+            self.inner.wait(wait);
+        }
+    }
+
+    /// Returns platform handle that becomes signaled when backend has new events, for waiting on
+    /// `Gilrs` together with other event sources (`poll()` on the file descriptor on Linux,
+    /// `WaitForMultipleObjects()` on the event object on Windows). The handle is owned by `Gilrs`.
+    ///
+    /// Only events that backend didn't report yet signal the handle. Call
+    /// [`next_event()`](#method.next_event) until it returns `None` before waiting, and keep in
+    /// mind that `ControlLost` events and [`Repeat`](ev/filter/struct.Repeat.html) filter need
+    /// periodic calls anyway. Returns `None` on macOS, wasm, unsupported platforms and when HID
+    /// drivers (`hidapi` feature) are used.
+    pub fn raw_wait_handle(&self) -> Option<RawWaitHandle> {
+        self.inner.raw_wait_handle()
+    }
+
+    /// Passes `AInputEvent` that application received from its input queue to gilrs. Android
+    /// doesn't let libraries read input events themselves, so all events have to be forwarded
+    /// this way. Returns `true` if event came from gamepad and was consumed, `false` if
+    /// application should handle it itself. Resulting events are returned by
+    /// [`next_event()`](#method.next_event).
+    ///
+    /// # Safety
+    ///
+    /// `event` must be null or point to valid `AInputEvent`.
+    #[cfg(target_os = "android")]
+    pub unsafe fn handle_android_input_event(
+        &mut self,
+        event: *const std::os::raw::c_void,
+    ) -> bool {
+        self.inner.handle_android_input_event(event)
+    }
+
+    /// Reports gamepad added by `InputDeviceListener.onInputDeviceAdded()`. Name, vendor and
+    /// product ID should come from `InputDevice`. Calling it is optional, but gamepads that are
+    /// only connected by their first event have generic name and no mapping from SDL database.
+    #[cfg(target_os = "android")]
+    pub fn android_device_added(&mut self, device_id: i32, name: &str, vendor: u16, product: u16) {
+        self.inner
+            .android_device_added(device_id, name, vendor, product)
+    }
+
+    /// Reports gamepad removed by `InputDeviceListener.onInputDeviceRemoved()`.
+    #[cfg(target_os = "android")]
+    pub fn android_device_removed(&mut self, device_id: i32) {
+        self.inner.android_device_removed(device_id)
+    }
+
+    /// Like [`next_event()`](#method.next_event), but returns only events of gamepad with given
+    /// `id`. Events of other gamepads are kept, in order, and returned by later calls to
+    /// `next_event()` or `next_event_for()` with their id.
+    ///
+    /// Events are processed (filtered and applied to cached state) when they are taken from
+    /// backend, so state of other gamepads may already include events that are still kept.
+    pub fn next_event_for(&mut self, id: GamepadId) -> Option<Event> {
+        if let Some(pos) = self.deferred_events.iter().position(|ev| ev.id == id) {
+            return self.deferred_events.remove(pos);
+        }
+
+        // Don't return events that are already deferred from `next_event()`.
+        let deferred = mem::take(&mut self.deferred_events);
+        let mut ev = None;
+        while let Some(next) = self.next_event() {
+            if next.id == id {
+                ev = Some(next);
+                break;
+            }
+
+            self.deferred_events.push_back(next);
+        }
+
+        let mut skipped = mem::replace(&mut self.deferred_events, deferred);
+        self.deferred_events.append(&mut skipped);
+
         ev
     }
 
+    /// Sets function that is called with every event taken from backend, after cached state is
+    /// updated. Replaces previously set listener.
+    ///
+    /// Events are still read by [`next_event()`](#method.next_event), so application has to call
+    /// it (or [`pump()`](#method.pump)) regularly, for example once per frame of its own event
+    /// loop. Listener is called on the same thread.
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// gilrs.set_listener(|ev| println!("{:?}", ev));
+    ///
+    /// // In application's event loop
+    /// gilrs.pump();
+    /// ```
+    pub fn set_listener<F>(&mut self, listener: F)
+    where
+        F: FnMut(&Event) + Send + 'static,
+    {
+        self.listener = Some(Listener(Box::new(listener)));
+    }
+
+    /// Removes listener set with [`set_listener()`](#method.set_listener).
+    pub fn remove_listener(&mut self) {
+        self.listener = None;
+    }
+
+    /// Returns iterator over pending events. It calls [`next_event()`](#method.next_event) until
+    /// there are no more events.
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// for ev in gilrs.poll_events() {
+    ///     println!("{:?}", ev);
+    /// }
+    /// ```
+    pub fn poll_events(&mut self) -> EventsIterator<'_> {
+        EventsIterator(self)
+    }
+
+    /// Returns iterator over pending events of gamepad with given `id`. See
+    /// [`next_event_for()`](#method.next_event_for).
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// let players: Vec<_> = gilrs.gamepads().map(|(id, _)| id).collect();
+    ///
+    /// for (player, &id) in players.iter().enumerate() {
+    ///     for ev in gilrs.events_for(id) {
+    ///         println!("Player {}: {:?}", player, ev.event);
+    ///     }
+    /// }
+    /// ```
+    pub fn events_for(&mut self, id: GamepadId) -> GamepadEventsIterator<'_> {
+        GamepadEventsIterator { gilrs: self, id }
+    }
+
+    /// Like [`next_event()`](#method.next_event), but returns `Err(Error::BackendDied)` instead
+    /// of `None` when there will be no more events, because platform backend stopped working and
+    /// couldn't be restarted automatically.
+    ///
+    /// Events that were already queued, including `Disconnected` events for all gamepads, are
+    /// returned first. After that you can try to call [`restart()`](#method.restart) or create new
+    /// `Gilrs`.
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// loop {
+    ///     match gilrs.try_next_event() {
+    ///         Ok(Some(event)) => println!("{:?}", event),
+    ///         Ok(None) => break,
+    ///         Err(e) => {
+    ///             eprintln!("{}, reinitializing", e);
+    ///             gilrs = gilrs::Gilrs::new().unwrap();
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    // `Error` is big only because of `NotImplemented`, which is never returned here.
+    #[allow(clippy::result_large_err)]
+    pub fn try_next_event(&mut self) -> Result<Option<Event>, Error> {
+        match self.next_event() {
+            Some(ev) => Ok(Some(ev)),
+            None if !self.inner.is_alive() => Err(Error::BackendDied),
+            None => Ok(None),
+        }
+    }
+
+    /// Drains all pending events and returns what happened since the previous call. Gamepad state
+    /// is updated as with [`next_event()`](#method.next_event), so this can replace the usual
+    /// `while let Some(ev) = gilrs.next_event()` loop in immediate-mode engines.
+    ///
+    /// Individual events are not available after calling this function. Counter is not increased,
+    /// call [`inc()`](#method.inc) afterwards if you rely on it.
+    ///
+    /// ```
+    /// use gilrs::{Button, Gilrs};
+    ///
+    /// let mut gilrs = Gilrs::new().unwrap();
+    ///
+    /// // In your update loop:
+    /// let frame = gilrs.pump();
+    /// for &id in frame.connected() {
+    ///     println!("{} connected", gilrs.gamepad(id).name());
+    /// }
+    /// for &(id, _) in frame.pressed().iter().filter(|&&(_, btn)| btn == Button::Start) {
+    ///     println!("Gamepad {} paused the game", id);
+    /// }
+    /// ```
+    pub fn pump(&mut self) -> PumpSummary {
+        let mut summary = PumpSummary::default();
+
+        while let Some(Event { id, event, .. }) = self.next_event() {
+            summary.events += 1;
+            match event {
+                EventType::ButtonPressed(btn, _) => summary.pressed.push((id, btn)),
+                EventType::ButtonReleased(btn, _) => summary.released.push((id, btn)),
+                EventType::AxisChanged(axis, ..) if !summary.axes_changed.contains(&(id, axis)) => {
+                    summary.axes_changed.push((id, axis))
+                }
+                EventType::Connected => summary.connected.push(id),
+                EventType::Disconnected => summary.disconnected.push(id),
+                _ => (),
+            }
+        }
+
+        summary
+    }
+
+    /// Returns oldest non-fatal problem reported by platform backend, or `None` if there are no new
+    /// problems.
+    ///
+    /// Backend errors don't stop `Gilrs` from working, but usually mean that some functionality,
+    /// like force feedback or battery status, is not available. Only limited number of errors is
+    /// kept, so you should call this function regularly, for example after processing events.
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// while let Some(err) = gilrs.next_backend_error() {
+    ///     eprintln!("Gamepad problem: {}", err);
+    /// }
+    /// ```
+    pub fn next_backend_error(&mut self) -> Option<BackendError> {
+        self.inner.next_backend_error().map(|err| BackendError {
+            id: err.id.map(GamepadId),
+            kind: err.kind,
+            description: err.description,
+            code: err.code,
+            time: err.time,
+        })
+    }
+
+    /// Stops platform backend, releasing threads, file descriptors and device handles that it
+    /// uses. Useful before system goes to sleep.
+    ///
+    /// Force feedback of all gamepads is stopped and `Disconnected` event is emitted for every
+    /// connected gamepad. Ids of gamepads are retained, so after [`restart()`](#method.restart)
+    /// gamepads that are still present will be reported as `Connected` with the same id. Does
+    /// nothing if backend is already stopped.
+    ///
+    /// Backend threads are also shut down when `Gilrs` is dropped, so contexts can be created and
+    /// destroyed repeatedly.
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// gilrs.stop();
+    /// // Process `Disconnected` events.
+    /// while let Some(_) = gilrs.next_event() {}
+    ///
+    /// // ... system is suspended ...
+    ///
+    /// gilrs.restart().unwrap();
+    /// ```
+    pub fn stop(&mut self) {
+        let connected: Vec<_> = self.gamepads().map(|(id, _)| id).collect();
+        for id in connected {
+            let _ = self.tx.send(Message::Close { id: id.0 });
+        }
+
+        self.inner.stop();
+    }
+
+    /// Recreates backend resources released by [`stop()`](#method.stop). Does nothing if backend
+    /// is not stopped.
+    ///
+    /// If backend could not be initialized, `Gilrs` stays stopped and you can try again later.
+    pub fn restart(&mut self) -> Result<(), Box<dyn error::Error + Send + Sync + 'static>> {
+        self.inner.restart()
+    }
+
+    /// Pauses polling gamepads and silences force feedback, for example when application is moved
+    /// to background on mobile or handheld devices. Unlike [`stop()`](#method.stop), gamepads stay
+    /// connected.
+    ///
+    /// While suspended, `next_event()` returns only events that were already queued or inserted
+    /// with `insert_event()`. Does nothing if `Gilrs` is already suspended.
+    pub fn suspend(&mut self) {
+        if self.suspended_at.is_some() {
+            return;
+        }
+
+        self.inner.suspend();
+        let _ = self.tx.send(Message::SetMuted { muted: true });
+        self.suspended_at = Some(utils::time_now());
+    }
+
+    /// Resumes polling and force feedback after [`suspend()`](#method.suspend) and returns how
+    /// long `Gilrs` was suspended.
+    ///
+    /// Cached state is re-synchronized with hardware: instead of replaying input from the time
+    /// application was suspended, `next_event()` returns events for buttons and axes whose value
+    /// changed in the meantime (on macOS events are replayed, because current state can't be
+    /// read).
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// gilrs.suspend();
+    /// assert!(gilrs.is_suspended());
+    ///
+    /// let suspended_for = gilrs.resume();
+    /// println!("Suspended for {:?}", suspended_for);
+    /// ```
+    pub fn resume(&mut self) -> Duration {
+        let since = match self.suspended_at.take() {
+            Some(since) => since,
+            None => return Duration::from_secs(0),
+        };
+
+        self.inner.resume();
+        let _ = self.tx.send(Message::SetMuted { muted: false });
+
+        let now = utils::time_now();
+        for data in &mut self.gamepads_data {
+            data.last_input = now;
+        }
+
+        now.duration_since(since).unwrap_or_default()
+    }
+
+    /// Returns `true` if `Gilrs` is suspended.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended_at.is_some()
+    }
+
+    /// Makes backend check connection state of all gamepads as soon as possible and report changes
+    /// with `Connected` and `Disconnected` events. `resume()` does this automatically.
+    ///
+    /// Only needed on Windows, where XInput slots are checked after device change notification,
+    /// which can arrive before controller is usable. Other backends are notified about changes
+    /// immediately.
+    pub fn refresh(&mut self) {
+        self.inner.refresh();
+    }
+
+    /// Returns name of platform's backend and library it loaded at runtime.
+    ///
+    /// ```
+    /// # let gilrs = gilrs::Gilrs::new().unwrap();
+    /// let info = gilrs.backend_info();
+    /// println!("Using {} ({:?})", info.name, info.library);
+    /// ```
+    pub fn backend_info(&self) -> BackendInfo {
+        self.inner.backend_info()
+    }
+
+    /// Loads quirks from TOML file (see [`Quirk`](struct.Quirk.html)) and applies them to all
+    /// gamepads. Replaces previously loaded quirks and file set by
+    /// `GilrsBuilder::set_quirks_file()`. File is watched and reloaded when it changes. Returns
+    /// number of loaded quirks.
+    pub fn load_quirks<P: Into<PathBuf>>(&mut self, path: P) -> Result<usize, QuirksError> {
+        let (file, db) = QuirksFile::load(path.into());
+        self.quirks_file = Some(file);
+        let db = db?;
+        let len = db.len();
+        self.set_quirks(db);
+
+        Ok(len)
+    }
+
+    /// Returns path of quirks file, if one was set.
+    pub fn quirks_file(&self) -> Option<&Path> {
+        self.quirks_file.as_ref().map(|file| file.path())
+    }
+
+    fn set_quirks(&mut self, db: QuirkDb) {
+        for data in &mut self.gamepads_data {
+            let quirk = db.get(data.info.vendor_id, data.info.product_id);
+            data.set_quirk(quirk);
+        }
+        self.quirks = db;
+    }
+
+    /// Activates Steam Input action set with given name for all controllers. Does nothing if
+    /// gamepads are not read through Steam Input.
+    #[cfg(all(
+        feature = "steam",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    pub fn set_steam_action_set(&mut self, name: &str) {
+        self.inner.set_steam_action_set(name);
+    }
+
+    /// Returns `true` if Steam Input backend is used instead of native one.
+    #[cfg(all(
+        feature = "steam",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    pub fn is_steam_input(&self) -> bool {
+        self.inner.is_steam_input()
+    }
+
+    /// Returns snapshot of counters describing health of event pipeline since `Gilrs` was created
+    /// or since last call to [`reset_metrics()`](#method.reset_metrics).
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// while let Some(_) = gilrs.next_event() {}
+    ///
+    /// let metrics = gilrs.metrics();
+    /// println!(
+    ///     "delivered: {}, longest poll: {:?}",
+    ///     metrics.events_delivered(),
+    ///     metrics.max_poll_duration()
+    /// );
+    /// ```
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            queue_depth: self.events.len(),
+            ..self.metrics
+        }
+    }
+
+    /// Resets all counters returned by [`metrics()`](#method.metrics) to zero.
+    pub fn reset_metrics(&mut self) {
+        self.metrics = Metrics::default();
+    }
+
+    /// Returns database of SDL mappings, including mappings set with
+    /// [`set_mapping()`](#method.set_mapping).
+    pub fn mapping_db(&self) -> &MappingDb {
+        &self.mappings
+    }
+
+    /// Returns snapshot of counters describing timing of force feedback thread since `Gilrs` was
+    /// created or since last call to [`reset_ff_diagnostics()`](#method.reset_ff_diagnostics).
+    pub fn ff_diagnostics(&self) -> ff::Diagnostics {
+        *self.ff_diagnostics.lock().unwrap()
+    }
+
+    /// Resets all counters returned by [`ff_diagnostics()`](#method.ff_diagnostics) to zero.
+    pub fn reset_ff_diagnostics(&mut self) {
+        *self.ff_diagnostics.lock().unwrap() = ff::Diagnostics::default();
+    }
+
     /// Returns next pending event.
     fn next_event_priv(&mut self) -> Option<Event> {
+        #[cfg(target_arch = "wasm32")]
+        self.ff_server.poll();
+
+        if let Some(db) = self
+            .quirks_file
+            .as_mut()
+            .and_then(|file| file.check(utils::time_now()))
+        {
+            self.set_quirks(db);
+        }
+
+        while let Ok((id, effect)) = self.ff_completed.try_recv() {
+            self.events.push_back(Event {
+                id: GamepadId(id),
+                event: EventType::ForceFeedbackEffectCompleted(EffectId(effect)),
+                time: utils::time_now(),
+                seq: 0,
+            });
+        }
+
         if let Some(ev) = self.events.pop_front() {
             Some(ev)
         } else {
-            match self.inner.next_event() {
+            let raw_event = {
+                enter_span!("gilrs::backend_poll");
+                // `Instant` is not available on wasm.
+                let start = utils::time_now();
+                let raw_event = self.inner.next_event();
+                let elapsed = utils::time_now().duration_since(start).unwrap_or_default();
+                self.metrics.record_poll(elapsed);
+
+                raw_event
+            };
+
+            match raw_event {
                 Some(RawEvent { id, event, time }) => {
+                    enter_span!("gilrs::map_event", gamepad = id);
                     trace!("Original event: {:?}", RawEvent { id, event, time });
                     let id = GamepadId(id);
 
+                    if let Some(data) = self.gamepads_data.get_mut(id.0) {
+                        data.last_input = time;
+                        data.control_lost = false;
+                    }
+
                     let event = match event {
                         RawEventType::ButtonPressed(nec) => {
                             let nec = Code(nec);
                             match self.gamepad(id).axis_or_btn_name(nec) {
                                 Some(AxisOrBtn::Btn(b)) => {
-                                    self.events.push_back(Event {
-                                        id,
-                                        time,
-                                        event: EventType::ButtonChanged(b, 1.0, nec),
-                                    });
+                                    // Value is reported by pressure axis if it's mapped.
+                                    if !self.gamepad(id).has_pressure_and_digital(b) {
+                                        self.events.push_back(Event {
+                                            id,
+                                            time,
+                                            seq: 0,
+                                            event: EventType::ButtonChanged(b, 1.0, nec),
+                                        });
+                                    }
 
                                     EventType::ButtonPressed(b, nec)
                                 }
-                                Some(AxisOrBtn::Axis(a)) => EventType::AxisChanged(a, 1.0, nec),
+                                Some(AxisOrBtn::Axis(a)) => {
+                                    let val = self
+                                        .gamepad(id)
+                                        .data
+                                        .mapping
+                                        .transform(&nec.0)
+                                        .map_or(1.0, |t| t.pressed_value());
+
+                                    EventType::AxisChanged(a, val, nec)
+                                }
                                 None => {
                                     self.events.push_back(Event {
                                         id,
                                         time,
+                                        seq: 0,
                                         event: EventType::ButtonChanged(Button::Unknown, 1.0, nec),
                                     });
 
@@ -222,11 +918,15 @@ impl Gilrs {
                             let nec = Code(nec);
                             match self.gamepad(id).axis_or_btn_name(nec) {
                                 Some(AxisOrBtn::Btn(b)) => {
-                                    self.events.push_back(Event {
-                                        id,
-                                        time,
-                                        event: EventType::ButtonChanged(b, 0.0, nec),
-                                    });
+                                    // Value is reported by pressure axis if it's mapped.
+                                    if !self.gamepad(id).has_pressure_and_digital(b) {
+                                        self.events.push_back(Event {
+                                            id,
+                                            time,
+                                            seq: 0,
+                                            event: EventType::ButtonChanged(b, 0.0, nec),
+                                        });
+                                    }
 
                                     EventType::ButtonReleased(b, nec)
                                 }
@@ -235,6 +935,7 @@ impl Gilrs {
                                     self.events.push_back(Event {
                                         id,
                                         time,
+                                        seq: 0,
                                         event: EventType::ButtonChanged(Button::Unknown, 0.0, nec),
                                     });
 
@@ -245,61 +946,54 @@ impl Gilrs {
                         RawEventType::AxisValueChanged(val, nec) => {
                             // Let's trust at least our backend code
                             let axis_info = *self.gamepad(id).inner.axis_info(nec).unwrap();
-                            let nec = Code(nec);
-
-                            match self.gamepad(id).axis_or_btn_name(nec) {
-                                Some(AxisOrBtn::Btn(b)) => {
-                                    let val = btn_value(&axis_info, val);
-
-                                    if val >= self.axis_to_btn_pressed
-                                        && !self.gamepad(id).state().is_pressed(nec)
-                                    {
-                                        self.events.push_back(Event {
-                                            id,
-                                            time,
-                                            event: EventType::ButtonChanged(b, val, nec),
-                                        });
-
-                                        EventType::ButtonPressed(b, nec)
-                                    } else if val <= self.axis_to_btn_released
-                                        && self.gamepad(id).state().is_pressed(nec)
-                                    {
-                                        self.events.push_back(Event {
-                                            id,
-                                            time,
-                                            event: EventType::ButtonChanged(b, val, nec),
-                                        });
-
-                                        EventType::ButtonReleased(b, nec)
-                                    } else {
-                                        EventType::ButtonChanged(b, val, nec)
-                                    }
-                                }
-                                Some(AxisOrBtn::Axis(a)) => {
-                                    EventType::AxisChanged(a, axis_value(&axis_info, val, a), nec)
-                                }
-                                None => EventType::AxisChanged(
-                                    Axis::Unknown,
-                                    axis_value(&axis_info, val, Axis::Unknown),
-                                    nec,
-                                ),
+                            let y_reversed = self.gamepad(id).inner.is_y_axis_reversed();
+                            let split = self.gamepad(id).data.mapping.split_codes(&nec).to_vec();
+
+                            let event = self.map_axis_value(
+                                id,
+                                val,
+                                Code(nec),
+                                &axis_info,
+                                y_reversed,
+                                time,
+                            );
+                            // Other elements mapped to part of the same axis. Like above, their
+                            // press or release comes before `ButtonChanged`.
+                            for code in split {
+                                let idx = self.events.len();
+                                let event = self.map_axis_value(
+                                    id,
+                                    val,
+                                    Code(code),
+                                    &axis_info,
+                                    y_reversed,
+                                    time,
+                                );
+                                self.events.insert(
+                                    idx,
+                                    Event {
+                                        id,
+                                        time,
+                                        seq: 0,
+                                        event,
+                                    },
+                                );
                             }
+
+                            event
                         }
                         RawEventType::Connected => {
+                            debug!(
+                                "Gamepad {} ({}) connected.",
+                                id,
+                                self.inner.gamepad(id.0).map(|g| g.name()).unwrap_or("")
+                            );
+
                             if id.0 == self.gamepads_data.len() {
-                                self.gamepads_data.push(GamepadData::new(
-                                    id,
-                                    self.tx.clone(),
-                                    self.inner.gamepad(id.0).unwrap(),
-                                    &self.mappings,
-                                ));
+                                let data = self.new_gamepad_data(id);
+                                self.gamepads_data.push(data);
                             } else if id.0 < self.gamepads_data.len() {
-                                self.gamepads_data[id.0] = GamepadData::new(
-                                    id,
-                                    self.tx.clone(),
-                                    self.inner.gamepad(id.0).unwrap(),
-                                    &self.mappings,
-                                );
+                                self.gamepads_data[id.0] = self.new_gamepad_data(id);
                             } else {
                                 error!(
                                     "Platform implementation error: got Connected event with id \
@@ -312,17 +1006,166 @@ impl Gilrs {
                             EventType::Connected
                         }
                         RawEventType::Disconnected => {
+                            debug!("Gamepad {} disconnected.", id);
                             let _ = self.tx.send(Message::Close { id: id.0 });
 
                             EventType::Disconnected
                         }
+                        RawEventType::Overflow(count) => {
+                            warn!("Gamepad {}: backend discarded {} events.", id, count);
+                            self.metrics.events_dropped += count as u64;
+
+                            EventType::Overflow(count)
+                        }
+                        RawEventType::Motion(data) => EventType::Motion(data),
+                        RawEventType::Touch(data) => EventType::Touch(data),
                     };
 
-                    Some(Event { id, event, time })
+                    self.metrics.record_queue_depth(self.events.len());
+
+                    Some(Event {
+                        id,
+                        event,
+                        time,
+                        seq: 0,
+                    })
+                }
+                None => self.check_control_timeout(),
+            }
+        }
+    }
+
+    /// Maps value of axis `nec` with `axis_info` to event of element mapped to it.
+    fn map_axis_value(
+        &mut self,
+        id: GamepadId,
+        val: i32,
+        nec: Code,
+        axis_info: &AxisInfo,
+        y_reversed: bool,
+        time: SystemTime,
+    ) -> EventType {
+        let transform = self.gamepad(id).data.mapping.transform(&nec.0);
+
+        match self.gamepad(id).axis_or_btn_name(nec) {
+            Some(AxisOrBtn::Btn(b)) => {
+                let val = match transform {
+                    Some(t) => t.btn_value(axis_value(axis_info, val, Axis::Unknown, false)),
+                    None => btn_value(axis_info, val),
+                };
+
+                if self.gamepad(id).has_pressure_and_digital(b) {
+                    // Press and release are reported by digital element.
+                    EventType::ButtonChanged(b, val, nec)
+                } else if val >= self.axis_to_btn_pressed
+                    && !self.gamepad(id).state().is_pressed(nec)
+                {
+                    self.events.push_back(Event {
+                        id,
+                        time,
+                        seq: 0,
+                        event: EventType::ButtonChanged(b, val, nec),
+                    });
+
+                    EventType::ButtonPressed(b, nec)
+                } else if val <= self.axis_to_btn_released
+                    && self.gamepad(id).state().is_pressed(nec)
+                {
+                    self.events.push_back(Event {
+                        id,
+                        time,
+                        seq: 0,
+                        event: EventType::ButtonChanged(b, val, nec),
+                    });
+
+                    EventType::ButtonReleased(b, nec)
+                } else {
+                    EventType::ButtonChanged(b, val, nec)
+                }
+            }
+            Some(AxisOrBtn::Axis(a)) => {
+                let mut val = match transform {
+                    // Mapping describes raw axis, Y axis is reversed after.
+                    Some(t) => reverse_y(
+                        t.axis_value(axis_value(axis_info, val, Axis::Unknown, false)),
+                        a,
+                        y_reversed,
+                    ),
+                    None => axis_value(axis_info, val, a, y_reversed),
+                };
+                if let Some(data) = self.gamepads_data.get_mut(id.0) {
+                    if data.quirk.inverted_axes.contains(&a) {
+                        val = -val;
+                    }
+                    data.noise.entry(nec).or_default().update(val);
+                }
+
+                EventType::AxisChanged(a, val, nec)
+            }
+            None => EventType::AxisChanged(
+                Axis::Unknown,
+                axis_value(axis_info, val, Axis::Unknown, y_reversed),
+                nec,
+            ),
+        }
+    }
+
+    /// Returns `ControlLost` event for first gamepad that didn't send any input for longer than
+    /// control timeout.
+    fn check_control_timeout(&mut self) -> Option<Event> {
+        let timeout = self.control_timeout?;
+        if self.is_suspended() {
+            return None;
+        }
+        let now = utils::time_now();
+
+        for id in 0..self.gamepads_data.len() {
+            let is_connected = self
+                .inner
+                .gamepad(id)
+                .map(|gp| gp.is_connected())
+                .unwrap_or(false);
+            let data = &mut self.gamepads_data[id];
+            let is_stale = now
+                .duration_since(data.last_input)
+                .map(|dur| dur >= timeout)
+                .unwrap_or(false);
+
+            if !is_connected || data.control_lost || !is_stale {
+                continue;
+            }
+
+            data.control_lost = true;
+            let id = GamepadId(id);
+            warn!("Gamepad {}: no input for {:?}, control lost.", id, timeout);
+
+            if self.zero_axes_on_control_lost {
+                for (nec, axis_data) in data.state.axes() {
+                    let axis = match data.mapping.map(&nec.0) {
+                        Some(AxisOrBtn::Axis(axis)) => axis,
+                        _ => Axis::Unknown,
+                    };
+
+                    if axis_data.value() != 0.0 {
+                        self.events.push_back(Event {
+                            id,
+                            event: EventType::AxisChanged(axis, 0.0, nec),
+                            time: now,
+                            seq: 0,
+                        });
+                    }
                 }
-                None => None,
             }
+
+            return Some(Event {
+                id,
+                event: EventType::ControlLost,
+                time: now,
+                seq: 0,
+            });
         }
+
+        None
     }
 
     /// Updates internal state according to `event`.
@@ -332,7 +1175,10 @@ impl Gilrs {
     pub fn update(&mut self, event: &Event) {
         use crate::EventType::*;
 
+        enter_span!("gilrs::update_state");
+
         let counter = self.counter;
+        let axis_history_len = self.axis_history_len;
 
         let data = match self.gamepads_data.get_mut(event.id.0) {
             Some(d) => d,
@@ -353,17 +1199,35 @@ impl Gilrs {
                 data.state.set_btn_value(nec, value, counter, event.time);
             }
             AxisChanged(_, value, nec) => {
-                data.state
-                    .update_axis(nec, AxisData::new(value, counter, event.time));
+                let axis_data = AxisData::new(value, counter, event.time);
+                if axis_history_len > 0 {
+                    data.state
+                        .push_axis_history(nec, axis_data, axis_history_len);
+                }
+                data.state.update_axis(nec, axis_data);
             }
-            Disconnected | Connected | Dropped => (),
+            Disconnected
+            | Connected
+            | Dropped
+            | ControlLost
+            | Overflow(_)
+            | Motion(_)
+            | Touch(_)
+            | Gesture(_)
+            | ForceFeedbackEffectCompleted(_) => (),
         }
     }
 
     /// Increases internal counter by one. Counter data is stored with state and can be used to
     /// determine when last event happened. You probably want to use this function in your update
     /// loop after processing events.
+    ///
+    /// It also starts new frame of `Orientation::delta` of every gamepad.
     pub fn inc(&mut self) {
+        for data in &mut self.gamepads_data {
+            data.fusion.reset_delta();
+        }
+
         // Counter is 62bit. See `ButtonData`.
         if self.counter == 0x3FFF_FFFF_FFFF_FFFF {
             self.counter = 0;
@@ -383,19 +1247,33 @@ impl Gilrs {
         self.counter = 0;
     }
 
-    fn finish_gamepads_creation(&mut self) {
-        let tx = self.tx.clone();
+    fn finish_gamepads_creation(&mut self, connected_events: bool) {
         for id in 0..self.inner.last_gamepad_hint() {
-            let gamepad = self.inner.gamepad(id).unwrap();
-            self.gamepads_data.push(GamepadData::new(
-                GamepadId(id),
-                tx.clone(),
-                gamepad,
-                &self.mappings,
-            ))
+            let data = self.new_gamepad_data(GamepadId(id));
+            self.gamepads_data.push(data);
+
+            if connected_events && self.inner.gamepad(id).unwrap().is_connected() {
+                self.events
+                    .push_back(Event::new(GamepadId(id), EventType::Connected));
+            }
         }
     }
 
+    fn new_gamepad_data(&self, id: GamepadId) -> GamepadData {
+        let mut data = GamepadData::new(
+            id,
+            self.tx.clone(),
+            self.inner.gamepad(id.0).unwrap(),
+            &self.mappings,
+            self.nintendo_layout,
+            self.ff_enabled,
+        );
+        data.deadzone = self.deadzone;
+        data.set_quirk(self.quirks.get(data.info.vendor_id, data.info.product_id));
+
+        data
+    }
+
     /// Returns handle to gamepad with given ID. Unlike `connected_gamepad()`, this function will
     /// also return handle to gamepad that is currently disconnected.
     ///
@@ -425,6 +1303,25 @@ impl Gilrs {
         }
     }
 
+    /// Returns information about gamepad captured when its last `Connected` event was processed
+    /// (or when `Gilrs` was created, for gamepads connected before that). Returns `None` if there
+    /// was no gamepad with given id.
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// use gilrs::EventType;
+    ///
+    /// while let Some(ev) = gilrs.next_event() {
+    ///     if ev.event == EventType::Connected {
+    ///         let info = gilrs.connected_info(ev.id).unwrap();
+    ///         println!("{} connected: {:?}", info.name(), info.vendor_id());
+    ///     }
+    /// }
+    /// ```
+    pub fn connected_info(&self, id: GamepadId) -> Option<&ConnectedInfo> {
+        self.gamepads_data.get(id.0).map(|data| &data.info)
+    }
+
     /// Returns a reference to connected gamepad or `None`.
     pub fn connected_gamepad(&self, id: GamepadId) -> Option<Gamepad<'_>> {
         // Make sure that it will not panic even with invalid GamepadId, so ConnectedGamepadIterator
@@ -459,6 +1356,7 @@ impl Gilrs {
     /// Adds `ev` at the end of internal event queue. It can later be retrieved with `next_event()`.
     pub fn insert_event(&mut self, ev: Event) {
         self.events.push_back(ev);
+        self.metrics.record_queue_depth(self.events.len());
     }
 
     pub(crate) fn ff_sender(&self) -> &Sender<Message> {
@@ -469,6 +1367,12 @@ impl Gilrs {
     /// compatible with SDL2 - if it is important, use
     /// [`set_mapping_strict()`](#method.set_mapping_strict).
     ///
+    /// Mapping is applied to next events and kept if gamepad reconnects. Codes used in mapping can
+    /// be read from events, for example when user is asked to press button for each action. To
+    /// use the mapping after restart, save returned string and pass it to
+    /// `GilrsBuilder::add_mappings()` or save all user's mappings with
+    /// `gilrs.mapping_db().save_to()`.
+    ///
     /// The `name` argument can be a string slice with custom gamepad name or `None`. If `None`,
     /// gamepad name reported by driver will be used.
     ///
@@ -506,7 +1410,7 @@ impl Gilrs {
         name: O,
     ) -> Result<String, MappingError> {
         if let Some(gamepad) = self.inner.gamepad(gamepad_id) {
-            if gamepad.is_connected() {
+            if !gamepad.is_connected() {
                 return Err(MappingError::NotConnected);
             }
 
@@ -526,6 +1430,11 @@ impl Gilrs {
             // We checked if gamepad is connected, so it should never panic
             let data = &mut self.gamepads_data[gamepad_id];
             data.mapping = mapping;
+            data.mapping_origin = Some(MappingOrigin::User);
+            // New mapping doesn't have triggers swapped, don't swap it back when quirks reload.
+            data.quirk.swap_triggers = false;
+            // Keep mapping when gamepad reconnects.
+            self.mappings.insert(&s);
 
             Ok(s)
         } else {
@@ -539,7 +1448,7 @@ impl Gilrs {
     /// # Errors
     ///
     /// Returns `MappingError::NotSdl2Compatible` if `mapping` have an entry for `Button::{C, Z}`
-    /// or `Axis::{LeftZ, RightZ}`.
+    /// or `Axis::{LeftZ, RightZ}`, including buttons mapped to these axes.
     pub fn set_mapping_strict<'b, O: Into<Option<&'b str>>>(
         &mut self,
         gamepad_id: usize,
@@ -548,8 +1457,13 @@ impl Gilrs {
     ) -> Result<String, MappingError> {
         if mapping.button(Button::C).is_some()
             || mapping.button(Button::Z).is_some()
+            || mapping.button_axis(Button::C).is_some()
+            || mapping.button_axis(Button::Z).is_some()
             || mapping.axis(Axis::LeftZ).is_some()
             || mapping.axis(Axis::RightZ).is_some()
+            || mapping
+                .axis_buttons()
+                .any(|(axis, ..)| axis == Axis::LeftZ || axis == Axis::RightZ)
         {
             Err(MappingError::NotSdl2Compatible)
         } else {
@@ -557,6 +1471,39 @@ impl Gilrs {
         }
     }
 
+    /// Changes how force feedback effects that play at the same time on one gamepad are
+    /// combined. Default is `MixMode::Sum`.
+    pub fn set_ff_mix_mode(&mut self, mode: MixMode) {
+        let _ = self.tx.send(Message::SetMixMode { mode });
+    }
+
+    /// Stops all force feedback effects, rumbles and actuator tests and turns off motors of all
+    /// gamepads, for example when game is paused, loses focus or crashes. Stopped effects can be
+    /// played again with `Effect::play()`.
+    ///
+    /// Motors are also turned off directly from calling thread, so this works even if force
+    /// feedback thread is not responding. On Linux, where effects can only be stopped by device
+    /// that played them, motors stop on their own at most two ticks after last update.
+    pub fn stop_all_ff(&self) {
+        let _ = self.tx.send(Message::StopAll);
+        if !self.ff_enabled {
+            return;
+        }
+
+        for id in 0..self.inner.last_gamepad_hint() {
+            let gamepad = match self.inner.gamepad(id) {
+                Some(gamepad) if gamepad.is_connected() && gamepad.is_ff_supported() => gamepad,
+                _ => continue,
+            };
+            if let Some(mut device) = gamepad.ff_device() {
+                if device.has_trigger_motors() {
+                    let _ = device.set_trigger_state(0, 0, Duration::from_secs(0));
+                }
+                let _ = device.set_ff_state(0, 0, Duration::from_secs(0));
+            }
+        }
+    }
+
     pub(crate) fn next_ff_id(&mut self) -> usize {
         // TODO: reuse free ids
         let id = self.next_id;
@@ -575,8 +1522,31 @@ pub struct GilrsBuilder {
     axis_to_btn_pressed: f32,
     axis_to_btn_released: f32,
     update_state: bool,
+    axis_history_len: usize,
+    control_timeout: Option<Duration>,
+    zero_axes_on_control_lost: bool,
+    nintendo_layout: NintendoLayout,
+    adaptive_jitter: bool,
     env_mappings: bool,
     included_mappings: bool,
+    poll_interval: Option<Duration>,
+    poll_mode: PollMode,
+    connection_check_interval: Option<Duration>,
+    deadzone: Option<f32>,
+    initial_connected_events: bool,
+    dpad_axis_events: bool,
+    touch_gestures: Option<GestureConfig>,
+    motion_fusion: Option<FusionConfig>,
+    event_queue_capacity: Option<usize>,
+    native_backend: bool,
+    backends: Vec<Box<dyn Backend>>,
+    quirks_file: Option<PathBuf>,
+    ff_tick_duration: Option<Duration>,
+    #[cfg(all(
+        feature = "steam",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    steam_action_set: Option<String>,
 }
 
 impl GilrsBuilder {
@@ -588,8 +1558,31 @@ impl GilrsBuilder {
             axis_to_btn_pressed: 0.75,
             axis_to_btn_released: 0.65,
             update_state: true,
+            axis_history_len: 0,
+            control_timeout: None,
+            zero_axes_on_control_lost: false,
+            nintendo_layout: NintendoLayout::Positional,
+            adaptive_jitter: false,
             env_mappings: true,
             included_mappings: true,
+            poll_interval: None,
+            poll_mode: PollMode::Fixed,
+            connection_check_interval: None,
+            deadzone: None,
+            initial_connected_events: false,
+            dpad_axis_events: false,
+            touch_gestures: None,
+            motion_fusion: None,
+            event_queue_capacity: None,
+            native_backend: true,
+            backends: Vec::new(),
+            quirks_file: None,
+            ff_tick_duration: None,
+            #[cfg(all(
+                feature = "steam",
+                any(target_os = "linux", target_os = "macos", target_os = "windows")
+            ))]
+            steam_action_set: None,
         }
     }
 
@@ -602,15 +1595,25 @@ impl GilrsBuilder {
         self
     }
 
-    /// Adds SDL mappings.
+    /// Adds SDL mappings. They take priority over included and environment mappings.
     pub fn add_mappings(mut self, mappings: &str) -> Self {
         self.mappings.insert(mappings);
 
         self
     }
 
-    /// If true, will add SDL mappings from `SDL_GAMECONTROLLERCONFIG` environment variable.
-    /// Defaults to true.
+    /// Replaces mapping database, including mappings added by `add_mappings()`. Included and
+    /// environment mappings are still added by `build()` if enabled, but they never override
+    /// mappings from the database with higher [`MappingOrigin`](enum.MappingOrigin.html).
+    pub fn set_mapping_db(mut self, db: MappingDb) -> Self {
+        self.mappings = db;
+
+        self
+    }
+
+    /// If true, will add SDL mappings from `SDL_GAMECONTROLLERCONFIG` and
+    /// `GILRS_GAMECONTROLLERCONFIG` environment variables and from file set by
+    /// `GILRS_MAPPINGS_FILE`. Defaults to true.
     pub fn add_env_mappings(mut self, env_mappings: bool) -> Self {
         self.env_mappings = env_mappings;
 
@@ -644,6 +1647,198 @@ impl GilrsBuilder {
         self
     }
 
+    /// Sets number of last values stored for each axis, which can be accessed with
+    /// `Gamepad::axis_history()`. History is updated together with cached state. Defaults to 0
+    /// (disabled).
+    pub fn set_axis_history(mut self, len: usize) -> Self {
+        self.axis_history_len = len;
+
+        self
+    }
+
+    /// Emit `EventType::ControlLost` when connected gamepad doesn't send any input for longer
+    /// than `timeout`. Useful when gamepad controls robot or vehicle and dead wireless link must
+    /// not look like stick held steady. Defaults to `None` (disabled).
+    ///
+    /// Gilrs can only see when backend last reported changed input. Most gamepads don't report
+    /// unchanged values, so `timeout` should be longer than time in which user is expected to
+    /// touch any control, or gamepad should have noisy axes (for example motion sensors).
+    pub fn set_control_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.control_timeout = timeout;
+
+        self
+    }
+
+    /// If `true`, `ControlLost` event is followed by `AxisChanged` events that set all axes of
+    /// this gamepad to 0.0. Defaults to `false`.
+    pub fn set_zero_axes_on_control_lost(mut self, zero: bool) -> Self {
+        self.zero_axes_on_control_lost = zero;
+
+        self
+    }
+
+    /// Sets whether face buttons of Nintendo controllers are mapped by position or by label.
+    /// Applies to SDL and default mappings, but not to mappings set with `Gilrs::set_mapping()`.
+    /// Defaults to `NintendoLayout::Positional`.
+    pub fn set_nintendo_layout(mut self, layout: NintendoLayout) -> Self {
+        self.nintendo_layout = layout;
+
+        self
+    }
+
+    /// If `true`, default filters use [`AdaptiveJitter`](ev/filter/struct.AdaptiveJitter.html)
+    /// instead of [`Jitter`](ev/filter/struct.Jitter.html), so threshold of each axis follows its
+    /// noise floor. Defaults to `false`.
+    pub fn set_adaptive_jitter(mut self, adaptive: bool) -> Self {
+        self.adaptive_jitter = adaptive;
+
+        self
+    }
+
+    /// Sets how often backend polls gamepads. Only used by backends that have to poll devices
+    /// (XInput on Windows), others are event driven and ignore it. `GILRS_POLL_MS` environment
+    /// variable takes precedence. Defaults to `None` (backend default, 10 ms on Windows).
+    pub fn set_poll_interval(mut self, interval: Option<Duration>) -> Self {
+        self.poll_interval = interval;
+
+        self
+    }
+
+    /// Sets whether polling backends slow down while no gamepad is connected
+    /// (`PollMode::Adaptive`) or poll at 1 kHz (`PollMode::LowLatency`). Only XInput backend on
+    /// Windows uses it. Defaults to `PollMode::Fixed`.
+    pub fn set_poll_mode(mut self, mode: PollMode) -> Self {
+        self.poll_mode = mode;
+
+        self
+    }
+
+    /// Sets how often backend looks for newly connected gamepads. Only XInput backend on Windows
+    /// has to check it periodically, and only if device notifications are unavailable. Defaults
+    /// to `None` (backend default, 1 s on Windows).
+    pub fn set_connection_check_interval(mut self, interval: Option<Duration>) -> Self {
+        self.connection_check_interval = interval;
+
+        self
+    }
+
+    /// Sets deadzone of all axes, overriding values reported by platform. It's returned by
+    /// `Gamepad::deadzone()` and used by [`deadzone`](ev/filter/fn.deadzone.html) filter. `build()`
+    /// will return error if value is outside [0.0, 1.0). Defaults to `None` (use deadzone reported
+    /// by platform).
+    pub fn set_deadzone(mut self, deadzone: Option<f32>) -> Self {
+        self.deadzone = deadzone;
+
+        self
+    }
+
+    /// If `true`, `Gilrs` will emit `Connected` event for every gamepad that is already connected
+    /// when it's created, so gamepads can be handled in one place. Defaults to `false`.
+    pub fn set_initial_connected_events(mut self, enabled: bool) -> Self {
+        self.initial_connected_events = enabled;
+
+        self
+    }
+
+    /// If `true`, every d-pad `ButtonPressed` and `ButtonReleased` event is followed by
+    /// `AxisChanged` event of `Axis::DPadX` or `Axis::DPadY` with value -1.0, 0.0 or 1.0. Default
+    /// filters already report d-pad axes as buttons (see
+    /// [`axis_dpad_to_button`](ev/filter/fn.axis_dpad_to_button.html)), so with both enabled
+    /// d-pad can be read either way regardless of how gamepad reports it. Gamepads that have
+    /// d-pad buttons and axes are left alone. Defaults to `false`.
+    pub fn set_dpad_axis_events(mut self, enabled: bool) -> Self {
+        self.dpad_axis_events = enabled;
+
+        self
+    }
+
+    /// Enables recognition of touchpad gestures with given parameters. Gestures are reported as
+    /// `EventType::Gesture` after `Touch` events that completed them. Defaults to `None`
+    /// (disabled).
+    pub fn set_touch_gestures(mut self, config: Option<GestureConfig>) -> Self {
+        self.touch_gestures = config;
+
+        self
+    }
+
+    /// Enables fusion of gyroscope and accelerometer readings from `EventType::Motion` into
+    /// orientation of gamepad with given parameters. It is returned by `Gamepad::orientation()`.
+    /// Defaults to `None` (disabled).
+    pub fn set_motion_fusion(mut self, config: Option<FusionConfig>) -> Self {
+        self.motion_fusion = config;
+
+        self
+    }
+
+    /// Limits number of events that platform backend keeps for application. If application
+    /// doesn't process events for long time and limit is reached, new input events are discarded
+    /// and `EventType::Overflow` with number of discarded events is emitted before next event of
+    /// the gamepad. Defaults to `None` (no limit).
+    ///
+    /// Currently only XInput backend on Windows supports it.
+    pub fn set_event_queue_capacity(mut self, capacity: Option<usize>) -> Self {
+        self.event_queue_capacity = capacity;
+
+        self
+    }
+
+    /// Adds backend whose gamepads are reported together with gamepads of native backend.
+    /// Backends are chained in order in which they were added, when the same device is reported
+    /// by more than one of them, only the first one is used. Backends are implemented with types
+    /// from `gilrs-core` crate, see `gilrs_core::Gilrs::add_backend()` for details.
+    pub fn add_backend<B: Backend + 'static>(mut self, backend: B) -> Self {
+        self.backends.push(Box::new(backend));
+
+        self
+    }
+
+    /// If `false`, platform's backend is not created and gamepads are reported only by backends
+    /// added with `add_backend()`. Without such backends `gilrs_core::DummyBackend` is used, so
+    /// `Gilrs` doesn't report any gamepads and doesn't interact with OS, which is useful for
+    /// servers and tests. `GILRS_BACKEND` environment variable takes precedence. Defaults to
+    /// `true`.
+    pub fn set_native_backend(mut self, enabled: bool) -> Self {
+        self.native_backend = enabled;
+
+        self
+    }
+
+    /// Sets TOML file with fixes for controllers with broken firmware, see
+    /// [`Quirk`](struct.Quirk.html) for its format. File is reloaded when it changes, so users can
+    /// add quirks without restarting application. Errors are logged. `GILRS_QUIRKS_FILE`
+    /// environment variable takes precedence. Defaults to `None`.
+    pub fn set_quirks_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.quirks_file = Some(path.into());
+
+        self
+    }
+
+    /// Sets how often force feedback effects are updated. Shorter ticks make short effects
+    /// crisper, but wake up force feedback thread more often. `build()` will return error if
+    /// duration is outside [1 ms, 50 ms] range. Defaults to `None` (50 ms).
+    ///
+    /// Duration of [`Ticks`](ff/struct.Ticks.html) changes with it, so it's shared by all `Gilrs`
+    /// instances in the process and should be set before effects are created. Every successful
+    /// `build()` sets it, `None` restores the default.
+    pub fn set_ff_tick_duration(mut self, duration: Option<Duration>) -> Self {
+        self.ff_tick_duration = duration;
+
+        self
+    }
+
+    /// Sets name of Steam Input action set activated for all controllers. Buttons and axes are
+    /// read from actions of this set, see [Steam Input](index.html#steam-input) for their names.
+    /// Defaults to `None` (`"gamepad"`).
+    #[cfg(all(
+        feature = "steam",
+        any(target_os = "linux", target_os = "macos", target_os = "windows")
+    ))]
+    pub fn set_steam_action_set(mut self, name: &str) -> Self {
+        self.steam_action_set = Some(name.to_owned());
+
+        self
+    }
+
     /// Creates `Gilrs`.
     pub fn build(mut self) -> Result<Gilrs, Error> {
         if self.included_mappings {
@@ -665,19 +1860,108 @@ impl GilrsBuilder {
             return Err(Error::InvalidAxisToBtn);
         }
 
+        if let Some(deadzone) = self.deadzone {
+            if !(0.0..1.0).contains(&deadzone) {
+                return Err(Error::InvalidDeadzone);
+            }
+        }
+
+        let ff_tick_duration = match self.ff_tick_duration {
+            Some(duration) => {
+                let ms = duration.as_millis();
+                if ms < 1 || ms > u128::from(ff::MAX_TICK_DURATION) {
+                    return Err(Error::InvalidFfTickDuration);
+                }
+                ms as u32
+            }
+            None => ff::DEFAULT_TICK_DURATION,
+        };
+
+        match env::var("GILRS_BACKEND") {
+            Ok(ref backend) if backend == "native" => self.native_backend = true,
+            Ok(ref backend) if backend == "dummy" => {
+                self.native_backend = false;
+                self.backends.clear();
+            }
+            Ok(backend) => warn!("Unknown backend {:?} set by GILRS_BACKEND.", backend),
+            Err(_) => (),
+        }
+        if let Some(interval) = env_poll_interval() {
+            self.poll_interval = Some(interval);
+            // Polling every millisecond would ignore the interval.
+            if self.poll_mode == PollMode::LowLatency {
+                self.poll_mode = PollMode::Fixed;
+            }
+        }
+        if let Some(path) = env::var_os("GILRS_QUIRKS_FILE") {
+            self.quirks_file = Some(path.into());
+        }
+        let (quirks_file, quirks) = match self.quirks_file {
+            Some(path) => {
+                let (file, quirks) = QuirksFile::load(path);
+                let quirks = quirks.unwrap_or_else(|e| {
+                    warn!("Failed to load quirks from {:?}: {}", file.path(), e);
+                    QuirkDb::default()
+                });
+                debug!("Loaded {} quirks.", quirks.len());
+                (Some(file), quirks)
+            }
+            None => (None, QuirkDb::default()),
+        };
+
         let mut is_dummy = false;
-        let inner = match gilrs_core::Gilrs::new() {
-            Ok(g) => g,
-            Err(PlatformError::NotImplemented(g)) => {
-                g
+        let mut inner = if self.native_backend {
+            match gilrs_core::Gilrs::new() {
+                Ok(g) => g,
+                Err(PlatformError::NotImplemented(g)) => {
+                    g
+                }
+                Err(PlatformError::Other(e)) => return Err(Error::Other(e)),
             }
-            Err(PlatformError::Other(e)) => return Err(Error::Other(e)),
+        } else if self.backends.is_empty() {
+            gilrs_core::Gilrs::with_backend(Box::new(DummyBackend))
+        } else {
+            gilrs_core::Gilrs::with_backend(self.backends.remove(0))
         };
 
+        if let Some(interval) = self.poll_interval {
+            inner.set_poll_interval(interval);
+        }
+        inner.set_poll_mode(self.poll_mode);
+        if let Some(interval) = self.connection_check_interval {
+            inner.set_connection_check_interval(interval);
+        }
+        inner.set_event_queue_capacity(self.event_queue_capacity);
+        for backend in self.backends.drain(..) {
+            inner.add_backend(backend);
+        }
+        #[cfg(all(
+            feature = "steam",
+            any(target_os = "linux", target_os = "macos", target_os = "windows")
+        ))]
+        {
+            if let Some(ref name) = self.steam_action_set {
+                inner.set_steam_action_set(name);
+            }
+        }
+
+        let ff_diagnostics = server::SharedDiagnostics::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        let (tx, ff_completed) =
+            server::init(ff_diagnostics.clone()).map_err(|e| Error::Other(Box::new(e)))?;
+        #[cfg(target_arch = "wasm32")]
+        let (tx, ff_server, ff_completed) = server::init(ff_diagnostics.clone());
+
+        // Applied after everything that can fail, so failed `build()` doesn't change ticks of
+        // other instances.
+        ff::set_tick_duration(ff_tick_duration);
+
         let mut gilrs = Gilrs {
             inner,
             next_id: 0,
-            tx: server::init(),
+            tx,
+            ff_completed,
+            ff_diagnostics,
             counter: 0,
             mappings: self.mappings,
             default_filters: self.default_filters,
@@ -685,20 +1969,85 @@ impl GilrsBuilder {
             axis_to_btn_pressed: self.axis_to_btn_pressed,
             axis_to_btn_released: self.axis_to_btn_released,
             update_state: self.update_state,
+            axis_history_len: self.axis_history_len,
+            control_timeout: self.control_timeout,
+            zero_axes_on_control_lost: self.zero_axes_on_control_lost,
+            nintendo_layout: self.nintendo_layout,
+            ff_enabled: env::var_os("GILRS_DISABLE_FF").is_none(),
+            adaptive_jitter: self.adaptive_jitter,
+            deadzone: self.deadzone,
+            dpad_axis_events: self.dpad_axis_events,
+            touch_gestures: self.touch_gestures,
+            motion_fusion: self.motion_fusion,
+            translated_events: VecDeque::new(),
+            next_seq: 1,
+            deferred_events: VecDeque::new(),
+            listener: None,
             gamepads_data: Vec::new(),
+            metrics: Metrics::default(),
+            suspended_at: None,
+            quirks,
+            quirks_file,
+            #[cfg(target_arch = "wasm32")]
+            ff_server,
         };
-        gilrs.finish_gamepads_creation();
+        gilrs.finish_gamepads_creation(self.initial_connected_events);
 
         Ok(gilrs)
     }
 }
 
+/// Reads poll interval from `GILRS_POLL_MS` environment variable.
+fn env_poll_interval() -> Option<Duration> {
+    let ms = env::var_os("GILRS_POLL_MS")?;
+    match ms.to_str().map(str::parse::<u64>) {
+        Some(Ok(ms)) if ms > 0 => Some(Duration::from_millis(ms)),
+        _ => {
+            warn!("Invalid value of GILRS_POLL_MS, using default.");
+            None
+        }
+    }
+}
+
 impl Default for GilrsBuilder {
     fn default() -> Self {
         Self::new()
     }
 }
 
+struct Listener(Box<dyn FnMut(&Event) + Send>);
+
+impl fmt::Debug for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Listener")
+    }
+}
+
+/// Iterator over pending events, returned by `Gilrs::poll_events()`.
+pub struct EventsIterator<'a>(&'a mut Gilrs);
+
+impl<'a> Iterator for EventsIterator<'a> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        self.0.next_event()
+    }
+}
+
+/// Iterator over pending events of one gamepad, returned by `Gilrs::events_for()`.
+pub struct GamepadEventsIterator<'a> {
+    gilrs: &'a mut Gilrs,
+    id: GamepadId,
+}
+
+impl<'a> Iterator for GamepadEventsIterator<'a> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        self.gilrs.next_event_for(self.id)
+    }
+}
+
 /// Iterator over all connected gamepads.
 pub struct ConnectedGamepadsIterator<'a>(&'a Gilrs, usize);
 
@@ -771,6 +2120,13 @@ impl<'a> Gamepad<'a> {
         self.inner.is_connected()
     }
 
+    /// Returns orientation of gamepad computed from its motion sensors. `None` if motion fusion
+    /// is not enabled by `GilrsBuilder::set_motion_fusion()` or gamepad didn't report any
+    /// `EventType::Motion` yet.
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.data.fusion.orientation()
+    }
+
     /// Examines cached gamepad state to check if given button is pressed. Panics if `btn` is
     /// `Unknown`.
     ///
@@ -799,6 +2155,61 @@ impl<'a> Gamepad<'a> {
         self.data.button_data(btn)
     }
 
+    /// Returns `true` if `btn` is not a trigger and has both analog axis (pressure) and digital
+    /// element mapped.
+    fn has_pressure_and_digital(&self, btn: Button) -> bool {
+        if btn == Button::LeftTrigger2 || btn == Button::RightTrigger2 {
+            return false;
+        }
+
+        let el = AxisOrBtn::Btn(btn);
+        let (mut analog, mut digital) = (false, false);
+        for nec in self.data.mapping.map_rev_all(&el) {
+            if self.inner.axes().contains(&nec) {
+                analog = true;
+            } else {
+                digital = true;
+            }
+        }
+
+        analog && digital
+    }
+
+    /// Returns combined state of trigger, which may have both analog axis and digital full-pull
+    /// click mapped to `btn`. Both parts are also reported as separate events with their own
+    /// `Code`.
+    ///
+    /// ```
+    /// # let gilrs = gilrs::Gilrs::new().unwrap();
+    /// use gilrs::Button;
+    ///
+    /// for (_, gamepad) in gilrs.gamepads() {
+    ///     let trigger = gamepad.trigger_data(Button::LeftTrigger2);
+    ///     if trigger.is_clicked() {
+    ///         println!("{} fully pulled", gamepad.name());
+    ///     } else {
+    ///         println!("{} at {}", gamepad.name(), trigger.value());
+    ///     }
+    /// }
+    /// ```
+    pub fn trigger_data(&self, btn: Button) -> TriggerData<'_> {
+        let mut trigger = TriggerData {
+            analog: None,
+            click: None,
+        };
+
+        for nec in self.data.mapping.map_rev_all(&AxisOrBtn::Btn(btn)) {
+            let data = self.data.state.button_data(Code(nec));
+            if self.inner.axes().contains(&nec) {
+                trigger.analog = trigger.analog.or(data);
+            } else {
+                trigger.click = trigger.click.or(data);
+            }
+        }
+
+        trigger
+    }
+
     /// Returns axis state and when it changed.
     ///
     /// If you know `Code` of the element that you want to examine, it's recommended to use methods
@@ -808,53 +2219,302 @@ impl<'a> Gamepad<'a> {
         self.data.axis_data(axis)
     }
 
-    /// Returns device's power supply state. See [`PowerInfo`](enum.PowerInfo.html) for details.
-    pub fn power_info(&self) -> PowerInfo {
-        self.inner.power_info()
+    /// Returns last values of axis and when they changed, from oldest to newest. Empty unless
+    /// enabled with `GilrsBuilder::set_axis_history()`.
+    ///
+    /// ```
+    /// # let gilrs = gilrs::GilrsBuilder::new().set_axis_history(8).build().unwrap();
+    /// use gilrs::Axis;
+    ///
+    /// for (_, gamepad) in gilrs.gamepads() {
+    ///     let samples: Vec<_> = gamepad.axis_history(Axis::LeftStickX).collect();
+    ///     println!("{} recent samples", samples.len());
+    /// }
+    /// ```
+    pub fn axis_history(&self, axis: Axis) -> AxisHistoryIter<'_> {
+        self.data.axis_history(axis)
+    }
+
+    /// Returns device's power supply state. See [`PowerInfo`](enum.PowerInfo.html) for details.
+    pub fn power_info(&self) -> PowerInfo {
+        if self.data.quirk.broken_battery {
+            PowerInfo::Unknown
+        } else {
+            self.inner.power_info()
+        }
+    }
+
+    /// Returns fixes applied to this gamepad, loaded from quirks file.
+    pub fn quirk(&self) -> &Quirk {
+        &self.data.quirk
+    }
+
+    /// Returns source of gamepad mapping. Can be used to filter gamepads which do not provide
+    /// unified controller layout.
+    ///
+    /// ```
+    /// use gilrs::MappingSource;
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    ///
+    /// for (_, gamepad) in gilrs.gamepads().filter(
+    ///     |gp| gp.1.mapping_source() != MappingSource::None)
+    /// {
+    ///     println!("{} is ready to use!", gamepad.name());
+    /// }
+    /// ```
+    pub fn mapping_source(&self) -> MappingSource {
+        if self.data.mapping.is_default() {
+            // TODO: check if it's Driver or None
+            MappingSource::Driver
+        } else {
+            MappingSource::SdlMappings
+        }
+    }
+
+    /// Returns mapping used by gamepad, with its source and element that each button and axis is
+    /// mapped from.
+    ///
+    /// ```
+    /// use gilrs::Gilrs;
+    ///
+    /// let gilrs = Gilrs::new().unwrap();
+    /// for (_, gamepad) in gilrs.gamepads() {
+    ///     let mapping = gamepad.mapping();
+    ///     for entry in mapping.entries() {
+    ///         println!("{:?} = {}", entry.element, entry.code);
+    ///     }
+    ///     for code in mapping.unmapped() {
+    ///         println!("{} is not mapped", code);
+    ///     }
+    /// }
+    /// ```
+    pub fn mapping(&self) -> ResolvedMapping {
+        ResolvedMapping::new(
+            &self.data.mapping,
+            self.mapping_source(),
+            self.data.mapping_origin,
+            self.inner.buttons(),
+            self.inner.axes(),
+        )
+    }
+
+    /// Returns true if force feedback is supported by device.
+    pub fn is_ff_supported(&self) -> bool {
+        self.inner.is_ff_supported()
+    }
+
+    /// Change gamepad position used by force feedback effects.
+    pub fn set_listener_position<Vec3: Into<[f32; 3]>>(
+        &self,
+        position: Vec3,
+    ) -> Result<(), FfError> {
+        if !self.is_connected() {
+            Err(FfError::Disconnected(self.id()))
+        } else if !self.is_ff_supported() {
+            Err(FfError::FfNotSupported(self.id()))
+        } else {
+            self.data.tx.send(Message::SetListenerPosition {
+                id: self.data.id.0,
+                position: position.into(),
+            })?;
+            Ok(())
+        }
+    }
+
+    /// Vibrates gamepad with given strength of strong and weak motor, from 0.0 to 1.0, for
+    /// `duration`. Rumble is played together with effects and replaces previous rumble, zero
+    /// `duration` stops it.
+    ///
+    /// This is a shortcut for the common case of short buzz that doesn't need
+    /// [`Effect`](ff/struct.Effect.html).
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # let gilrs = gilrs::Gilrs::new().unwrap();
+    /// for (_, gamepad) in gilrs.gamepads() {
+    ///     let _ = gamepad.rumble(1.0, 0.5, Duration::from_millis(200));
+    /// }
+    /// ```
+    pub fn rumble(&self, strong: f32, weak: f32, duration: Duration) -> Result<(), FfError> {
+        if !self.is_connected() {
+            Err(FfError::Disconnected(self.id()))
+        } else if !self.is_ff_supported() {
+            Err(FfError::FfNotSupported(self.id()))
+        } else {
+            let magnitude = |value: f32| (utils::clamp(value, 0.0, 1.0) * 65535.0) as u16;
+            self.data.tx.send(Message::Rumble {
+                id: self.data.id.0,
+                strong: magnitude(strong),
+                weak: magnitude(weak),
+                duration: Ticks::from(duration),
+            })?;
+            Ok(())
+        }
+    }
+
+    /// Pulses each force feedback motor, one after another, including motors in triggers if
+    /// gamepad has them. Each pulse takes 0.4 seconds with the same pause between pulses, and
+    /// test overrides effects played on this gamepad.
+    ///
+    /// Returned `ActuatorTest` reports motors whose pulse was sent to gamepad without error once
+    /// the test ends. Backends can't tell whether motor actually moved, so user still has to
+    /// confirm that they felt each pulse, for example on "test vibration" screen.
+    ///
+    /// ```
+    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// let mut tests = Vec::new();
+    /// for (id, gamepad) in gilrs.gamepads() {
+    ///     if let Ok(test) = gamepad.test_actuators() {
+    ///         tests.push((id, test));
+    ///     }
+    /// }
+    ///
+    /// while let Some((id, mut test)) = tests.pop() {
+    ///     while gilrs.next_event().is_some() {}
+    ///     match test.result() {
+    ///         Some(actuators) => println!("{:?} motors of gamepad {} work", actuators, id),
+    ///         None => tests.insert(0, (id, test)),
+    ///     }
+    /// }
+    /// ```
+    pub fn test_actuators(&self) -> Result<ActuatorTest, FfError> {
+        if !self.is_connected() {
+            Err(FfError::Disconnected(self.id()))
+        } else if !self.is_ff_supported() {
+            Err(FfError::FfNotSupported(self.id()))
+        } else {
+            let (tx, rx) = mpsc::channel();
+            self.data.tx.send(Message::TestActuators {
+                id: self.data.id.0,
+                result: tx,
+            })?;
+            Ok(ActuatorTest::new(rx))
+        }
+    }
+
+    /// Grabs the device so that other applications stop receiving its input while this `Gilrs`
+    /// instance keeps reading it. Same as `set_exclusive(true)`.
+    ///
+    /// ```no_run
+    /// # let gilrs = gilrs::Gilrs::new().unwrap();
+    /// for (id, gamepad) in gilrs.gamepads() {
+    ///     if let Err(e) = gamepad.claim_exclusive() {
+    ///         eprintln!("Can't grab gamepad {}: {}", id, e);
+    ///     }
+    /// }
+    /// ```
+    pub fn claim_exclusive(&self) -> Result<(), ClaimError> {
+        self.set_exclusive(true)
+    }
+
+    /// Releases a claim made with `claim_exclusive()`. Same as `set_exclusive(false)`.
+    pub fn release_exclusive(&self) -> Result<(), ClaimError> {
+        self.set_exclusive(false)
+    }
+
+    /// Grabs or releases the device. While grabbed, other applications stop receiving its input
+    /// and this `Gilrs` instance keeps reading it. Useful for remappers and emulators that would
+    /// otherwise cause duplicate input.
+    ///
+    /// On Linux, device is grabbed with `EVIOCGRAB`. On Windows, HID gamepads are reopened
+    /// without sharing, which fails if other application already has the device open, and
+    /// XInput controllers can't be grabbed. Other platforms return `ClaimError::NotSupported`.
+    /// The grab is released when the gamepad disconnects.
+    pub fn set_exclusive(&self, exclusive: bool) -> Result<(), ClaimError> {
+        if !self.is_connected() {
+            Err(ClaimError::Disconnected(self.id()))
+        } else if !self.inner.is_exclusive_supported() {
+            Err(ClaimError::NotSupported(self.id()))
+        } else {
+            self.inner.set_exclusive(exclusive).map_err(ClaimError::Io)
+        }
+    }
+
+    /// Returns `true` if gamepad can be grabbed with `set_exclusive()`.
+    pub fn is_exclusive_supported(&self) -> bool {
+        self.inner.is_exclusive_supported()
     }
 
-    /// Returns source of gamepad mapping. Can be used to filter gamepads which do not provide
-    /// unified controller layout.
+    /// Returns true if gamepad has microphone mute LED, like DualSense.
+    pub fn has_mic_mute_led(&self) -> bool {
+        self.inner.has_mic_led()
+    }
+
+    /// Turns microphone mute LED on or off. The mute button itself is reported as `Button::Misc1`,
+    /// which lets voice chat follow the button and keep the LED in sync:
     ///
-    /// ```
-    /// use gilrs::MappingSource;
-    /// # let mut gilrs = gilrs::Gilrs::new().unwrap();
+    /// ```no_run
+    /// use gilrs::{Button, EventType, Gilrs};
     ///
-    /// for (_, gamepad) in gilrs.gamepads().filter(
-    ///     |gp| gp.1.mapping_source() != MappingSource::None)
-    /// {
-    ///     println!("{} is ready to use!", gamepad.name());
+    /// let mut gilrs = Gilrs::new().unwrap();
+    /// let mut muted = false;
+    ///
+    /// while let Some(ev) = gilrs.next_event() {
+    ///     if let EventType::ButtonPressed(Button::Misc1, _) = ev.event {
+    ///         muted = !muted;
+    ///         let _ = gilrs.gamepad(ev.id).set_mic_mute_led(muted);
+    ///     }
     /// }
     /// ```
-    pub fn mapping_source(&self) -> MappingSource {
-        if self.data.mapping.is_default() {
-            // TODO: check if it's Driver or None
-            MappingSource::Driver
+    ///
+    /// Implemented on Linux, for drivers that expose `*:micmute` LED in sysfs, and by DualSense
+    /// HID driver (`hidapi` feature). Writing to sysfs usually requires udev rule granting access
+    /// to the `brightness` file.
+    pub fn set_mic_mute_led(&self, muted: bool) -> Result<(), LedError> {
+        if !self.is_connected() {
+            Err(LedError::Disconnected(self.id()))
+        } else if !self.has_mic_mute_led() {
+            Err(LedError::NotSupported(self.id()))
         } else {
-            MappingSource::SdlMappings
+            self.inner.set_mic_led(muted).map_err(LedError::Io)
         }
     }
 
-    /// Returns true if force feedback is supported by device.
-    pub fn is_ff_supported(&self) -> bool {
-        self.inner.is_ff_supported()
+    /// Returns true if color of gamepad's lightbar can be changed, like on DualShock 4 and
+    /// DualSense.
+    pub fn has_lightbar(&self) -> bool {
+        self.inner.has_lightbar()
     }
 
-    /// Change gamepad position used by force feedback effects.
-    pub fn set_listener_position<Vec3: Into<[f32; 3]>>(
+    /// Sets color of lightbar. By default it shows color of player (blue, red, green, pink).
+    ///
+    /// Only implemented by HID drivers (`hidapi` feature).
+    pub fn set_lightbar_color(&self, red: u8, green: u8, blue: u8) -> Result<(), LedError> {
+        if !self.is_connected() {
+            Err(LedError::Disconnected(self.id()))
+        } else if !self.has_lightbar() {
+            Err(LedError::NotSupported(self.id()))
+        } else {
+            self.inner
+                .set_lightbar(red, green, blue)
+                .map_err(LedError::Io)
+        }
+    }
+
+    /// Returns `true` if gamepad's triggers have programmable resistance, like on DualSense
+    /// handled by HID driver.
+    #[cfg(feature = "adaptive-triggers")]
+    pub fn supports_adaptive_triggers(&self) -> bool {
+        self.inner.has_adaptive_triggers()
+    }
+
+    /// Sets resistance of trigger. See [`ff::adaptive_trigger`](ff/adaptive_trigger/index.html).
+    #[cfg(feature = "adaptive-triggers")]
+    pub fn set_trigger_effect(
         &self,
-        position: Vec3,
-    ) -> Result<(), FfError> {
+        trigger: crate::ff::adaptive_trigger::Trigger,
+        effect: crate::ff::adaptive_trigger::TriggerEffect,
+    ) -> Result<(), crate::ff::adaptive_trigger::TriggerError> {
+        use crate::ff::adaptive_trigger::TriggerError;
+
         if !self.is_connected() {
-            Err(FfError::Disconnected(self.id()))
-        } else if !self.is_ff_supported() {
-            Err(FfError::FfNotSupported(self.id()))
+            Err(TriggerError::Disconnected(self.id()))
+        } else if !self.supports_adaptive_triggers() {
+            Err(TriggerError::NotSupported(self.id()))
         } else {
-            self.data.tx.send(Message::SetListenerPosition {
-                id: self.data.id.0,
-                position: position.into(),
-            })?;
-            Ok(())
+            self.inner
+                .set_trigger_effect(trigger, effect.to_report())
+                .map_err(TriggerError::Io)
         }
     }
 
@@ -873,12 +2533,33 @@ impl<'a> Gamepad<'a> {
         self.data.axis_code(axis)
     }
 
+    /// Returns estimated amplitude of noise reported by axis when it's not moved, or `None` if
+    /// axis didn't report any value yet.
+    ///
+    /// Estimate is updated continuously from all axis events, before any filter is applied. Worn
+    /// potentiometers will have higher noise floor than Hall effect sticks.
+    ///
+    /// ```
+    /// # let gilrs = gilrs::Gilrs::new().unwrap();
+    /// for (_, gamepad) in gilrs.gamepads() {
+    ///     if let Some(code) = gamepad.axis_code(gilrs::Axis::LeftStickX) {
+    ///         println!("{}: {:?}", gamepad.name(), gamepad.noise_floor(code));
+    ///     }
+    /// }
+    /// ```
+    pub fn noise_floor(&self, axis: Code) -> Option<f32> {
+        self.data.noise.get(&axis).map(|noise| noise.floor)
+    }
+
     /// Returns area in which axis events should be ignored.
     pub fn deadzone(&self, axis: Code) -> Option<f32> {
+        let deadzone = self.data.deadzone;
         self.inner.axis_info(axis.0).map(|i| {
             let range = i.max as f32 - i.min as f32;
 
-            if range == 0.0 {
+            if let Some(deadzone) = deadzone {
+                deadzone
+            } else if range == 0.0 {
                 0.0
             } else {
                 i.deadzone
@@ -893,17 +2574,83 @@ impl<'a> Gamepad<'a> {
         self.data.id
     }
 
-    pub(crate) fn mapping(&self) -> &Mapping {
+    pub(crate) fn raw_mapping(&self) -> &Mapping {
         &self.data.mapping
     }
+
+    pub(crate) fn inner(&self) -> &'a gilrs_core::Gamepad {
+        self.inner
+    }
+}
+
+/// Information about gamepad captured when its `Connected` event was processed.
+///
+/// Unlike properties of [`Gamepad`](struct.Gamepad.html), it doesn't change until the same id is
+/// reported as connected again, so it can be read even if gamepad has been disconnected right
+/// after connecting. See [`Gilrs::connected_info()`](struct.Gilrs.html#method.connected_info).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectedInfo {
+    name: String,
+    uuid: [u8; 16],
+    vendor_id: Option<u16>,
+    product_id: Option<u16>,
+}
+
+impl ConnectedInfo {
+    fn new(gamepad: &gilrs_core::Gamepad) -> Self {
+        let uuid = gamepad.uuid();
+        // Vendor and product IDs are stored in bytes 4-5 and 8-9 of SDL compatible UUID.
+        let vendor_id = u16::from_le_bytes([uuid[4], uuid[5]]);
+        let product_id = u16::from_le_bytes([uuid[8], uuid[9]]);
+        let known = vendor_id != 0 || product_id != 0;
+
+        ConnectedInfo {
+            name: gamepad.name().to_owned(),
+            uuid,
+            vendor_id: if known { Some(vendor_id) } else { None },
+            product_id: if known { Some(product_id) } else { None },
+        }
+    }
+
+    /// Returns the name of the gamepad supplied by the OS.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns gamepad's UUID.
+    pub fn uuid(&self) -> [u8; 16] {
+        self.uuid
+    }
+
+    /// Returns USB vendor ID or `None` if backend doesn't report it.
+    pub fn vendor_id(&self) -> Option<u16> {
+        self.vendor_id
+    }
+
+    /// Returns USB product ID or `None` if backend doesn't report it.
+    pub fn product_id(&self) -> Option<u16> {
+        self.product_id
+    }
 }
 
 #[derive(Debug)]
 struct GamepadData {
     state: GamepadState,
     mapping: Mapping,
+    // `None` for default mapping.
+    mapping_origin: Option<MappingOrigin>,
     tx: Sender<Message>,
     id: GamepadId,
+    last_input: SystemTime,
+    control_lost: bool,
+    // Indexed by EvCode (nec). Updated before filters are applied.
+    noise: FnvHashMap<Code, NoiseFloor>,
+    // Overrides deadzone reported by backend.
+    deadzone: Option<f32>,
+    info: ConnectedInfo,
+    quirk: Quirk,
+    gestures: GestureRecognizer,
+    fusion: SensorFusion,
 }
 
 impl GamepadData {
@@ -912,14 +2659,50 @@ impl GamepadData {
         tx: Sender<Message>,
         gamepad: &gilrs_core::Gamepad,
         db: &MappingDb,
+        nintendo_layout: NintendoLayout,
+        ff_enabled: bool,
     ) -> Self {
-        let mapping = db
-            .get(Uuid::from_bytes(gamepad.uuid()))
-            .and_then(|s| Mapping::parse_sdl_mapping(s, gamepad.buttons(), gamepad.axes()).ok())
-            .unwrap_or_else(|| Mapping::default(gamepad));
+        let uuid = Uuid::from_bytes(gamepad.uuid());
+        let mut mapping_origin = None;
+        let mut mapping = match db.get(gamepad.uuid()) {
+            Some(s) => match Mapping::parse_sdl_mapping(s, gamepad.buttons(), gamepad.axes()) {
+                Ok(mapping) => {
+                    debug!(
+                        "Gamepad {} ({}): using SDL mapping \"{}\".",
+                        id,
+                        uuid,
+                        mapping.name()
+                    );
+                    mapping_origin = db.origin(gamepad.uuid());
+                    mapping
+                }
+                Err(e) => {
+                    warn!(
+                        "Gamepad {} ({}): failed to parse SDL mapping, using default: {}",
+                        id, uuid, e
+                    );
+                    Mapping::default(gamepad)
+                }
+            },
+            None => {
+                debug!(
+                    "Gamepad {} ({}): no SDL mapping found, using default.",
+                    id, uuid
+                );
+                Mapping::default(gamepad)
+            }
+        };
+
+        let info = ConnectedInfo::new(gamepad);
+        let is_nintendo = info.vendor_id == Some(NINTENDO_VENDOR_ID);
+        if nintendo_layout == NintendoLayout::ByLabel && is_nintendo {
+            debug!("Gamepad {}: mapping face buttons by label.", id);
+            mapping.swap_face_buttons();
+        }
 
-        if gamepad.is_ff_supported() && gamepad.is_connected() {
+        if ff_enabled && gamepad.is_ff_supported() && gamepad.is_connected() {
             if let Some(device) = gamepad.ff_device() {
+                debug!("Gamepad {}: opened force feedback device.", id);
                 let _ = tx.send(Message::Open { id: id.0, device });
             }
         }
@@ -927,9 +2710,29 @@ impl GamepadData {
         GamepadData {
             state: GamepadState::new(),
             mapping,
+            mapping_origin,
             tx,
             id,
+            last_input: utils::time_now(),
+            control_lost: false,
+            noise: FnvHashMap::default(),
+            deadzone: None,
+            info,
+            quirk: Quirk::default(),
+            gestures: GestureRecognizer::default(),
+            fusion: SensorFusion::default(),
+        }
+    }
+
+    fn set_quirk(&mut self, mut quirk: Quirk) {
+        if self.mapping_origin == Some(MappingOrigin::User) {
+            quirk.swap_triggers = false;
+        }
+        if quirk.swap_triggers != self.quirk.swap_triggers {
+            debug!("Gamepad {}: swapping triggers.", self.id);
+            self.mapping.swap_triggers();
         }
+        self.quirk = quirk;
     }
 
     /// if `mapping_source()` is `SdlMappings` returns the name of the mapping used by the gamepad.
@@ -954,10 +2757,17 @@ impl GamepadData {
     pub fn is_pressed(&self, btn: Button) -> bool {
         assert_ne!(btn, Button::Unknown);
 
-        self.button_code(btn)
-            .or_else(|| btn.to_nec())
-            .map(|nec| self.state.is_pressed(nec))
-            .unwrap_or(false)
+        let el = AxisOrBtn::Btn(btn);
+        let mut codes = self.mapping.map_rev_all(&el).map(Code).peekable();
+        if codes.peek().is_none() {
+            return btn
+                .to_nec()
+                .map(|nec| self.state.is_pressed(nec))
+                .unwrap_or(false);
+        }
+
+        // Dual-stage triggers have both analog and digital part mapped to the same button.
+        codes.any(|nec| self.state.is_pressed(nec))
     }
 
     /// Examines cached gamepad state to check axis's value. Panics if `axis` is `Unknown`.
@@ -993,6 +2803,14 @@ impl GamepadData {
             .and_then(|nec| self.state.axis_data(nec))
     }
 
+    /// Returns last values of axis and when they changed, from oldest to newest.
+    pub fn axis_history(&self, axis: Axis) -> AxisHistoryIter<'_> {
+        match self.axis_code(axis) {
+            Some(nec) => self.state.axis_history(nec),
+            None => AxisHistoryIter::empty(),
+        }
+    }
+
     /// Returns `AxisOrBtn` mapped to `Code`.
     pub fn axis_or_btn_name(&self, ec: Code) -> Option<AxisOrBtn> {
         self.mapping.map(&ec.0)
@@ -1009,6 +2827,46 @@ impl GamepadData {
     }
 }
 
+const NINTENDO_VENDOR_ID: u16 = 0x057e;
+
+/// How face buttons of Nintendo controllers are mapped.
+///
+/// Nintendo controllers have A and B, and X and Y buttons swapped compared to Xbox controllers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NintendoLayout {
+    /// Buttons are mapped by their position, so `Button::South` is the lower button (labeled B).
+    /// This is the same as for other controllers.
+    Positional,
+    /// Buttons are mapped by their label, so `Button::South` is button labeled A (right one).
+    ByLabel,
+}
+
+/// Running estimate of noise reported by idle axis.
+#[derive(Copy, Clone, Debug, Default)]
+struct NoiseFloor {
+    last: Option<f32>,
+    floor: f32,
+}
+
+impl NoiseFloor {
+    /// Changes bigger than this are treated as deliberate movement.
+    const MAX_NOISE: f32 = 0.02;
+    /// How fast estimate follows new samples.
+    const SMOOTHING: f32 = 0.05;
+
+    fn update(&mut self, value: f32) {
+        if let Some(last) = self.last {
+            let delta = (value - last).abs();
+            if delta <= Self::MAX_NOISE {
+                self.floor += (delta - self.floor) * Self::SMOOTHING;
+            }
+        }
+
+        self.last = Some(value);
+    }
+}
+
 /// Source of gamepad mappings.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MappingSource {
@@ -1025,6 +2883,11 @@ pub enum MappingSource {
 ///
 /// It's not possible to create instance of this type directly, but you can obtain one from Gamepad
 /// handle or any event. ID is valid for entire lifetime of `Gilrs` context.
+///
+/// When gamepad is reconnected, it gets back its old ID if the backend can tell it's the same
+/// device. On Linux devices are matched by serial number, then by port they are plugged into, and
+/// then by UUID; a gamepad with different serial number never takes over the ID. XInput only
+/// exposes 4 numbered slots, so on Windows ID is the slot number.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct GamepadId(pub(crate) usize);
@@ -1041,7 +2904,7 @@ impl Display for GamepadId {
     }
 }
 
-fn axis_value(info: &AxisInfo, val: i32, axis: Axis) -> f32 {
+fn axis_value(info: &AxisInfo, val: i32, axis: Axis, y_reversed: bool) -> f32 {
     let mut range = info.max as f32 - info.min as f32;
     let mut val = val as f32 - info.min as f32;
 
@@ -1057,14 +2920,18 @@ fn axis_value(info: &AxisInfo, val: i32, axis: Axis) -> f32 {
 
     val = val / range * 2.0 - 1.0;
 
-    if gilrs_core::IS_Y_AXIS_REVERSED
+    utils::clamp(reverse_y(val, axis, y_reversed), -1.0, 1.0)
+}
+
+fn reverse_y(val: f32, axis: Axis, y_reversed: bool) -> f32 {
+    if y_reversed
         && (axis == Axis::LeftStickY || axis == Axis::RightStickY || axis == Axis::DPadY)
         && val != 0.0
     {
-        val = -val;
+        -val
+    } else {
+        val
     }
-
-    utils::clamp(val, -1.0, 1.0)
 }
 
 fn btn_value(info: &AxisInfo, val: i32) -> f32 {
@@ -1075,7 +2942,27 @@ fn btn_value(info: &AxisInfo, val: i32) -> f32 {
     utils::clamp(val, 0.0, 1.0)
 }
 
-/// Error type which can be returned when creating `Gilrs`.
+/// Error type returned when creating `Gilrs`.
+///
+/// It also has variants for errors returned by mapping and force feedback functions, and for
+/// problems reported by backend, so applications can use it as single error type for everything
+/// related to gamepads:
+///
+/// ```
+/// use gilrs::{Error, Gilrs};
+///
+/// fn init() -> Result<Gilrs, Error> {
+///     let mut gilrs = Gilrs::new()?;
+///     if let Some(err) = gilrs.next_backend_error() {
+///         return Err(err.into());
+///     }
+///
+///     Ok(gilrs)
+/// }
+/// ```
+// Boxing `Gilrs` in `NotImplemented` would be breaking change.
+#[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
 #[derive(Debug)]
 pub enum Error {
     /// Gilrs does not support current platform, but you can use dummy context from this error if
@@ -1083,6 +2970,20 @@ pub enum Error {
     NotImplemented(Gilrs),
     /// Either `pressed ≤ released` or one of values is outside [0.0, 1.0] range.
     InvalidAxisToBtn,
+    /// Deadzone set with `GilrsBuilder::set_deadzone()` is outside [0.0, 1.0) range.
+    InvalidDeadzone,
+    /// Duration set with `GilrsBuilder::set_ff_tick_duration()` is outside [1 ms, 50 ms] range.
+    InvalidFfTickDuration,
+    /// Mapping could not be created or applied.
+    Mapping(MappingError),
+    /// Force feedback operation failed.
+    Ff(FfError),
+    /// Platform backend stopped working and couldn't be restarted. Returned by
+    /// `Gilrs::try_next_event()`.
+    BackendDied,
+    /// Platform backend failed to perform I/O operation. See `BackendError::code` for platform
+    /// specific error code.
+    Backend(BackendError),
     /// Platform specific error.
     Other(Box<dyn error::Error + Send + Sync + 'static>),
 }
@@ -1094,6 +2995,14 @@ impl Display for Error {
             Error::InvalidAxisToBtn => f.write_str(
                 "Either `pressed ≤ released` or one of values is outside [0.0, 1.0] range.",
             ),
+            Error::InvalidDeadzone => f.write_str("Deadzone is outside [0.0, 1.0) range."),
+            Error::InvalidFfTickDuration => {
+                f.write_str("Force feedback tick duration is outside [1 ms, 50 ms] range.")
+            }
+            Error::BackendDied => f.write_str("Platform backend stopped working."),
+            Error::Mapping(ref e) => e.fmt(f),
+            Error::Ff(ref e) => e.fmt(f),
+            Error::Backend(ref e) => e.fmt(f),
             Error::Other(ref e) => e.fmt(f),
         }
     }
@@ -1102,15 +3011,352 @@ impl Display for Error {
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
+            Error::Mapping(e) => Some(e),
+            Error::Ff(e) => Some(e),
+            Error::Backend(e) => Some(e),
             Error::Other(e) => Some(e.as_ref()),
             _ => None,
         }
     }
 }
 
+impl From<MappingError> for Error {
+    fn from(err: MappingError) -> Self {
+        Error::Mapping(err)
+    }
+}
+
+impl From<FfError> for Error {
+    fn from(err: FfError) -> Self {
+        Error::Ff(err)
+    }
+}
+
+impl From<BackendError> for Error {
+    fn from(err: BackendError) -> Self {
+        Error::Backend(err)
+    }
+}
+
+/// Error returned by `Gamepad::set_exclusive()`, `Gamepad::claim_exclusive()` and
+/// `Gamepad::release_exclusive()`.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum ClaimError {
+    /// Gamepad is not connected.
+    Disconnected(GamepadId),
+    /// Platform can't give exclusive access to gamepads.
+    NotSupported(GamepadId),
+    /// Platform backend failed to grab or release the device.
+    Io(io::Error),
+}
+
+impl Display for ClaimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClaimError::Disconnected(id) => write!(f, "Gamepad {} is disconnected.", id),
+            ClaimError::NotSupported(id) => {
+                write!(f, "Exclusive access to gamepad {} is not supported.", id)
+            }
+            ClaimError::Io(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for ClaimError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ClaimError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Error returned by `Gamepad::set_mic_mute_led()` and `Gamepad::set_lightbar_color()`.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum LedError {
+    /// Gamepad is not connected.
+    Disconnected(GamepadId),
+    /// Gamepad doesn't have the LED or platform can't control it.
+    NotSupported(GamepadId),
+    /// Platform backend failed to change the LED.
+    Io(io::Error),
+}
+
+impl Display for LedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedError::Disconnected(id) => write!(f, "Gamepad {} is disconnected.", id),
+            LedError::NotSupported(id) => {
+                write!(f, "Gamepad {} doesn't have controllable LED.", id)
+            }
+            LedError::Io(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for LedError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            LedError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Non-fatal problem reported by platform backend. See
+/// [`Gilrs::next_backend_error()`](struct.Gilrs.html#method.next_backend_error).
+#[derive(Clone, PartialEq, Debug)]
+pub struct BackendError {
+    /// Id of gamepad this error is related to, if known.
+    pub id: Option<GamepadId>,
+    /// Operation that failed.
+    pub kind: BackendErrorKind,
+    /// Platform specific description of the error.
+    pub description: String,
+    /// Platform specific error code, like `errno` on Linux or code returned by XInput on Windows.
+    pub code: Option<i32>,
+    /// Time when error occurred.
+    pub time: SystemTime,
+}
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "{} (gamepad {}): {}", self.kind, id, self.description),
+            None => write!(f, "{}: {}", self.kind, self.description),
+        }
+    }
+}
+
+impl error::Error for BackendError {}
+
+/// Events processed by one call to [`Gilrs::pump()`](struct.Gilrs.html#method.pump).
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PumpSummary {
+    events: usize,
+    pressed: Vec<(GamepadId, Button)>,
+    released: Vec<(GamepadId, Button)>,
+    axes_changed: Vec<(GamepadId, Axis)>,
+    connected: Vec<GamepadId>,
+    disconnected: Vec<GamepadId>,
+}
+
+impl PumpSummary {
+    /// Number of events processed, including ones not covered by other methods.
+    pub fn events(&self) -> usize {
+        self.events
+    }
+
+    /// Buttons pressed during this pump, in order. The same button can appear more than once.
+    pub fn pressed(&self) -> &[(GamepadId, Button)] {
+        &self.pressed
+    }
+
+    /// Buttons released during this pump, in order.
+    pub fn released(&self) -> &[(GamepadId, Button)] {
+        &self.released
+    }
+
+    /// Axes whose value changed during this pump. Each axis is listed once, use
+    /// `Gamepad::value()` to get its current value.
+    pub fn axes_changed(&self) -> &[(GamepadId, Axis)] {
+        &self.axes_changed
+    }
+
+    /// Gamepads connected during this pump.
+    pub fn connected(&self) -> &[GamepadId] {
+        &self.connected
+    }
+
+    /// Gamepads disconnected during this pump.
+    pub fn disconnected(&self) -> &[GamepadId] {
+        &self.disconnected
+    }
+
+    /// Returns true if `btn` was pressed on gamepad `id` during this pump.
+    pub fn was_pressed(&self, id: GamepadId, btn: Button) -> bool {
+        self.pressed.contains(&(id, btn))
+    }
+
+    /// Returns true if `btn` was released on gamepad `id` during this pump.
+    pub fn was_released(&self, id: GamepadId, btn: Button) -> bool {
+        self.released.contains(&(id, btn))
+    }
+}
+
+/// Counters describing health of event pipeline. See
+/// [`Gilrs::metrics()`](struct.Gilrs.html#method.metrics).
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Metrics {
+    events_delivered: u64,
+    events_dropped: u64,
+    events_filtered: u64,
+    queue_depth: usize,
+    queue_high_water_mark: usize,
+    polls: u64,
+    last_poll_duration: Duration,
+    max_poll_duration: Duration,
+}
+
+impl Metrics {
+    /// Number of events returned by `Gilrs::next_event()`.
+    pub fn events_delivered(&self) -> u64 {
+        self.events_delivered
+    }
+
+    /// Number of events that backend discarded because its queue was full, reported by
+    /// `EventType::Overflow`.
+    pub fn events_dropped(&self) -> u64 {
+        self.events_dropped
+    }
+
+    /// Number of events dropped by default filters, for example by deadzone or jitter filter.
+    pub fn events_filtered(&self) -> u64 {
+        self.events_filtered
+    }
+
+    /// Number of events currently waiting in internal queue.
+    pub fn queue_depth(&self) -> usize {
+        self.queue_depth
+    }
+
+    /// The biggest number of events that waited in internal queue at once.
+    pub fn queue_high_water_mark(&self) -> usize {
+        self.queue_high_water_mark
+    }
+
+    /// Number of times platform backend was polled for new events.
+    pub fn polls(&self) -> u64 {
+        self.polls
+    }
+
+    /// Time spent in the most recent backend poll.
+    pub fn last_poll_duration(&self) -> Duration {
+        self.last_poll_duration
+    }
+
+    /// The longest time spent in single backend poll.
+    pub fn max_poll_duration(&self) -> Duration {
+        self.max_poll_duration
+    }
+
+    fn record_poll(&mut self, duration: Duration) {
+        self.polls += 1;
+        self.last_poll_duration = duration;
+        self.max_poll_duration = self.max_poll_duration.max(duration);
+    }
+
+    fn record_queue_depth(&mut self, depth: usize) {
+        self.queue_high_water_mark = self.queue_high_water_mark.max(depth);
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{axis_value, Axis, AxisInfo};
+    use super::{
+        axis_value, Axis, AxisInfo, Button, Code, GamepadId, GilrsBuilder, MappingData, Metrics,
+        NoiseFloor, QuirkDb,
+    };
+    use gilrs_core::native_ev_codes as nec;
+    use gilrs_core::{Backend, BackendGamepad, EvCode, Event};
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    struct TestGamepad;
+
+    impl BackendGamepad for TestGamepad {
+        fn name(&self) -> &str {
+            "Test"
+        }
+
+        fn uuid(&self) -> [u8; 16] {
+            // Vendor ID 0x0079, product ID 0x0006.
+            [3, 0, 0, 0, 0x79, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0]
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn buttons(&self) -> &[EvCode] {
+            &[nec::BTN_LT2, nec::BTN_RT2]
+        }
+
+        fn axes(&self) -> &[EvCode] {
+            &[]
+        }
+
+        fn axis_info(&self, _nec: EvCode) -> Option<&AxisInfo> {
+            None
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestBackend(TestGamepad);
+
+    impl Backend for TestBackend {
+        fn next_event(&mut self) -> Option<Event> {
+            None
+        }
+
+        fn gamepad(&self, id: usize) -> Option<&(dyn BackendGamepad + 'static)> {
+            if id == 0 {
+                Some(&self.0)
+            } else {
+                None
+            }
+        }
+
+        fn last_gamepad_hint(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn without_native_backend() {
+        let mut gilrs = GilrsBuilder::new()
+            .set_native_backend(false)
+            .build()
+            .unwrap();
+
+        assert!(gilrs.next_event().is_none());
+        assert_eq!(gilrs.gamepads().count(), 0);
+        assert!(gilrs.raw_wait_handle().is_none());
+    }
+
+    #[test]
+    fn swap_triggers_quirk_keeps_user_mapping() {
+        let mut gilrs = GilrsBuilder::new()
+            .set_native_backend(false)
+            .add_included_mappings(false)
+            .add_env_mappings(false)
+            .add_backend(TestBackend(TestGamepad))
+            .build()
+            .unwrap();
+        let swap = "[[quirk]]\nvendor = 0x0079\nswap_triggers = true\n";
+        let lt2 = |gilrs: &super::Gilrs| {
+            gilrs
+                .gamepad(GamepadId(0))
+                .button_code(Button::LeftTrigger2)
+        };
+
+        gilrs.set_quirks(QuirkDb::parse(swap).unwrap());
+        assert_eq!(lt2(&gilrs), Some(Code(nec::BTN_RT2)));
+
+        let mut data = MappingData::new();
+        data.insert_btn(Code(nec::BTN_LT2), Button::LeftTrigger2);
+        data.insert_btn(Code(nec::BTN_RT2), Button::RightTrigger2);
+        gilrs.set_mapping(0, &data, None).unwrap();
+        assert!(!gilrs.gamepad(GamepadId(0)).quirk().swap_triggers);
+
+        // Neither removing nor adding quirk again changes user's mapping.
+        gilrs.set_quirks(QuirkDb::default());
+        assert_eq!(lt2(&gilrs), Some(Code(nec::BTN_LT2)));
+        gilrs.set_quirks(QuirkDb::parse(swap).unwrap());
+        assert_eq!(lt2(&gilrs), Some(Code(nec::BTN_LT2)));
+    }
 
     #[test]
     fn axis_value_documented_case() {
@@ -1120,7 +3366,7 @@ mod tests {
             deadzone: None,
         };
         let axis = Axis::LeftStickY;
-        assert_eq!(0., axis_value(&info, 127, axis));
+        assert_eq!(0., axis_value(&info, 127, axis, true));
     }
     #[test]
     fn axis_value_overflow() {
@@ -1131,8 +3377,36 @@ mod tests {
         };
         let axis = Axis::LeftStickY;
 
-        assert_eq!(0., axis_value(&info, -1, axis));
-        assert_eq!(0., axis_value(&info, 0, axis));
-        assert_eq!(0., axis_value(&info, 1, axis));
+        assert_eq!(0., axis_value(&info, -1, axis, true));
+        assert_eq!(0., axis_value(&info, 0, axis, true));
+        assert_eq!(0., axis_value(&info, 1, axis, true));
+    }
+
+    #[test]
+    fn metrics_keep_maximums() {
+        let mut metrics = Metrics::default();
+        metrics.record_poll(Duration::from_millis(5));
+        metrics.record_poll(Duration::from_millis(1));
+        metrics.record_queue_depth(3);
+        metrics.record_queue_depth(1);
+
+        assert_eq!(metrics.polls(), 2);
+        assert_eq!(metrics.last_poll_duration(), Duration::from_millis(1));
+        assert_eq!(metrics.max_poll_duration(), Duration::from_millis(5));
+        assert_eq!(metrics.queue_high_water_mark(), 3);
+    }
+
+    #[test]
+    fn noise_floor_ignores_movement() {
+        let mut noise = NoiseFloor::default();
+        for i in 0..200 {
+            noise.update(if i % 2 == 0 { 0.005 } else { -0.005 });
+        }
+        let idle = noise.floor;
+        assert!(idle > 0.008 && idle <= 0.01, "{}", idle);
+
+        noise.update(1.0);
+        noise.update(-1.0);
+        assert_eq!(noise.floor, idle);
     }
 }
@@ -37,7 +37,7 @@
 //!
 //! loop {
 //!     // Examine new events
-//!     while let Some(Event { id, event, time }) = gilrs.next_event() {
+//!     while let Some(Event { id, event, time, .. }) = gilrs.next_event() {
 //!         println!("{:?} New event from {}: {:?}", time, id, event);
 //!         active_gamepad = Some(id);
 //!     }
@@ -58,10 +58,13 @@
 //! |                  | Input | Hotplugging | Force feedback |
 //! |------------------|:-----:|:-----------:|:--------------:|
 //! | Linux            |   ✓   |      ✓      |        ✓       |
+//! | FreeBSD          |   ✓   |      ✓      |        ✓       |
 //! | Windows (XInput) |   ✓   |      ✓      |        ✓       |
+//! | Windows (HID)    |   ✓   |      ✓      |        ✕       |
 //! | OS X             |   ✓   |      ✓      |        ✕       |
-//! | Wasm             |   ✓   |      ✓      |       n/a      |
-//! | Android          |   ✕   |      ✕      |        ✕       |
+//! | Wasm             |   ✓   |      ✓      |  experimental  |
+//! | Android          |   ✓   |      ✓      |        ✕       |
+//! | iOS/tvOS         |   ✓   |      ✓      |        ✓       |
 //!
 //! Controller layout
 //! -----------------
@@ -82,8 +85,58 @@
 //! Cargo features
 //! --------------
 //!
+//! - `log` (enabled by default) - report what library is doing through [`log`](#logging).
 //! - `serde-serialize` - enable deriving of serde's `Serialize` and `Deserialize` for
 //!   various types.
+//! - `tracing` - emit [`tracing`](https://docs.rs/tracing) spans around polling backend, mapping
+//!   events and running filters.
+//! - `steam` - on Linux, macOS and Windows read gamepads through Steam Input when game is
+//!   started by Steam (see [Steam Input](#steam-input)).
+//! - `hidapi` - on Linux, macOS and Windows read some controllers directly with
+//!   [`hidapi`](https://docs.rs/hidapi) (see [HID drivers](#hid-drivers)). Requires Rust 1.79.
+//! - `adaptive-triggers` - enable [`ff::adaptive_trigger`](ff/adaptive_trigger/index.html)
+//!   module that sets resistance of DualSense triggers. Implies `hidapi`.
+//! - `audio-haptics` - enable [`ff::audio`](ff/audio/index.html) module that converts audio
+//!   samples to rumble.
+//! - `gpio` - on Linux enable `gilrs_core::GpioBackend` for joysticks wired to GPIO pins (see
+//!   [GPIO joysticks](#gpio-joysticks)).
+//! - `net` - enable [`net`](net/index.html) module with backend for gamepads connected over
+//!   network and sender of local gamepads' events.
+//! - `virtual-gamepad` - on Linux and Windows enable [`output`](output/index.html) module with
+//!   gamepads that other applications can read.
+//! - `wasm-ff` - experimental force feedback on wasm (see [Wasm](#wasm)).
+//!
+//! Environment variables
+//! ---------------------
+//!
+//! End users can work around problems with their gamepads without changes to application:
+//!
+//! - `SDL_GAMECONTROLLERCONFIG` - additional SDL mappings, one per line.
+//! - `GILRS_GAMECONTROLLERCONFIG` - the same as above, but takes precedence, so it can fix
+//!   mappings set by Steam.
+//! - `GILRS_MAPPINGS_FILE` - path to file with additional SDL mappings. Both variables above take
+//!   precedence over it.
+//! - `GILRS_DISABLE_FF` - if set, force feedback devices are not opened.
+//! - `GILRS_BACKEND` - `native` to use platform's backend even if application disabled it,
+//!   `dummy` to not report any gamepads.
+//! - `GILRS_POLL_MS` - time between polls of backends that poll devices (XInput on Windows),
+//!   overrides `GilrsBuilder::set_poll_interval()` and `PollMode::LowLatency`. Defaults to 10.
+//! - `GILRS_QUIRKS_FILE` - path to TOML file with fixes for controllers (see `Quirk`), overrides
+//!   `GilrsBuilder::set_quirks_file()`.
+//!
+//! Variables are read when `Gilrs` is created. Mappings from environment are not loaded if
+//! application disabled them with `GilrsBuilder::add_env_mappings(false)`. To enable debug logs,
+//! use your application's logger (see below).
+//!
+//! Logging
+//! -------
+//!
+//! GilRs reports device discovery, hotplug, selected mappings, force feedback uploads and backend
+//! errors using the [`log`](https://docs.rs/log) crate, mostly at `debug` and `trace` level. If
+//! your controller is not detected, enable one of `log`'s backends (for example
+//! [`env_logger`](https://docs.rs/env_logger) with `RUST_LOG=gilrs=trace,gilrs_core=trace`). To
+//! remove these messages from your binary, disable default `log` feature or use `log`'s
+//! `max_level_*` and `release_max_level_*` features.
 //!
 //! Platform specific notes
 //! ======================
@@ -98,6 +151,73 @@
 //! To build GilRs, you will need pkg-config and libudev .pc file. On some
 //! distributions this file is packaged in separate archive (for example `libudev-dev` in Debian).
 //!
+//! Windows
+//! -------
+//!
+//! XInput is loaded at runtime from `xinput1_4.dll`, `xinput1_3.dll` or `xinput9_1_0.dll`,
+//! whichever is found first (`Gilrs::backend_info()` reports which one). If none of them exists,
+//! for example on Wine without XInput, only gamepads handled by HID backend are reported.
+//!
+//! Steam Input
+//! -----------
+//!
+//! With `steam` feature, GilRs loads Steamworks API library (`steam_api64.dll`,
+//! `libsteam_api.so` or `libsteam_api.dylib`) at runtime. If it's found and Steam Input can be
+//! initialized, gamepads are read through it instead of native backend, so they are not reported
+//! twice and use configuration chosen by user in Steam. Otherwise native backend is used.
+//!
+//! Game's input configuration must contain action set `gamepad` (see
+//! `GilrsBuilder::set_steam_action_set()`) with digital actions `south`, `east`, `north`,
+//! `west`, `left_bumper`, `right_bumper`, `select`, `start`, `mode`, `left_thumb`, `right_thumb`,
+//! `dpad_up`, `dpad_down`, `dpad_left`, `dpad_right` and analog actions `left_stick`,
+//! `right_stick`, `left_trigger`, `right_trigger`. Back buttons of Steam Deck (`l4`, `r4`, `l5`,
+//! `r5` actions) are reported as `Button::Unknown`. Actions missing from configuration are
+//! skipped. Steam Input doesn't report UUID of gamepads, so SDL mappings are not used.
+//!
+//! HID drivers
+//! -----------
+//!
+//! With `hidapi` feature, some controllers are read by GilRs' own drivers, over USB and
+//! Bluetooth. Unlike native backends, drivers report motion sensors (`EventType::Motion`) and
+//! battery level, and support rumble:
+//!
+//! - Switch Pro Controller and Joy-Cons (each Joy-Con is separate gamepad).
+//! - DualShock 4 and DualSense. They also report touchpad (`EventType::Touch`) and their
+//!   lightbar can be changed with `Gamepad::set_lightbar_color()`. Microphone button of DualSense
+//!   is `Button::Misc1` and its LED is controlled by `Gamepad::set_mic_mute_led()`.
+//!
+//! While controller is handled by driver, native gamepad representing it is reported as
+//! disconnected. On Linux, controllers bound to `hid-nintendo`, `hid-playstation` or `hid-sony`
+//! kernel driver are left to native backend.
+//!
+//! Driver's gamepads are polled when `Gilrs::next_event()` or `Gilrs::wait()` is called, so
+//! `Gilrs::raw_wait_handle()` is not available with this feature. On Linux, user needs
+//! permission to open `/dev/hidraw*` devices.
+//!
+//! GPIO joysticks
+//! --------------
+//!
+//! Buttons and sticks of arcade cabinets and handheld builds are often connected to GPIO and ADC
+//! pins and exposed by `gpio-keys` and `adc-joystick` kernel drivers. These devices usually
+//! report keyboard keys, so they are not recognized as gamepads. With `gpio` feature,
+//! `gilrs_core::GpioBackend` combines them into gamepads with configured button and axis codes:
+//!
+//! ```ignore
+//! use gilrs::GilrsBuilder;
+//! use gilrs_core::{native_ev_codes as nec, GpioBackend, GpioGamepad};
+//!
+//! let stick = GpioGamepad::new("Arcade stick")
+//!     .add_device("gpio-keys")
+//!     .map_button(28, nec::BTN_SOUTH)
+//!     .map_button(103, nec::BTN_DPAD_UP);
+//! let gilrs = GilrsBuilder::new()
+//!     .add_backend(GpioBackend::new().add_gamepad(stick))
+//!     .build()
+//!     .unwrap();
+//! ```
+//!
+//! Codes are translated to `Button` and `Axis` by default mapping, SDL mappings are not used.
+//!
 //! Wasm
 //! ----
 //!
@@ -106,22 +226,71 @@
 //! wasm32-unknown-unknown. For wasm-bindgen, you will need the wasm-bindgen cli or a tool like
 //! [wasm-pack](https://rustwasm.github.io/wasm-pack/installer/).
 //! Unlike other platforms, events are only generated when you call `Gilrs::next_event()`.
+//! Force feedback is experimental and has to be enabled with `wasm-ff` feature. It is updated from
+//! `Gilrs::next_event()` and requires browser that implements `GamepadHapticActuator`.
 
+#[cfg(feature = "log")]
 #[macro_use]
-extern crate log;
+extern crate log_rs as log;
+
+// Without `log` feature, logging macros only type check their arguments.
+#[cfg(not(feature = "log"))]
+macro_rules! log_stub {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+#[cfg(not(feature = "log"))]
+macro_rules! trace { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+#[cfg(not(feature = "log"))]
+macro_rules! debug { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+#[cfg(not(feature = "log"))]
+macro_rules! info { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+#[cfg(not(feature = "log"))]
+macro_rules! warn { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+#[cfg(not(feature = "log"))]
+macro_rules! error { ($($arg:tt)*) => { log_stub!($($arg)*) }; }
+
+/// Enters `tracing` span that lasts until the end of current scope. Expands to nothing if
+/// `tracing` feature is disabled.
+macro_rules! enter_span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($($args)*).entered();
+    };
+}
 
 mod constants;
 mod gamepad;
 mod mapping;
+mod quirks;
 mod utils;
 
 pub mod ev;
 pub mod ff;
+#[cfg(all(feature = "futures", not(target_arch = "wasm32")))]
+pub mod futures;
+pub mod lobby;
+#[cfg(all(feature = "net", not(target_arch = "wasm32")))]
+pub mod net;
+#[cfg(all(
+    feature = "virtual-gamepad",
+    any(target_os = "linux", target_os = "windows")
+))]
+pub mod output;
 
 pub use crate::ev::filter::Filter;
 pub use crate::ev::{Axis, Button, Event, EventType};
 pub use crate::gamepad::{
-    ConnectedGamepadsIterator, Error, Gamepad, GamepadId, Gilrs, GilrsBuilder, MappingSource,
-    PowerInfo,
+    BackendError, BackendErrorKind, BackendInfo, ClaimError, ConnectedGamepadsIterator,
+    ConnectedInfo, Error, EventsIterator, Gamepad, GamepadEventsIterator, GamepadId, Gilrs,
+    GilrsBuilder, LedError, MappingSource, Metrics, MotionData, NintendoLayout, PollMode,
+    PowerInfo, PumpSummary, RawWaitHandle, TouchData,
+};
+pub use crate::mapping::{
+    validate_mapping, AxisRange, AxisTransform, MappingData as Mapping, MappingDb, MappingEntry,
+    MappingError, MappingOrigin, MappingSummary, ResolvedMapping,
 };
-pub use crate::mapping::{MappingData as Mapping, MappingError};
+pub use crate::quirks::{Quirk, QuirksError};
@@ -0,0 +1,453 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! "Press button to join" helper for local multiplayer.
+//!
+//! `Lobby` assigns gamepads to player slots when they press join button and frees slots when
+//! player presses leave button or gamepad is disconnected. Feed it every event returned by
+//! `Gilrs::next_event()`, including events from gamepads that were connected after lobby was
+//! created.
+//!
+//! `Session` builds on `Lobby` for drop-in/drop-out games. Instead of freeing the slot when
+//! claimed gamepad is disconnected, it pauses and waits until the same physical device is
+//! connected again. Devices are recognized by `GamepadId`, which backends give back to reconnected
+//! gamepad when they can tell it's the same device (see [`GamepadId`](../struct.GamepadId.html)),
+//! so another gamepad of the same model doesn't take over the slot.
+//!
+//! # Example
+//!
+//! ```
+//! use gilrs::{Button, Gilrs};
+//! use gilrs::lobby::{Lobby, LobbyEvent};
+//!
+//! let mut gilrs = Gilrs::new().unwrap();
+//! let mut lobby = Lobby::new(4).leave_button(Some(Button::East));
+//!
+//! loop {
+//!     while let Some(event) = gilrs.next_event() {
+//!         match lobby.handle_event(&event) {
+//!             Some(LobbyEvent::Join { slot, id }) => println!("{} joined as {}", id, slot),
+//!             Some(LobbyEvent::Leave { slot, .. }) => println!("Player {} left", slot),
+//!             _ => (),
+//!         }
+//!     }
+//!     # break;
+//! }
+//! ```
+
+use crate::ev::{Button, Event, EventType};
+use crate::gamepad::{GamepadId, Gilrs};
+
+/// Event emitted by `Lobby` when player slot changes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LobbyEvent {
+    /// Gamepad `id` has been assigned to `slot`.
+    Join { slot: usize, id: GamepadId },
+    /// Gamepad `id` no longer occupy `slot`.
+    Leave {
+        slot: usize,
+        id: GamepadId,
+        reason: LeaveReason,
+    },
+    /// Gamepad `id` that occupy `slot` has been disconnected. Only emitted by `Session`.
+    Paused { slot: usize, id: GamepadId },
+    /// Gamepad `id` that was disconnected from `slot` is back. Only emitted by `Session`.
+    Resumed { slot: usize, id: GamepadId },
+}
+
+/// Why player left the lobby.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LeaveReason {
+    /// Leave button has been pressed.
+    Button,
+    /// Gamepad has been disconnected.
+    Disconnected,
+    /// Slot has been freed by calling `Lobby::remove()` or `Lobby::clear()`.
+    Removed,
+}
+
+/// Assigns gamepads to player slots. See [module documentation](index.html) for details.
+#[derive(Clone, Debug)]
+pub struct Lobby {
+    slots: Vec<Option<GamepadId>>,
+    join_button: Button,
+    leave_button: Option<Button>,
+}
+
+impl Lobby {
+    /// Creates new lobby for up to `max_players` players. By default gamepads join by pressing
+    /// `Button::Start` and there is no leave button.
+    pub fn new(max_players: usize) -> Self {
+        Lobby {
+            slots: vec![None; max_players],
+            join_button: Button::Start,
+            leave_button: None,
+        }
+    }
+
+    /// Sets button that assigns gamepad to the first free slot.
+    pub fn join_button(mut self, button: Button) -> Self {
+        self.join_button = button;
+        self
+    }
+
+    /// Sets button that frees gamepad's slot. `None` disables leaving by button.
+    pub fn leave_button(mut self, button: Option<Button>) -> Self {
+        self.leave_button = button;
+        self
+    }
+
+    /// Updates lobby with `event` and returns what changed, if anything.
+    ///
+    /// Join button is ignored when lobby is full or gamepad already has a slot.
+    pub fn handle_event(&mut self, event: &Event) -> Option<LobbyEvent> {
+        match event.event {
+            EventType::ButtonPressed(btn, _) if btn == self.join_button => self.join(event.id),
+            EventType::ButtonPressed(btn, _) if Some(btn) == self.leave_button => {
+                self.leave(event.id, LeaveReason::Button)
+            }
+            EventType::Disconnected => self.leave(event.id, LeaveReason::Disconnected),
+            _ => None,
+        }
+    }
+
+    /// Frees slot occupied by gamepad `id`.
+    pub fn remove(&mut self, id: GamepadId) -> Option<LobbyEvent> {
+        self.leave(id, LeaveReason::Removed)
+    }
+
+    /// Frees all slots, returning `Leave` event for each player.
+    pub fn clear(&mut self) -> Vec<LobbyEvent> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(slot, id)| {
+                id.take().map(|id| LobbyEvent::Leave {
+                    slot,
+                    id,
+                    reason: LeaveReason::Removed,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns slot occupied by gamepad `id`.
+    pub fn slot(&self, id: GamepadId) -> Option<usize> {
+        self.slots.iter().position(|&slot| slot == Some(id))
+    }
+
+    /// Returns gamepad assigned to `slot`.
+    pub fn player(&self, slot: usize) -> Option<GamepadId> {
+        self.slots.get(slot).and_then(|&id| id)
+    }
+
+    /// Returns iterator over occupied slots and their gamepads.
+    pub fn players(&self) -> impl Iterator<Item = (usize, GamepadId)> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, id)| id.map(|id| (slot, id)))
+    }
+
+    /// Returns number of occupied slots.
+    pub fn len(&self) -> usize {
+        self.players().count()
+    }
+
+    /// Returns `true` if no gamepad has joined.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if all slots are occupied.
+    pub fn is_full(&self) -> bool {
+        self.slots.iter().all(Option::is_some)
+    }
+
+    /// Returns maximum number of players.
+    pub fn max_players(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn join(&mut self, id: GamepadId) -> Option<LobbyEvent> {
+        if self.slot(id).is_some() {
+            return None;
+        }
+
+        let slot = self.slots.iter().position(Option::is_none)?;
+        self.slots[slot] = Some(id);
+
+        Some(LobbyEvent::Join { slot, id })
+    }
+
+    fn leave(&mut self, id: GamepadId, reason: LeaveReason) -> Option<LobbyEvent> {
+        let slot = self.slot(id)?;
+        self.slots[slot] = None;
+
+        Some(LobbyEvent::Leave { slot, id, reason })
+    }
+}
+
+/// Tracks claimed gamepads and pauses when one of them is disconnected.
+///
+/// ```
+/// use gilrs::Gilrs;
+/// use gilrs::lobby::{Lobby, LobbyEvent, Session};
+///
+/// let mut gilrs = Gilrs::new().unwrap();
+/// let mut session = Session::new(Lobby::new(2));
+///
+/// loop {
+///     while let Some(event) = gilrs.next_event() {
+///         if let Some(LobbyEvent::Paused { slot, .. }) = session.handle_event(&event, &gilrs) {
+///             println!("Reconnect gamepad of player {}", slot);
+///         }
+///     }
+///
+///     if !session.is_paused() {
+///         // Update game
+///     }
+///     # break;
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Session {
+    lobby: Lobby,
+    claims: Vec<Option<Claim>>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Claim {
+    uuid: Option<[u8; 16]>,
+    connected: bool,
+}
+
+impl Session {
+    /// Creates new session that assigns slots using `lobby`.
+    pub fn new(lobby: Lobby) -> Self {
+        let mut claims = vec![None; lobby.max_players()];
+        for (slot, _) in lobby.players() {
+            // We don't know UUID of gamepads that joined before, so only their ID is checked.
+            claims[slot] = Some(Claim {
+                uuid: None,
+                connected: true,
+            });
+        }
+
+        Session { lobby, claims }
+    }
+
+    /// Updates session with `event` and returns what changed, if anything. `gilrs` is used to get
+    /// UUID of gamepads. Gamepad that gets ID of disconnected player, but has different UUID (for
+    /// example other model plugged into the same port), doesn't resume the session.
+    pub fn handle_event(&mut self, event: &Event, gilrs: &Gilrs) -> Option<LobbyEvent> {
+        let uuid = gilrs.connected_gamepad(event.id).map(|gp| gp.uuid());
+        self.handle_event_with_uuid(event, uuid)
+    }
+
+    fn handle_event_with_uuid(
+        &mut self,
+        event: &Event,
+        uuid: Option<[u8; 16]>,
+    ) -> Option<LobbyEvent> {
+        match event.event {
+            EventType::Disconnected => {
+                let slot = self.lobby.slot(event.id)?;
+                let claim = self.claims[slot].as_mut()?;
+                claim.connected = false;
+
+                Some(LobbyEvent::Paused { slot, id: event.id })
+            }
+            EventType::Connected => {
+                let slot = self.lobby.slot(event.id)?;
+                let claim = self.claims[slot].as_mut()?;
+                if claim.connected || (claim.uuid.is_some() && claim.uuid != uuid) {
+                    return None;
+                }
+
+                claim.connected = true;
+                claim.uuid = claim.uuid.or(uuid);
+
+                Some(LobbyEvent::Resumed { slot, id: event.id })
+            }
+            _ => {
+                let ev = self.lobby.handle_event(event);
+                match ev {
+                    Some(LobbyEvent::Join { slot, .. }) => {
+                        self.claims[slot] = Some(Claim {
+                            uuid,
+                            connected: true,
+                        })
+                    }
+                    Some(LobbyEvent::Leave { slot, .. }) => self.claims[slot] = None,
+                    _ => (),
+                }
+
+                ev
+            }
+        }
+    }
+
+    /// Frees slot occupied by gamepad `id`, for example when player doesn't want to wait for
+    /// disconnected gamepad.
+    pub fn remove(&mut self, id: GamepadId) -> Option<LobbyEvent> {
+        let ev = self.lobby.remove(id);
+        if let Some(LobbyEvent::Leave { slot, .. }) = ev {
+            self.claims[slot] = None;
+        }
+
+        ev
+    }
+
+    /// Returns `true` if any claimed gamepad is disconnected.
+    pub fn is_paused(&self) -> bool {
+        self.disconnected().next().is_some()
+    }
+
+    /// Returns iterator over slots, which gamepads are disconnected, and their last IDs.
+    pub fn disconnected(&self) -> impl Iterator<Item = (usize, GamepadId)> + '_ {
+        let claims = &self.claims;
+        self.lobby
+            .players()
+            .filter(move |&(slot, _)| match claims[slot] {
+                Some(claim) => !claim.connected,
+                None => false,
+            })
+    }
+
+    /// Returns underlying lobby.
+    pub fn lobby(&self) -> &Lobby {
+        &self.lobby
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ev::Code;
+    use gilrs_core::native_ev_codes as nec;
+
+    fn press(id: usize, btn: Button) -> Event {
+        let code = match btn {
+            Button::South => nec::BTN_SOUTH,
+            Button::East => nec::BTN_EAST,
+            Button::Start => nec::BTN_START,
+            _ => unimplemented!(),
+        };
+
+        Event::new(GamepadId(id), EventType::ButtonPressed(btn, Code(code)))
+    }
+
+    #[test]
+    fn join_fills_free_slots_and_disconnect_frees_them() {
+        let mut lobby = Lobby::new(2);
+
+        assert_eq!(
+            lobby.handle_event(&press(3, Button::Start)),
+            Some(LobbyEvent::Join {
+                slot: 0,
+                id: GamepadId(3)
+            })
+        );
+        assert_eq!(lobby.handle_event(&press(3, Button::Start)), None);
+        assert_eq!(
+            lobby.handle_event(&press(1, Button::Start)),
+            Some(LobbyEvent::Join {
+                slot: 1,
+                id: GamepadId(1)
+            })
+        );
+        assert!(lobby.is_full());
+        assert_eq!(lobby.handle_event(&press(2, Button::Start)), None);
+
+        let disconnected = Event::new(GamepadId(3), EventType::Disconnected);
+        assert_eq!(
+            lobby.handle_event(&disconnected),
+            Some(LobbyEvent::Leave {
+                slot: 0,
+                id: GamepadId(3),
+                reason: LeaveReason::Disconnected
+            })
+        );
+        assert_eq!(
+            lobby.handle_event(&press(2, Button::Start)),
+            Some(LobbyEvent::Join {
+                slot: 0,
+                id: GamepadId(2)
+            })
+        );
+    }
+
+    #[test]
+    fn custom_join_and_leave_buttons() {
+        let mut lobby = Lobby::new(2)
+            .join_button(Button::South)
+            .leave_button(Some(Button::East));
+
+        assert_eq!(lobby.handle_event(&press(0, Button::Start)), None);
+        assert_eq!(
+            lobby.handle_event(&press(0, Button::South)),
+            Some(LobbyEvent::Join {
+                slot: 0,
+                id: GamepadId(0)
+            })
+        );
+        assert_eq!(lobby.handle_event(&press(1, Button::East)), None);
+        assert_eq!(
+            lobby.handle_event(&press(0, Button::East)),
+            Some(LobbyEvent::Leave {
+                slot: 0,
+                id: GamepadId(0),
+                reason: LeaveReason::Button
+            })
+        );
+        assert!(lobby.is_empty());
+    }
+
+    #[test]
+    fn session_resumes_when_same_device_returns() {
+        let mut session = Session::new(Lobby::new(2));
+        let uuid = Some([1; 16]);
+
+        session.handle_event_with_uuid(&press(0, Button::Start), uuid);
+        session.handle_event_with_uuid(&press(1, Button::Start), Some([2; 16]));
+
+        let disconnected = Event::new(GamepadId(0), EventType::Disconnected);
+        assert_eq!(
+            session.handle_event_with_uuid(&disconnected, None),
+            Some(LobbyEvent::Paused {
+                slot: 0,
+                id: GamepadId(0)
+            })
+        );
+        assert!(session.is_paused());
+        assert_eq!(session.lobby().player(0), Some(GamepadId(0)));
+
+        // Another gamepad of the same model.
+        let same_model = Event::new(GamepadId(2), EventType::Connected);
+        assert_eq!(session.handle_event_with_uuid(&same_model, uuid), None);
+        // Different model that got ID of disconnected gamepad.
+        let same_id = Event::new(GamepadId(0), EventType::Connected);
+        assert_eq!(
+            session.handle_event_with_uuid(&same_id, Some([3; 16])),
+            None
+        );
+        assert!(session.is_paused());
+
+        assert_eq!(
+            session.handle_event_with_uuid(&same_id, uuid),
+            Some(LobbyEvent::Resumed {
+                slot: 0,
+                id: GamepadId(0)
+            })
+        );
+        assert!(!session.is_paused());
+        assert_eq!(session.lobby().player(0), Some(GamepadId(0)));
+    }
+}
@@ -9,6 +9,8 @@
 mod parser;
 
 use crate::ev::{self, Axis, AxisOrBtn, Button};
+use crate::utils;
+use crate::{Gamepad, MappingSource};
 use gilrs_core::native_ev_codes as nec;
 use gilrs_core::EvCode;
 
@@ -16,6 +18,9 @@ use std::collections::HashMap;
 use std::env;
 use std::error::Error;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs;
+use std::io;
+use std::path::Path;
 
 use fnv::FnvHashMap;
 use uuid::Uuid;
@@ -23,17 +28,36 @@ use vec_map::VecMap;
 
 use self::parser::{Error as ParserError, ErrorKind as ParserErrorKind, Parser, Token};
 
-/// Platform name used by SDL mappings
-#[cfg(target_os = "linux")]
+/// Virtual d-pad buttons reported from hat 0, their SDL hat direction and axis of the hat.
+const HAT_DIRECTIONS: [(EvCode, u8, EvCode); 4] = [
+    (nec::BTN_DPAD_UP, 1, nec::AXIS_DPADY),
+    (nec::BTN_DPAD_RIGHT, 2, nec::AXIS_DPADX),
+    (nec::BTN_DPAD_DOWN, 4, nec::AXIS_DPADY),
+    (nec::BTN_DPAD_LEFT, 8, nec::AXIS_DPADX),
+];
+
+/// Platform name used by SDL mappings. BSDs use the same evdev codes as Linux.
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "dragonfly"))]
 const SDL_PLATFORM_NAME: &str = "Linux";
 #[cfg(target_os = "macos")]
 const SDL_PLATFORM_NAME: &'static str = "Mac OS X";
 #[cfg(target_os = "windows")]
 const SDL_PLATFORM_NAME: &'static str = "Windows";
+#[cfg(target_os = "android")]
+const SDL_PLATFORM_NAME: &str = "Android";
+#[cfg(target_os = "ios")]
+const SDL_PLATFORM_NAME: &str = "iOS";
+#[cfg(target_os = "tvos")]
+const SDL_PLATFORM_NAME: &str = "tvOS";
 #[cfg(all(
     not(target_os = "linux"),
+    not(target_os = "freebsd"),
+    not(target_os = "dragonfly"),
     not(target_os = "macos"),
-    not(target_os = "windows")
+    not(target_os = "windows"),
+    not(target_os = "android"),
+    not(target_os = "ios"),
+    not(target_os = "tvos")
 ))]
 const SDL_PLATFORM_NAME: &'static str = "Unknown";
 
@@ -44,6 +68,11 @@ const SDL_PLATFORM_NAME: &'static str = "Unknown";
 /// This struct is internal, `MappingData` is exported in public interface as `Mapping`.
 pub struct Mapping {
     mappings: FnvHashMap<EvCode, AxisOrBtn>,
+    // Only transforms that are not identity.
+    transforms: FnvHashMap<EvCode, AxisTransform>,
+    // Virtual codes of other elements mapped to part of the same axis, like both triggers on one
+    // combined axis. Virtual codes have their own entries in `mappings` and `transforms`.
+    split_axes: FnvHashMap<EvCode, Vec<EvCode>>,
     name: String,
     default: bool,
     hats_mapped: u8,
@@ -53,6 +82,8 @@ impl Mapping {
     pub fn new() -> Self {
         Mapping {
             mappings: FnvHashMap::default(),
+            transforms: FnvHashMap::default(),
+            split_axes: FnvHashMap::default(),
             name: String::new(),
             default: false,
             hats_mapped: 0,
@@ -161,8 +192,21 @@ impl Mapping {
             }
         }
 
+        // Microphone button of DualSense, reported by HID driver.
+        #[cfg(all(
+            feature = "hidapi",
+            any(target_os = "linux", target_os = "macos", target_os = "windows")
+        ))]
+        {
+            if gamepad.buttons().contains(&nec::BTN_MISC1) {
+                mappings.insert(nec::BTN_MISC1, Btn(Button::Misc1));
+            }
+        }
+
         Mapping {
             mappings,
+            transforms: FnvHashMap::default(),
+            split_axes: FnvHashMap::default(),
             name: String::new(),
             default: true,
             hats_mapped: 0,
@@ -180,15 +224,29 @@ impl Mapping {
         name: &str,
         uuid: Uuid,
     ) -> Result<(Self, String), MappingError> {
-        use crate::constants::*;
-
         if !Self::is_name_valid(name) {
             return Err(MappingError::InvalidName);
         }
 
-        let mut mappings = FnvHashMap::default();
+        let mut mapping = Mapping::new();
         let mut sdl_mappings = format!("{},{},", uuid.to_simple(), name);
 
+        // D-pad buttons that gamepad doesn't have are reported by hat.
+        for (button, &ev_code) in &data.buttons {
+            if let Some((direction, hat_axis)) = Self::hat(ev_code, buttons, axes) {
+                let (ident, mapped_btn) = Self::sdl_button(button as u16)?;
+                let to_axis = if hat_axis == nec::AXIS_DPADX {
+                    Axis::DPadX
+                } else {
+                    Axis::DPadY
+                };
+                sdl_mappings.push_str(&format!("{}:h0.{},", ident, direction));
+                mapping.mappings.insert(hat_axis, AxisOrBtn::Axis(to_axis));
+                mapping.mappings.insert(ev_code, AxisOrBtn::Btn(mapped_btn));
+                mapping.hats_mapped |= direction;
+            }
+        }
+
         {
             let mut add_button = |ident, ev_code, mapped_btn| {
                 Self::add_button(
@@ -197,73 +255,74 @@ impl Mapping {
                     mapped_btn,
                     buttons,
                     &mut sdl_mappings,
-                    &mut mappings,
+                    &mut mapping.mappings,
                 )
             };
 
             for (button, &ev_code) in &data.buttons {
-                match button as u16 {
-                    BTN_SOUTH => add_button("a", ev_code, Button::South)?,
-                    BTN_EAST => add_button("b", ev_code, Button::East)?,
-                    BTN_WEST => add_button("x", ev_code, Button::West)?,
-                    BTN_NORTH => add_button("y", ev_code, Button::North)?,
-                    BTN_LT => add_button("leftshoulder", ev_code, Button::LeftTrigger)?,
-                    BTN_RT => add_button("rightshoulder", ev_code, Button::RightTrigger)?,
-                    BTN_LT2 => add_button("lefttrigger", ev_code, Button::LeftTrigger2)?,
-                    BTN_RT2 => add_button("righttrigger", ev_code, Button::RightTrigger2)?,
-                    BTN_SELECT => add_button("back", ev_code, Button::Select)?,
-                    BTN_START => add_button("start", ev_code, Button::Start)?,
-                    BTN_MODE => add_button("guide", ev_code, Button::Mode)?,
-                    BTN_LTHUMB => add_button("leftstick", ev_code, Button::LeftThumb)?,
-                    BTN_RTHUMB => add_button("rightstick", ev_code, Button::RightThumb)?,
-                    BTN_DPAD_UP => add_button("dpup", ev_code, Button::DPadUp)?,
-                    BTN_DPAD_DOWN => add_button("dpdown", ev_code, Button::DPadDown)?,
-                    BTN_DPAD_LEFT => add_button("dpleft", ev_code, Button::DPadLeft)?,
-                    BTN_DPAD_RIGHT => add_button("dpright", ev_code, Button::DPadRight)?,
-                    BTN_C => add_button("c", ev_code, Button::C)?,
-                    BTN_Z => add_button("z", ev_code, Button::Z)?,
-                    BTN_UNKNOWN => return Err(MappingError::UnknownElement),
-                    _ => unreachable!(),
+                if Self::hat(ev_code, buttons, axes).is_some() {
+                    continue;
                 }
+                let (ident, mapped_btn) = Self::sdl_button(button as u16)?;
+                add_button(ident, ev_code, mapped_btn)?;
             }
         }
 
-        {
-            let mut add_axis = |ident, ev_code, mapped_axis| {
-                Self::add_axis(
-                    ident,
-                    ev_code,
-                    mapped_axis,
-                    axes,
-                    &mut sdl_mappings,
-                    &mut mappings,
-                )
-            };
+        for (axis, &ev_code) in &data.axes {
+            let (ident, mapped_axis) = Self::sdl_axis(axis as u16)?;
+            let transform = data.axis_transform(mapped_axis);
+            let n_axis = Self::axis_index(ev_code, axes)?;
+            sdl_mappings.push_str(&format!(
+                "{}{}:{},",
+                transform.output.sdl_prefix(),
+                ident,
+                transform.sdl_axis(n_axis)
+            ));
+            mapping.insert_axis(ev_code, AxisOrBtn::Axis(mapped_axis), transform);
+        }
 
-            for (axis, &ev_code) in &data.axes {
-                match axis as u16 {
-                    AXIS_LSTICKX => add_axis("leftx", ev_code, Axis::LeftStickX)?,
-                    AXIS_LSTICKY => add_axis("lefty", ev_code, Axis::LeftStickY)?,
-                    AXIS_RSTICKX => add_axis("rightx", ev_code, Axis::RightStickX)?,
-                    AXIS_RSTICKY => add_axis("righty", ev_code, Axis::RightStickY)?,
-                    AXIS_LEFTZ => add_axis("leftz", ev_code, Axis::LeftZ)?,
-                    AXIS_RIGHTZ => add_axis("rightz", ev_code, Axis::RightZ)?,
-                    AXIS_UNKNOWN => return Err(MappingError::UnknownElement),
-                    _ => unreachable!(),
-                }
-            }
+        // Analog axes mapped to buttons, for example pressure-sensitive face buttons.
+        for (button, &ev_code) in &data.button_axes {
+            let (ident, mapped_btn) = Self::sdl_button(button as u16)?;
+            let transform = data.button_axis_transform(mapped_btn);
+            let n_axis = Self::axis_index(ev_code, axes)?;
+            sdl_mappings.push_str(&format!("{}:{},", ident, transform.sdl_axis(n_axis)));
+            mapping.insert_axis(ev_code, AxisOrBtn::Btn(mapped_btn), transform);
         }
 
-        let mapping = Mapping {
-            mappings,
-            name: name.to_owned(),
-            default: false,
-            hats_mapped: 0,
-        };
+        // Digital buttons mapped to axes.
+        for &(axis, range, ev_code) in data.axis_buttons.values() {
+            let (ident, mapped_axis) = Self::sdl_axis(axis as u16)?;
+            let n_btn = buttons
+                .iter()
+                .position(|&x| x == ev_code)
+                .ok_or(MappingError::InvalidCode(ev::Code(ev_code)))?;
+            sdl_mappings.push_str(&format!("{}{}:b{},", range.sdl_prefix(), ident, n_btn));
+            mapping
+                .mappings
+                .insert(ev_code, AxisOrBtn::Axis(mapped_axis));
+            mapping.set_transform(ev_code, AxisTransform::new().output(range));
+        }
+
+        sdl_mappings.push_str(&format!("platform:{},", SDL_PLATFORM_NAME));
+        mapping.name = name.to_owned();
 
         Ok((mapping, sdl_mappings))
     }
 
+    /// Returns SDL direction and axis of hat if `ev_code` is virtual d-pad button reported by
+    /// hat 0.
+    fn hat(ev_code: EvCode, buttons: &[EvCode], axes: &[EvCode]) -> Option<(u8, EvCode)> {
+        if buttons.contains(&ev_code) {
+            return None;
+        }
+
+        HAT_DIRECTIONS
+            .iter()
+            .find(|&&(code, _, axis)| code == ev_code && axes.contains(&axis))
+            .map(|&(_, direction, axis)| (direction, axis))
+    }
+
     pub fn parse_sdl_mapping(
         line: &str,
         buttons: &[EvCode],
@@ -289,12 +348,23 @@ impl Mapping {
                 }
                 Token::Uuid(_) => (),
                 Token::Name(name) => mapping.name = name.to_owned(),
-                Token::AxisMapping { from, to, .. } => {
+                Token::AxisMapping {
+                    from,
+                    to,
+                    input,
+                    output,
+                    inverted,
+                } => {
                     let axis = axes
                         .get(from as usize)
                         .cloned()
                         .ok_or(ParseSdlMappingError::InvalidAxis)?;
-                    mapping.mappings.insert(axis, to);
+                    let transform = AxisTransform {
+                        input,
+                        output,
+                        inverted,
+                    };
+                    mapping.insert_axis(axis, to, transform);
                 }
                 Token::ButtonMapping { from, to } => {
                     let btn = buttons
@@ -302,39 +372,47 @@ impl Mapping {
                         .cloned()
                         .ok_or(ParseSdlMappingError::InvalidButton)?;
                     mapping.mappings.insert(btn, AxisOrBtn::Btn(to));
+                    mapping.transforms.remove(&btn);
+                }
+                Token::ButtonAxisMapping { from, to, output } => {
+                    let btn = buttons
+                        .get(from as usize)
+                        .cloned()
+                        .ok_or(ParseSdlMappingError::InvalidButton)?;
+                    mapping.mappings.insert(btn, AxisOrBtn::Axis(to));
+                    mapping.set_transform(btn, AxisTransform::new().output(output));
                 }
                 Token::HatMapping { hat, direction, to } => {
-                    if hat != 0 || !to.is_dpad() {
+                    if hat != 0 {
                         warn!(
-                            "Hat mappings are only supported for dpads (requested to map hat \
-                             {}.{} to {:?}",
+                            "Only hat 0 is supported (requested to map hat {}.{} to {:?})",
                             hat, direction, to
                         );
-                    } else {
-                        // We  don't have anything like "hat" in gilrs, so let's jus assume that
-                        // user want to map dpad axes.
-                        //
-                        // We have to add mappings for axes AND buttons, because axis_dpad_to_button
-                        // filter may transform event to button event.
-                        let (from_axis, from_btn) = match direction {
-                            1 => (nec::AXIS_DPADY, nec::BTN_DPAD_UP),
-                            4 => (nec::AXIS_DPADY, nec::BTN_DPAD_DOWN),
-                            2 => (nec::AXIS_DPADX, nec::BTN_DPAD_RIGHT),
-                            8 => (nec::AXIS_DPADX, nec::BTN_DPAD_LEFT),
-                            0 => continue, // FIXME: I have no idea what 0 means here
-                            _ => return Err(ParseSdlMappingError::UnknownHatDirection),
-                        };
-
-                        let to_axis = match to {
-                            Button::DPadLeft | Button::DPadRight => Axis::DPadX,
-                            Button::DPadUp | Button::DPadDown => Axis::DPadY,
-                            _ => unreachable!(),
-                        };
-
-                        mapping.mappings.insert(from_axis, AxisOrBtn::Axis(to_axis));
-                        mapping.mappings.insert(from_btn, AxisOrBtn::Btn(to));
-                        mapping.hats_mapped |= direction as u8;
+                        continue;
                     }
+                    // Hat is centered.
+                    if direction == 0 {
+                        continue;
+                    }
+
+                    // Backends report hat 0 as d-pad axes. `axis_dpad_to_button` filter
+                    // converts them to events of virtual d-pad buttons, so we have to add
+                    // mappings for axes AND buttons. Direction of hat decides which axis and
+                    // virtual button are used, `to` can be any button.
+                    let (from_btn, direction, from_axis) = HAT_DIRECTIONS
+                        .iter()
+                        .find(|&&(_, dir, _)| u16::from(dir) == direction)
+                        .cloned()
+                        .ok_or(ParseSdlMappingError::UnknownHatDirection)?;
+                    let to_axis = if from_axis == nec::AXIS_DPADX {
+                        Axis::DPadX
+                    } else {
+                        Axis::DPadY
+                    };
+
+                    mapping.mappings.insert(from_axis, AxisOrBtn::Axis(to_axis));
+                    mapping.mappings.insert(from_btn, AxisOrBtn::Btn(to));
+                    mapping.hats_mapped |= direction;
                 }
             }
         }
@@ -342,6 +420,80 @@ impl Mapping {
         Ok(mapping)
     }
 
+    /// Maps axis `code` to `el`. If other part of the axis is already mapped to different element,
+    /// `el` gets virtual code, so both elements have their own state.
+    fn insert_axis(&mut self, code: EvCode, el: AxisOrBtn, transform: AxisTransform) {
+        let code = match self.mappings.get(&code) {
+            Some(&mapped) if mapped != el => match self.virtual_code(code, el) {
+                Some(virtual_code) => virtual_code,
+                None => {
+                    warn!("No free code for {:?} mapped to part of axis {}", el, code);
+                    return;
+                }
+            },
+            _ => code,
+        };
+
+        self.mappings.insert(code, el);
+        self.set_transform(code, transform);
+    }
+
+    /// Returns virtual code of `el` mapped to part of `axis`, reusing existing one.
+    fn virtual_code(&mut self, axis: EvCode, el: AxisOrBtn) -> Option<EvCode> {
+        let mappings = &self.mappings;
+        let split = self.split_axes.entry(axis).or_default();
+        if let Some(&code) = split.iter().find(|code| mappings.get(code) == Some(&el)) {
+            return Some(code);
+        }
+
+        let code = *nec::SPLIT_AXES
+            .iter()
+            .find(|code| !mappings.contains_key(code))?;
+        split.push(code);
+
+        Some(code)
+    }
+
+    fn set_transform(&mut self, code: EvCode, transform: AxisTransform) {
+        if transform.is_identity() {
+            self.transforms.remove(&code);
+        } else {
+            self.transforms.insert(code, transform);
+        }
+    }
+
+    /// Returns SDL name of button with index `button` in `MappingData`.
+    fn sdl_button(button: u16) -> Result<(&'static str, Button), MappingError> {
+        use crate::constants::*;
+
+        let btn = match button {
+            BTN_SOUTH => ("a", Button::South),
+            BTN_EAST => ("b", Button::East),
+            BTN_WEST => ("x", Button::West),
+            BTN_NORTH => ("y", Button::North),
+            BTN_LT => ("leftshoulder", Button::LeftTrigger),
+            BTN_RT => ("rightshoulder", Button::RightTrigger),
+            BTN_LT2 => ("lefttrigger", Button::LeftTrigger2),
+            BTN_RT2 => ("righttrigger", Button::RightTrigger2),
+            BTN_SELECT => ("back", Button::Select),
+            BTN_START => ("start", Button::Start),
+            BTN_MODE => ("guide", Button::Mode),
+            BTN_LTHUMB => ("leftstick", Button::LeftThumb),
+            BTN_RTHUMB => ("rightstick", Button::RightThumb),
+            BTN_DPAD_UP => ("dpup", Button::DPadUp),
+            BTN_DPAD_DOWN => ("dpdown", Button::DPadDown),
+            BTN_DPAD_LEFT => ("dpleft", Button::DPadLeft),
+            BTN_DPAD_RIGHT => ("dpright", Button::DPadRight),
+            BTN_C => ("c", Button::C),
+            BTN_Z => ("z", Button::Z),
+            BTN_MISC1 => ("misc1", Button::Misc1),
+            BTN_UNKNOWN => return Err(MappingError::UnknownElement),
+            _ => unreachable!(),
+        };
+
+        Ok(btn)
+    }
+
     fn add_button(
         ident: &str,
         ev_code: EvCode,
@@ -359,21 +511,29 @@ impl Mapping {
         Ok(())
     }
 
-    fn add_axis(
-        ident: &str,
-        ev_code: EvCode,
-        mapped_axis: Axis,
-        axes: &[EvCode],
-        sdl_mappings: &mut String,
-        mappings: &mut FnvHashMap<EvCode, AxisOrBtn>,
-    ) -> Result<(), MappingError> {
-        let n_axis = axes
-            .iter()
+    /// Returns SDL name of axis with index `axis` in `MappingData`.
+    fn sdl_axis(axis: u16) -> Result<(&'static str, Axis), MappingError> {
+        use crate::constants::*;
+
+        let axis = match axis {
+            AXIS_LSTICKX => ("leftx", Axis::LeftStickX),
+            AXIS_LSTICKY => ("lefty", Axis::LeftStickY),
+            AXIS_RSTICKX => ("rightx", Axis::RightStickX),
+            AXIS_RSTICKY => ("righty", Axis::RightStickY),
+            AXIS_LEFTZ => ("leftz", Axis::LeftZ),
+            AXIS_RIGHTZ => ("rightz", Axis::RightZ),
+            AXIS_DPADX | AXIS_DPADY => return Err(MappingError::NotSdl2Compatible),
+            AXIS_UNKNOWN => return Err(MappingError::UnknownElement),
+            _ => unreachable!(),
+        };
+
+        Ok(axis)
+    }
+
+    fn axis_index(ev_code: EvCode, axes: &[EvCode]) -> Result<usize, MappingError> {
+        axes.iter()
             .position(|&x| x == ev_code)
-            .ok_or(MappingError::InvalidCode(ev::Code(ev_code)))?;
-        sdl_mappings.push_str(&format!("{}:a{},", ident, n_axis));
-        mappings.insert(ev_code, AxisOrBtn::Axis(mapped_axis));
-        Ok(())
+            .ok_or(MappingError::InvalidCode(ev::Code(ev_code)))
     }
 
     fn is_name_valid(name: &str) -> bool {
@@ -384,10 +544,56 @@ impl Mapping {
         self.mappings.get(code).cloned()
     }
 
+    /// Returns transform of element with `code` if it's not identity.
+    pub fn transform(&self, code: &EvCode) -> Option<AxisTransform> {
+        self.transforms.get(code).cloned()
+    }
+
+    /// Returns virtual codes of other elements mapped to part of axis `code`. Events of these
+    /// elements use virtual code, so `map()` and `transform()` work with it.
+    pub fn split_codes(&self, code: &EvCode) -> &[EvCode] {
+        self.split_axes
+            .get(code)
+            .map_or(&[][..], |codes| &codes[..])
+    }
+
     pub fn map_rev(&self, el: &AxisOrBtn) -> Option<EvCode> {
         self.mappings.iter().find(|x| x.1 == el).map(|x| *x.0)
     }
 
+    /// Like `map_rev()`, but returns all codes mapped to `el`. Dual-stage triggers can have both
+    /// analog axis and digital button mapped to the same element.
+    pub fn map_rev_all<'a>(&'a self, el: &'a AxisOrBtn) -> impl Iterator<Item = EvCode> + 'a {
+        self.mappings
+            .iter()
+            .filter(move |x| x.1 == el)
+            .map(|x| *x.0)
+    }
+
+    /// Swaps `South` with `East` and `West` with `North`.
+    pub fn swap_face_buttons(&mut self) {
+        for el in self.mappings.values_mut() {
+            *el = match *el {
+                AxisOrBtn::Btn(Button::South) => AxisOrBtn::Btn(Button::East),
+                AxisOrBtn::Btn(Button::East) => AxisOrBtn::Btn(Button::South),
+                AxisOrBtn::Btn(Button::West) => AxisOrBtn::Btn(Button::North),
+                AxisOrBtn::Btn(Button::North) => AxisOrBtn::Btn(Button::West),
+                other => other,
+            };
+        }
+    }
+
+    /// Swaps `LeftTrigger2` with `RightTrigger2`.
+    pub fn swap_triggers(&mut self) {
+        for el in self.mappings.values_mut() {
+            *el = match *el {
+                AxisOrBtn::Btn(Button::LeftTrigger2) => AxisOrBtn::Btn(Button::RightTrigger2),
+                AxisOrBtn::Btn(Button::RightTrigger2) => AxisOrBtn::Btn(Button::LeftTrigger2),
+                other => other,
+            };
+        }
+    }
+
     pub fn is_default(&self) -> bool {
         self.default
     }
@@ -436,31 +642,102 @@ impl Display for ParseSdlMappingError {
     }
 }
 
-#[derive(Debug)]
+/// Where mapping in [`MappingDb`](struct.MappingDb.html) comes from. Mapping is replaced only by
+/// mapping from the same or later variant, so user's remaps are never overridden by included
+/// database, regardless of order in which mappings were added.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MappingOrigin {
+    /// Database included in gilrs, added by `MappingDb::add_included_mappings()`.
+    Included,
+    /// Environment variables, added by `MappingDb::add_env_mappings()`.
+    Environment,
+    /// Directory of mapping files, added by `MappingDb::load_dir()`.
+    Directory,
+    /// Added by `MappingDb::insert()`, `MappingDb::load_from()` or `Gilrs::set_mapping()`.
+    User,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    mapping: String,
+    origin: MappingOrigin,
+}
+
+/// SDL2 mappings used to map gamepads, merged from multiple sources. See
+/// [`MappingOrigin`](enum.MappingOrigin.html) for priority rules.
+///
+/// By default, `GilrsBuilder` adds included and environment mappings to its database. Custom
+/// database can be set with `GilrsBuilder::set_mapping_db()` and user's remaps saved with
+/// `save_to()`:
+///
+/// ```no_run
+/// use gilrs::{GilrsBuilder, MappingDb};
+///
+/// let mut db = MappingDb::new();
+/// # let game_dir = std::path::Path::new("");
+/// // Mappings shipped with the game.
+/// db.load_dir(game_dir.join("mappings")).unwrap();
+/// // Remaps saved by previous run, missing file is not an error.
+/// let _ = db.load_from(game_dir.join("user_mappings.txt"));
+///
+/// let mut gilrs = GilrsBuilder::new().set_mapping_db(db).build().unwrap();
+/// // …
+/// // gilrs.set_mapping(…);
+/// gilrs.mapping_db().save_to(game_dir.join("user_mappings.txt")).unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
 pub struct MappingDb {
-    mappings: HashMap<Uuid, String>,
+    mappings: HashMap<Uuid, Entry>,
 }
 
 impl MappingDb {
+    /// Creates empty database.
     pub fn new() -> Self {
         MappingDb {
             mappings: HashMap::new(),
         }
     }
 
+    /// Adds mappings included from https://github.com/gabomdq/SDL_GameControllerDB.
     pub fn add_included_mappings(&mut self) {
-        self.insert(include_str!(
-            "../../SDL_GameControllerDB/gamecontrollerdb.txt"
-        ));
+        self.insert_with_origin(
+            include_str!("../../SDL_GameControllerDB/gamecontrollerdb.txt"),
+            MappingOrigin::Included,
+        );
     }
 
+    /// Adds mappings from `SDL_GAMECONTROLLERCONFIG` and `GILRS_GAMECONTROLLERCONFIG`
+    /// environment variables and from file set by `GILRS_MAPPINGS_FILE`.
     pub fn add_env_mappings(&mut self) {
+        let origin = MappingOrigin::Environment;
+        if let Some(path) = env::var_os("GILRS_MAPPINGS_FILE") {
+            match fs::read_to_string(&path) {
+                Ok(mappings) => {
+                    self.insert_with_origin(&mappings, origin);
+                }
+                Err(e) => warn!("Failed to read mappings from {:?}: {}", path, e),
+            }
+        }
+
         if let Ok(mapping) = env::var("SDL_GAMECONTROLLERCONFIG") {
-            self.insert(&mapping);
+            self.insert_with_origin(&mapping, origin);
         }
+
+        // Loaded last so it can override mappings set by Steam and mappings file.
+        if let Ok(mapping) = env::var("GILRS_GAMECONTROLLERCONFIG") {
+            self.insert_with_origin(&mapping, origin);
+        }
+    }
+
+    /// Adds SDL2 mappings, one per line, with `MappingOrigin::User`. Mappings for other
+    /// platforms and lines that are not mappings are ignored. Returns number of added mappings.
+    pub fn insert(&mut self, s: &str) -> usize {
+        self.insert_with_origin(s, MappingOrigin::User)
     }
 
-    pub fn insert(&mut self, s: &str) {
+    fn insert_with_origin(&mut self, s: &str, origin: MappingOrigin) -> usize {
+        let mut count = 0;
         for mapping in s.lines() {
             let pat = "platform:";
             if let Some(offset) = mapping.find(pat).map(|o| o + pat.len()) {
@@ -472,27 +749,255 @@ impl MappingDb {
                 }
             }
 
-            mapping
-                .split(',')
-                .next()
-                .and_then(|s| Uuid::parse_str(s).ok())
-                .and_then(|uuid| self.mappings.insert(uuid, mapping.to_owned()));
+            let uuid = match mapping.split(',').next().map(Uuid::parse_str) {
+                Some(Ok(uuid)) => uuid,
+                _ => continue,
+            };
+            if let Some(entry) = self.mappings.get(&uuid) {
+                if entry.origin > origin {
+                    continue;
+                }
+            }
+            let entry = Entry {
+                mapping: mapping.to_owned(),
+                origin,
+            };
+            self.mappings.insert(uuid, entry);
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Adds mappings from all `.txt` files in directory, in order of their names, with
+    /// `MappingOrigin::Directory`. Returns number of added mappings.
+    pub fn load_dir<P: AsRef<Path>>(&mut self, dir: P) -> io::Result<usize> {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        paths.retain(|path| path.extension().and_then(|ext| ext.to_str()) == Some("txt"));
+        paths.sort();
+
+        let mut count = 0;
+        for path in paths {
+            let mappings = fs::read_to_string(&path)?;
+            count += self.insert_with_origin(&mappings, MappingOrigin::Directory);
+        }
+
+        Ok(count)
+    }
+
+    /// Adds mappings from file with `MappingOrigin::User`, for example saved by `save_to()`.
+    /// Returns number of added mappings.
+    pub fn load_from<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        let mappings = fs::read_to_string(path)?;
+
+        Ok(self.insert(&mappings))
+    }
+
+    /// Saves mappings with `MappingOrigin::User` to file, one per line, sorted by UUID.
+    pub fn save_to<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut user = self
+            .mappings
+            .iter()
+            .filter(|(_, entry)| entry.origin == MappingOrigin::User)
+            .collect::<Vec<_>>();
+        user.sort_by_key(|&(uuid, _)| *uuid);
+
+        let mut out = String::new();
+        for (_, entry) in user {
+            out.push_str(&entry.mapping);
+            out.push('\n');
         }
+
+        fs::write(path, out)
+    }
+
+    /// Returns mapping for gamepad with given UUID.
+    pub fn get(&self, uuid: [u8; 16]) -> Option<&str> {
+        self.mappings
+            .get(&Uuid::from_bytes(uuid))
+            .map(|entry| entry.mapping.as_ref())
+    }
+
+    /// Returns where mapping for gamepad with given UUID comes from.
+    pub fn origin(&self, uuid: [u8; 16]) -> Option<MappingOrigin> {
+        self.mappings
+            .get(&Uuid::from_bytes(uuid))
+            .map(|entry| entry.origin)
     }
 
-    pub fn get(&self, uuid: Uuid) -> Option<&str> {
-        self.mappings.get(&uuid).map(String::as_ref)
+    /// Removes mapping for gamepad with given UUID, regardless of its origin.
+    pub fn remove(&mut self, uuid: [u8; 16]) -> Option<String> {
+        self.mappings
+            .remove(&Uuid::from_bytes(uuid))
+            .map(|entry| entry.mapping)
     }
 
     pub fn len(&self) -> usize {
         self.mappings.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+}
+
+/// Part of axis range, used by [`AxisTransform`](struct.AxisTransform.html) and
+/// `Mapping::insert_axis_btn()`. In SDL2 mappings, halves are written as `+` and `-` prefixes.
+#[non_exhaustive]
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AxisRange {
+    /// Values from 0.0 to -1.0.
+    LowerHalf,
+    /// Values from 0.0 to 1.0.
+    UpperHalf,
+    /// Values from -1.0 to 1.0.
+    Full,
+}
+
+impl AxisRange {
+    fn sdl_prefix(self) -> &'static str {
+        match self {
+            AxisRange::LowerHalf => "-",
+            AxisRange::UpperHalf => "+",
+            AxisRange::Full => "",
+        }
+    }
+}
+
+/// Describes how value of physical axis is converted to value of mapped axis or button.
+///
+/// Part of axis range selected by `input` is scaled to `output` range, optionally inverted. For
+/// example, combined trigger axis can be split into two buttons with `AxisRange::LowerHalf` and
+/// `AxisRange::UpperHalf` input (SDL2 `-a2` and `+a2`), and stick of flight controller that
+/// reports only positive values can use `AxisRange::UpperHalf` input with `AxisRange::Full` output.
+/// Values outside of input range are treated as the nearest end of the range.
+///
+/// ```
+/// use gilrs::{AxisRange, AxisTransform};
+///
+/// // SDL2 `lefty:a1~`.
+/// let inverted = AxisTransform::new().inverted(true);
+/// // SDL2 `lefttrigger:-a2`.
+/// let negative_half = AxisTransform::new().input(AxisRange::LowerHalf);
+/// # let _ = (inverted, negative_half);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AxisTransform {
+    input: AxisRange,
+    output: AxisRange,
+    inverted: bool,
+}
+
+impl AxisTransform {
+    /// Creates transform that maps full range of axis to full range, without inversion.
+    pub fn new() -> Self {
+        AxisTransform {
+            input: AxisRange::Full,
+            output: AxisRange::Full,
+            inverted: false,
+        }
+    }
+
+    /// Changes part of physical axis range that is used.
+    pub fn input(mut self, input: AxisRange) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Changes part of mapped axis range that is produced. Ignored for buttons, which always
+    /// have values from 0.0 to 1.0.
+    pub fn output(mut self, output: AxisRange) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Inverts direction of input range.
+    pub fn inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    pub fn input_range(&self) -> AxisRange {
+        self.input
+    }
+
+    pub fn output_range(&self) -> AxisRange {
+        self.output
+    }
+
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    fn is_identity(&self) -> bool {
+        *self == AxisTransform::new()
+    }
+
+    /// Returns position of `value` (-1.0 to 1.0) in input range, from 0.0 to 1.0.
+    fn position(&self, value: f32) -> f32 {
+        let pos = match self.input {
+            AxisRange::LowerHalf => -value,
+            AxisRange::UpperHalf => value,
+            AxisRange::Full => (value + 1.0) / 2.0,
+        };
+        // Other half of axis doesn't move element, even inverted one. It can be mapped to another
+        // element, like the other trigger on combined axis.
+        if pos < 0.0 && self.input != AxisRange::Full {
+            return 0.0;
+        }
+        let pos = utils::clamp(pos, 0.0, 1.0);
+
+        if self.inverted {
+            1.0 - pos
+        } else {
+            pos
+        }
+    }
+
+    /// Converts normalized value of physical axis to value of mapped axis.
+    pub(crate) fn axis_value(&self, value: f32) -> f32 {
+        let pos = self.position(value);
+        match self.output {
+            AxisRange::LowerHalf => -pos,
+            AxisRange::UpperHalf => pos,
+            AxisRange::Full => pos * 2.0 - 1.0,
+        }
+    }
+
+    /// Converts normalized value of physical axis to value of mapped button.
+    pub(crate) fn btn_value(&self, value: f32) -> f32 {
+        self.position(value)
+    }
+
+    /// Returns value of axis when button mapped to it is pressed.
+    pub(crate) fn pressed_value(&self) -> f32 {
+        match self.output {
+            AxisRange::LowerHalf => -1.0,
+            AxisRange::UpperHalf | AxisRange::Full => 1.0,
+        }
+    }
+
+    /// Formats SDL2 value of axis with index `n_axis`, like `+a2~`.
+    fn sdl_axis(&self, n_axis: usize) -> String {
+        let inverted = if self.inverted { "~" } else { "" };
+
+        format!("{}a{}{}", self.input.sdl_prefix(), n_axis, inverted)
+    }
+}
+
+impl Default for AxisTransform {
+    fn default() -> Self {
+        AxisTransform::new()
+    }
 }
 
 /// Stores data used to map gamepad buttons and axes.
 ///
 /// After you add all mappings, use
-/// [`Gamepad::set_mapping(…)`](struct.Gamepad.html#method.set_mapping) to change mapping of
+/// [`Gilrs::set_mapping(…)`](struct.Gilrs.html#method.set_mapping) to change mapping of
 /// existing gamepad.
 ///
 /// See `examples/mapping.rs` for more detailed example.
@@ -501,6 +1006,24 @@ impl MappingDb {
 pub struct MappingData {
     buttons: VecMap<EvCode>,
     axes: VecMap<EvCode>,
+    button_axes: VecMap<EvCode>,
+    // Only transforms that are not identity.
+    axis_transforms: VecMap<AxisTransform>,
+    button_axis_transforms: VecMap<AxisTransform>,
+    // Indexed by `axis_button_idx()`.
+    axis_buttons: VecMap<(Axis, AxisRange, EvCode)>,
+}
+
+fn axis_button_idx(axis: Axis, range: AxisRange) -> usize {
+    axis as usize * 3 + range as usize
+}
+
+fn insert_transform(transforms: &mut VecMap<AxisTransform>, idx: usize, transform: AxisTransform) {
+    if transform.is_identity() {
+        transforms.remove(idx);
+    } else {
+        transforms.insert(idx, transform);
+    }
 }
 
 impl MappingData {
@@ -509,6 +1032,10 @@ impl MappingData {
         MappingData {
             buttons: VecMap::with_capacity(18),
             axes: VecMap::with_capacity(11),
+            button_axes: VecMap::new(),
+            axis_transforms: VecMap::new(),
+            button_axis_transforms: VecMap::new(),
+            axis_buttons: VecMap::new(),
         }
     }
 
@@ -527,11 +1054,102 @@ impl MappingData {
         self.buttons.insert(to as usize, from.0).map(ev::Code)
     }
 
-    /// Inserts new axis mapping.
+    /// Inserts new axis mapping without transform.
     pub fn insert_axis(&mut self, from: ev::Code, to: Axis) -> Option<ev::Code> {
+        self.insert_axis_with(from, to, AxisTransform::new())
+    }
+
+    /// Inserts new axis mapping with transform, for example inverted or half axis.
+    pub fn insert_axis_with(
+        &mut self,
+        from: ev::Code,
+        to: Axis,
+        transform: AxisTransform,
+    ) -> Option<ev::Code> {
+        insert_transform(&mut self.axis_transforms, to as usize, transform);
         self.axes.insert(to as usize, from.0).map(ev::Code)
     }
 
+    /// Returns transform of axis mapped to axis index.
+    pub fn axis_transform(&self, idx: Axis) -> AxisTransform {
+        self.axis_transforms
+            .get(idx as usize)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns `EvCode` of analog axis associated with button index.
+    pub fn button_axis(&self, idx: Button) -> Option<ev::Code> {
+        self.button_axes.get(idx as usize).cloned().map(ev::Code)
+    }
+
+    /// Inserts new mapping from analog axis to button, for example pressure-sensitive face button.
+    /// Button can also have digital element mapped with `insert_btn()`.
+    pub fn insert_btn_axis(&mut self, from: ev::Code, to: Button) -> Option<ev::Code> {
+        self.insert_btn_axis_with(from, to, AxisTransform::new())
+    }
+
+    /// Like `insert_btn_axis()`, but with transform, for example half of combined trigger axis.
+    /// Output range of transform is ignored.
+    pub fn insert_btn_axis_with(
+        &mut self,
+        from: ev::Code,
+        to: Button,
+        transform: AxisTransform,
+    ) -> Option<ev::Code> {
+        insert_transform(&mut self.button_axis_transforms, to as usize, transform);
+        self.button_axes.insert(to as usize, from.0).map(ev::Code)
+    }
+
+    /// Returns transform of analog axis mapped to button index.
+    pub fn button_axis_transform(&self, idx: Button) -> AxisTransform {
+        self.button_axis_transforms
+            .get(idx as usize)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Removes mapping from analog axis to button and returns associated `EvCode`.
+    pub fn remove_button_axis(&mut self, idx: Button) -> Option<ev::Code> {
+        self.button_axis_transforms.remove(idx as usize);
+        self.button_axes.remove(idx as usize).map(ev::Code)
+    }
+
+    /// Returns `EvCode` of digital button associated with part of axis.
+    pub fn axis_button(&self, idx: Axis, range: AxisRange) -> Option<ev::Code> {
+        self.axis_buttons
+            .get(axis_button_idx(idx, range))
+            .map(|&(.., code)| ev::Code(code))
+    }
+
+    /// Inserts new mapping from digital button to axis, for example d-pad of flight stick mapped
+    /// to stick. When button is pressed, axis has value 1.0, or -1.0 for `AxisRange::LowerHalf`,
+    /// and 0.0 when it's released. Each half of axis can be mapped to different button.
+    pub fn insert_axis_btn(
+        &mut self,
+        from: ev::Code,
+        to: Axis,
+        range: AxisRange,
+    ) -> Option<ev::Code> {
+        self.axis_buttons
+            .insert(axis_button_idx(to, range), (to, range, from.0))
+            .map(|(.., code)| ev::Code(code))
+    }
+
+    /// Removes mapping from digital button to part of axis and returns associated `EvCode`.
+    pub fn remove_axis_button(&mut self, idx: Axis, range: AxisRange) -> Option<ev::Code> {
+        self.axis_buttons
+            .remove(axis_button_idx(idx, range))
+            .map(|(.., code)| ev::Code(code))
+    }
+
+    /// Returns all mappings from digital buttons to axes.
+    pub(crate) fn axis_buttons(&self) -> impl Iterator<Item = (Axis, AxisRange, ev::Code)> + '_ {
+        self.axis_buttons
+            .values()
+            .map(|&(axis, range, code)| (axis, range, ev::Code(code)))
+    }
+
     /// Removes button and returns associated `NativEvCode`.
     pub fn remove_button(&mut self, idx: Button) -> Option<ev::Code> {
         self.buttons.remove(idx as usize).map(ev::Code)
@@ -539,8 +1157,264 @@ impl MappingData {
 
     /// Removes axis and returns associated `NativEvCode`.
     pub fn remove_axis(&mut self, idx: Axis) -> Option<ev::Code> {
+        self.axis_transforms.remove(idx as usize);
         self.axes.remove(idx as usize).map(ev::Code)
     }
+
+    /// Returns SDL2 representation of mapping for `gamepad`, the same as returned by
+    /// `Gilrs::set_mapping()`. It ends with `platform` field of current platform. Virtual d-pad
+    /// buttons of gamepad that reports d-pad with hat are encoded as hat 0, regardless of button
+    /// they are mapped to.
+    ///
+    /// The `name` argument can be a string slice with custom gamepad name or `None`. If `None`,
+    /// gamepad name reported by driver will be used.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `Gilrs::set_mapping()`.
+    pub fn to_sdl_string<'b, O: Into<Option<&'b str>>>(
+        &self,
+        gamepad: &Gamepad<'_>,
+        name: O,
+    ) -> Result<String, MappingError> {
+        let gamepad = gamepad.inner();
+        let name = name.into().unwrap_or_else(|| gamepad.name());
+        let (_, s) = Mapping::from_data(
+            self,
+            gamepad.buttons(),
+            gamepad.axes(),
+            name,
+            Uuid::from_bytes(gamepad.uuid()),
+        )?;
+
+        Ok(s)
+    }
+
+    /// Creates mapping for `gamepad` from SDL2 mapping string, for example exported by SDL-based
+    /// tool. UUID and name in the string are ignored. Directions of hat 0 are mapped from virtual
+    /// d-pad buttons.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MappingError::InvalidSdlMapping` if the string is malformed, is for different
+    /// platform, references element that gamepad doesn't have or uses other hats or the same
+    /// element mapped from several axes, which `Mapping` can't represent.
+    pub fn parse_sdl_string(mapping: &str, gamepad: &Gamepad<'_>) -> Result<Self, MappingError> {
+        let gamepad = gamepad.inner();
+
+        MappingData::parse_sdl(mapping, gamepad.buttons(), gamepad.axes())
+    }
+
+    fn parse_sdl(mapping: &str, buttons: &[EvCode], axes: &[EvCode]) -> Result<Self, MappingError> {
+        let mut data = MappingData::new();
+        let mut parser = Parser::new(mapping.trim_end());
+
+        loop {
+            let position = parser.position();
+            let err = |reason| MappingError::InvalidSdlMapping { position, reason };
+            let token = match parser.next_token() {
+                Some(Ok(token)) => token,
+                Some(Err(ref e)) if e.kind() == &ParserErrorKind::EmptyValue => continue,
+                Some(Err(e)) => {
+                    return Err(MappingError::InvalidSdlMapping {
+                        position: e.position(),
+                        reason: e.kind().description(),
+                    })
+                }
+                None => break,
+            };
+
+            match token {
+                Token::Uuid(_) | Token::Name(_) => (),
+                Token::Platform(platform) => {
+                    if platform != SDL_PLATFORM_NAME {
+                        return Err(err("mapping is for different platform"));
+                    }
+                }
+                Token::ButtonMapping { from, to } => {
+                    let code = buttons
+                        .get(from as usize)
+                        .ok_or_else(|| err("gamepad doesn't have this button"))?;
+                    data.insert_btn(ev::Code(*code), to);
+                }
+                Token::AxisMapping {
+                    from,
+                    to,
+                    input,
+                    output,
+                    inverted,
+                } => {
+                    let code = ev::Code(
+                        *axes
+                            .get(from as usize)
+                            .ok_or_else(|| err("gamepad doesn't have this axis"))?,
+                    );
+                    let transform = AxisTransform {
+                        input,
+                        output,
+                        inverted,
+                    };
+                    let previous = match to {
+                        AxisOrBtn::Axis(axis) => data.insert_axis_with(code, axis, transform),
+                        AxisOrBtn::Btn(btn) => data.insert_btn_axis_with(code, btn, transform),
+                    };
+                    if previous.is_some() {
+                        return Err(err("element is mapped from more than one axis"));
+                    }
+                }
+                Token::ButtonAxisMapping { from, to, output } => {
+                    let code = buttons
+                        .get(from as usize)
+                        .ok_or_else(|| err("gamepad doesn't have this button"))?;
+                    data.insert_axis_btn(ev::Code(*code), to, output);
+                }
+                Token::HatMapping { hat, direction, to } => {
+                    if hat != 0 {
+                        return Err(err("only hat 0 is supported"));
+                    }
+                    // Hat is centered.
+                    if direction == 0 {
+                        continue;
+                    }
+                    let code = HAT_DIRECTIONS
+                        .iter()
+                        .find(|&&(_, dir, _)| u16::from(dir) == direction)
+                        .map(|&(code, ..)| code)
+                        .ok_or_else(|| err("hat direction isn't 0, 1, 2, 4 or 8"))?;
+                    data.insert_btn(ev::Code(code), to);
+                }
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+/// Element of gamepad and button or axis it's mapped to, part of
+/// [`ResolvedMapping`](struct.ResolvedMapping.html).
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MappingEntry {
+    /// Code of element reported by backend or virtual d-pad button of hat.
+    pub code: ev::Code,
+    pub element: AxisOrBtn,
+    /// How value of element is converted, identity for most elements.
+    pub transform: AxisTransform,
+}
+
+/// Mapping used by gamepad, returned by `Gamepad::mapping()`.
+///
+/// It reflects mapping after all changes, like `Gilrs::set_mapping()`, `NintendoLayout` and
+/// quirks, so it can be shown on settings screen or used to explain why element doesn't send
+/// events.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedMapping {
+    source: MappingSource,
+    origin: Option<MappingOrigin>,
+    name: Option<String>,
+    entries: Vec<MappingEntry>,
+    unmapped: Vec<ev::Code>,
+}
+
+impl ResolvedMapping {
+    pub(crate) fn new(
+        mapping: &Mapping,
+        source: MappingSource,
+        origin: Option<MappingOrigin>,
+        buttons: &[EvCode],
+        axes: &[EvCode],
+    ) -> Self {
+        let mut entries = Vec::new();
+        let mut unmapped = Vec::new();
+        for &code in buttons.iter().chain(axes) {
+            if mapping.map(&code).is_none() {
+                unmapped.push(ev::Code(code));
+            }
+            let split = mapping.split_codes(&code);
+            for virtual_code in Some(code).iter().chain(split) {
+                if let Some(element) = mapping.map(virtual_code) {
+                    entries.push(MappingEntry {
+                        code: ev::Code(code),
+                        element,
+                        transform: mapping.transform(virtual_code).unwrap_or_default(),
+                    });
+                }
+            }
+        }
+
+        // Virtual d-pad buttons of hat.
+        let mut virtual_codes = mapping
+            .mappings
+            .keys()
+            .filter(|code| {
+                !buttons.contains(code) && !axes.contains(code) && !nec::SPLIT_AXES.contains(code)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        virtual_codes.sort();
+        for code in virtual_codes {
+            entries.push(MappingEntry {
+                code: ev::Code(code),
+                element: mapping.mappings[&code],
+                transform: mapping.transform(&code).unwrap_or_default(),
+            });
+        }
+
+        ResolvedMapping {
+            source,
+            origin,
+            name: if mapping.is_default() {
+                None
+            } else {
+                Some(mapping.name().to_owned())
+            },
+            entries,
+            unmapped,
+        }
+    }
+
+    pub fn source(&self) -> MappingSource {
+        self.source
+    }
+
+    /// Returns where SDL mapping comes from, `None` if gamepad doesn't use SDL mapping.
+    pub fn origin(&self) -> Option<MappingOrigin> {
+        self.origin
+    }
+
+    /// Returns name of SDL mapping, `None` if gamepad doesn't use SDL mapping.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(String::as_ref)
+    }
+
+    /// Returns mapped elements, buttons first, in order reported by backend, followed by virtual
+    /// d-pad buttons.
+    pub fn entries(&self) -> &[MappingEntry] {
+        &self.entries
+    }
+
+    /// Returns button or axis that element with `code` is mapped to.
+    pub fn element(&self, code: ev::Code) -> Option<AxisOrBtn> {
+        self.entries
+            .iter()
+            .find(|entry| entry.code == code)
+            .map(|entry| entry.element)
+    }
+
+    /// Returns codes of all elements mapped to `element`. Button with analog and digital element
+    /// or axis split between two buttons have more than one.
+    pub fn codes(&self, element: AxisOrBtn) -> impl Iterator<Item = ev::Code> + '_ {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.element == element)
+            .map(|entry| entry.code)
+    }
+
+    /// Returns codes of elements that gamepad has, but that are not mapped. Their events have
+    /// `Button::Unknown` or `Axis::Unknown`.
+    pub fn unmapped(&self) -> &[ev::Code] {
+        &self.unmapped
+    }
 }
 
 /// The error type for functions related to gamepad mapping.
@@ -560,6 +1434,11 @@ pub enum MappingError {
     UnknownElement,
     /// `Mapping` have button or axis that are not present in SDL2.
     NotSdl2Compatible,
+    /// SDL mapping string is malformed. `position` is byte offset of the offending field.
+    InvalidSdlMapping {
+        position: usize,
+        reason: &'static str,
+    },
 }
 
 impl Error for MappingError {}
@@ -582,12 +1461,169 @@ impl Display for MappingError {
             }
             MappingError::UnknownElement => "Button::Unknown and Axis::Unknown are not allowed",
             MappingError::NotSdl2Compatible => "one of buttons or axes is not compatible with SDL2",
+            MappingError::InvalidSdlMapping { position, reason } => {
+                sbuf = format!("invalid SDL mapping: {} at {}", reason, position);
+                sbuf.as_ref()
+            }
         };
 
         f.write_str(s)
     }
 }
 
+/// Elements that every SDL mapping is expected to bind. Missing ones are reported by
+/// `MappingSummary::missing()`.
+const ESSENTIAL: [AxisOrBtn; 11] = [
+    AxisOrBtn::Btn(Button::South),
+    AxisOrBtn::Btn(Button::East),
+    AxisOrBtn::Btn(Button::North),
+    AxisOrBtn::Btn(Button::West),
+    AxisOrBtn::Btn(Button::Start),
+    AxisOrBtn::Btn(Button::DPadUp),
+    AxisOrBtn::Btn(Button::DPadDown),
+    AxisOrBtn::Btn(Button::DPadLeft),
+    AxisOrBtn::Btn(Button::DPadRight),
+    AxisOrBtn::Axis(Axis::LeftStickX),
+    AxisOrBtn::Axis(Axis::LeftStickY),
+];
+
+/// Result of [`validate_mapping()`](fn.validate_mapping.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MappingSummary {
+    uuid: Uuid,
+    name: String,
+    platform: Option<String>,
+    elements: Vec<AxisOrBtn>,
+    missing: Vec<AxisOrBtn>,
+}
+
+impl MappingSummary {
+    /// Returns UUID of gamepad model the mapping is for.
+    pub fn uuid(&self) -> [u8; 16] {
+        *self.uuid.as_bytes()
+    }
+
+    /// Returns name of the gamepad from the mapping.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns value of `platform` field, if present.
+    pub fn platform(&self) -> Option<&str> {
+        self.platform.as_ref().map(String::as_ref)
+    }
+
+    /// Returns all buttons and axes bound by the mapping, in order of appearance.
+    pub fn elements(&self) -> &[AxisOrBtn] {
+        &self.elements
+    }
+
+    /// Returns essential elements (face buttons, start, d-pad and left stick) that the mapping
+    /// doesn't bind.
+    pub fn missing(&self) -> &[AxisOrBtn] {
+        &self.missing
+    }
+
+    /// Returns true if all essential elements are bound.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Parses and checks SDL mapping string without a connected gamepad.
+///
+/// Returns `MappingError::InvalidSdlMapping` if the string is malformed or uses unknown fields,
+/// and `MappingError::DuplicatedEntry` if the same button or axis is bound twice. Essential
+/// elements that are not bound are not an error, they are listed by `MappingSummary::missing()`.
+///
+/// ```
+/// let summary = gilrs::validate_mapping(
+///     "03000000260900008888000000010001,GameCube {WiseGroup USB box},a:b0,b:b2,y:b3,x:b1,\
+///      start:b7,dpup:h0.1,dpleft:h0.8,dpdown:h0.4,dpright:h0.2,leftx:a0,lefty:a1,\
+///      platform:Linux,",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(summary.name(), "GameCube {WiseGroup USB box}");
+/// assert_eq!(summary.platform(), Some("Linux"));
+/// assert!(summary.is_complete());
+///
+/// assert!(gilrs::validate_mapping("03000000260900008888000000010001,Pad,foo:b0,").is_err());
+/// ```
+pub fn validate_mapping(mapping: &str) -> Result<MappingSummary, MappingError> {
+    let mut summary = MappingSummary {
+        uuid: Uuid::nil(),
+        name: String::new(),
+        platform: None,
+        elements: Vec::new(),
+        missing: Vec::new(),
+    };
+    let mut bound = Vec::new();
+    let mut parser = Parser::new(mapping.trim_end());
+
+    loop {
+        let position = parser.position();
+        let token = match parser.next_token() {
+            Some(Ok(token)) => token,
+            Some(Err(ref e)) if e.kind() == &ParserErrorKind::EmptyValue => continue,
+            Some(Err(e)) => {
+                return Err(MappingError::InvalidSdlMapping {
+                    position: e.position(),
+                    reason: e.kind().description(),
+                })
+            }
+            None => break,
+        };
+
+        let (element, range) = match token {
+            Token::Uuid(uuid) => {
+                summary.uuid = uuid;
+                continue;
+            }
+            Token::Name(name) => {
+                summary.name = name.to_owned();
+                continue;
+            }
+            Token::Platform(platform) => {
+                summary.platform = Some(platform.to_owned());
+                continue;
+            }
+            Token::AxisMapping { to, output, .. } => (to, output),
+            Token::ButtonMapping { to, .. } => (AxisOrBtn::Btn(to), AxisRange::Full),
+            Token::ButtonAxisMapping { to, output, .. } => (AxisOrBtn::Axis(to), output),
+            Token::HatMapping { direction, to, .. } => match direction {
+                0 | 1 | 2 | 4 | 8 => (AxisOrBtn::Btn(to), AxisRange::Full),
+                _ => {
+                    return Err(MappingError::InvalidSdlMapping {
+                        position,
+                        reason: "hat direction isn't 0, 1, 2, 4 or 8",
+                    })
+                }
+            },
+        };
+
+        // Halves of the same axis can be mapped from different elements.
+        let duplicated = bound.iter().any(|&(el, r)| {
+            el == element && (r == range || r == AxisRange::Full || range == AxisRange::Full)
+        });
+        if duplicated {
+            return Err(MappingError::DuplicatedEntry);
+        }
+        bound.push((element, range));
+        if !summary.elements.contains(&element) {
+            summary.elements.push(element);
+        }
+    }
+
+    summary.missing = ESSENTIAL
+        .iter()
+        .filter(|el| !summary.elements.contains(el))
+        .cloned()
+        .collect();
+
+    Ok(summary)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -640,6 +1676,185 @@ mod tests {
         Mapping::parse_sdl_mapping(TEST_STR, &BUTTONS, &AXES).unwrap();
     }
 
+    #[test]
+    fn dual_stage_trigger() {
+        let line = format!("{}lefttrigger:b8,", TEST_STR);
+        let mapping = Mapping::parse_sdl_mapping(&line, &BUTTONS, &AXES).unwrap();
+        let el = AxisOrBtn::Btn(Button::LeftTrigger2);
+        let mut codes = mapping.map_rev_all(&el).collect::<Vec<_>>();
+        codes.sort();
+
+        let mut expected = vec![AXES[4], BUTTONS[8]];
+        expected.sort();
+        assert_eq!(codes, expected);
+    }
+
+    #[test]
+    fn axis_transforms() {
+        let line = "03000000260900008888000000010001,Stick,leftx:a0~,lefty:+a1,\
+                    lefttrigger:-a4,righttrigger:+a4~,-rightx:b3,+rightx:b4,";
+        let mapping = Mapping::parse_sdl_mapping(line, &BUTTONS, &AXES).unwrap();
+        let value = |code, raw| mapping.transform(&code).unwrap().axis_value(raw);
+        assert_eq!(value(AXES[0], 0.5), -0.5);
+        assert_eq!(value(AXES[1], -0.5), -1.0);
+        assert_eq!(value(AXES[1], 0.5), 0.0);
+
+        // Combined trigger axis.
+        let trigger = |code, raw| mapping.transform(&code).unwrap().btn_value(raw);
+        assert_eq!(
+            mapping.map(&AXES[4]),
+            Some(AxisOrBtn::Btn(Button::LeftTrigger2))
+        );
+        assert_eq!(trigger(AXES[4], -1.0), 1.0);
+        assert_eq!(trigger(AXES[4], 1.0), 0.0);
+        let split = mapping.split_codes(&AXES[4]);
+        assert_eq!(split.len(), 1);
+        assert_eq!(
+            mapping.map(&split[0]),
+            Some(AxisOrBtn::Btn(Button::RightTrigger2))
+        );
+        assert_eq!(trigger(split[0], 1.0), 0.0);
+        assert_eq!(trigger(split[0], 0.0), 1.0);
+        // Pressing the other trigger doesn't press this one.
+        assert_eq!(trigger(split[0], -1.0), 0.0);
+
+        assert_eq!(
+            mapping.map(&BUTTONS[3]),
+            Some(AxisOrBtn::Axis(Axis::RightStickX))
+        );
+        assert_eq!(
+            mapping.transform(&BUTTONS[3]).unwrap().pressed_value(),
+            -1.0
+        );
+        assert_eq!(mapping.transform(&BUTTONS[4]).unwrap().pressed_value(), 1.0);
+
+        let mut data = MappingData::new();
+        data.insert_axis_with(
+            ev::Code(AXES[0]),
+            Axis::LeftStickX,
+            AxisTransform::new().inverted(true),
+        );
+        data.insert_btn_axis_with(
+            ev::Code(AXES[4]),
+            Button::LeftTrigger2,
+            AxisTransform::new().input(AxisRange::LowerHalf),
+        );
+        data.insert_axis_btn(
+            ev::Code(BUTTONS[3]),
+            Axis::RightStickX,
+            AxisRange::LowerHalf,
+        );
+        data.insert_axis_btn(
+            ev::Code(BUTTONS[4]),
+            Axis::RightStickX,
+            AxisRange::UpperHalf,
+        );
+        let (from_data, exported) =
+            Mapping::from_data(&data, &BUTTONS, &AXES, "Stick", Uuid::nil()).unwrap();
+        for field in &[
+            ",leftx:a0~,",
+            ",lefttrigger:-a4,",
+            ",-rightx:b3,",
+            ",+rightx:b4,",
+        ] {
+            assert!(exported.contains(field), "{} in {}", field, exported);
+        }
+        assert_eq!(
+            from_data,
+            Mapping::parse_sdl_mapping(&exported, &BUTTONS, &AXES).unwrap()
+        );
+        let parsed = MappingData::parse_sdl(&exported, &BUTTONS, &AXES).unwrap();
+        assert_eq!(
+            parsed.axis_button(Axis::RightStickX, AxisRange::UpperHalf),
+            Some(ev::Code(BUTTONS[4]))
+        );
+        assert!(parsed.axis_transform(Axis::LeftStickX).is_inverted());
+
+        let summary = validate_mapping(line).unwrap();
+        assert_eq!(summary.elements().len(), 5);
+        assert_eq!(
+            validate_mapping("03000000260900008888000000010001,Pad,-leftx:b0,leftx:a0,"),
+            Err(MappingError::DuplicatedEntry)
+        );
+    }
+
+    #[test]
+    fn hat_to_buttons() {
+        // Arcade stick with face buttons on hat.
+        let line = "03000000260900008888000000010001,Stick,a:h0.4,b:h0.2,x:h0.8,y:h0.1,";
+        let mapping = Mapping::parse_sdl_mapping(line, &BUTTONS, &AXES).unwrap();
+        assert_eq!(mapping.hats_mapped(), 0b1111);
+        assert_eq!(
+            mapping.map(&nec::BTN_DPAD_DOWN),
+            Some(AxisOrBtn::Btn(Button::South))
+        );
+
+        let data = MappingData::parse_sdl(line, &BUTTONS, &AXES).unwrap();
+        assert_eq!(data.button(Button::North), Some(ev::Code(nec::BTN_DPAD_UP)));
+        let (from_data, exported) =
+            Mapping::from_data(&data, &BUTTONS, &AXES, "Stick", Uuid::nil()).unwrap();
+        assert!(exported.contains(",y:h0.1,"), "{}", exported);
+        assert_eq!(from_data, mapping);
+
+        // Axis follows direction of hat, not mapped button.
+        let line = "03000000260900008888000000010001,Pad,dpup:h0.2,";
+        let mapping = Mapping::parse_sdl_mapping(line, &BUTTONS, &AXES).unwrap();
+        assert_eq!(
+            mapping.map(&nec::AXIS_DPADX),
+            Some(AxisOrBtn::Axis(Axis::DPadX))
+        );
+
+        let line = "03000000260900008888000000010001,Stick,a:h1.1,";
+        assert!(MappingData::parse_sdl(line, &BUTTONS, &AXES).is_err());
+    }
+
+    #[test]
+    fn resolved() {
+        let mapping = Mapping::parse_sdl_mapping(TEST_STR, &BUTTONS, &AXES).unwrap();
+        let origin = Some(MappingOrigin::Included);
+        let resolved = ResolvedMapping::new(
+            &mapping,
+            MappingSource::SdlMappings,
+            origin,
+            &BUTTONS,
+            &AXES,
+        );
+
+        assert_eq!(resolved.name(), Some("GameCube {WiseGroup USB box}"));
+        assert_eq!(resolved.entries()[0].code, ev::Code(BUTTONS[0]));
+        assert_eq!(
+            resolved.element(ev::Code(BUTTONS[0])),
+            Some(AxisOrBtn::Btn(Button::South))
+        );
+        assert_eq!(
+            resolved
+                .codes(AxisOrBtn::Btn(Button::DPadUp))
+                .collect::<Vec<_>>(),
+            [ev::Code(nec::BTN_DPAD_UP)]
+        );
+        // 6 buttons, 6 axes, 2 hat axes and 4 virtual d-pad buttons.
+        assert_eq!(resolved.entries().len(), 18);
+        assert!(resolved.unmapped().contains(&ev::Code(BUTTONS[4])));
+        assert!(resolved.unmapped().contains(&ev::Code(AXES[8])));
+    }
+
+    #[test]
+    fn swap_face_buttons() {
+        let mut mapping = Mapping::parse_sdl_mapping(TEST_STR, &BUTTONS, &AXES).unwrap();
+        mapping.swap_face_buttons();
+
+        assert_eq!(mapping.map(&BUTTONS[0]), Some(AxisOrBtn::Btn(Button::East)));
+        assert_eq!(
+            mapping.map(&BUTTONS[2]),
+            Some(AxisOrBtn::Btn(Button::South))
+        );
+        assert_eq!(mapping.map(&BUTTONS[3]), Some(AxisOrBtn::Btn(Button::West)));
+        assert_eq!(
+            mapping.map(&BUTTONS[1]),
+            Some(AxisOrBtn::Btn(Button::North))
+        );
+    }
+
     #[test]
     fn from_data() {
         let uuid = Uuid::nil();
@@ -664,6 +1879,7 @@ mod tests {
         data.insert_btn(buttons[7], Button::DPadDown);
         data.insert_btn(buttons[8], Button::DPadLeft);
         data.insert_btn(buttons[9], Button::RightThumb);
+        data.insert_btn_axis(axes[6], Button::South);
 
         let (mappings, sdl_mappings) =
             Mapping::from_data(&data, &BUTTONS, &AXES, name, uuid).unwrap();
@@ -673,18 +1889,63 @@ mod tests {
         let incorrect_mappings = Mapping::from_data(&data, &BUTTONS, &AXES, "Inval,id name", uuid);
         assert_eq!(Err(MappingError::InvalidName), incorrect_mappings);
 
+        // Without hat axes, d-pad button must be reported by gamepad.
         data.insert_btn(ev::Code(nec::BTN_DPAD_RIGHT), Button::DPadRight);
-        let incorrect_mappings = Mapping::from_data(&data, &BUTTONS, &AXES, name, uuid);
+        let incorrect_mappings = Mapping::from_data(&data, &BUTTONS, &AXES[..6], name, uuid);
         assert_eq!(
             Err(MappingError::InvalidCode(ev::Code(nec::BTN_DPAD_RIGHT))),
             incorrect_mappings
         );
+        let (_, sdl_mappings) = Mapping::from_data(&data, &BUTTONS, &AXES, name, uuid).unwrap();
+        assert!(sdl_mappings.contains(",dpright:h0.2,"));
 
         data.insert_btn(ev::Code(BUTTONS[3]), Button::Unknown);
         let incorrect_mappings = Mapping::from_data(&data, &BUTTONS, &AXES, name, uuid);
         assert_eq!(Err(MappingError::UnknownElement), incorrect_mappings);
     }
 
+    #[test]
+    fn sdl_string_round_trip() {
+        let line = format!("{}platform:{},", TEST_STR, SDL_PLATFORM_NAME);
+        let data = MappingData::parse_sdl(&line, &BUTTONS, &AXES).unwrap();
+        assert_eq!(
+            data.button(Button::DPadLeft),
+            Some(ev::Code(nec::BTN_DPAD_LEFT))
+        );
+        assert_eq!(
+            data.button_axis(Button::LeftTrigger2),
+            Some(ev::Code(AXES[4]))
+        );
+
+        let uuid = Uuid::parse_str("03000000260900008888000000010001").unwrap();
+        let name = "GameCube {WiseGroup USB box}";
+        let (mapping, exported) = Mapping::from_data(&data, &BUTTONS, &AXES, name, uuid).unwrap();
+        assert!(exported.ends_with(&format!(",platform:{},", SDL_PLATFORM_NAME)));
+        assert_eq!(
+            mapping,
+            Mapping::parse_sdl_mapping(TEST_STR, &BUTTONS, &AXES).unwrap()
+        );
+        assert_eq!(
+            mapping,
+            Mapping::parse_sdl_mapping(&exported, &BUTTONS, &AXES).unwrap()
+        );
+
+        for (line, reason) in &[
+            ("a:b20,", "gamepad doesn't have this button"),
+            (
+                "-leftx:a0,+leftx:a1,",
+                "element is mapped from more than one axis",
+            ),
+            ("platform:Other,", "mapping is for different platform"),
+        ] {
+            let line = format!("03000000260900008888000000010001,Pad,{}", line);
+            match MappingData::parse_sdl(&line, &BUTTONS, &AXES) {
+                Err(MappingError::InvalidSdlMapping { reason: r, .. }) => assert_eq!(r, *reason),
+                e => panic!("unexpected result {:?}", e),
+            }
+        }
+    }
+
     #[test]
     fn with_mappings() {
         let mappings = format!(
@@ -697,7 +1958,81 @@ mod tests {
 
         assert_eq!(
             Some(TEST_STR),
-            db.get(Uuid::parse_str("03000000260900008888000000010001").unwrap())
+            db.get(
+                *Uuid::parse_str("03000000260900008888000000010001")
+                    .unwrap()
+                    .as_bytes()
+            )
         );
     }
+
+    #[test]
+    fn mapping_db_priority() {
+        let uuid = *Uuid::parse_str("03000000260900008888000000010001")
+            .unwrap()
+            .as_bytes();
+        let user = TEST_STR.replace("GameCube", "Remapped");
+
+        let mut db = MappingDb::new();
+        assert_eq!(db.insert(&user), 1);
+        // Included mappings added later don't override user's mapping.
+        db.add_included_mappings();
+        assert_eq!(db.get(uuid), Some(user.as_str()));
+        assert_eq!(db.origin(uuid), Some(MappingOrigin::User));
+
+        let path = env::temp_dir().join(format!("gilrs_mappings_{}.txt", std::process::id()));
+        db.save_to(&path).unwrap();
+        let mut loaded = MappingDb::new();
+        loaded.insert_with_origin(TEST_STR, MappingOrigin::Directory);
+        assert_eq!(loaded.load_from(&path).unwrap(), 1);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.get(uuid), Some(user.as_str()));
+        assert_eq!(loaded.len(), 1);
+
+        assert_eq!(loaded.remove(uuid), Some(user));
+        assert!(loaded.is_empty());
+    }
+
+    #[test]
+    fn env_mappings_priority() {
+        let uuid = *Uuid::parse_str("03000000260900008888000000010001")
+            .unwrap()
+            .as_bytes();
+        let from_var = TEST_STR.replace("GameCube", "FromVariable");
+        let path = env::temp_dir().join(format!("gilrs_env_mappings_{}.txt", std::process::id()));
+        fs::write(&path, TEST_STR).unwrap();
+
+        env::set_var("GILRS_MAPPINGS_FILE", &path);
+        env::set_var("GILRS_GAMECONTROLLERCONFIG", &from_var);
+        let mut db = MappingDb::new();
+        db.add_env_mappings();
+        env::remove_var("GILRS_MAPPINGS_FILE");
+        env::remove_var("GILRS_GAMECONTROLLERCONFIG");
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(db.get(uuid), Some(from_var.as_str()));
+        assert_eq!(db.origin(uuid), Some(MappingOrigin::Environment));
+    }
+
+    #[test]
+    fn validate() {
+        let summary = validate_mapping(TEST_STR).unwrap();
+        assert!(summary.is_complete());
+        assert_eq!(summary.elements().len(), 16);
+
+        let summary =
+            validate_mapping("03000000260900008888000000010001,Pad,a:b0,leftx:a0,").unwrap();
+        assert!(!summary.is_complete());
+        assert!(summary.missing().contains(&AxisOrBtn::Btn(Button::East)));
+        assert!(!summary.missing().contains(&AxisOrBtn::Btn(Button::South)));
+
+        assert_eq!(
+            validate_mapping("03000000260900008888000000010001,Pad,a:b0,a:b1,"),
+            Err(MappingError::DuplicatedEntry)
+        );
+        match validate_mapping("03000000260900008888000000010001,Pad,dpup:h0.3,") {
+            Err(MappingError::InvalidSdlMapping { position, .. }) => assert_eq!(position, 37),
+            e => panic!("unexpected result {:?}", e),
+        }
+    }
 }
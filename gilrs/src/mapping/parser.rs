@@ -9,10 +9,11 @@ use std::fmt::{self, Display};
 
 use uuid::Uuid;
 
+use super::AxisRange;
 use crate::ev::{Axis, AxisOrBtn, Button};
 
 // Must be sorted!
-static BUTTONS_SDL: [&str; 19] = [
+static BUTTONS_SDL: [&str; 20] = [
     "a",
     "b",
     "back",
@@ -25,6 +26,7 @@ static BUTTONS_SDL: [&str; 19] = [
     "leftshoulder",
     "leftstick",
     "lefttrigger",
+    "misc1",
     "rightshoulder",
     "rightstick",
     "righttrigger",
@@ -33,7 +35,7 @@ static BUTTONS_SDL: [&str; 19] = [
     "y",
     "z",
 ];
-static BUTTONS: [Button; 19] = [
+static BUTTONS: [Button; 20] = [
     Button::South,
     Button::East,
     Button::Select,
@@ -46,6 +48,7 @@ static BUTTONS: [Button; 19] = [
     Button::LeftTrigger,
     Button::LeftThumb,
     Button::LeftTrigger2,
+    Button::Misc1,
     Button::RightTrigger,
     Button::RightThumb,
     Button::RightTrigger2,
@@ -56,7 +59,7 @@ static BUTTONS: [Button; 19] = [
 ];
 
 // Must be sorted!
-static AXES_SDL: [&str; 25] = [
+static AXES_SDL: [&str; 26] = [
     "a",
     "b",
     "back",
@@ -72,6 +75,7 @@ static AXES_SDL: [&str; 25] = [
     "leftx",
     "lefty",
     "leftz",
+    "misc1",
     "rightshoulder",
     "rightstick",
     "righttrigger",
@@ -83,7 +87,7 @@ static AXES_SDL: [&str; 25] = [
     "y",
     "z",
 ];
-static AXES: [AxisOrBtn; 25] = [
+static AXES: [AxisOrBtn; 26] = [
     AxisOrBtn::Btn(Button::South),
     AxisOrBtn::Btn(Button::East),
     AxisOrBtn::Btn(Button::Select),
@@ -99,6 +103,7 @@ static AXES: [AxisOrBtn; 25] = [
     AxisOrBtn::Axis(Axis::LeftStickX),
     AxisOrBtn::Axis(Axis::LeftStickY),
     AxisOrBtn::Axis(Axis::LeftZ),
+    AxisOrBtn::Btn(Button::Misc1),
     AxisOrBtn::Btn(Button::RightTrigger),
     AxisOrBtn::Btn(Button::RightThumb),
     AxisOrBtn::Btn(Button::RightTrigger2),
@@ -126,6 +131,10 @@ impl<'a> Parser<'a> {
         }
     }
 
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
     pub fn next_token(&mut self) -> Option<Result<Token<'_>, Error>> {
         if self.pos >= self.data.len() {
             None
@@ -264,21 +273,21 @@ impl<'a> Parser<'a> {
         .parse::<u16>()
         .or_else(|_| Err(Error::new(ErrorKind::InvalidValue, pos)))?;
 
-        if is_axis {
-            let key = match key.get(0..1) {
-                Some("+") => {
-                    output = AxisRange::UpperHalf;
+        let key = match key.get(0..1) {
+            Some("+") => {
+                output = AxisRange::UpperHalf;
 
-                    &key[1..]
-                }
-                Some("-") => {
-                    output = AxisRange::LowerHalf;
+                &key[1..]
+            }
+            Some("-") => {
+                output = AxisRange::LowerHalf;
 
-                    &key[1..]
-                }
-                _ => key,
-            };
+                &key[1..]
+            }
+            _ => key,
+        };
 
+        if is_axis {
             let idx = AXES_SDL
                 .binary_search(&key)
                 .or_else(|_| Err(Error::new(ErrorKind::UnknownAxis, pos)))?;
@@ -290,15 +299,21 @@ impl<'a> Parser<'a> {
                 output,
                 inverted,
             })
-        } else {
-            let idx = BUTTONS_SDL
-                .binary_search(&key)
-                .or_else(|_| Err(Error::new(ErrorKind::UnknownButton, pos)))?;
-
+        } else if let (AxisRange::Full, Ok(idx)) = (output, BUTTONS_SDL.binary_search(&key)) {
             Ok(Token::ButtonMapping {
                 from,
                 to: BUTTONS[idx],
             })
+        } else {
+            // Button mapped to axis, like `-leftx:b13`.
+            let idx = AXES_SDL
+                .binary_search(&key)
+                .or_else(|_| Err(Error::new(ErrorKind::UnknownButton, pos)))?;
+
+            match AXES[idx] {
+                AxisOrBtn::Axis(to) => Ok(Token::ButtonAxisMapping { from, to, output }),
+                AxisOrBtn::Btn(to) => Ok(Token::ButtonMapping { from, to }),
+            }
         }
     }
 
@@ -325,6 +340,11 @@ pub enum Token<'a> {
         from: u16,
         to: Button,
     },
+    ButtonAxisMapping {
+        from: u16,
+        to: Axis,
+        output: AxisRange,
+    },
     // This is just SDL representation, we will convert this to axis mapping later
     HatMapping {
         hat: u16,
@@ -334,13 +354,6 @@ pub enum Token<'a> {
     },
 }
 
-#[repr(u8)]
-pub enum AxisRange {
-    LowerHalf,
-    UpperHalf,
-    Full,
-}
-
 #[derive(Copy, Clone, Eq, PartialEq)]
 enum State {
     Uuid,
@@ -363,6 +376,10 @@ impl Error {
     pub fn kind(&self) -> &ErrorKind {
         &self.kind
     }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -379,9 +396,9 @@ pub enum ErrorKind {
 
 impl StdError for Error {}
 
-impl Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self.kind {
+impl ErrorKind {
+    pub fn description(&self) -> &'static str {
+        match self {
             ErrorKind::InvalidGuid => "GUID is invalid",
             ErrorKind::InvalidKeyValPair => "expected key value pair",
             ErrorKind::InvalidValue => "value is not valid",
@@ -390,8 +407,12 @@ impl Display for Error {
             ErrorKind::UnknownButton => "invalid button name",
             ErrorKind::InvalidParserState => "attempt to parse after unrecoverable error",
             ErrorKind::UnexpectedEnd => "mapping does not have all required fields",
-        };
+        }
+    }
+}
 
-        f.write_fmt(format_args!("{} at {}", s, self.position))
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.kind.description(), self.position)
     }
 }
@@ -0,0 +1,993 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Gamepads connected over network.
+//!
+//! This module is available with `net` feature. [`NetworkBackend`](struct.NetworkBackend.html)
+//! receives events over TCP or UDP and reports remote gamepads as regular gamepads, so phone app
+//! or another computer can act as a controller. [`NetworkSender`](struct.NetworkSender.html)
+//! forwards events of local gamepads to it.
+//!
+//! ```no_run
+//! use gilrs::net::{NetworkBackend, NetworkSender};
+//! use gilrs::{Gilrs, GilrsBuilder};
+//!
+//! // Receiving side.
+//! let backend = NetworkBackend::bind_tcp("0.0.0.0:7447").unwrap();
+//! let receiver = GilrsBuilder::new().add_backend(backend).build().unwrap();
+//!
+//! // Sending side.
+//! let mut gilrs = Gilrs::new().unwrap();
+//! let mut sender = NetworkSender::connect_tcp("192.168.1.10:7447").unwrap();
+//! while let Some(ev) = gilrs.next_event() {
+//!     sender.send_event(&gilrs, &ev).unwrap();
+//! }
+//! ```
+//!
+//! Protocol
+//! --------
+//!
+//! Messages are UTF-8 lines terminated by `\n`. UDP datagram can contain multiple lines. Client
+//! starts with handshake `HELLO 1`, where `1` is version of protocol. Over TCP server replies with
+//! `OK 1` or `ERROR <reason>` and closes connection. Other messages are:
+//!
+//! - `DEVICE <id> <uuid> <name>` - announces gamepad with client's id, UUID as 32 hex digits
+//!   and name. It has to be sent before gamepad's events.
+//! - `REMOVE <id>` - gamepad was disconnected.
+//! - `PRESS <id> <button>` and `RELEASE <id> <button>` - button was pressed or released.
+//! - `BUTTON <id> <button> <value>` - value of trigger, from 0.0 to 1.0.
+//! - `AXIS <id> <axis> <value>` - value of axis, from -1.0 to 1.0, positive Y is up.
+//!
+//! Buttons and axes are numeric values of `Button` and `Axis` (`Button::South as u16`).
+//! `Button::Misc1` and unknown elements are not supported. TCP clients that don't send handshake
+//! within 5 seconds are disconnected. Messages from UDP clients that didn't send handshake are
+//! ignored, and UDP clients that don't repeat it for 10 seconds are disconnected. `NetworkSender`
+//! repeats handshake and announcements every second while it sends events or
+//! `NetworkSender::keep_alive()` is called.
+//!
+//! Backend accepts up to 16 clients and 32 gamepads, 8 per client. Other clients and
+//! announcements are rejected.
+
+use crate::{Axis, Button, Event, EventType, Gilrs};
+
+use gilrs_core::native_ev_codes as nec;
+use gilrs_core::{
+    AxisInfo, Backend, BackendGamepad, EvCode, Event as RawEvent, EventType as RawEventType,
+};
+use uuid::Uuid;
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::mem;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+const PROTOCOL_VERSION: u32 = 1;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often `NetworkSender` repeats handshake and announcements over UDP.
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+/// UDP client that doesn't repeat handshake for this long is disconnected.
+const UDP_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest line accepted from TCP client, longer lines close connection.
+const MAX_LINE_LEN: usize = 4096;
+const MAX_DATAGRAM_LEN: usize = 65536;
+/// Clients are not authenticated, limit how much memory they can make backend allocate.
+const MAX_CLIENTS: usize = 16;
+const MAX_GAMEPADS: usize = 32;
+const MAX_GAMEPADS_PER_CLIENT: usize = 8;
+
+/// Raw value of axes, `AxisChanged` values are multiplied by it.
+const AXIS_MAX: i32 = 32767;
+/// Raw value of fully pressed trigger.
+const TRIGGER_MAX: i32 = 255;
+
+const BUTTONS: [(Button, EvCode); 19] = [
+    (Button::South, nec::BTN_SOUTH),
+    (Button::East, nec::BTN_EAST),
+    (Button::North, nec::BTN_NORTH),
+    (Button::West, nec::BTN_WEST),
+    (Button::C, nec::BTN_C),
+    (Button::Z, nec::BTN_Z),
+    (Button::LeftTrigger, nec::BTN_LT),
+    (Button::LeftTrigger2, nec::BTN_LT2),
+    (Button::RightTrigger, nec::BTN_RT),
+    (Button::RightTrigger2, nec::BTN_RT2),
+    (Button::Select, nec::BTN_SELECT),
+    (Button::Start, nec::BTN_START),
+    (Button::Mode, nec::BTN_MODE),
+    (Button::LeftThumb, nec::BTN_LTHUMB),
+    (Button::RightThumb, nec::BTN_RTHUMB),
+    (Button::DPadUp, nec::BTN_DPAD_UP),
+    (Button::DPadDown, nec::BTN_DPAD_DOWN),
+    (Button::DPadLeft, nec::BTN_DPAD_LEFT),
+    (Button::DPadRight, nec::BTN_DPAD_RIGHT),
+];
+
+/// Triggers with analog value, reported as axes.
+const TRIGGERS: [(Button, EvCode); 4] = [
+    (Button::LeftTrigger, nec::AXIS_LT),
+    (Button::LeftTrigger2, nec::AXIS_LT2),
+    (Button::RightTrigger, nec::AXIS_RT),
+    (Button::RightTrigger2, nec::AXIS_RT2),
+];
+
+const AXES: [(Axis, EvCode); 8] = [
+    (Axis::LeftStickX, nec::AXIS_LSTICKX),
+    (Axis::LeftStickY, nec::AXIS_LSTICKY),
+    (Axis::LeftZ, nec::AXIS_LEFTZ),
+    (Axis::RightStickX, nec::AXIS_RSTICKX),
+    (Axis::RightStickY, nec::AXIS_RSTICKY),
+    (Axis::RightZ, nec::AXIS_RIGHTZ),
+    (Axis::DPadX, nec::AXIS_DPADX),
+    (Axis::DPadY, nec::AXIS_DPADY),
+];
+
+// Sender already applied deadzone.
+const AXIS_INFO: AxisInfo = AxisInfo {
+    min: -AXIS_MAX,
+    max: AXIS_MAX,
+    deadzone: Some(0),
+};
+const TRIGGER_INFO: AxisInfo = AxisInfo {
+    min: 0,
+    max: TRIGGER_MAX,
+    deadzone: Some(0),
+};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Message {
+    Hello(u32),
+    Device { id: usize, uuid: Uuid, name: String },
+    Remove(usize),
+    Press(usize, Button),
+    Release(usize, Button),
+    Value(usize, Button, f32),
+    Axis(usize, Axis, f32),
+}
+
+impl Message {
+    fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.trim_end_matches('\r').splitn(4, ' ');
+        let kind = parts.next()?;
+        let mut next = || parts.next();
+
+        let msg = match kind {
+            "HELLO" => Message::Hello(next()?.parse().ok()?),
+            "DEVICE" => Message::Device {
+                id: next()?.parse().ok()?,
+                uuid: Uuid::parse_str(next()?).ok()?,
+                name: next()?.to_owned(),
+            },
+            "REMOVE" => Message::Remove(next()?.parse().ok()?),
+            "PRESS" => Message::Press(next()?.parse().ok()?, button(next()?.parse().ok()?)?),
+            "RELEASE" => Message::Release(next()?.parse().ok()?, button(next()?.parse().ok()?)?),
+            "BUTTON" => {
+                let id = next()?.parse().ok()?;
+                let btn = button(next()?.parse().ok()?)?;
+                Message::Value(id, btn, value(next()?, 0.0)?)
+            }
+            "AXIS" => {
+                let id = next()?.parse().ok()?;
+                let axis = axis(next()?.parse().ok()?)?;
+                Message::Axis(id, axis, value(next()?, -1.0)?)
+            }
+            _ => return None,
+        };
+
+        Some(msg)
+    }
+
+    fn to_line(&self) -> String {
+        match *self {
+            Message::Hello(version) => format!("HELLO {}\n", version),
+            Message::Device {
+                id,
+                ref uuid,
+                ref name,
+            } => {
+                // Name can't span multiple lines.
+                let name = name.replace(&['\n', '\r'][..], " ");
+                format!("DEVICE {} {} {}\n", id, uuid.to_simple(), name)
+            }
+            Message::Remove(id) => format!("REMOVE {}\n", id),
+            Message::Press(id, btn) => format!("PRESS {} {}\n", id, btn as u16),
+            Message::Release(id, btn) => format!("RELEASE {} {}\n", id, btn as u16),
+            Message::Value(id, btn, val) => format!("BUTTON {} {} {}\n", id, btn as u16, val),
+            Message::Axis(id, axis, val) => format!("AXIS {} {} {}\n", id, axis as u16, val),
+        }
+    }
+}
+
+fn button(code: u16) -> Option<Button> {
+    BUTTONS
+        .iter()
+        .find(|&&(btn, _)| btn as u16 == code)
+        .map(|&(btn, _)| btn)
+}
+
+fn axis(code: u16) -> Option<Axis> {
+    AXES.iter()
+        .find(|&&(axis, _)| axis as u16 == code)
+        .map(|&(axis, _)| axis)
+}
+
+/// Parses value and checks that it's between `min` and 1.0.
+fn value(s: &str, min: f32) -> Option<f32> {
+    let val: f32 = s.parse().ok()?;
+    if val >= min && val <= 1.0 {
+        Some(val)
+    } else {
+        None
+    }
+}
+
+/// Backend that reports gamepads of clients connected over TCP or UDP. Add it with
+/// `GilrsBuilder::add_backend()`.
+///
+/// Sockets are polled when `Gilrs::next_event()` is called. Gamepad announced by client gets id
+/// of disconnected gamepad with the same name and UUID, if there is one. When limit of gamepads
+/// is reached, ids of other disconnected gamepads are reused.
+#[derive(Debug)]
+pub struct NetworkBackend {
+    listener: Option<TcpListener>,
+    socket: Option<UdpSocket>,
+    clients: Vec<Client>,
+    /// UDP clients that sent handshake and when they sent it last time.
+    udp_clients: Vec<(SocketAddr, Instant)>,
+    /// Receive buffer of UDP socket, empty for TCP.
+    datagram: Vec<u8>,
+    gamepads: Vec<Gamepad>,
+    events: VecDeque<RawEvent>,
+    max_gamepads: usize,
+    max_gamepads_per_client: usize,
+    handshake_timeout: Duration,
+    udp_client_timeout: Duration,
+}
+
+impl NetworkBackend {
+    /// Creates backend that accepts TCP connections on given address.
+    pub fn bind_tcp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        info!("Accepting network gamepads on {}", listener.local_addr()?);
+
+        Ok(Self::new(Some(listener), None))
+    }
+
+    /// Creates backend that receives UDP datagrams on given address.
+    pub fn bind_udp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        info!("Receiving network gamepads on {}", socket.local_addr()?);
+
+        Ok(Self::new(None, Some(socket)))
+    }
+
+    fn new(listener: Option<TcpListener>, socket: Option<UdpSocket>) -> Self {
+        NetworkBackend {
+            listener,
+            datagram: match socket {
+                Some(_) => vec![0; MAX_DATAGRAM_LEN],
+                None => Vec::new(),
+            },
+            socket,
+            clients: Vec::new(),
+            udp_clients: Vec::new(),
+            gamepads: Vec::new(),
+            events: VecDeque::new(),
+            max_gamepads: MAX_GAMEPADS,
+            max_gamepads_per_client: MAX_GAMEPADS_PER_CLIENT,
+            handshake_timeout: HANDSHAKE_TIMEOUT,
+            udp_client_timeout: UDP_CLIENT_TIMEOUT,
+        }
+    }
+
+    /// Returns address backend is bound to, useful if it was bound to port 0.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match (&self.listener, &self.socket) {
+            (Some(listener), _) => listener.local_addr(),
+            (_, Some(socket)) => socket.local_addr(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn poll(&mut self) {
+        if let Some(ref listener) = self.listener {
+            loop {
+                match listener.accept() {
+                    Ok((stream, addr)) if self.clients.len() >= MAX_CLIENTS => {
+                        warn!("Rejecting network client {}, too many clients", addr);
+                        let _ = (&stream).write_all(b"ERROR too many clients\n");
+                    }
+                    Ok((stream, addr)) => match Client::new(stream, addr) {
+                        Ok(client) => {
+                            debug!("Network client {} connected", addr);
+                            self.clients.push(client);
+                        }
+                        Err(e) => warn!("Failed to set up connection with {}: {}", addr, e),
+                    },
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        warn!("Failed to accept network client: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+
+        let mut clients = mem::take(&mut self.clients);
+        clients.retain(|client| self.read_client(client));
+        // Clients are not added while reading.
+        self.clients = clients;
+
+        self.read_datagrams();
+    }
+
+    /// Handles messages from TCP client. Returns `false` if connection was closed.
+    fn read_client(&mut self, client: &Client) -> bool {
+        let (lines, open) = client.read_lines();
+        for line in lines {
+            let msg = match Message::parse(&line) {
+                Some(msg) => msg,
+                None => {
+                    debug!("Invalid message from {}: {:?}", client.addr, line);
+                    continue;
+                }
+            };
+
+            match msg {
+                Message::Hello(PROTOCOL_VERSION) if !client.handshake_done() => {
+                    client.set_handshake_done();
+                    if client.send(&format!("OK {}\n", PROTOCOL_VERSION)).is_err() {
+                        return self.close_client(client.addr);
+                    }
+                }
+                Message::Hello(version) if !client.handshake_done() => {
+                    let _ = client.send(&format!("ERROR unsupported version {}\n", version));
+                    return self.close_client(client.addr);
+                }
+                _ if !client.handshake_done() => return self.close_client(client.addr),
+                msg => self.handle(client.addr, msg),
+            }
+        }
+
+        if !client.handshake_done() && client.accepted.elapsed() >= self.handshake_timeout {
+            debug!("Network client {} didn't send handshake", client.addr);
+            return self.close_client(client.addr);
+        }
+
+        open || self.close_client(client.addr)
+    }
+
+    /// Disconnects gamepads of client. Always returns `false`.
+    fn close_client(&mut self, addr: SocketAddr) -> bool {
+        debug!("Network client {} disconnected", addr);
+        for id in 0..self.gamepads.len() {
+            if self.gamepads[id].client == Some(addr) {
+                self.disconnect(id);
+            }
+        }
+
+        false
+    }
+
+    fn read_datagrams(&mut self) {
+        loop {
+            let (len, addr) = match self.socket {
+                Some(ref socket) => match socket.recv_from(&mut self.datagram) {
+                    Ok(received) => received,
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        debug!("Failed to receive datagram: {}", e);
+                        break;
+                    }
+                },
+                None => break,
+            };
+
+            let text = String::from_utf8_lossy(&self.datagram[..len]).into_owned();
+            for line in text.lines() {
+                let known = self.udp_clients.iter().position(|&(a, _)| a == addr);
+                match Message::parse(line) {
+                    Some(Message::Hello(PROTOCOL_VERSION)) => match known {
+                        Some(idx) => self.udp_clients[idx].1 = Instant::now(),
+                        None if self.udp_clients.len() >= MAX_CLIENTS => {
+                            debug!("Ignoring UDP client {}, too many clients", addr);
+                        }
+                        None => {
+                            debug!("Network client {} connected over UDP", addr);
+                            self.udp_clients.push((addr, Instant::now()));
+                        }
+                    },
+                    Some(msg) if known.is_some() => self.handle(addr, msg),
+                    _ => debug!("Ignoring message from {}: {:?}", addr, line),
+                }
+            }
+        }
+
+        let timeout = self.udp_client_timeout;
+        let mut clients = mem::take(&mut self.udp_clients);
+        clients.retain(|&(addr, last_hello)| {
+            last_hello.elapsed() < timeout || self.close_client(addr)
+        });
+        self.udp_clients = clients;
+    }
+
+    fn handle(&mut self, addr: SocketAddr, msg: Message) {
+        let (remote_id, event) = match msg {
+            Message::Hello(_) => return,
+            Message::Device { id, uuid, name } => return self.announce(addr, id, uuid, name),
+            Message::Remove(id) => {
+                if let Some(local) = self.find(addr, id) {
+                    self.disconnect(local);
+                }
+                return;
+            }
+            Message::Press(id, btn) => (id, RawEventType::ButtonPressed(button_code(btn))),
+            Message::Release(id, btn) => (id, RawEventType::ButtonReleased(button_code(btn))),
+            Message::Value(id, btn, val) => {
+                let code = match TRIGGERS.iter().find(|&&(b, _)| b == btn) {
+                    Some(&(_, code)) => code,
+                    None => return,
+                };
+                let val = (val * TRIGGER_MAX as f32).round() as i32;
+                (id, RawEventType::AxisValueChanged(val, code))
+            }
+            Message::Axis(id, axis, val) => {
+                let code = AXES.iter().find(|&&(a, _)| a == axis).unwrap().1;
+                let val = (val * AXIS_MAX as f32).round() as i32;
+                (id, RawEventType::AxisValueChanged(val, code))
+            }
+        };
+
+        match self.find(addr, remote_id) {
+            Some(local) => self.events.push_back(RawEvent::new(local, event)),
+            None => debug!("Event from {} for unknown gamepad {}", addr, remote_id),
+        }
+    }
+
+    fn announce(&mut self, addr: SocketAddr, remote_id: usize, uuid: Uuid, name: String) {
+        if self.find(addr, remote_id).is_some() {
+            return;
+        }
+
+        let client_gamepads = self
+            .gamepads
+            .iter()
+            .filter(|g| g.client == Some(addr))
+            .count();
+        if client_gamepads >= self.max_gamepads_per_client {
+            warn!("Ignoring gamepad {} of {}, too many gamepads", name, addr);
+            return;
+        }
+
+        let local = match self
+            .gamepads
+            .iter()
+            .position(|g| g.client.is_none() && g.uuid == uuid && g.name == name)
+        {
+            Some(local) => local,
+            None if self.gamepads.len() < self.max_gamepads => {
+                self.gamepads.push(Gamepad::new(uuid, name));
+                self.gamepads.len() - 1
+            }
+            None => match self.gamepads.iter().position(|g| g.client.is_none()) {
+                Some(local) => {
+                    self.gamepads[local] = Gamepad::new(uuid, name);
+                    local
+                }
+                None => {
+                    warn!("Ignoring gamepad {} of {}, too many gamepads", name, addr);
+                    return;
+                }
+            },
+        };
+
+        let gamepad = &mut self.gamepads[local];
+        info!("Network gamepad {} ({}) connected.", gamepad.name, addr);
+        gamepad.client = Some(addr);
+        gamepad.remote_id = remote_id;
+        self.events
+            .push_back(RawEvent::new(local, RawEventType::Connected));
+    }
+
+    fn disconnect(&mut self, local: usize) {
+        let gamepad = &mut self.gamepads[local];
+        if gamepad.client.take().is_some() {
+            info!("Network gamepad {} disconnected.", gamepad.name);
+            self.events
+                .push_back(RawEvent::new(local, RawEventType::Disconnected));
+        }
+    }
+
+    fn find(&self, addr: SocketAddr, remote_id: usize) -> Option<usize> {
+        self.gamepads
+            .iter()
+            .position(|g| g.client == Some(addr) && g.remote_id == remote_id)
+    }
+}
+
+impl Backend for NetworkBackend {
+    fn next_event(&mut self) -> Option<RawEvent> {
+        if self.events.is_empty() {
+            self.poll();
+        }
+
+        self.events.pop_front()
+    }
+
+    fn gamepad(&self, id: usize) -> Option<&(dyn BackendGamepad + 'static)> {
+        self.gamepads
+            .get(id)
+            .map(|g| g as &(dyn BackendGamepad + 'static))
+    }
+
+    fn last_gamepad_hint(&self) -> usize {
+        self.gamepads.len()
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+}
+
+fn button_code(btn: Button) -> EvCode {
+    BUTTONS.iter().find(|&&(b, _)| b == btn).unwrap().1
+}
+
+#[derive(Debug)]
+struct Client {
+    stream: TcpStream,
+    addr: SocketAddr,
+    accepted: Instant,
+    /// Received bytes of incomplete line.
+    buf: RefCell<Vec<u8>>,
+    handshake_done: Cell<bool>,
+}
+
+impl Client {
+    fn new(stream: TcpStream, addr: SocketAddr) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+
+        Ok(Client {
+            stream,
+            addr,
+            accepted: Instant::now(),
+            buf: Default::default(),
+            handshake_done: Default::default(),
+        })
+    }
+
+    fn handshake_done(&self) -> bool {
+        self.handshake_done.get()
+    }
+
+    fn set_handshake_done(&self) {
+        self.handshake_done.set(true)
+    }
+
+    fn send(&self, line: &str) -> io::Result<()> {
+        (&self.stream).write_all(line.as_bytes())
+    }
+
+    /// Returns complete lines that were received and `false` if connection is closed.
+    fn read_lines(&self) -> (Vec<String>, bool) {
+        let mut buf = self.buf.borrow_mut();
+        let mut chunk = [0; 1024];
+        let open = loop {
+            match (&self.stream).read(&mut chunk) {
+                Ok(0) => break false,
+                Ok(n) => {
+                    buf.extend_from_slice(&chunk[..n]);
+                    // Don't let client that never sends newline grow the buffer. If there are
+                    // complete lines, the rest is read on next poll.
+                    if buf.len() > MAX_LINE_LEN {
+                        break true;
+                    }
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => break true,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => {
+                    debug!("Failed to read from {}: {}", self.addr, e);
+                    break false;
+                }
+            }
+        };
+
+        let mut lines = Vec::new();
+        while let Some(end) = buf.iter().position(|&b| b == b'\n') {
+            let line = buf.drain(..=end).collect::<Vec<_>>();
+            lines.push(String::from_utf8_lossy(&line[..end]).into_owned());
+        }
+
+        (lines, open && buf.len() <= MAX_LINE_LEN)
+    }
+}
+
+#[derive(Debug)]
+struct Gamepad {
+    name: String,
+    uuid: Uuid,
+    /// `None` if gamepad is disconnected.
+    client: Option<SocketAddr>,
+    /// Id assigned by client.
+    remote_id: usize,
+    buttons: Vec<EvCode>,
+    axes: Vec<EvCode>,
+}
+
+impl Gamepad {
+    fn new(uuid: Uuid, name: String) -> Self {
+        Gamepad {
+            name,
+            uuid,
+            client: None,
+            remote_id: 0,
+            buttons: BUTTONS.iter().map(|&(_, code)| code).collect(),
+            axes: AXES
+                .iter()
+                .map(|&(_, code)| code)
+                .chain(TRIGGERS.iter().map(|&(_, code)| code))
+                .collect(),
+        }
+    }
+}
+
+impl BackendGamepad for Gamepad {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn uuid(&self) -> [u8; 16] {
+        *self.uuid.as_bytes()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    fn buttons(&self) -> &[EvCode] {
+        &self.buttons
+    }
+
+    fn axes(&self) -> &[EvCode] {
+        &self.axes
+    }
+
+    fn axis_info(&self, nec: EvCode) -> Option<&AxisInfo> {
+        if TRIGGERS.iter().any(|&(_, code)| code == nec) {
+            Some(&TRIGGER_INFO)
+        } else if AXES.iter().any(|&(_, code)| code == nec) {
+            Some(&AXIS_INFO)
+        } else {
+            None
+        }
+    }
+}
+
+/// Forwards events of local gamepads to [`NetworkBackend`](struct.NetworkBackend.html).
+#[derive(Debug)]
+pub struct NetworkSender {
+    transport: Transport,
+    /// Gamepads that were announced to backend.
+    announced: Vec<usize>,
+    last_announce: Option<Instant>,
+}
+
+#[derive(Debug)]
+enum Transport {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+impl NetworkSender {
+    /// Connects to backend created by `NetworkBackend::bind_tcp()` and waits for response to
+    /// handshake.
+    pub fn connect_tcp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        stream.write_all(Message::Hello(PROTOCOL_VERSION).to_line().as_bytes())?;
+
+        let mut reply = String::new();
+        BufReader::new(&stream).read_line(&mut reply)?;
+        if reply.trim_end() != format!("OK {}", PROTOCOL_VERSION) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("handshake rejected: {}", reply.trim_end()),
+            ));
+        }
+
+        Ok(NetworkSender {
+            transport: Transport::Tcp(stream),
+            announced: Vec::new(),
+            last_announce: None,
+        })
+    }
+
+    /// Creates sender that sends datagrams to backend created by `NetworkBackend::bind_udp()`.
+    /// UDP doesn't confirm delivery, so this function doesn't check that backend exists.
+    pub fn connect_udp<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "no address to connect to"))?;
+        let local: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+
+        Ok(NetworkSender {
+            transport: Transport::Udp(socket),
+            announced: Vec::new(),
+            last_announce: None,
+        })
+    }
+
+    /// Sends event of gamepad from `gilrs`. Gamepad is announced before its first event.
+    /// `Connected`, `Disconnected`, button and axis events are sent, other events are ignored.
+    pub fn send_event(&mut self, gilrs: &Gilrs, event: &Event) -> io::Result<()> {
+        let id: usize = event.id.into();
+        let mut lines = self.repeated_handshake();
+
+        let msg = match event.event {
+            EventType::ButtonPressed(btn, _) => button(btn as u16).map(|b| Message::Press(id, b)),
+            EventType::ButtonReleased(btn, _) => {
+                button(btn as u16).map(|b| Message::Release(id, b))
+            }
+            EventType::ButtonChanged(btn, val, _) if TRIGGERS.iter().any(|&(b, _)| b == btn) => {
+                Some(Message::Value(id, btn, val))
+            }
+            EventType::AxisChanged(axis, val, _) if axis != Axis::Unknown => {
+                Some(Message::Axis(id, axis, val))
+            }
+            EventType::Connected => None,
+            EventType::Disconnected => {
+                if let Some(pos) = self.announced.iter().position(|&a| a == id) {
+                    self.announced.remove(pos);
+                    lines += &Message::Remove(id).to_line();
+                }
+                return self.send(&lines);
+            }
+            _ => return self.send(&lines),
+        };
+
+        if !self.announced.contains(&id) {
+            let gamepad = gilrs.gamepad(event.id);
+            lines += &Message::Device {
+                id,
+                uuid: Uuid::from_bytes(gamepad.uuid()),
+                name: gamepad.name().to_owned(),
+            }
+            .to_line();
+            self.announced.push(id);
+        }
+
+        if let Some(msg) = msg {
+            lines += &msg.to_line();
+        }
+
+        self.send(&lines)
+    }
+
+    /// Repeats handshake over UDP if it wasn't sent in last second, so backend doesn't disconnect
+    /// this sender while its gamepads are idle. Call it regularly, for example once per frame.
+    /// Does nothing over TCP.
+    pub fn keep_alive(&mut self) -> io::Result<()> {
+        let lines = self.repeated_handshake();
+        self.send(&lines)
+    }
+
+    /// Returns handshake if it's time to repeat it. Gamepads are announced again after it.
+    fn repeated_handshake(&mut self) -> String {
+        if let Transport::Tcp(_) = self.transport {
+            return String::new();
+        }
+        if let Some(time) = self.last_announce {
+            if time.elapsed() < ANNOUNCE_INTERVAL {
+                return String::new();
+            }
+        }
+
+        self.announced.clear();
+        self.last_announce = Some(Instant::now());
+        Message::Hello(PROTOCOL_VERSION).to_line()
+    }
+
+    fn send(&mut self, lines: &str) -> io::Result<()> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        match self.transport {
+            Transport::Tcp(ref mut stream) => stream.write_all(lines.as_bytes()),
+            Transport::Udp(ref socket) => socket.send(lines.as_bytes()).map(|_| ()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Message, NetworkBackend};
+    use crate::{Axis, Button};
+    use gilrs_core::native_ev_codes as nec;
+    use gilrs_core::{Backend, EventType};
+    use uuid::Uuid;
+
+    use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+    use std::net::{TcpStream, UdpSocket};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Polls backend until `count` events are received or a second passes.
+    fn events(backend: &mut NetworkBackend, count: usize) -> Vec<(usize, EventType)> {
+        let start = Instant::now();
+        let mut events = Vec::new();
+        while events.len() < count && start.elapsed() < Duration::from_secs(1) {
+            match backend.next_event() {
+                Some(ev) => events.push((ev.id, ev.event)),
+                None => thread::sleep(Duration::from_millis(1)),
+            }
+        }
+
+        events
+    }
+
+    fn device(id: usize, uuid: u8, name: &str) -> String {
+        Message::Device {
+            id,
+            uuid: Uuid::from_bytes([uuid; 16]),
+            name: name.to_owned(),
+        }
+        .to_line()
+    }
+
+    #[test]
+    fn tcp_loopback() {
+        let mut backend = NetworkBackend::bind_tcp("127.0.0.1:0").unwrap();
+        backend.max_gamepads = 2;
+        backend.max_gamepads_per_client = 2;
+        let addr = backend.local_addr().unwrap();
+
+        // Client that didn't send handshake is disconnected.
+        let mut rogue = TcpStream::connect(addr).unwrap();
+        rogue.write_all(device(0, 9, "Rogue").as_bytes()).unwrap();
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let press = Message::Press(0, Button::South).to_line();
+        let lines = format!("HELLO 1\n{}{}", device(0, 1, "A"), press);
+        client.write_all(lines.as_bytes()).unwrap();
+
+        assert_eq!(
+            events(&mut backend, 2),
+            [
+                (0, EventType::Connected),
+                (0, EventType::ButtonPressed(nec::BTN_SOUTH))
+            ]
+        );
+        let mut reply = String::new();
+        BufReader::new(&client).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "OK 1\n");
+        match rogue.read(&mut [0; 16]) {
+            Ok(0) => (),
+            Err(ref e) if e.kind() == ErrorKind::ConnectionReset => (),
+            res => panic!("rogue client not disconnected: {:?}", res),
+        }
+
+        // The third gamepad is over limit of client, its events are ignored.
+        let lines = format!(
+            "{}{}{}REMOVE 0\n{}",
+            device(1, 2, "B"),
+            device(2, 3, "C"),
+            Message::Press(2, Button::South).to_line(),
+            Message::Press(1, Button::East).to_line(),
+        );
+        client.write_all(lines.as_bytes()).unwrap();
+        assert_eq!(
+            events(&mut backend, 3),
+            [
+                (1, EventType::Connected),
+                (0, EventType::Disconnected),
+                (1, EventType::ButtonPressed(nec::BTN_EAST))
+            ]
+        );
+
+        // Backend has maximum number of gamepads, id of disconnected one is reused.
+        client.write_all(device(3, 3, "C").as_bytes()).unwrap();
+        assert_eq!(events(&mut backend, 1), [(0, EventType::Connected)]);
+        assert_eq!(backend.gamepad(0).unwrap().name(), "C");
+        assert_eq!(backend.last_gamepad_hint(), 2);
+
+        drop(client);
+        assert_eq!(
+            events(&mut backend, 2),
+            [(0, EventType::Disconnected), (1, EventType::Disconnected)]
+        );
+    }
+
+    #[test]
+    fn tcp_handshake_timeout() {
+        let mut backend = NetworkBackend::bind_tcp("127.0.0.1:0").unwrap();
+        backend.handshake_timeout = Duration::from_millis(50);
+        let mut silent = TcpStream::connect(backend.local_addr().unwrap()).unwrap();
+        silent
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+
+        assert_eq!(events(&mut backend, 1), []);
+        assert!(backend.clients.is_empty());
+        match silent.read(&mut [0; 16]) {
+            Ok(0) => (),
+            Err(ref e) if e.kind() == ErrorKind::ConnectionReset => (),
+            res => panic!("silent client not disconnected: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn udp_client_timeout() {
+        let mut backend = NetworkBackend::bind_udp("127.0.0.1:0").unwrap();
+        backend.udp_client_timeout = Duration::from_millis(500);
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.connect(backend.local_addr().unwrap()).unwrap();
+
+        let lines = format!("HELLO 1\n{}", device(0, 1, "A"));
+        client.send(lines.as_bytes()).unwrap();
+        assert_eq!(events(&mut backend, 1), [(0, EventType::Connected)]);
+        assert_eq!(backend.datagram.len(), super::MAX_DATAGRAM_LEN);
+
+        // Repeated handshake keeps client connected.
+        thread::sleep(Duration::from_millis(300));
+        client.send(b"HELLO 1\n").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert!(backend.next_event().is_none());
+        thread::sleep(Duration::from_millis(300));
+        assert!(backend.next_event().is_none());
+
+        // Client that stopped repeating it is disconnected.
+        assert_eq!(events(&mut backend, 1), [(0, EventType::Disconnected)]);
+        assert!(backend.udp_clients.is_empty());
+    }
+
+    #[test]
+    fn message_round_trip() {
+        let msgs = [
+            Message::Hello(1),
+            Message::Device {
+                id: 3,
+                uuid: Uuid::from_bytes([7; 16]),
+                name: "Phone controller".to_owned(),
+            },
+            Message::Remove(3),
+            Message::Press(3, Button::South),
+            Message::Release(3, Button::DPadLeft),
+            Message::Value(3, Button::RightTrigger2, 0.5),
+            Message::Axis(3, Axis::LeftStickY, -0.25),
+        ];
+
+        for msg in &msgs {
+            let line = msg.to_line();
+            assert_eq!(
+                Message::parse(line.trim_end()).as_ref(),
+                Some(msg),
+                "{}",
+                line
+            );
+        }
+
+        assert_eq!(Message::parse("PRESS 0 20"), None);
+        assert_eq!(Message::parse("AXIS 0 1 1.5"), None);
+        assert_eq!(Message::parse("HELLO"), None);
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Virtual gamepads visible to other applications.
+//!
+//! This module is available with `virtual-gamepad` feature on Linux and Windows.
+//! [`VirtualGamepad`](struct.VirtualGamepad.html) is seen by other applications as Xbox 360
+//! controller, so tools that remap or combine gamepads can pass their result to games:
+//!
+//! ```no_run
+//! use gilrs::output::VirtualGamepad;
+//! use gilrs::{Button, EventType, Gilrs};
+//!
+//! let mut gilrs = Gilrs::new().unwrap();
+//! let mut output = VirtualGamepad::new("Remapped gamepad").unwrap();
+//!
+//! loop {
+//!     while let Some(ev) = gilrs.next_event() {
+//!         // Swap South and East.
+//!         let event = match ev.event {
+//!             EventType::ButtonPressed(Button::South, code) => {
+//!                 EventType::ButtonPressed(Button::East, code)
+//!             }
+//!             EventType::ButtonPressed(Button::East, code) => {
+//!                 EventType::ButtonPressed(Button::South, code)
+//!             }
+//!             EventType::ButtonReleased(Button::South, code) => {
+//!                 EventType::ButtonReleased(Button::East, code)
+//!             }
+//!             EventType::ButtonReleased(Button::East, code) => {
+//!                 EventType::ButtonReleased(Button::South, code)
+//!             }
+//!             event => event,
+//!         };
+//!         output.send_event(&event).unwrap();
+//!     }
+//!     # break;
+//! }
+//! ```
+//!
+//! On Linux, virtual gamepad is created with uinput and user needs write access to
+//! `/dev/uinput`. Note that `Gilrs` of the same application reports it as well, so remapping
+//! tools usually ignore it or grab source gamepads with `Gamepad::claim_exclusive()`. On Windows,
+//! [ViGEmBus](https://github.com/nefarius/ViGEmBus) driver has to be installed and
+//! `ViGEmClient.dll` is loaded at runtime.
+
+use crate::{Axis, Button, EventType};
+
+use gilrs_core::native_ev_codes as nec;
+
+use std::io;
+
+/// Gamepad created by application and exposed to OS as Xbox 360 controller. It's removed when
+/// dropped.
+///
+/// Changes made by `set_*()` functions are sent to OS by `sync()`. Buttons and axes that Xbox
+/// 360 controller doesn't have (`Button::C`, `Button::Z`, `Button::Misc1`, unknown elements)
+/// are ignored.
+#[derive(Debug)]
+pub struct VirtualGamepad {
+    inner: gilrs_core::VirtualGamepad,
+}
+
+impl VirtualGamepad {
+    /// Creates new virtual gamepad. Name is ignored on Windows.
+    pub fn new(name: &str) -> io::Result<Self> {
+        Ok(VirtualGamepad {
+            inner: gilrs_core::VirtualGamepad::new(name)?,
+        })
+    }
+
+    /// Presses or releases button.
+    pub fn set_button(&mut self, btn: Button, pressed: bool) {
+        if let Some(code) = btn.to_nec() {
+            self.inner.set_button(code.0, pressed);
+        }
+    }
+
+    /// Sets value of button, from 0.0 to 1.0. `LeftTrigger2` and `RightTrigger2` are analog,
+    /// other buttons are pressed if value is greater than 0.5.
+    pub fn set_button_value(&mut self, btn: Button, value: f32) {
+        match btn {
+            Button::LeftTrigger2 => self.inner.set_axis(nec::AXIS_LT2, value),
+            Button::RightTrigger2 => self.inner.set_axis(nec::AXIS_RT2, value),
+            btn => self.set_button(btn, value > 0.5),
+        }
+    }
+
+    /// Sets value of axis, from -1.0 to 1.0. Positive values of Y axes are up. `LeftZ` and
+    /// `RightZ` are triggers, negative values are treated as 0.0.
+    pub fn set_axis(&mut self, axis: Axis, value: f32) {
+        let code = match axis {
+            Axis::LeftStickX => nec::AXIS_LSTICKX,
+            Axis::LeftStickY => nec::AXIS_LSTICKY,
+            Axis::LeftZ => nec::AXIS_LEFTZ,
+            Axis::RightStickX => nec::AXIS_RSTICKX,
+            Axis::RightStickY => nec::AXIS_RSTICKY,
+            Axis::RightZ => nec::AXIS_RIGHTZ,
+            Axis::DPadX => nec::AXIS_DPADX,
+            Axis::DPadY => nec::AXIS_DPADY,
+            Axis::Unknown => return,
+        };
+
+        self.inner.set_axis(code, value);
+    }
+
+    /// Releases all buttons and centers all axes.
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    /// Sends changes to OS.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.inner.sync()
+    }
+
+    /// Applies event of other gamepad and sends changes to OS. `Disconnected` resets state of
+    /// virtual gamepad, events that don't change state of buttons or axes are ignored.
+    pub fn send_event(&mut self, event: &EventType) -> io::Result<()> {
+        match *event {
+            // Analog value of triggers comes with `ButtonChanged`.
+            EventType::ButtonPressed(Button::LeftTrigger2, _)
+            | EventType::ButtonPressed(Button::RightTrigger2, _)
+            | EventType::ButtonReleased(Button::LeftTrigger2, _)
+            | EventType::ButtonReleased(Button::RightTrigger2, _) => return Ok(()),
+            EventType::ButtonPressed(btn, _) => self.set_button(btn, true),
+            EventType::ButtonReleased(btn, _) => self.set_button(btn, false),
+            EventType::ButtonChanged(btn @ Button::LeftTrigger2, value, _)
+            | EventType::ButtonChanged(btn @ Button::RightTrigger2, value, _) => {
+                self.set_button_value(btn, value)
+            }
+            EventType::AxisChanged(axis, value, _) => self.set_axis(axis, value),
+            EventType::Disconnected => self.reset(),
+            _ => return Ok(()),
+        }
+
+        self.sync()
+    }
+}
@@ -0,0 +1,436 @@
+// Copyright 2016-2018 Mateusz Sieczko and other GilRs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Fixes for controllers whose firmware reports wrong values, keyed by vendor and product ID.
+
+use crate::Axis;
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often quirks file is checked for changes.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fixes applied to gamepad. Returned by `Gamepad::quirk()`.
+///
+/// Quirks are read from TOML file with one `[[quirk]]` table per controller:
+///
+/// ```toml
+/// [[quirk]]
+/// name = "Some gamepad"  # Optional, only for people reading the file.
+/// vendor = 0x0079
+/// product = 0x0006       # Optional, without it quirk applies to all products of the vendor.
+/// invert_axes = ["LeftStickY", "RightStickY"]
+/// swap_triggers = true
+/// broken_battery = true
+/// ```
+///
+/// File is not read by full TOML parser, only the subset used above is accepted:
+///
+/// - `[[quirk]]` tables, no other tables or inline tables,
+/// - one `key = value` per line, keys can't be quoted or dotted,
+/// - decimal and hexadecimal integers, `true` and `false`,
+/// - basic strings with `\"`, `\\`, `\n` and `\t` escapes, no literal or multi-line strings,
+/// - arrays of strings on one line, strings in arrays can't contain commas.
+///
+/// Anything else makes loading fail with `QuirksError::InvalidLine`. Unknown keys are ignored
+/// with warning.
+///
+/// Quirks are applied by `Gilrs` to events and state of gamepads, not by platform backends, so
+/// they also work for gamepads of custom backends.
+#[non_exhaustive]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Quirk {
+    /// Axes whose values are negated.
+    pub inverted_axes: Vec<Axis>,
+    /// Gamepad reports `LeftTrigger2` as `RightTrigger2` and vice versa. Not applied to mappings
+    /// set with `Gilrs::set_mapping()`, they are created from codes that gamepad really sends.
+    pub swap_triggers: bool,
+    /// Gamepad reports wrong battery status, `Gamepad::power_info()` returns
+    /// `PowerInfo::Unknown`.
+    pub broken_battery: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    vendor: u16,
+    product: Option<u16>,
+    quirk: Quirk,
+}
+
+/// Quirks loaded from file.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct QuirkDb {
+    entries: Vec<Entry>,
+}
+
+impl QuirkDb {
+    pub fn parse(s: &str) -> Result<Self, QuirksError> {
+        let mut entries = Vec::new();
+        // Entry and whether `vendor` was set.
+        let mut current: Option<(Entry, bool)> = None;
+
+        for (idx, line) in s.lines().enumerate() {
+            let err = |reason| QuirksError::InvalidLine {
+                line: idx + 1,
+                reason,
+            };
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                if line != "[[quirk]]" {
+                    return Err(err("unknown table"));
+                }
+                if let Some(entry) = current.take() {
+                    entries.push(finish(entry).map_err(err)?);
+                }
+                let entry = Entry {
+                    vendor: 0,
+                    product: None,
+                    quirk: Quirk::default(),
+                };
+                current = Some((entry, false));
+                continue;
+            }
+
+            let (entry, has_vendor) = match current {
+                Some((ref mut entry, ref mut has_vendor)) => (entry, has_vendor),
+                None => return Err(err("key outside of [[quirk]] table")),
+            };
+            let mut kv = line.splitn(2, '=');
+            let key = kv.next().unwrap().trim();
+            let value = kv.next().ok_or_else(|| err("expected '='"))?.trim();
+            if !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(err("unsupported key, only bare keys are allowed"));
+            }
+
+            match key {
+                "name" => {
+                    parse_string(value).ok_or_else(|| err("invalid string"))?;
+                }
+                "vendor" => {
+                    entry.vendor = parse_u16(value).ok_or_else(|| err("invalid vendor ID"))?;
+                    *has_vendor = true;
+                }
+                "product" => {
+                    let product = parse_u16(value).ok_or_else(|| err("invalid product ID"))?;
+                    entry.product = Some(product);
+                }
+                "invert_axes" => {
+                    entry.quirk.inverted_axes = parse_array(value)
+                        .ok_or_else(|| err("invalid array"))?
+                        .iter()
+                        .map(|name| axis(name).ok_or_else(|| err("unknown axis")))
+                        .collect::<Result<_, _>>()?;
+                }
+                "swap_triggers" => {
+                    entry.quirk.swap_triggers =
+                        parse_bool(value).ok_or_else(|| err("invalid boolean"))?;
+                }
+                "broken_battery" => {
+                    entry.quirk.broken_battery =
+                        parse_bool(value).ok_or_else(|| err("invalid boolean"))?;
+                }
+                key => warn!(
+                    "Quirks: ignoring unknown key {:?} on line {}.",
+                    key,
+                    idx + 1
+                ),
+            }
+        }
+
+        if let Some(entry) = current {
+            // Reported at the end of file, position of table is not tracked.
+            let line = s.lines().count();
+            entries
+                .push(finish(entry).map_err(|reason| QuirksError::InvalidLine { line, reason })?);
+        }
+
+        Ok(QuirkDb { entries })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, QuirksError> {
+        let s = fs::read_to_string(path).map_err(QuirksError::Io)?;
+
+        QuirkDb::parse(&s)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns quirk for given gamepad. Entry with matching product ID takes precedence over
+    /// entry for all products of the vendor, later entries override earlier ones.
+    pub fn get(&self, vendor: Option<u16>, product: Option<u16>) -> Quirk {
+        let vendor = match vendor {
+            Some(vendor) => vendor,
+            None => return Quirk::default(),
+        };
+        let matching = |exact: bool| {
+            self.entries.iter().rev().find(|e| {
+                e.vendor == vendor
+                    && if exact {
+                        e.product.is_some() && e.product == product
+                    } else {
+                        e.product.is_none()
+                    }
+            })
+        };
+
+        matching(true)
+            .or_else(|| matching(false))
+            .map(|e| e.quirk.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn finish((entry, has_vendor): (Entry, bool)) -> Result<Entry, &'static str> {
+    if has_vendor {
+        Ok(entry)
+    } else {
+        Err("[[quirk]] table without vendor")
+    }
+}
+
+/// Removes comment, `#` in strings is not treated as comment.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if in_string => {
+                escaped = !escaped;
+                continue;
+            }
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => (),
+        }
+        escaped = false;
+    }
+
+    line
+}
+
+fn parse_u16(s: &str) -> Option<u16> {
+    let s = s.replace('_', "");
+    match s.get(..2) {
+        Some("0x") => u16::from_str_radix(&s[2..], 16).ok(),
+        _ => s.parse().ok(),
+    }
+}
+
+fn parse_bool(s: &str) -> Option<bool> {
+    match s {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_string(s: &str) -> Option<String> {
+    if s.len() < 2 || !s.starts_with('"') || !s.ends_with('"') {
+        return None;
+    }
+
+    let mut out = String::new();
+    let mut chars = s[1..s.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                _ => return None,
+            },
+            '"' => return None,
+            c => out.push(c),
+        }
+    }
+
+    Some(out)
+}
+
+/// Parses array of strings. Strings can't contain commas.
+fn parse_array(s: &str) -> Option<Vec<String>> {
+    if !s.starts_with('[') || !s.ends_with(']') {
+        return None;
+    }
+
+    s[1..s.len() - 1]
+        .split(',')
+        .map(str::trim)
+        // Trailing comma is allowed.
+        .filter(|item| !item.is_empty())
+        .map(parse_string)
+        .collect()
+}
+
+fn axis(name: &str) -> Option<Axis> {
+    let axis = match name {
+        "LeftStickX" => Axis::LeftStickX,
+        "LeftStickY" => Axis::LeftStickY,
+        "LeftZ" => Axis::LeftZ,
+        "RightStickX" => Axis::RightStickX,
+        "RightStickY" => Axis::RightStickY,
+        "RightZ" => Axis::RightZ,
+        "DPadX" => Axis::DPadX,
+        "DPadY" => Axis::DPadY,
+        _ => return None,
+    };
+
+    Some(axis)
+}
+
+/// File with quirks that is reloaded when it changes.
+#[derive(Debug)]
+pub(crate) struct QuirksFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: SystemTime,
+}
+
+impl QuirksFile {
+    /// Loads file for the first time.
+    pub fn load(path: PathBuf) -> (Self, Result<QuirkDb, QuirksError>) {
+        let modified = modified(&path);
+        let db = QuirkDb::load(&path);
+        let file = QuirksFile {
+            path,
+            modified,
+            last_check: crate::utils::time_now(),
+        };
+
+        (file, db)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns new quirks if file changed since last check. Errors are logged and previous quirks
+    /// are kept.
+    pub fn check(&mut self, now: SystemTime) -> Option<QuirkDb> {
+        match now.duration_since(self.last_check) {
+            Ok(elapsed) if elapsed >= RELOAD_INTERVAL => (),
+            _ => return None,
+        }
+        self.last_check = now;
+
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        match QuirkDb::load(&self.path) {
+            Ok(db) => {
+                info!("Reloaded {} quirks from {:?}", db.len(), self.path);
+                Some(db)
+            }
+            Err(e) => {
+                warn!("Failed to reload quirks from {:?}: {}", self.path, e);
+                None
+            }
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Error returned when loading quirks.
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum QuirksError {
+    /// Failed to read the file.
+    Io(io::Error),
+    /// File is not valid. `line` starts from 1.
+    InvalidLine { line: usize, reason: &'static str },
+}
+
+impl Error for QuirksError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            QuirksError::Io(e) => Some(e),
+            QuirksError::InvalidLine { .. } => None,
+        }
+    }
+}
+
+impl Display for QuirksError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            QuirksError::Io(_) => f.write_str("failed to read quirks file"),
+            QuirksError::InvalidLine { line, reason } => {
+                write!(f, "invalid quirks file, line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuirkDb, QuirksError};
+    use crate::Axis;
+
+    const QUIRKS: &str = r#"
+# Test file
+[[quirk]]
+name = "Generic # gamepad"
+vendor = 0x0079
+invert_axes = ["LeftStickY", "RightStickY",]
+
+[[quirk]]
+vendor = 0x0079
+product = 6 # Overrides entry above.
+swap_triggers = true
+broken_battery = true
+"#;
+
+    #[test]
+    fn parse_and_get() {
+        let db = QuirkDb::parse(QUIRKS).unwrap();
+        assert_eq!(db.len(), 2);
+
+        let quirk = db.get(Some(0x0079), Some(1));
+        assert_eq!(quirk.inverted_axes, [Axis::LeftStickY, Axis::RightStickY]);
+        assert!(!quirk.swap_triggers);
+
+        let quirk = db.get(Some(0x0079), Some(6));
+        assert!(quirk.inverted_axes.is_empty());
+        assert!(quirk.swap_triggers && quirk.broken_battery);
+
+        assert_eq!(db.get(Some(0x045e), Some(6)), Default::default());
+        assert_eq!(db.get(None, None), Default::default());
+
+        match QuirkDb::parse("[[quirk]]\nvendor = 0x0079\nswap_triggers = yes\n") {
+            Err(QuirksError::InvalidLine { line: 3, .. }) => (),
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        // Valid TOML, but not supported.
+        for line in &["\"swap_triggers\" = true", "quirk.swap_triggers = true"] {
+            match QuirkDb::parse(&format!("[[quirk]]\nvendor = 0x0079\n{}\n", line)) {
+                Err(QuirksError::InvalidLine { line: 3, .. }) => (),
+                res => panic!("unexpected result for {:?}: {:?}", line, res),
+            }
+        }
+    }
+}